@@ -0,0 +1,32 @@
+//! Prints the component structs generated from Noita's
+//! `component_documentation.txt`, for pasting into `noita/types/components.rs`
+//!
+//! `cargo run --example gen_components -- path/to/component_documentation.txt [Component...]`
+
+use std::{env, fs, process::ExitCode};
+
+use noita_utility_box::noita::component_docs;
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else {
+        eprintln!("usage: gen_components <component_documentation.txt> [Component...]");
+        return ExitCode::FAILURE;
+    };
+    let only = args.collect::<Vec<_>>();
+
+    let docs = match fs::read_to_string(&path) {
+        Ok(docs) => docs,
+        Err(e) => {
+            eprintln!("Couldn't read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for component in component_docs::parse(&docs) {
+        if only.is_empty() || only.contains(&component.name) {
+            println!("{}", component_docs::generate(&component));
+        }
+    }
+    ExitCode::SUCCESS
+}
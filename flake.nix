@@ -91,7 +91,7 @@
           packages = {
             default = buildPackage {
               nativeBuildInputs = with pkgs; [ makeWrapper copyDesktopItems pkg-config ];
-              buildInputs = [ pkgs.openssl ];
+              buildInputs = with pkgs; [ openssl alsa-lib ];
               postInstall = ''
                 wrapProgram $out/bin/${name} --prefix LD_LIBRARY_PATH : ${dynamicDeps}
                 mkdir -p $out/share/icons/hicolor/256x256/apps
@@ -138,7 +138,7 @@
                 Architecture: amd64
                 Maintainer: necauqua <him@necauq.ua>
                 Description: ${description}
-                Depends: openssl
+                Depends: openssl, libasound2
                 EOF
                 ${pkgs.dpkg}/bin/dpkg-deb --build package
                 mv package.deb $out
@@ -1,32 +1,89 @@
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+    sync::{mpsc::Receiver, Arc},
+    time::{Duration, Instant},
+};
 
 use eframe::{
-    egui::{self, Frame, RichText, TextWrapMode, Ui, ViewportBuilder, WidgetText},
+    egui::{self, Frame, TextWrapMode, Ui, ViewportBuilder, WidgetText},
     get_value, icon_data, set_value, NativeOptions,
 };
-use egui_tiles::{Container, Linear, LinearDir, SimplificationOptions, Tabs, Tile, TileId, Tiles};
-use noita_utility_box::noita::{Noita, Seed};
+use egui_tiles::{
+    Behavior, Container, Linear, LinearDir, SimplificationOptions, Tabs, Tile, TileId, Tiles,
+};
+use noita_utility_box::{
+    memory::set_retry_policy,
+    noita::{Noita, Seed},
+};
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
 
 use crate::{
+    cli::Args,
+    command_palette::{Command, CommandPalette},
+    events::{EventBus, EventCursor, GameEvent},
+    file_output::FileOutput,
+    i18n::{self, tr},
+    instance::Instance,
+    mini_mode, overlay,
+    perf::{self, ReadRates, ToolStats},
+    profiles::{self, Profile, ProfilesData},
+    reader::{GameReader, GameState},
+    screenshot,
+    server::{self, LocalServer},
+    settings_file,
+    sound::{self, Alert},
+    theme,
     tools::{
         address_maps::AddressMapsData, settings::SettingsData, Tool, ToolError, ToolInfo, TOOLS,
     },
+    trail::{Trail, TrailPoint},
     update_check::UpdateChecker,
-    util::{persist, Tickable, UpdatableApp},
+    util::{persist, Promise, Tickable, UpdatableApp},
+    window_placement::WindowPlacement,
 };
 
 #[derive(Default)]
 pub struct AppState {
     pub settings: SettingsData,
     pub address_maps: AddressMapsData,
+    pub profiles: ProfilesData,
 
     hidden_tools: Vec<Pane>,
-    tool_request: Option<(TileId, Pane)>,
+    /// Tools popped out into their own windows
+    detached_tools: Vec<Pane>,
+    detach_request: Option<TileId>,
+    tool_request: Option<(TileId, Pane, Placement)>,
+    visibility_request: Option<(&'static ToolInfo, bool)>,
+    layout_reset_request: bool,
+    settings_file_request: Option<settings_file::Request>,
+    profile_request: Option<profiles::Request>,
 
     pub noita: Option<Noita>,
+    reader: Option<GameReader>,
+    /// Game events for the tools, see [EventBus::poll]
+    pub events: EventBus,
     pub seed: Option<Seed>,
+    /// The path the player took this run, see [SettingsData::record_trail]
+    pub trail: Trail,
+    /// The nearest orb the orb radar found, for the overlay and the dashboard
+    pub orb_target: Option<egui::Pos2>,
+    /// Set by the tools that show [AppState::orb_target], so that the orb
+    /// radar keeps it updated for them
+    pub orb_target_wanted: bool,
+    /// Game frame counter as of the current update, None if not connected
+    /// or if it could not be read
+    pub game_frame: Option<u32>,
+    /// See [SettingsData::file_output]
+    pub file_output: FileOutput,
+    /// See [SettingsData::server]
+    pub server: LocalServer,
+
+    toasts: Vec<Toast>,
+    /// When the window border was last flashed, see [AppState::flash]
+    flashed_at: Option<Instant>,
+    read_rates: ReadRates,
 
     #[cfg(debug_assertions)]
     repaints: u64,
@@ -39,12 +96,261 @@ impl AppState {
             None => ToolError::retry("Not connected to Noita"),
         }
     }
+
+    /// Connect to (or disconnect from) a game, restarting the background
+    /// reader
+    pub fn set_noita(&mut self, mut noita: Option<Noita>) {
+        // before the clones are made, so that they share the cache
+        if let Some(noita) = &mut noita {
+            noita.set_caching(self.settings.cache_reads);
+        }
+        self.reader = noita
+            .clone()
+            .map(|noita| GameReader::spawn(noita, self.events.clone()));
+        self.server.set_noita(noita.clone());
+        self.noita = noita;
+    }
+
+    /// The latest state published by the background reader
+    pub fn game(&self) -> Arc<GameState> {
+        self.reader
+            .as_ref()
+            .map(GameReader::latest)
+            .unwrap_or_default()
+    }
+
+    fn refresh_game_state(&mut self) {
+        set_retry_policy(self.settings.retry_policy());
+
+        let game = self.game();
+        self.game_frame = game.frame;
+        self.seed = game.seed;
+
+        if game.new_run {
+            self.trail.clear();
+        }
+        if let (true, Some((player, _)), Some(frame)) =
+            (self.settings.record_trail, &game.player, game.frame)
+        {
+            let pos = player.transform.pos;
+            let point = TrailPoint {
+                pos: egui::pos2(pos.x, pos.y),
+                frame,
+            };
+            let settings = &self.settings;
+            self.trail
+                .record(point, settings.trail_resolution, settings.trail_length);
+        }
+
+        if let Some(noita) = self.noita.as_mut() {
+            noita.set_caching(self.settings.cache_reads);
+            match game.frame {
+                Some(frame) => noita.sync_frame(frame),
+                // can't tell if anything changed, so don't keep stale reads
+                None => noita.bump_cache(),
+            }
+        }
+    }
+
+    /// Whether the game advanced since the `last_frame` a tool has seen,
+    /// updating it. Lets tools skip re-reading the same data while the game
+    /// is paused - if the frame is unknown this is always true
+    pub fn game_advanced(&self, last_frame: &mut Option<u32>) -> bool {
+        let advanced = self.game_frame.is_none() || *last_frame != self.game_frame;
+        *last_frame = self.game_frame;
+        advanced
+    }
+
+    /// Show a short message in the corner of the window for a few seconds,
+    /// it also goes to the log
+    pub fn toast(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        tracing::info!(text, "Toast");
+        self.push_toast(Toast::new(text, false));
+    }
+
+    pub fn toast_error(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        tracing::warn!(text, "Toast");
+        self.push_toast(Toast::new(text, true));
+    }
+
+    fn push_toast(&mut self, toast: Toast) {
+        // the same thing happening again just restarts the timer
+        self.toasts.retain(|t| t.text != toast.text);
+        self.toasts.push(toast);
+        if self.toasts.len() > Toast::MAX_SHOWN {
+            self.toasts.remove(0);
+        }
+    }
+
+    /// Flash the window border red, for alarms
+    pub fn flash(&mut self) {
+        self.flashed_at = Some(Instant::now());
+    }
+
+    /// Show or hide the tool after the tree finishes drawing
+    pub fn set_tool_visible(&mut self, info: &'static ToolInfo, visible: bool) {
+        self.visibility_request = Some((info, visible));
+    }
+
+    /// The tool ui or its error, for both the tree panes and the detached
+    /// windows
+    fn pane_contents(&mut self, ui: &mut Ui, pane: &mut Pane) {
+        loop {
+            if let Some(e) = pane.error.as_ref() {
+                if e.show(ui) {
+                    pane.error = None;
+                }
+                break;
+            }
+            let _span = tracing::debug_span!("ui", tool = pane.title).entered();
+            match pane.stats.ui.measure(|| pane.tool.ui(ui, self)) {
+                Ok(()) => {}
+                Err(ToolError::ImmediateRetry(e)) => {
+                    ui.label(format!("{e}"));
+                }
+                Err(e) => {
+                    pane.stats.last_error = Some(format!("{}: {e}", e.kind()));
+                    pane.error = Some(e);
+                    continue; // goto drawing the error lol
+                }
+            }
+            break;
+        }
+
+        #[cfg(debug_assertions)]
+        {
+            use eframe::egui::{Align, Layout, RichText};
+
+            ui.with_layout(Layout::bottom_up(Align::RIGHT), |ui| {
+                ui.label(RichText::new(format!("Repaints: {}", self.repaints)).small());
+                ui.label(
+                    RichText::new("⚠ Debug build ⚠")
+                        .small()
+                        .color(ui.visuals().warn_fg_color),
+                )
+            });
+        }
+    }
+
+    /// Create or switch a profile after the tree finishes drawing
+    pub fn request_profile(&mut self, request: profiles::Request) {
+        self.profile_request = Some(request);
+    }
+
+    /// Export or import the whole persisted state after the tree finishes
+    /// drawing
+    pub fn request_settings_file(&mut self, request: settings_file::Request) {
+        self.settings_file_request = Some(request);
+    }
+
+    /// Put the visible tools back into the default arrangement after the
+    /// tree finishes drawing
+    pub fn reset_layout(&mut self) {
+        self.layout_reset_request = true;
+    }
+}
+
+/// Where a tool re-added from the ➕ menu goes
+#[derive(Debug, Clone, Copy)]
+enum Placement {
+    /// Another tab in the tab bar the menu was opened from
+    Tab,
+    /// A new tab bar to the right of that one, to see both at once
+    Split,
+}
+
+struct Toast {
+    text: String,
+    error: bool,
+    shown_at: Instant,
+}
+
+impl Toast {
+    const DURATION: Duration = Duration::from_secs(5);
+    const MAX_SHOWN: usize = 5;
+
+    fn new(text: String, error: bool) -> Self {
+        Self {
+            text,
+            error,
+            shown_at: Instant::now(),
+        }
+    }
+}
+
+fn toasts_ui(ctx: &egui::Context, toasts: &mut Vec<Toast>) {
+    toasts.retain(|t| t.shown_at.elapsed() < Toast::DURATION);
+    if toasts.is_empty() {
+        return;
+    }
+
+    egui::Area::new(egui::Id::new("toasts"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-8.0, -8.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            for toast in toasts.iter() {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    let color = if toast.error {
+                        ui.visuals().error_fg_color
+                    } else {
+                        ui.visuals().text_color()
+                    };
+                    ui.label(egui::RichText::new(&toast.text).color(color));
+                });
+            }
+        });
+
+    // to make them disappear without waiting for input
+    let next_expiry = toasts
+        .iter()
+        .map(|t| Toast::DURATION.saturating_sub(t.shown_at.elapsed()))
+        .min()
+        .unwrap_or_default();
+    ctx.request_repaint_after(next_expiry);
+}
+
+/// See [Pane::tick]
+const TICK_SLACK: Duration = Duration::from_millis(5);
+
+/// Initial inner size of a detached tool window
+const DETACHED_SIZE: egui::Vec2 = egui::vec2(420.0, 520.0);
+
+const FLASH_DURATION: Duration = Duration::from_millis(1500);
+
+fn flash_ui(ctx: &egui::Context, flashed_at: &mut Option<Instant>) {
+    let Some(at) = *flashed_at else {
+        return;
+    };
+    let elapsed = at.elapsed();
+    if elapsed >= FLASH_DURATION {
+        *flashed_at = None;
+        return;
+    }
+    // blink three times while fading out
+    let t = elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32();
+    if (t * 3.0).fract() < 0.5 {
+        let color = egui::Color32::RED.gamma_multiply(1.0 - t);
+        ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("flash"),
+        ))
+        .rect_stroke(
+            ctx.screen_rect().shrink(4.0),
+            egui::Rounding::ZERO,
+            egui::Stroke::new(8.0, color),
+        );
+    }
+    ctx.request_repaint();
 }
 
 persist!(AppState {
     settings: SettingsData,
     address_maps: AddressMapsData,
+    profiles: ProfilesData,
     hidden_tools: Vec<Pane>,
+    detached_tools: Vec<Pane>,
 });
 
 #[derive(Serialize, Deserialize, SmartDefault)]
@@ -57,15 +363,40 @@ pub struct NoitaUtilityBox {
 
     #[default(default_tree())]
     tree: egui_tiles::Tree<Pane>,
+
+    /// Arguments from other launches while we were running
+    #[serde(skip)]
+    forwarded_args: Option<Receiver<Args>>,
+
+    /// For the alerts that don't belong to any tool
+    #[serde(skip)]
+    events: EventCursor,
+
+    #[serde(skip)]
+    death_screenshot: Option<Promise<anyhow::Result<PathBuf>>>,
+
+    #[serde(skip)]
+    command_palette: CommandPalette,
+
+    /// Where the main window was for each monitor setup
+    window: WindowPlacement,
 }
 
 #[derive(Serialize, Deserialize)]
 struct Pane {
     title: String,
     tool: Box<dyn Tool>,
+    /// Where the tool window was when the tool was detached, for each
+    /// monitor setup
+    #[serde(default)]
+    placement: WindowPlacement,
 
     #[serde(skip)]
     error: Option<ToolError>,
+    #[serde(skip)]
+    stats: ToolStats,
+    #[serde(skip)]
+    last_tick: Option<Instant>,
 }
 
 impl Pane {
@@ -73,23 +404,67 @@ impl Pane {
         Self {
             title: tool_info.title.into(),
             tool: (tool_info.default_constructor)(),
+            placement: WindowPlacement::default(),
             error: None,
+            stats: ToolStats::default(),
+            last_tick: None,
         }
     }
+
+    /// The title in the current UI language, the persisted one is for the
+    /// tools that are not registered anymore
+    fn display_title(&self) -> &str {
+        ToolInfo::of(&*self.tool).map_or(&self.title, |info| info.display_title())
+    }
+
+    fn tick(&mut self, ctx: &egui::Context, state: &mut AppState) {
+        let interval = ToolInfo::of(&*self.tool)
+            .map_or(Duration::ZERO, |info| state.settings.tick_interval(info));
+        // the loop sleeps for about as long as the intervals, so a tick that
+        // comes a hair early should not have to wait for a whole extra sleep
+        if self
+            .last_tick
+            .is_some_and(|at| at.elapsed() + TICK_SLACK < interval)
+        {
+            return;
+        }
+        self.last_tick = Some(Instant::now());
+
+        let _span = tracing::debug_span!("tick", tool = self.title).entered();
+        self.stats.tick.measure(|| self.tool.tick(ctx, state));
+    }
 }
 
 fn default_tree() -> egui_tiles::Tree<Pane> {
+    layout_tree(TOOLS.iter().map(|info| Pane::new(info)).collect())
+}
+
+/// The process panel on the left and the rest of the tools in tabs next to
+/// it, in the default order
+fn layout_tree(mut panes: Vec<Pane>) -> egui_tiles::Tree<Pane> {
     let mut tiles = egui_tiles::Tiles::default();
 
+    panes.sort_by_key(|pane| TOOLS.iter().position(|info| info.is_it(&*pane.tool)));
+
     // first tool is the process panel
-    let (first, rest) = TOOLS.split_first().expect("No tools defined");
+    let first = TOOLS.first().expect("No tools defined");
+    let (split_tab, tabs): (Vec<_>, Vec<_>) =
+        panes.into_iter().partition(|pane| first.is_it(&*pane.tool));
 
-    let split_tab = vec![tiles.insert_pane(Pane::new(first))];
+    let split_tab = split_tab
+        .into_iter()
+        .map(|pane| tiles.insert_pane(pane))
+        .collect::<Vec<_>>();
+    let tabs = tabs
+        .into_iter()
+        .map(|pane| tiles.insert_pane(pane))
+        .collect::<Vec<_>>();
 
-    let tabs = rest
-        .iter()
-        .map(|tool| tiles.insert_pane(Pane::new(tool)))
-        .collect();
+    // one of them was closed, no need for the split then
+    if split_tab.is_empty() || tabs.is_empty() {
+        let root = tiles.insert_tab_tile([split_tab, tabs].concat());
+        return egui_tiles::Tree::new("tool_tree", root, tiles);
+    }
 
     let split_tab = tiles.insert_tab_tile(split_tab);
     let tabs = tiles.insert_tab_tile(tabs);
@@ -104,7 +479,7 @@ fn default_tree() -> egui_tiles::Tree<Pane> {
     egui_tiles::Tree::new("tool_tree", root, tiles)
 }
 
-impl egui_tiles::Behavior<Pane> for AppState {
+impl Behavior<Pane> for AppState {
     fn simplification_options(&self) -> SimplificationOptions {
         SimplificationOptions {
             all_panes_must_have_tabs: true,
@@ -112,7 +487,7 @@ impl egui_tiles::Behavior<Pane> for AppState {
         }
     }
     fn tab_title_for_pane(&mut self, pane: &Pane) -> WidgetText {
-        pane.title.clone().into()
+        pane.display_title().into()
     }
 
     fn on_tab_close(&mut self, tiles: &mut Tiles<Pane>, tile_id: TileId) -> bool {
@@ -142,24 +517,74 @@ impl egui_tiles::Behavior<Pane> for AppState {
         _tiles: &Tiles<Pane>,
         ui: &mut Ui,
         tile_id: TileId,
-        _tabs: &Tabs,
+        tabs: &Tabs,
         _scroll_offset: &mut f32,
     ) {
-        if self.hidden_tools.is_empty() {
+        if let Some(active) = tabs.active {
+            if ui
+                .button("⧉")
+                .on_hover_text(tr(
+                    "Pop the tool out into its own window, close the window to put it back",
+                ))
+                .clicked()
+            {
+                self.detach_request = Some(active);
+            }
+        }
+        if ui
+            .button("🗕")
+            .on_hover_text(tr(
+                "Mini mode - collapse the window into a small always-on-top strip of live values",
+            ))
+            .clicked()
+        {
+            self.settings.mini_mode.enabled = true;
+        }
+        // the ones hidden in the settings stay closed until shown there
+        let settings = &self.settings;
+        let in_menu =
+            |pane: &Pane| !ToolInfo::of(&*pane.tool).is_some_and(|info| settings.is_hidden(info));
+        if !self.hidden_tools.iter().any(in_menu) {
             return;
         }
         egui::menu::menu_button(ui, "➕", |ui| {
             ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
+
+            self.hidden_tools
+                .sort_by_key(|pane| ToolInfo::of(&*pane.tool).map(|info| settings.tool_rank(info)));
+
             let mut clicked = None;
             for (i, closed) in self.hidden_tools.iter().enumerate() {
-                if ui.button(&closed.title).clicked() {
-                    clicked = Some(i);
+                if !in_menu(closed) {
+                    continue;
                 }
+                let pinned = ToolInfo::of(&*closed.tool).is_some_and(|i| settings.is_pinned(i));
+                let title = if pinned {
+                    format!("⭐ {}", closed.display_title())
+                } else {
+                    closed.display_title().to_owned()
+                };
+                ui.horizontal(|ui| {
+                    if ui
+                        .small_button("◫")
+                        .on_hover_text(tr("Open next to these tabs, to see both at once"))
+                        .clicked()
+                    {
+                        clicked = Some((i, Placement::Split));
+                    }
+                    if ui.button(title).clicked() {
+                        clicked = Some((i, Placement::Tab));
+                    }
+                });
+            }
+            if clicked.is_some() {
+                ui.close_menu();
             }
 
             // postpone re-adding the tool until after the tree finishes drawing,
             // where we actually have the (mutable) reference to the tree
-            self.tool_request = clicked.map(|i| (tile_id, self.hidden_tools.remove(i)));
+            self.tool_request =
+                clicked.map(|(i, placement)| (tile_id, self.hidden_tools.remove(i), placement));
         });
         ui.add_space(4.0);
     }
@@ -171,90 +596,154 @@ impl egui_tiles::Behavior<Pane> for AppState {
         pane: &mut Pane,
     ) -> egui_tiles::UiResponse {
         // re-add margins but inside of the panes
-        Frame::central_panel(ui.style()).show(ui, |ui| {
-            loop {
-                if let Some(e) = pane.error.as_ref() {
-                    // bad state is informative, don't scream with red
-                    let color = if matches!(e, ToolError::BadState(_)) {
-                        ui.visuals().warn_fg_color
-                    } else {
-                        ui.visuals().error_fg_color
-                    };
+        Frame::central_panel(ui.style()).show(ui, |ui| self.pane_contents(ui, pane));
 
-                    ui.label(RichText::new(e.to_string()).color(color));
+        egui_tiles::UiResponse::None
+    }
+}
 
-                    if ui.button("Retry").clicked() {
-                        pane.error = None;
-                    }
-                    break;
+impl Tickable for NoitaUtilityBox {
+    fn tick(&mut self, ctx: &egui::Context) -> std::time::Duration {
+        self.state.refresh_game_state();
+
+        for event in self.state.events.poll(&mut self.events) {
+            match event {
+                GameEvent::ShiftReady => {
+                    sound::play(Alert::ShiftReady, &self.state.settings.sounds);
                 }
-                match pane.tool.ui(ui, self) {
-                    Ok(()) => {}
-                    Err(ToolError::ImmediateRetry(e)) => {
-                        ui.label(format!("{e}"));
-                    }
-                    Err(e) => {
-                        pane.error = Some(e);
-                        continue; // goto drawing the error lol
-                    }
+                GameEvent::PlayerDied if self.state.settings.screenshot_on_death => {
+                    self.take_death_screenshot();
                 }
-                break;
+                _ => {}
             }
-
-            #[cfg(debug_assertions)]
-            {
-                use eframe::egui::{Align, Layout, RichText};
-
-                ui.with_layout(Layout::bottom_up(Align::RIGHT), |ui| {
-                    ui.label(RichText::new(format!("Repaints: {}", self.repaints)).small());
-                    ui.label(
-                        RichText::new("⚠ Debug build ⚠")
-                            .small()
-                            .color(ui.visuals().warn_fg_color),
-                    )
-                });
+        }
+        if let Some(result) = self.death_screenshot.as_mut().and_then(|p| p.poll_take()) {
+            self.death_screenshot = None;
+            match result {
+                Ok(path) => self.state.toast(format!("Saved {}", path.display())),
+                Err(e) => self
+                    .state
+                    .toast_error(format!("Death screenshot failed: {e:#}")),
             }
-        });
+        }
 
-        egui_tiles::UiResponse::None
-    }
-}
+        let game = self.state.game();
+        let s = &mut self.state;
+        s.file_output.update(&s.settings.file_output, &game);
+        s.server.update(&s.settings.server, game.clone());
+        for action in s.server.take_actions() {
+            self.apply_server_action(ctx, action);
+        }
 
-impl Tickable for NoitaUtilityBox {
-    fn tick(&mut self, ctx: &egui::Context) -> std::time::Duration {
         for tile in self.tree.tiles.tiles_mut() {
             if let Tile::Pane(pane) = tile {
-                pane.tool.tick(ctx, &mut self.state);
+                pane.tick(ctx, &mut self.state);
             }
         }
 
         // untie the &mut hidden tools from &mut state
         let mut hidden_tools = std::mem::take(&mut self.state.hidden_tools);
         for tile in &mut hidden_tools {
-            tile.tool.tick(ctx, &mut self.state);
+            tile.tick(ctx, &mut self.state);
         }
         self.state.hidden_tools = hidden_tools;
 
-        Duration::from_secs_f32(self.state.settings.background_update_interval)
+        let mut detached_tools = std::mem::take(&mut self.state.detached_tools);
+        for tile in &mut detached_tools {
+            tile.tick(ctx, &mut self.state);
+        }
+        self.state.detached_tools = detached_tools;
+
+        self.state.settings.background_sleep()
     }
 }
 
 impl eframe::App for NoitaUtilityBox {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        i18n::set_language(self.state.settings.language);
+        theme::update(ctx, &mut self.state.settings.theme);
         self.update_checker.check(ctx, &mut self.state);
+        self.state.refresh_game_state();
+
+        if let Some(rx) = &self.forwarded_args {
+            for args in rx.try_iter().collect::<Vec<_>>() {
+                self.apply_args(args);
+            }
+        }
 
-        egui::CentralPanel::default()
-            // remove margin
-            .frame(Frame::none().fill(ctx.style().visuals.panel_fill))
-            .show(ctx, |ui| {
-                self.tree.ui(&mut self.state, ui);
+        // mini mode shrinks the window, that's not where it goes back to
+        if !self.state.settings.mini_mode.enabled {
+            if let Some(placement) = self.window.track(ctx) {
+                placement.apply(ctx, egui::ViewportId::ROOT);
+            }
+        }
 
-                if let Some((tile_id, tool)) = self.state.tool_request.take() {
-                    let pane = self.tree.tiles.insert_pane(tool);
-                    self.tree
-                        .move_tile_to_container(pane, tile_id, usize::MAX, true);
-                }
-            });
+        if !mini_mode::show(ctx, &mut self.state) {
+            egui::CentralPanel::default()
+                // remove margin
+                .frame(Frame::none().fill(ctx.style().visuals.panel_fill))
+                .show(ctx, |ui| {
+                    self.tree.ui(&mut self.state, ui);
+
+                    if let Some((tile_id, tool, placement)) = self.state.tool_request.take() {
+                        let pane = self.tree.tiles.insert_pane(tool);
+                        match placement {
+                            Placement::Tab => {
+                                self.tree
+                                    .move_tile_to_container(pane, tile_id, usize::MAX, true);
+                            }
+                            Placement::Split => self.split_container(tile_id, pane),
+                        }
+                    }
+                    if let Some(tile_id) = self.state.detach_request.take() {
+                        self.detach(tile_id);
+                    }
+                    if std::mem::take(&mut self.state.layout_reset_request) {
+                        self.reset_layout();
+                    }
+
+                    if let Some((info, visible)) = self.state.visibility_request.take() {
+                        self.set_tool_visible(info, visible);
+                    }
+                });
+        }
+
+        if let Some(request) = self.state.profile_request.take() {
+            self.apply_profile_request(request);
+        }
+        if let Some(request) = self.state.settings_file_request.take() {
+            self.apply_settings_file(request);
+        }
+
+        self.detached_ui(ctx);
+
+        if let Some(command) = self.command_palette.show(ctx) {
+            self.run_command(ctx, command);
+        }
+
+        toasts_ui(ctx, &mut self.state.toasts);
+        flash_ui(ctx, &mut self.state.flashed_at);
+        overlay::show(ctx, &mut self.state);
+
+        if self.state.settings.show_perf_overlay {
+            let tools = self
+                .tree
+                .tiles
+                .tiles()
+                .filter_map(|tile| match tile {
+                    Tile::Pane(pane) => Some(pane),
+                    _ => None,
+                })
+                .chain(&self.state.hidden_tools)
+                .chain(&self.state.detached_tools)
+                .map(|pane| (&*pane.title, &pane.stats));
+            perf::overlay(
+                ctx,
+                &mut self.state.settings.show_perf_overlay,
+                &mut self.state.read_rates,
+                tools,
+            );
+        }
 
         #[cfg(debug_assertions)]
         {
@@ -268,6 +757,340 @@ impl eframe::App for NoitaUtilityBox {
 }
 
 impl NoitaUtilityBox {
+    fn apply_server_action(&mut self, ctx: &egui::Context, action: server::Action) {
+        tracing::info!(?action, "Local server action");
+        match action {
+            server::Action::CopySeed => match self.state.seed {
+                Some(seed) => {
+                    ctx.copy_text(seed.to_string());
+                    self.state.toast("Copied the seed");
+                }
+                None => self.state.toast_error("No seed to copy"),
+            },
+            server::Action::ResetRadar => self.switch_tool_option("OrbRadar", "reset"),
+        }
+        ctx.request_repaint();
+    }
+
+    fn run_command(&mut self, ctx: &egui::Context, command: Command) {
+        tracing::info!(?command, "Command palette");
+        match command {
+            Command::OpenTool(info) => self.focus_tool(ctx, info),
+            Command::CopySeed => self.apply_server_action(ctx, server::Action::CopySeed),
+            Command::ResetRadar => self.apply_server_action(ctx, server::Action::ResetRadar),
+            Command::Reconnect => self.switch_tool_option("ProcessPanel", "reconnect"),
+            Command::DiscoverAddresses => self.switch_tool_option("ProcessPanel", "discover"),
+            Command::ResetLayout => self.reset_layout(),
+            Command::MiniMode => self.state.settings.mini_mode.enabled = true,
+        }
+        ctx.request_repaint();
+    }
+
+    /// Turn on a switch option of a tool, for the tools that do things on
+    /// them like resetting
+    fn switch_tool_option(&mut self, id: &str, key: &str) {
+        let info = TOOLS.iter().find(|info| info.id == id);
+        if let Some(tool) = info.and_then(|info| self.tool_mut(info)) {
+            if let Err(e) = tool.set_option(key, "true") {
+                tracing::warn!(id, key, "Tool option failed: {e:#}");
+            }
+        }
+    }
+
+    fn apply_profile_request(&mut self, request: profiles::Request) {
+        let tools = self.profile_tools();
+        let profiles = &mut self.state.profiles;
+        match request {
+            profiles::Request::Create(name) => {
+                let address_map = profiles.active().and_then(|p| p.address_map.clone());
+                profiles.remove(&name);
+                profiles.profiles.push(Profile {
+                    name: name.clone(),
+                    address_map,
+                    tools,
+                });
+                profiles.active = Some(name.clone());
+                self.state.toast(format!("Created the profile {name}"));
+            }
+            profiles::Request::Switch(name) => {
+                // the changes made while it was active stay with it
+                if let Some(active) = profiles.active_mut() {
+                    active.tools = tools;
+                }
+                let tools = name
+                    .as_deref()
+                    .and_then(|name| profiles.get(name))
+                    .map(|profile| profile.tools.clone());
+                profiles.active.clone_from(&name);
+                if let Some(tools) = tools {
+                    self.load_profile_tools(&tools);
+                }
+                tracing::info!(?name, "Switched the profile");
+            }
+        }
+    }
+
+    /// Configs of the tools that are not shared between the profiles
+    fn profile_tools(&self) -> BTreeMap<String, serde_json::Value> {
+        let tree_panes = self.tree.tiles.tiles().filter_map(|tile| match tile {
+            Tile::Pane(pane) => Some(pane),
+            _ => None,
+        });
+        tree_panes
+            .chain(&self.state.hidden_tools)
+            .chain(&self.state.detached_tools)
+            .filter_map(|pane| {
+                let info = ToolInfo::of(&*pane.tool)?;
+                if profiles::SHARED_TOOLS.contains(&info.id) {
+                    return None;
+                }
+                let json = serde_json::to_value(&pane.tool)
+                    .inspect_err(|e| tracing::warn!(tool = info.id, "Couldn't save the tool: {e}"))
+                    .ok()?;
+                Some((info.id.to_owned(), json))
+            })
+            .collect()
+    }
+
+    fn load_profile_tools(&mut self, tools: &BTreeMap<String, serde_json::Value>) {
+        for (id, json) in tools {
+            let Some(info) = TOOLS.iter().find(|info| info.id == id) else {
+                continue;
+            };
+            // the older profiles had the configs as JSON strings
+            let tool = match json {
+                serde_json::Value::String(json) => serde_json::from_str::<Box<dyn Tool>>(json),
+                json => serde_json::from_value::<Box<dyn Tool>>(json.clone()),
+            };
+            let tool = match tool {
+                Ok(tool) => tool,
+                Err(e) => {
+                    tracing::warn!(tool = id, "Couldn't load the tool from the profile: {e}");
+                    continue;
+                }
+            };
+            if let Some(pane) = self.pane_mut(info) {
+                pane.tool = tool;
+                pane.error = None;
+            }
+        }
+    }
+
+    fn apply_settings_file(&mut self, request: settings_file::Request) {
+        match request {
+            settings_file::Request::Export {
+                path,
+                include_secrets,
+            } => {
+                let result = serde_json::to_value(&*self)
+                    .map_err(Into::into)
+                    .and_then(|value| settings_file::write(&path, value, include_secrets));
+                match result {
+                    Ok(()) => self
+                        .state
+                        .toast(format!("Exported the settings to {}", path.display())),
+                    Err(e) => self.state.toast_error(format!("Export failed: {e:#}")),
+                }
+            }
+            settings_file::Request::Import(path) => match self.import_settings(&path) {
+                Ok(()) => self
+                    .state
+                    .toast(format!("Imported the settings from {}", path.display())),
+                Err(e) => self.state.toast_error(format!("Import failed: {e:#}")),
+            },
+        }
+    }
+
+    /// Replace everything that's persisted with what's in the file, the
+    /// connection to the game is dropped as the tools are all new
+    fn import_settings(&mut self, path: &std::path::Path) -> anyhow::Result<()> {
+        let current = serde_json::to_value(&*self)?;
+        let value = settings_file::read(path, &current)?;
+        let imported: Self = serde_json::from_value(value)?;
+
+        self.tree = imported.tree;
+        let s = &mut self.state;
+        s.settings = SettingsData {
+            newest_version: s.settings.newest_version.take(),
+            transfer: s.settings.transfer.clone(),
+            ..imported.state.settings
+        };
+        s.address_maps = imported.state.address_maps;
+        s.profiles = imported.state.profiles;
+        s.hidden_tools = imported.state.hidden_tools;
+        s.detached_tools = imported.state.detached_tools;
+        s.set_noita(None);
+        self.ensure_all_tools_present();
+        Ok(())
+    }
+
+    fn take_death_screenshot(&mut self) {
+        // one is enough if the deaths come faster than the screenshots
+        if self.death_screenshot.is_some() {
+            return;
+        }
+        let Some(pid) = self.state.noita.as_ref().map(|n| n.proc().pid()) else {
+            return;
+        };
+        let seed = self.state.seed;
+        self.death_screenshot = Some(Promise::spawn(async move {
+            tokio::task::spawn_blocking(move || screenshot::save_death_screenshot(pid, seed))
+                .await
+                .unwrap_or_else(|e| Err(e.into()))
+        }));
+    }
+
+    fn find_pane(&self, info: &ToolInfo) -> Option<TileId> {
+        self.tree.tiles.iter().find_map(|(id, tile)| match tile {
+            Tile::Pane(pane) if info.is_it(&*pane.tool) => Some(*id),
+            _ => None,
+        })
+    }
+
+    /// Where to put tools that are shown from outside of the tab bar - next
+    /// to the settings tool (where the request most likely came from) or
+    /// whatever tab container we find
+    fn default_container(&self) -> Option<TileId> {
+        TOOLS
+            .iter()
+            .find(|info| info.id == "Settings")
+            .and_then(|info| self.find_pane(info))
+            .and_then(|id| self.tree.tiles.parent_of(id))
+            .or_else(|| {
+                self.tree.tiles.iter().find_map(|(id, tile)| {
+                    matches!(tile, Tile::Container(Container::Tabs(_))).then_some(*id)
+                })
+            })
+    }
+
+    fn detach(&mut self, tile_id: TileId) {
+        if !self.state.is_tab_closable(&self.tree.tiles, tile_id) {
+            return;
+        }
+        if let Some(Tile::Pane(pane)) = self.tree.tiles.remove(tile_id) {
+            tracing::info!(tool = pane.title, "Detached a tool");
+            self.state.detached_tools.push(pane);
+        }
+    }
+
+    /// Put a detached tool back into the tree, next to the settings
+    fn reattach(&mut self, pane: Pane) {
+        let Some(container) = self.default_container() else {
+            // can't happen as at least one tool stays in the tree
+            self.state.hidden_tools.push(pane);
+            return;
+        };
+        let pane = self.tree.tiles.insert_pane(pane);
+        self.tree
+            .move_tile_to_container(pane, container, usize::MAX, true);
+    }
+
+    /// Each detached tool is drawn in its own native window
+    fn detached_ui(&mut self, ctx: &egui::Context) {
+        let mut detached_tools = std::mem::take(&mut self.state.detached_tools);
+        let mut closed = None;
+        for (i, pane) in detached_tools.iter_mut().enumerate() {
+            let id = ToolInfo::of(&*pane.tool).map_or(&*pane.title, |info| info.id);
+            let viewport = egui::ViewportId::from_hash_of(("detached", id));
+            let mut builder = ViewportBuilder::default()
+                .with_title(format!("{} - Noita Utility Box", pane.display_title()));
+            // only when it's created, the builder is re-applied every frame
+            // and would fight the user dragging the window around
+            if !ctx.input(|i| i.raw.viewports.contains_key(&viewport)) {
+                builder = pane
+                    .placement
+                    .builder(builder.with_inner_size(DETACHED_SIZE));
+            }
+
+            let state = &mut self.state;
+            ctx.show_viewport_immediate(viewport, builder, |ctx, _| {
+                egui::CentralPanel::default().show(ctx, |ui| state.pane_contents(ui, pane));
+
+                if let Some(placement) = pane.placement.track(ctx) {
+                    placement.apply(ctx, viewport);
+                }
+                if ctx.input(|input| input.viewport().close_requested()) {
+                    closed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = closed {
+            let pane = detached_tools.remove(i);
+            tracing::info!(tool = pane.title, "Reattached a tool");
+            self.reattach(pane);
+        }
+        // the detached ones could've been added to while drawing
+        detached_tools.append(&mut self.state.detached_tools);
+        self.state.detached_tools = detached_tools;
+    }
+
+    /// Put the pane into a new tab container to the right of the given one
+    fn split_container(&mut self, container: TileId, pane: TileId) {
+        let tiles = &mut self.tree.tiles;
+        let Some(tile) = tiles.remove(container) else {
+            return;
+        };
+        // the split takes the place (and the id) of the container, so that
+        // its parent does not need to know about it
+        let moved = tiles.insert_new(tile);
+        let tabs = tiles.insert_tab_tile(vec![pane]);
+        let split = Linear::new_binary(LinearDir::Horizontal, [moved, tabs], 0.5);
+        tiles.insert(container, Tile::Container(Container::Linear(split)));
+    }
+
+    fn reset_layout(&mut self) {
+        let ids = self
+            .tree
+            .tiles
+            .iter()
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let panes = ids
+            .into_iter()
+            .filter_map(|id| match self.tree.tiles.remove(id) {
+                Some(Tile::Pane(pane)) => Some(pane),
+                _ => None,
+            })
+            .collect();
+        self.tree = layout_tree(panes);
+        tracing::info!("Reset the tool layout");
+    }
+
+    fn set_tool_visible(&mut self, info: &ToolInfo, visible: bool) {
+        if !visible {
+            let detached = &mut self.state.detached_tools;
+            if let Some(i) = detached.iter().position(|pane| info.is_it(&*pane.tool)) {
+                let pane = detached.remove(i);
+                self.state.hidden_tools.push(pane);
+                return;
+            }
+            let Some(tile_id) = self.find_pane(info) else {
+                return;
+            };
+            if self.state.is_tab_closable(&self.tree.tiles, tile_id) {
+                self.state.on_tab_close(&mut self.tree.tiles, tile_id);
+            }
+            return;
+        }
+        let Some(i) = self
+            .state
+            .hidden_tools
+            .iter()
+            .position(|pane| info.is_it(&*pane.tool))
+        else {
+            return;
+        };
+        let Some(container) = self.default_container() else {
+            return;
+        };
+        let pane = self
+            .tree
+            .tiles
+            .insert_pane(self.state.hidden_tools.remove(i));
+        self.tree
+            .move_tile_to_container(pane, container, usize::MAX, true);
+    }
+
     // in case of bugs or whatever that would cause tools to be lost from storage
     // or, more likely, new tools being added in new versions
     fn ensure_all_tools_present(&mut self) {
@@ -279,6 +1102,9 @@ impl NoitaUtilityBox {
             };
             tools.retain(|info| !info.is_it(&*pane.tool));
         }
+        for pane in &self.state.detached_tools {
+            tools.retain(|info| !info.is_it(&*pane.tool));
+        }
 
         // also ensure there's no duplicates in hidden tools lol
         let mut unique_tools = HashSet::new();
@@ -307,7 +1133,69 @@ impl NoitaUtilityBox {
             .extend(tools.iter().map(|info| Pane::new(info)));
     }
 
-    pub fn run() -> eframe::Result {
+    fn pane_mut(&mut self, info: &ToolInfo) -> Option<&mut Pane> {
+        let in_tree = self.tree.tiles.tiles_mut().find_map(|tile| match tile {
+            Tile::Pane(pane) if info.is_it(&*pane.tool) => Some(pane),
+            _ => None,
+        });
+        in_tree.or_else(|| {
+            self.state
+                .hidden_tools
+                .iter_mut()
+                .chain(&mut self.state.detached_tools)
+                .find(|pane| info.is_it(&*pane.tool))
+        })
+    }
+
+    fn tool_mut(&mut self, info: &ToolInfo) -> Option<&mut dyn Tool> {
+        self.pane_mut(info).map(|pane| &mut *pane.tool)
+    }
+
+    /// Open the tool if it's closed and switch to its tab
+    fn show_tool(&mut self, info: &ToolInfo) {
+        self.set_tool_visible(info, true);
+        self.tree.make_active(|_, tile| match tile {
+            Tile::Pane(pane) => info.is_it(&*pane.tool),
+            _ => false,
+        });
+    }
+
+    /// Same as [Self::show_tool], but brings up the window of the detached
+    /// tools
+    fn focus_tool(&mut self, ctx: &egui::Context, info: &ToolInfo) {
+        let detached = &self.state.detached_tools;
+        if !detached.iter().any(|pane| info.is_it(&*pane.tool)) {
+            self.show_tool(info);
+            return;
+        }
+        ctx.send_viewport_cmd_to(
+            egui::ViewportId::from_hash_of(("detached", info.id)),
+            egui::ViewportCommand::Focus,
+        );
+    }
+
+    fn apply_args(&mut self, args: Args) {
+        if let Some(info) = args.tool {
+            self.show_tool(info);
+        }
+
+        for (key, value) in &args.options {
+            let applied = match self.state.settings.set_option(key, value) {
+                Ok(false) => match args.tool.and_then(|info| self.tool_mut(info)) {
+                    Some(tool) => tool.set_option(key, value),
+                    None => Ok(false),
+                },
+                r => r,
+            };
+            match applied {
+                Ok(true) => tracing::info!(key, value, "Applied command-line option"),
+                Ok(false) => tracing::warn!(key, "Unknown command-line option"),
+                Err(e) => tracing::warn!(key, "Bad command-line option: {e:#}"),
+            }
+        }
+    }
+
+    pub fn run(args: Args, instance: Instance) -> eframe::Result {
         let rt = tokio::runtime::Runtime::new().unwrap();
         let _guard = rt.enter();
 
@@ -333,6 +1221,8 @@ impl NoitaUtilityBox {
                     .unwrap_or_default();
 
                 app.ensure_all_tools_present();
+                app.apply_args(args);
+                app.forwarded_args = instance.listen(&cc.egui_ctx);
 
                 Ok(Box::new(UpdatableApp::new(app, &cc.egui_ctx)))
             }),
@@ -0,0 +1,83 @@
+use anyhow::{bail, Context, Result};
+use convert_case::{Case, Casing};
+
+use crate::tools::{ToolInfo, TOOLS};
+
+/// Startup overrides, e.g. `--tool orb-radar --realtime --look-for-sampo on`
+///
+/// Options are applied on top of the persisted state - first to the global
+/// settings and then to the selected tool
+#[derive(Debug, Default)]
+pub struct Args {
+    pub tool: Option<&'static ToolInfo>,
+    pub options: Vec<(String, String)>,
+    pub help: bool,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut result = Self::default();
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.next() {
+            let Some(key) = arg.strip_prefix("--") else {
+                bail!("Unexpected argument: {arg}");
+            };
+            let (key, value) = match key.split_once('=') {
+                Some((key, value)) => (key.to_owned(), value.to_owned()),
+                // flags without a value are just switched on
+                None => match args.next_if(|next| !next.starts_with("--")) {
+                    Some(value) => (key.to_owned(), value),
+                    None => (key.to_owned(), "on".to_owned()),
+                },
+            };
+            match &*key {
+                "help" => result.help = true,
+                "tool" => {
+                    let info = find_tool(&value)
+                        .with_context(|| format!("Unknown tool: {value}\n\n{}", usage()))?;
+                    result.tool = Some(info);
+                }
+                _ => result.options.push((key, value)),
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn tool_names(info: &ToolInfo) -> [String; 2] {
+    [
+        info.id.to_case(Case::Kebab),
+        info.title.to_case(Case::Kebab),
+    ]
+}
+
+fn find_tool(name: &str) -> Option<&'static ToolInfo> {
+    let name = name.to_case(Case::Kebab);
+    TOOLS
+        .iter()
+        .copied()
+        .find(|info| tool_names(info).contains(&name))
+}
+
+pub fn usage() -> String {
+    let tools = TOOLS
+        .iter()
+        .map(|info| format!("  {}", tool_names(info).join(", ")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "Usage: noita-utility-box [--tool <name>] [--<option> [value]]...\n\n\
+         Tools:\n{tools}\n\n\
+         Options are tool settings like --realtime, switches take on/off"
+    )
+}
+
+/// Parse an on/off style switch value
+pub fn parse_switch(value: &str) -> Result<bool> {
+    match &*value.to_ascii_lowercase() {
+        "on" | "true" | "yes" | "1" => Ok(true),
+        "off" | "false" | "no" | "0" => Ok(false),
+        _ => bail!("Expected on/off, got {value}"),
+    }
+}
@@ -0,0 +1,180 @@
+//! The Ctrl+K popup for jumping to a tool or running an action by typing a
+//! part of its name, faster than going through the tabs and the ➕ menu
+
+use eframe::egui::{
+    vec2, Align2, Area, Context, Frame, Id, Key, KeyboardShortcut, Label, Modifiers, Order,
+    RichText, TextEdit,
+};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use smart_default::SmartDefault;
+
+use crate::{
+    i18n::tr,
+    tools::{material_list::layout_text_with_indices, ToolInfo, TOOLS},
+};
+
+pub const SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::K);
+
+/// How many of the best matches are listed
+const MAX_SHOWN: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    OpenTool(&'static ToolInfo),
+    CopySeed,
+    Reconnect,
+    DiscoverAddresses,
+    ResetRadar,
+    ResetLayout,
+    MiniMode,
+}
+
+impl Command {
+    const ACTIONS: [Command; 6] = [
+        Command::CopySeed,
+        Command::Reconnect,
+        Command::DiscoverAddresses,
+        Command::ResetRadar,
+        Command::ResetLayout,
+        Command::MiniMode,
+    ];
+
+    fn all() -> impl Iterator<Item = Command> {
+        TOOLS
+            .iter()
+            .map(|&info| Command::OpenTool(info))
+            .chain(Self::ACTIONS)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::OpenTool(info) => info.display_title(),
+            Command::CopySeed => tr("Copy seed"),
+            Command::Reconnect => tr("Reconnect to Noita"),
+            Command::DiscoverAddresses => tr("Discover addresses"),
+            Command::ResetRadar => tr("Reset orb radar"),
+            Command::ResetLayout => tr("Reset layout"),
+            Command::MiniMode => tr("Mini mode"),
+        }
+    }
+
+    fn kind(self) -> &'static str {
+        match self {
+            Command::OpenTool(_) => tr("tool"),
+            _ => tr("action"),
+        }
+    }
+}
+
+#[derive(SmartDefault)]
+pub struct CommandPalette {
+    open: bool,
+    query: String,
+    /// Index of the highlighted match, Enter runs it
+    selected: usize,
+    #[default(SkimMatcherV2::default().ignore_case())]
+    matcher: SkimMatcherV2,
+}
+
+impl CommandPalette {
+    /// The commands matching the query with the matched char indices, best
+    /// ones first
+    fn matches(&self) -> Vec<(Command, Vec<usize>)> {
+        if self.query.is_empty() {
+            return Command::all().map(|c| (c, Vec::new())).collect();
+        }
+        let mut matches = Command::all()
+            .filter_map(|command| {
+                let (score, indices) = self.matcher.fuzzy_indices(command.name(), &self.query)?;
+                Some((score, command, indices))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(score, ..)| -score);
+        matches
+            .into_iter()
+            .take(MAX_SHOWN)
+            .map(|(_, command, indices)| (command, indices))
+            .collect()
+    }
+
+    /// Toggle on the shortcut and draw the popup if it's open, returns the
+    /// command that was picked
+    pub fn show(&mut self, ctx: &Context) -> Option<Command> {
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT)) {
+            self.open = !self.open;
+            self.query.clear();
+            self.selected = 0;
+        }
+        if !self.open {
+            return None;
+        }
+
+        // before the text edit gets to them, so that enter doesn't unfocus it
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if escape {
+            self.open = false;
+            return None;
+        }
+
+        let matches = self.matches();
+        let last = matches.len().saturating_sub(1);
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(last);
+
+        let mut picked = matches
+            .get(self.selected)
+            .filter(|_| enter)
+            .map(|(command, _)| *command);
+
+        Area::new(Id::new("command_palette"))
+            .order(Order::Foreground)
+            .anchor(Align2::CENTER_TOP, vec2(0.0, 40.0))
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(320.0);
+                    let edit = ui.add(
+                        TextEdit::singleline(&mut self.query)
+                            .hint_text(tr("Type a tool or an action"))
+                            .desired_width(f32::INFINITY),
+                    );
+                    edit.request_focus();
+                    if edit.changed() {
+                        self.selected = 0;
+                    }
+
+                    if matches.is_empty() {
+                        ui.weak(tr("Nothing found"));
+                    }
+                    for (i, (command, indices)) in matches.into_iter().enumerate() {
+                        let text = layout_text_with_indices(ui, command.name(), indices, false);
+                        ui.horizontal(|ui| {
+                            if ui.selectable_label(i == self.selected, text).clicked() {
+                                picked = Some(command);
+                            }
+                            ui.add(
+                                Label::new(RichText::new(command.kind()).small().weak())
+                                    .selectable(false),
+                            );
+                        });
+                    }
+                });
+            });
+
+        if picked.is_some() {
+            self.open = false;
+        }
+        picked
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use noita_utility_box::noita::Seed;
+
+use crate::reader::GameState;
+
+/// How many events are kept for the tools that haven't looked at them yet
+const MAX_EVENTS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    NewRun,
+    SeedChanged(Seed),
+    PlayerDied,
+    PolymorphStarted,
+    PolymorphEnded,
+    /// The fungal shift cooldown ran out
+    ShiftReady,
+}
+
+impl GameEvent {
+    /// What happened between two consecutive reads of the game
+    pub fn diff(prev: &GameState, next: &GameState) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+
+        if next.new_run {
+            events.push(GameEvent::NewRun);
+        }
+        if let Some(seed) = next.seed {
+            if prev.seed != Some(seed) {
+                events.push(GameEvent::SeedChanged(seed));
+            }
+        }
+
+        match (&prev.player, &next.player) {
+            (Some(_), None) if next.frame > prev.frame => events.push(GameEvent::PlayerDied),
+            (Some((_, false)), Some((_, true))) => events.push(GameEvent::PolymorphStarted),
+            (Some((_, true)), Some((_, false))) => events.push(GameEvent::PolymorphEnded),
+            _ => {}
+        }
+
+        if matches!(prev.shift_cooldown, Some(1..)) && next.shift_cooldown == Some(0) {
+            events.push(GameEvent::ShiftReady);
+        }
+
+        events
+    }
+}
+
+#[derive(Debug, Default)]
+struct EventLog {
+    next_id: u64,
+    events: VecDeque<(u64, GameEvent)>,
+}
+
+/// Game events published by the background reader, every tool reads them
+/// at its own pace with its own [EventCursor]
+#[derive(Debug, Default, Clone)]
+pub struct EventBus(Arc<Mutex<EventLog>>);
+
+/// Where a tool is in the event stream
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EventCursor(u64);
+
+impl EventBus {
+    pub fn publish(&self, event: GameEvent) {
+        tracing::info!(?event, "Game event");
+        let mut log = self.0.lock().unwrap();
+        let id = log.next_id;
+        log.next_id += 1;
+        log.events.push_back((id, event));
+        if log.events.len() > MAX_EVENTS {
+            log.events.pop_front();
+        }
+    }
+
+    /// Events published since the cursor, moving it past them
+    pub fn poll(&self, cursor: &mut EventCursor) -> Vec<GameEvent> {
+        let log = self.0.lock().unwrap();
+        let events = log
+            .events
+            .iter()
+            .filter(|(id, _)| *id >= cursor.0)
+            .map(|(_, e)| e.clone())
+            .collect();
+        cursor.0 = log.next_id;
+        events
+    }
+}
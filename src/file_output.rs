@@ -0,0 +1,99 @@
+//! Plain text files with the game values, for OBS text sources (or anything
+//! else) to read - no network setup needed.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::reader::GameState;
+
+#[derive(Debug, Clone, Serialize, Deserialize, SmartDefault)]
+#[serde(default)]
+pub struct FileOutputSettings {
+    pub enabled: bool,
+    /// Where the files go, empty for the outputs folder in the storage dir
+    pub dir: String,
+    #[default(true)]
+    pub seed: bool,
+    #[default(true)]
+    pub hp: bool,
+    #[default(true)]
+    pub gold: bool,
+    #[default(true)]
+    pub shift_timer: bool,
+    #[default(true)]
+    pub orbs: bool,
+}
+
+impl FileOutputSettings {
+    pub fn dir(&self) -> Option<PathBuf> {
+        if self.dir.trim().is_empty() {
+            eframe::storage_dir(env!("CARGO_PKG_NAME")).map(|dir| dir.join("outputs"))
+        } else {
+            Some(PathBuf::from(self.dir.trim()))
+        }
+    }
+}
+
+/// Writes a `<name>.txt` file for each enabled value when it changes
+#[derive(Debug, Default)]
+pub struct FileOutput {
+    dir: Option<PathBuf>,
+    written: HashMap<&'static str, String>,
+    pub error: Option<String>,
+}
+
+impl FileOutput {
+    pub fn update(&mut self, settings: &FileOutputSettings, game: &GameState) {
+        if !settings.enabled {
+            self.dir = None;
+            self.written.clear();
+            self.error = None;
+            return;
+        }
+        let Some(dir) = settings.dir() else {
+            self.error = Some("No storage dir".into());
+            return;
+        };
+        if self.dir.as_ref() != Some(&dir) {
+            self.written.clear();
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                self.error = Some(format!("Creating {}: {e}", dir.display()));
+                return;
+            }
+            self.dir = Some(dir.clone());
+        }
+
+        let values = [
+            ("seed", settings.seed, game.seed.map(|s| s.to_string())),
+            ("hp", settings.hp, game.hp_value()),
+            ("gold", settings.gold, game.gold.map(|g| g.to_string())),
+            (
+                "shift_timer",
+                settings.shift_timer,
+                game.shift_timer_value(),
+            ),
+            ("orbs", settings.orbs, game.orbs.map(|n| n.to_string())),
+        ];
+
+        self.error = None;
+        for (name, enabled, value) in values {
+            if !enabled {
+                continue;
+            }
+            // an empty file rather than a stale value when there's no game
+            let value = value.unwrap_or_default();
+            if self.written.get(name) == Some(&value) {
+                continue;
+            }
+            let path = dir.join(format!("{name}.txt"));
+            match std::fs::write(&path, &value) {
+                Ok(()) => {
+                    self.written.insert(name, value);
+                }
+                Err(e) => self.error = Some(format!("Writing {}: {e}", path.display())),
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! UI translations, keyed by the English text itself so that anything not
+//! translated yet just stays in English
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        LazyLock,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum Language {
+    #[default]
+    English,
+    Russian,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::Russian, Language::German];
+
+    /// The name of the language in itself, so that it can be found in the
+    /// list whatever the current one is
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Russian => "Русский",
+            Language::German => "Deutsch",
+        }
+    }
+
+    fn source(self) -> Option<&'static str> {
+        match self {
+            Language::English => None,
+            Language::Russian => Some(include_str!("../res/i18n/ru.json")),
+            Language::German => Some(include_str!("../res/i18n/de.json")),
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        Self::ALL.get(value as usize).copied().unwrap_or_default()
+    }
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+static TABLES: LazyLock<HashMap<Language, HashMap<String, String>>> = LazyLock::new(|| {
+    Language::ALL
+        .into_iter()
+        .filter_map(|lang| {
+            let source = lang.source()?;
+            match serde_json::from_str(source) {
+                Ok(table) => Some((lang, table)),
+                Err(e) => {
+                    tracing::error!(?lang, %e, "Broken translation table");
+                    None
+                }
+            }
+        })
+        .collect()
+});
+
+pub fn language() -> Language {
+    Language::from_u8(CURRENT.load(Ordering::Relaxed))
+}
+
+/// Switch the language of all the [tr] calls, cheap enough to call every
+/// frame
+pub fn set_language(lang: Language) {
+    CURRENT.store(lang as u8, Ordering::Relaxed);
+}
+
+/// The given English UI text in the current language
+pub fn tr(text: &'static str) -> &'static str {
+    lookup(language(), text).unwrap_or(text)
+}
+
+fn lookup(lang: Language, text: &str) -> Option<&'static str> {
+    TABLES.get(&lang)?.get(text).map(|s| s.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_tables_load() {
+        for lang in Language::ALL.into_iter().filter(|l| l.source().is_some()) {
+            let table = TABLES.get(&lang).expect("table failed to parse");
+            assert!(table.values().all(|s| !s.is_empty()), "{lang:?}");
+        }
+    }
+
+    #[test]
+    fn falls_back_to_english() {
+        assert_eq!(lookup(Language::German, "Theme"), Some("Design"));
+        assert_eq!(lookup(Language::German, "Not translated"), None);
+        assert_eq!(lookup(Language::English, "Theme"), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{Ipv4Addr, Shutdown, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use eframe::egui::{self, ViewportCommand};
+
+use crate::cli::Args;
+
+// arbitrary, just needs to be stable between versions
+const ADDR: (Ipv4Addr, u16) = (Ipv4Addr::LOCALHOST, 47613);
+const MAGIC: &str = "noita-utility-box";
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+pub enum Instance {
+    /// We're the first one, listen for the arguments of later launches.
+    ///
+    /// They have to send the token we put in the storage dir, so that only
+    /// the same user can talk to us, anything can connect to localhost
+    Primary(TcpListener, io::Result<String>),
+    /// Arguments were handed to an already running instance
+    Forwarded,
+    /// Something else is using the port, or the running instance did not
+    /// answer, just run as is
+    Standalone(anyhow::Error),
+}
+
+/// Rewritten by each primary instance
+fn token_path() -> Option<PathBuf> {
+    eframe::storage_dir(env!("CARGO_PKG_NAME")).map(|dir| dir.join("instance-token"))
+}
+
+fn write_token() -> io::Result<String> {
+    let path =
+        token_path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No storage dir"))?;
+    let token = format!("{:016x}{:016x}", fastrand::u64(..), fastrand::u64(..));
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, &token)?;
+    Ok(token)
+}
+
+impl Instance {
+    /// Check for a running instance and give it our arguments if there is one
+    pub fn acquire(args: &[String]) -> Self {
+        if let Ok(listener) = TcpListener::bind(ADDR) {
+            return Self::Primary(listener, write_token());
+        }
+        match forward(args) {
+            Ok(()) => Self::Forwarded,
+            Err(e) => Self::Standalone(e),
+        }
+    }
+
+    /// Start accepting forwarded arguments, if we're the primary instance.
+    /// Also logs why we're not, as this runs after the logging is set up
+    pub fn listen(self, ctx: &egui::Context) -> Option<Receiver<Args>> {
+        let (listener, token) = match self {
+            Self::Primary(listener, Ok(token)) => (listener, token),
+            Self::Primary(_, Err(e)) => {
+                tracing::warn!(%e, "Failed to write the instance token, not accepting forwarded launches");
+                return None;
+            }
+            Self::Forwarded => return None,
+            Self::Standalone(e) => {
+                tracing::warn!("Failed to reach the running instance, started a new one: {e:#}");
+                return None;
+            }
+        };
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let received = stream
+                    .map_err(Into::into)
+                    .and_then(|stream| receive(stream, &token));
+                let args = match received {
+                    Ok(args) => args,
+                    Err(e) => {
+                        tracing::warn!("Bad forwarded launch: {e:#}");
+                        continue;
+                    }
+                };
+                tracing::info!(?args, "Another launch forwarded its arguments");
+                if tx.send(args).is_err() {
+                    break;
+                }
+                ctx.send_viewport_cmd(ViewportCommand::Minimized(false));
+                ctx.send_viewport_cmd(ViewportCommand::Focus);
+                ctx.request_repaint();
+            }
+        });
+        Some(rx)
+    }
+}
+
+fn forward(args: &[String]) -> anyhow::Result<()> {
+    let path = token_path().context("No storage dir")?;
+    let token = std::fs::read_to_string(&path)
+        .with_context(|| format!("Reading the instance token from {}", path.display()))?;
+
+    let mut stream = TcpStream::connect_timeout(&ADDR.into(), TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    writeln!(stream, "{MAGIC}")?;
+    writeln!(stream, "{}", token.trim())?;
+    for arg in args {
+        writeln!(stream, "{arg}")?;
+    }
+    stream.flush()?;
+    stream.shutdown(Shutdown::Write)?;
+
+    // and only the running instance knows to answer
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+    anyhow::ensure!(
+        reply.trim() == MAGIC,
+        "Something else is listening on the port"
+    );
+    Ok(())
+}
+
+fn receive(stream: TcpStream, token: &str) -> anyhow::Result<Args> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut lines = BufReader::new(&stream).lines();
+    match lines.next() {
+        Some(Ok(magic)) if magic == MAGIC => {}
+        _ => anyhow::bail!("Not a noita-utility-box launch"),
+    }
+    match lines.next() {
+        Some(Ok(sent)) if sent == token => {}
+        _ => anyhow::bail!("Wrong instance token"),
+    }
+    let args = Args::parse(lines.collect::<io::Result<Vec<_>>>()?)?;
+    writeln!(&stream, "{MAGIC}")?;
+    Ok(args)
+}
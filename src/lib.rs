@@ -1,3 +1,4 @@
 pub mod memory;
 pub mod noita;
+pub mod plugin;
 pub mod util;
@@ -0,0 +1,156 @@
+//! A client for the LiveSplit Server component, so that the run timer can
+//! drive an existing LiveSplit layout.
+//!
+//! The protocol is a line of text per command over TCP, with no replies for
+//! the ones we send. The connection lives on a thread of its own and is
+//! (re)opened lazily when there's something to send.
+//!
+//! Game time updates are only worth sending while they're fresh, so the
+//! queued ones are dropped when a newer one is behind them, and they don't
+//! reconnect after a failure - the next start, split or reset does.
+
+use std::{
+    io::{self, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// The default port of the LiveSplit Server component
+pub const DEFAULT_ADDRESS: &str = "localhost:16834";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Start,
+    Split,
+    Reset,
+    /// In seconds, LiveSplit should be comparing against game time
+    GameTime(f64),
+}
+
+impl Command {
+    fn lines(self) -> Vec<String> {
+        match self {
+            // game time is paused so that it only moves when we set it
+            Command::Start => vec![
+                "reset".into(),
+                "starttimer".into(),
+                "initgametime".into(),
+                "pausegametime".into(),
+            ],
+            Command::Split => vec!["split".into()],
+            Command::Reset => vec!["reset".into()],
+            Command::GameTime(secs) => vec![format!("setgametime {secs:.3}")],
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Status {
+    #[default]
+    Idle,
+    Connected,
+    Error(String),
+}
+
+#[derive(Debug)]
+pub struct LiveSplit {
+    address: String,
+    tx: Sender<Command>,
+    status: Arc<Mutex<Status>>,
+    /// How many [Command::GameTime] are in the channel
+    queued_times: Arc<AtomicUsize>,
+}
+
+impl LiveSplit {
+    /// Start the connection thread, it stops when this is dropped
+    pub fn new(address: &str) -> Self {
+        let (tx, rx) = mpsc::channel::<Command>();
+        let status = Arc::new(Mutex::new(Status::Idle));
+        let queued_times = Arc::new(AtomicUsize::new(0));
+
+        let thread_status = status.clone();
+        let thread_queued_times = queued_times.clone();
+        let thread_address = address.to_owned();
+        let spawned = thread::Builder::new()
+            .name("livesplit".into())
+            .spawn(move || {
+                let mut stream = None;
+                for command in rx {
+                    if let Command::GameTime(_) = command {
+                        // a newer one is queued, or it'd be a reconnect
+                        // attempt per game time while LiveSplit is closed
+                        let stale = thread_queued_times.fetch_sub(1, Ordering::Relaxed) > 1;
+                        let disconnected =
+                            matches!(*thread_status.lock().unwrap(), Status::Error(_));
+                        if stale || disconnected {
+                            continue;
+                        }
+                    }
+                    let result = send(&mut stream, &thread_address, command);
+                    let new_status = match result {
+                        Ok(()) => Status::Connected,
+                        Err(e) => {
+                            tracing::debug!(%e, ?command, "LiveSplit command failed");
+                            stream = None;
+                            Status::Error(e.to_string())
+                        }
+                    };
+                    *thread_status.lock().unwrap() = new_status;
+                }
+            });
+        if let Err(e) = spawned {
+            tracing::warn!(%e, "Failed to spawn the LiveSplit thread");
+        }
+
+        Self {
+            address: address.to_owned(),
+            tx,
+            status,
+            queued_times,
+        }
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn send(&self, command: Command) {
+        if let Command::GameTime(_) = command {
+            self.queued_times.fetch_add(1, Ordering::Relaxed);
+        }
+        // if the thread is gone, we already logged why
+        let _ = self.tx.send(command);
+    }
+
+    pub fn status(&self) -> Status {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+fn send(stream: &mut Option<TcpStream>, address: &str, command: Command) -> io::Result<()> {
+    let stream = match stream {
+        Some(stream) => stream,
+        None => {
+            let addr = address
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Bad address"))?;
+            let new = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+            new.set_nodelay(true)?;
+            stream.insert(new)
+        }
+    };
+    for line in command.lines() {
+        stream.write_all(line.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+    }
+    stream.flush()
+}
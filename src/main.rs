@@ -2,6 +2,8 @@
 
 use anyhow::{anyhow, Context, Result};
 use app::NoitaUtilityBox;
+use cli::Args;
+use instance::Instance;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{
     fmt::{self, format::FmtSpan},
@@ -10,10 +12,32 @@ use tracing_subscriber::{
 };
 
 mod app;
+mod cli;
+mod command_palette;
+mod events;
+mod file_output;
+mod i18n;
+mod instance;
+mod livesplit;
+mod mini_mode;
 mod orb_searcher;
+mod overlay;
+mod palette;
+mod perf;
+mod profiles;
+mod reader;
+mod readout;
+mod screenshot;
+mod scripting;
+mod server;
+mod settings_file;
+mod sound;
+mod theme;
 mod tools;
+mod trail;
 mod update_check;
 mod util;
+mod window_placement;
 
 fn setup_logging() -> Result<WorkerGuard> {
     let storage_dir = eframe::storage_dir(env!("CARGO_PKG_NAME")).context("No storage dir")?;
@@ -47,9 +71,22 @@ fn setup_logging() -> Result<WorkerGuard> {
 fn main() -> Result<()> {
     color_eyre::install().unwrap();
 
+    let raw_args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = Args::parse(raw_args.clone())?;
+    if args.help {
+        println!("{}", cli::usage());
+        return Ok(());
+    }
+
+    // before logging setup so that we don't truncate the log of the running one
+    let instance = Instance::acquire(&raw_args);
+    if let Instance::Forwarded = instance {
+        return Ok(());
+    }
+
     let _guard = setup_logging()?;
 
-    NoitaUtilityBox::run().map_err(|e| anyhow!("{e:#}"))?;
+    NoitaUtilityBox::run(args, instance).map_err(|e| anyhow!("{e:#}"))?;
 
     Ok(())
 }
@@ -0,0 +1,416 @@
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    marker::PhantomData,
+};
+
+use super::*;
+
+#[derive(Debug, FromBytes, IntoBytes)]
+#[repr(C, packed)]
+pub struct StdListNode<T> {
+    next: Ptr<StdListNode<T>>,
+    prev: Ptr<StdListNode<T>>,
+    value: T,
+}
+
+/// MSVC `std::list`, a doubly linked list with a sentinel head node
+#[derive(FromBytes, IntoBytes)]
+#[repr(C, packed)]
+pub struct StdList<T> {
+    head: Ptr<StdListNode<T>>,
+    len: TargetSize,
+}
+
+impl<T> Clone for StdList<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for StdList<T> {}
+
+impl<T> StdList<T> {
+    pub fn len(&self) -> TargetSize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T> Debug for StdList<T>
+where
+    T: MemoryStorage,
+    T::Value: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.len() < 4096 {
+            if let Some(s) =
+                DEBUG_PROCESS.with_borrow(|proc| proc.as_ref().and_then(|h| self.read(h).ok()))
+            {
+                return Debug::fmt(&s, f);
+            }
+        }
+        write!(f, "StdList[{} * {}]", self.len(), debug_type::<T>())
+    }
+}
+
+impl<T: MemoryStorage> MemoryStorage for StdList<T> {
+    type Value = Vec<T::Value>;
+
+    fn read(&self, proc: &ProcessRef) -> io::Result<Self::Value> {
+        let mut result = Vec::with_capacity(self.len as _);
+        let head_ptr = self.head;
+        let mut node_ptr = head_ptr.read(proc)?.next;
+
+        // bounded by len in case we're reading garbage
+        while node_ptr != head_ptr && !node_ptr.is_null() && result.len() < self.len as usize {
+            let node = node_ptr.read(proc)?;
+            result.push({ node.value }.read(proc)?);
+            node_ptr = node.next;
+        }
+        Ok(result)
+    }
+}
+
+/// MSVC `std::deque` - a ring buffer of pointers to fixed size blocks
+#[derive(FromBytes, IntoBytes)]
+#[repr(C, packed)]
+pub struct StdDeque<T> {
+    map: Ptr<RawPtr>,
+    map_size: TargetSize,
+    offset: TargetSize,
+    len: TargetSize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Clone for StdDeque<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for StdDeque<T> {}
+
+impl<T> StdDeque<T> {
+    /// Elements per block, the `_DEQUESIZ` thing from the MSVC STL
+    const BLOCK_SIZE: TargetSize = match size_of::<T>() {
+        0..=1 => 16,
+        2 => 8,
+        3..=4 => 4,
+        5..=8 => 2,
+        _ => 1,
+    };
+
+    pub fn len(&self) -> TargetSize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The block (index into the map) and the index inside of that block
+    fn locate(&self, index: TargetSize) -> (TargetSize, TargetSize) {
+        let offset = self.offset + index;
+        let block = (offset / Self::BLOCK_SIZE) & (self.map_size - 1);
+        (block, offset % Self::BLOCK_SIZE)
+    }
+}
+
+impl<T> Debug for StdDeque<T>
+where
+    T: MemoryStorage,
+    T::Value: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.len() < 4096 {
+            if let Some(s) =
+                DEBUG_PROCESS.with_borrow(|proc| proc.as_ref().and_then(|h| self.read(h).ok()))
+            {
+                return Debug::fmt(&s, f);
+            }
+        }
+        write!(f, "StdDeque[{} * {}]", self.len(), debug_type::<T>())
+    }
+}
+
+impl<T: MemoryStorage> MemoryStorage for StdDeque<T> {
+    type Value = Vec<T::Value>;
+
+    fn read(&self, proc: &ProcessRef) -> io::Result<Self::Value> {
+        if self.len == 0 || !self.map_size.is_power_of_two() {
+            return Ok(Vec::new());
+        }
+        let map = proc.read_multiple::<RawPtr>(self.map.addr(), self.map_size)?;
+
+        // read each used block once
+        let mut blocks = BTreeMap::new();
+        let mut result = Vec::with_capacity(self.len as _);
+        for i in 0..self.len {
+            let (block, idx) = self.locate(i);
+            let items = match blocks.entry(block) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    e.insert(map[block as usize].read_multiple::<T>(proc, Self::BLOCK_SIZE)?)
+                }
+            };
+            result.push(items[idx as usize].read(proc)?);
+        }
+        Ok(result)
+    }
+}
+
+#[derive(Debug, FromBytes, IntoBytes)]
+#[repr(C, packed)]
+pub struct StdPair<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+/// MSVC `std::unordered_map` - all the elements are in a `std::list`, and
+/// the buckets are (first, last) iterator pairs into that list
+#[derive(FromBytes, IntoBytes)]
+#[repr(C, packed)]
+pub struct StdUnorderedMap<K, V> {
+    max_load_factor: f32,
+    list: StdList<StdPair<K, V>>,
+    buckets: StdVec<Ptr<StdListNode<StdPair<K, V>>>>,
+    mask: TargetSize,
+    max_idx: TargetSize,
+}
+assert_layout!(StdUnorderedMap<u32, u32>: 0x20);
+
+impl<K, V> Clone for StdUnorderedMap<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<K, V> Copy for StdUnorderedMap<K, V> {}
+
+impl<K, V> StdUnorderedMap<K, V> {
+    pub fn len(&self) -> TargetSize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+}
+
+impl<K, V> Debug for StdUnorderedMap<K, V>
+where
+    K: MemoryStorage,
+    V: MemoryStorage,
+    K::Value: Eq + Hash + Debug,
+    V::Value: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.len() < 512 {
+            if let Some(s) =
+                DEBUG_PROCESS.with_borrow(|proc| proc.as_ref().and_then(|h| self.read(h).ok()))
+            {
+                return Debug::fmt(&s, f);
+            }
+        }
+        write!(
+            f,
+            "StdUnorderedMap[{} * ({} => {})]",
+            self.len(),
+            debug_type::<K>(),
+            debug_type::<V>()
+        )
+    }
+}
+
+impl<K, V> MemoryStorage for StdUnorderedMap<K, V>
+where
+    K: MemoryStorage,
+    K::Value: Eq + Hash,
+    V: MemoryStorage,
+{
+    type Value = HashMap<K::Value, V::Value>;
+
+    fn read(&self, proc: &ProcessRef) -> io::Result<Self::Value> {
+        let list = self.list;
+        let mut result = HashMap::with_capacity(list.len() as _);
+        let head_ptr = list.head;
+        let mut node_ptr = head_ptr.read(proc)?.next;
+
+        while node_ptr != head_ptr && !node_ptr.is_null() && result.len() < list.len() as usize {
+            let node = node_ptr.read(proc)?;
+            let StdPair { key, value } = node.value;
+            result.insert(key.read(proc)?, value.read(proc)?);
+            node_ptr = node.next;
+        }
+        Ok(result)
+    }
+}
+
+impl<K: MemoryStorage, V> StdUnorderedMap<K, V> {
+    #[track_caller]
+    pub fn get<Q>(&self, proc: &ProcessRef, key: &Q) -> io::Result<Option<V::Value>>
+    where
+        V: MemoryStorage,
+        Q: MsvcHash + PartialEq + ?Sized,
+        K::Value: Borrow<Q>,
+    {
+        self.get_raw(proc, key)?.map(|v| v.read(proc)).transpose()
+    }
+
+    /// Only reads the one bucket the key hashes into
+    #[track_caller]
+    pub fn get_raw<Q>(&self, proc: &ProcessRef, key: &Q) -> io::Result<Option<V>>
+    where
+        V: Pod,
+        Q: MsvcHash + PartialEq + ?Sized,
+        K::Value: Borrow<Q>,
+    {
+        let bucket = key.msvc_hash() & self.mask;
+        let buckets = self.buckets;
+        let (Some(first), Some(last)) = (
+            buckets.read_at(bucket * 2, proc)?,
+            buckets.read_at(bucket * 2 + 1, proc)?,
+        ) else {
+            return Ok(None);
+        };
+        // empty buckets point at the list end
+        let end = self.list.head;
+        if first == end || first.is_null() {
+            return Ok(None);
+        }
+
+        let mut node_ptr = first;
+        for _ in 0..self.len() {
+            let node = node_ptr.read(proc)?;
+            let StdPair {
+                key: node_key,
+                value,
+            } = node.value;
+            if node_key.read(proc)?.borrow() == key {
+                return Ok(Some(value));
+            }
+            let next = node.next;
+            if node_ptr == last || next == end {
+                break;
+            }
+            node_ptr = next;
+        }
+        Ok(None)
+    }
+}
+
+/// The MSVC `std::hash` - 32-bit FNV-1a over the bytes of the value
+pub trait MsvcHash {
+    fn msvc_hash(&self) -> u32;
+}
+
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, b| {
+        (hash ^ *b as u32).wrapping_mul(0x01000193)
+    })
+}
+
+impl MsvcHash for str {
+    fn msvc_hash(&self) -> u32 {
+        fnv1a(self.as_bytes())
+    }
+}
+
+impl MsvcHash for String {
+    fn msvc_hash(&self) -> u32 {
+        self.as_str().msvc_hash()
+    }
+}
+
+macro_rules! msvc_hash_primitives {
+    ($($t:ty),*) => {
+        $(
+            impl MsvcHash for $t {
+                fn msvc_hash(&self) -> u32 {
+                    fnv1a(&self.to_le_bytes())
+                }
+            }
+        )*
+    };
+}
+
+msvc_hash_primitives!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+#[cfg(test)]
+#[test]
+fn std_list() -> io::Result<()> {
+    // the nodes are (next, prev, value), the head is the sentinel
+    let proc = MockProcess::new(1)
+        .with(0x1000, &[0x2000u32, 3])
+        .with(0x2000, &[0x2010u32, 0x2030, 0])
+        .with(0x2010, &[0x2020u32, 0x2000, 10])
+        .with(0x2020, &[0x2030u32, 0x2010, 20])
+        .with(0x2030, &[0x2000u32, 0x2020, 30])
+        .into_process();
+
+    let list = Ptr::<StdList<u32>>::of(0x1000).read(&proc)?;
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.read(&proc)?, [10, 20, 30]);
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn std_deque() -> io::Result<()> {
+    // 4 u32s per block, starts in the middle of the last block of the map
+    // and wraps around to the first one
+    let proc = MockProcess::new(1)
+        .with(0x1000, &[0x3000u32, 4, 13, 6])
+        .with(0x3000, &[0x4000u32, 0x4010, 0x4020, 0x4030])
+        .with(0x4000, &[4u32, 5, 6, 0xdead])
+        .with(0x4030, &[0xdeadu32, 1, 2, 3])
+        .into_process();
+
+    let deque = Ptr::<StdDeque<u32>>::of(0x1000).read(&proc)?;
+    assert_eq!(StdDeque::<u32>::BLOCK_SIZE, 4);
+    assert_eq!(deque.locate(0), (3, 1));
+    assert_eq!(deque.locate(3), (0, 0));
+    assert_eq!(deque.read(&proc)?, [1, 2, 3, 4, 5, 6]);
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn std_unordered_map() -> io::Result<()> {
+    // 8 buckets, 1 and 9 collide in bucket 4 and 2 is alone in bucket 7
+    assert_eq!(
+        [1u32, 9, 2, 17, 5].map(|k| k.msvc_hash() & 7),
+        [4, 4, 7, 4, 0]
+    );
+
+    let mut buckets = [0x2000u32; 16];
+    buckets[8..10].copy_from_slice(&[0x2010, 0x2020]);
+    buckets[14..16].copy_from_slice(&[0x2030, 0x2030]);
+
+    let proc = MockProcess::new(1)
+        .with(
+            0x1000,
+            &[1f32.to_bits(), 0x2000, 3, 0x3000, 0x3040, 0x3040, 7, 8],
+        )
+        // the nodes are (next, prev, key, value)
+        .with(0x2000, &[0x2010u32, 0x2030, 0, 0])
+        .with(0x2010, &[0x2020u32, 0x2000, 1, 100])
+        .with(0x2020, &[0x2030u32, 0x2010, 9, 900])
+        .with(0x2030, &[0x2000u32, 0x2020, 2, 200])
+        .with(0x3000, &buckets)
+        .into_process();
+
+    let map = Ptr::<StdUnorderedMap<u32, u32>>::of(0x1000).read(&proc)?;
+    assert_eq!(
+        map.read(&proc)?,
+        HashMap::from([(1, 100), (9, 900), (2, 200)])
+    );
+    assert_eq!(map.get(&proc, &1u32)?, Some(100));
+    assert_eq!(map.get(&proc, &9u32)?, Some(900));
+    assert_eq!(map.get(&proc, &2u32)?, Some(200));
+    // the walk stops at the last node of the bucket
+    assert_eq!(map.get(&proc, &17u32)?, None);
+    // and empty buckets point at the head
+    assert_eq!(map.get(&proc, &5u32)?, None);
+    Ok(())
+}
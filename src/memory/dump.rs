@@ -0,0 +1,240 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use super::{ProcessMemory, ProcessRef};
+
+const MAGIC: &[u8; 8] = b"NUBDUMP1";
+
+/// Non-overlapping byte regions, merged when new ones touch existing ones
+#[derive(Debug, Default, Clone)]
+struct Regions(BTreeMap<u32, Vec<u8>>);
+
+impl Regions {
+    /// False if the region runs past the end of the address space, then
+    /// it's not added
+    fn insert(&mut self, addr: u32, bytes: &[u8]) -> bool {
+        let Some(end) = u32::try_from(bytes.len())
+            .ok()
+            .and_then(|len| addr.checked_add(len))
+        else {
+            return false;
+        };
+
+        // all the regions that overlap or touch the new one
+        let touching = self
+            .0
+            .range(..=end)
+            .rev()
+            .take_while(|(start, b)| **start + b.len() as u32 >= addr)
+            .map(|(start, _)| *start)
+            .collect::<Vec<_>>();
+
+        let start = touching.last().map_or(addr, |s| (*s).min(addr));
+        let mut merged = Vec::new();
+        for s in touching.into_iter().rev() {
+            let old = self.0.remove(&s).unwrap();
+            let offset = (s - start) as usize;
+            if merged.len() < offset + old.len() {
+                merged.resize(offset + old.len(), 0);
+            }
+            merged[offset..offset + old.len()].copy_from_slice(&old);
+        }
+        let offset = (addr - start) as usize;
+        if merged.len() < offset + bytes.len() {
+            merged.resize(offset + bytes.len(), 0);
+        }
+        // newer bytes win
+        merged[offset..offset + bytes.len()].copy_from_slice(bytes);
+        self.0.insert(start, merged);
+        true
+    }
+
+    fn get(&self, addr: usize, len: usize) -> Option<&[u8]> {
+        let addr = u32::try_from(addr).ok()?;
+        let (start, bytes) = self.0.range(..=addr).next_back()?;
+        let offset = (addr - start) as usize;
+        bytes.get(offset..offset + len)
+    }
+}
+
+/// Memory of a process saved to a file, which can be opened as a
+/// [ProcessRef] later - for reproducing issues without the game (or the
+/// exact game build).
+///
+/// Since the interesting data is all over the heap, a dump is made by
+/// recording everything that was read from the process while it was
+/// running, see [DumpRecorder]
+#[derive(Debug, Clone)]
+pub struct MemoryDump {
+    pid: u32,
+    regions: Regions,
+}
+
+impl MemoryDump {
+    pub fn regions(&self) -> usize {
+        self.regions.0.len()
+    }
+
+    pub fn size(&self) -> usize {
+        self.regions.0.values().map(Vec::len).sum()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut out = Vec::with_capacity(self.size() + self.regions() * 8 + 16);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&self.pid.to_le_bytes());
+        out.extend_from_slice(&(self.regions() as u32).to_le_bytes());
+        for (addr, bytes) in &self.regions.0 {
+            out.extend_from_slice(&addr.to_le_bytes());
+            out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            out.extend_from_slice(bytes);
+        }
+        fs::write(path, out)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let mut rest = data
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Not a memory dump file"))?;
+        fn take<'a>(rest: &mut &'a [u8], n: usize) -> io::Result<&'a [u8]> {
+            let (head, tail) = rest.split_at_checked(n).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Truncated memory dump")
+            })?;
+            *rest = tail;
+            Ok(head)
+        }
+        fn u32(rest: &mut &[u8]) -> io::Result<u32> {
+            Ok(u32::from_le_bytes(take(rest, 4)?.try_into().unwrap()))
+        }
+
+        let pid = u32(&mut rest)?;
+        let count = u32(&mut rest)?;
+        let mut regions = Regions::default();
+        for _ in 0..count {
+            let addr = u32(&mut rest)?;
+            let len = u32(&mut rest)?;
+            if !regions.insert(addr, take(&mut rest, len as usize)?) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Memory dump region at 0x{addr:x} (+{len}) is out of bounds"),
+                ));
+            }
+        }
+        Ok(Self { pid, regions })
+    }
+
+    pub fn into_process(self) -> ProcessRef {
+        ProcessRef::from_memory(self)
+    }
+}
+
+impl ProcessMemory for MemoryDump {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn read_memory(&self, addr: usize, buf: &mut [u8]) -> io::Result<()> {
+        let bytes = self.regions.get(addr, buf.len()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("0x{addr:x} (+{}) was not recorded in the dump", buf.len()),
+            )
+        })?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn write_memory(&self, _addr: usize, _buf: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Memory dumps are read-only",
+        ))
+    }
+}
+
+/// Passes everything through to a live process while remembering all the
+/// successful reads, to make a [MemoryDump] of them
+#[derive(Debug, Clone)]
+pub struct DumpRecorder {
+    inner: ProcessRef,
+    recorded: Arc<Mutex<Regions>>,
+}
+
+impl DumpRecorder {
+    pub fn new(inner: ProcessRef) -> Self {
+        Self {
+            inner,
+            recorded: Default::default(),
+        }
+    }
+
+    /// What was recorded so far
+    pub fn dump(&self) -> MemoryDump {
+        MemoryDump {
+            pid: self.inner.pid(),
+            regions: self.recorded.lock().unwrap().clone(),
+        }
+    }
+}
+
+impl ProcessMemory for DumpRecorder {
+    fn pid(&self) -> u32 {
+        self.inner.pid()
+    }
+
+    fn read_memory(&self, addr: usize, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.0.read_memory(addr, buf)?;
+        if let Ok(addr) = u32::try_from(addr) {
+            self.recorded.lock().unwrap().insert(addr, buf);
+        }
+        Ok(())
+    }
+
+    fn read_scattered(&self, regions: &mut [(usize, &mut [u8])]) -> io::Result<()> {
+        self.inner.0.read_scattered(regions)?;
+        let mut recorded = self.recorded.lock().unwrap();
+        for (addr, buf) in regions {
+            if let Ok(addr) = u32::try_from(*addr) {
+                recorded.insert(addr, buf);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_memory(&self, addr: usize, buf: &[u8]) -> io::Result<()> {
+        self.inner.0.write_memory(addr, buf)
+    }
+
+    fn is_alive(&self) -> bool {
+        self.inner.0.is_alive()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn steam_compat_data_path(&self) -> &str {
+        self.inner.0.steam_compat_data_path()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn regions_merge() {
+    let mut regions = Regions::default();
+    regions.insert(0x10, &[1, 2, 3, 4]);
+    regions.insert(0x18, &[9, 9]);
+    regions.insert(0x12, &[5, 6, 7, 8, 0, 0]);
+    assert_eq!(regions.0.len(), 1);
+    assert_eq!(
+        regions.get(0x10, 10),
+        Some(&[1, 2, 5, 6, 7, 8, 0, 0, 9, 9][..])
+    );
+    assert_eq!(regions.get(0x10, 11), None);
+
+    assert!(!regions.insert(u32::MAX - 1, &[1, 2, 3]));
+    assert!(regions.insert(u32::MAX - 1, &[1]));
+    assert_eq!(regions.0.len(), 2);
+}
@@ -2,11 +2,39 @@ use std::{ffi::CStr, io, ops::Range};
 
 use iced_x86::{Code, Decoder, DecoderOptions, Instruction};
 use memchr::memmem;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use thiserror::Error;
 use zerocopy::{FromBytes, IntoBytes};
 
 use crate::memory::{Ibo, MemoryStorage, ProcessRef};
 
+/// How much of the image each thread of [par_scan] looks through at once
+const SCAN_CHUNK: usize = 1 << 20;
+
+/// Run the search over chunks of the haystack in parallel, returning the
+/// first match position. The chunks overlap by `window - 1` bytes, so
+/// anything at most `window` bytes long is found even if it crosses a
+/// chunk boundary
+pub fn par_scan(
+    haystack: &[u8],
+    window: usize,
+    find: impl Fn(&[u8]) -> Option<usize> + Sync,
+) -> Option<usize> {
+    (0..haystack.len().div_ceil(SCAN_CHUNK))
+        .into_par_iter()
+        .find_map_first(|i| {
+            let start = i * SCAN_CHUNK;
+            let end = (start + SCAN_CHUNK + window.saturating_sub(1)).min(haystack.len());
+            find(&haystack[start..end]).map(|pos| start + pos)
+        })
+}
+
+/// Parallel [memmem::find]
+pub fn par_find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let finder = memmem::Finder::new(needle);
+    par_scan(haystack, needle.len(), |chunk| finder.find(chunk))
+}
+
 #[derive(Error, Debug)]
 pub enum ReadImageError {
     #[error("No MZ header, not win32")]
@@ -92,6 +120,39 @@ impl PeHeader {
         &self.export_name
     }
 
+    pub fn image_base(&self) -> u32 {
+        self.image_base
+    }
+
+    /// A hash of everything in the header, to tell apart exes that happen to
+    /// have the same timestamp without reading the whole image. Stable
+    /// across runs, so it can be used as a key on disk
+    pub fn fingerprint(&self) -> u64 {
+        // FNV-1a, std hashers are not guaranteed to be stable
+        let mut hash = 0xcbf29ce484222325u64;
+        let fields = [
+            self.timestamp,
+            self.image_base,
+            self.size_of_image,
+            self.text.start as u32,
+            self.text.end as u32,
+            self.rdata.start as u32,
+            self.rdata.end as u32,
+        ];
+        let bytes = fields.iter().flat_map(|f| f.to_le_bytes());
+        for b in bytes.chain(self.export_name.iter().copied()) {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    /// Everything after the code - all the (mutable or not) data sections,
+    /// which is where the static globals live. Absolute addresses.
+    pub fn static_data(&self) -> Range<u32> {
+        self.image_base + self.rdata.start as u32..self.image_base + self.size_of_image
+    }
+
     pub fn read(proc: &ProcessRef) -> Result<Self, ReadImageError> {
         let dos_header = Ibo::<DosHeaderData>::of(0x0).read(proc)?;
         if dos_header.magic != *b"MZ" {
@@ -235,6 +296,12 @@ impl ExeImage {
         &self.header
     }
 
+    /// Absolute addresses of the code
+    pub fn text_addrs(&self) -> Range<u32> {
+        let start = self.header.image_base + self.header.text.start as u32;
+        start..start + self.header.text.len() as u32
+    }
+
     /// Find the program address of the given C string in rdata
     pub fn find_string(&self, needle: &CStr) -> Option<u32> {
         // if let Some(&res) = self.cached_strings.get(needle.to_bytes_with_nul()) {
@@ -242,7 +309,7 @@ impl ExeImage {
         //     return Some(res);
         // }
 
-        let res = memmem::find(self.rdata(), needle.to_bytes_with_nul())
+        let res = par_find(self.rdata(), needle.to_bytes_with_nul())
             .map(|pos| (pos + self.header.rdata.start + self.header.image_base as usize) as u32);
         if let Some(res) = res {
             tracing::debug!("Found string {needle:?} at 0x{res:x}");
@@ -260,7 +327,7 @@ impl ExeImage {
         // }
 
         let [a, b, c, d] = self.find_string(needle)?.to_le_bytes();
-        let res = memmem::find(self.text(), &[0x68, a, b, c, d]);
+        let res = par_find(self.text(), &[0x68, a, b, c, d]);
 
         if let Some(res) = res {
             tracing::debug!("Found PUSH {needle:?} at offset 0x{res:x}",);
@@ -287,3 +354,37 @@ impl ExeImage {
         })
     }
 }
+
+#[cfg(test)]
+impl ExeImage {
+    /// A made up image with the code at 0x1000 past the image base and the
+    /// data right after it, for testing the scanners
+    pub(crate) fn synthetic(image_base: u32, text: &[u8], rdata: &[u8]) -> Self {
+        let mut image = vec![0; 0x1000];
+        image.extend_from_slice(text);
+        image.extend_from_slice(rdata);
+        let text = 0x1000..0x1000 + text.len();
+        let rdata = text.end..image.len();
+        Self {
+            header: PeHeader {
+                timestamp: 0,
+                export_name: b"noita.exe\0".to_vec(),
+                text,
+                rdata,
+                image_base,
+                size_of_image: image.len() as u32,
+            },
+            image,
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn chunked_find() {
+    let mut haystack = vec![0; SCAN_CHUNK * 3];
+    haystack[SCAN_CHUNK * 2 - 2..SCAN_CHUNK * 2 + 2].copy_from_slice(b"noit");
+    assert_eq!(par_find(&haystack, b"noit"), Some(SCAN_CHUNK * 2 - 2));
+    assert_eq!(par_find(&haystack, b"noita"), None);
+    assert_eq!(par_find(&haystack[..10], b"\0\0"), Some(0));
+}
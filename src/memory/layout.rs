@@ -0,0 +1,270 @@
+use std::{collections::HashMap, fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use zerocopy::FromBytes;
+
+use super::{Addr, ByteBool, MemoryStorage, ProcessRef, RawPtr, StdString, PTR_SIZE};
+
+/// Struct layouts loaded from a JSON file at runtime, for when the game
+/// moves a field around and the compiled-in structs are wrong until the
+/// next release - a community layout file can be applied instead.
+///
+/// ```json
+/// {
+///   "structs": {
+///     "DamageModelComponent": {
+///       "size": 660,
+///       "fields": [
+///         { "name": "hp", "offset": 0, "type": "f64" },
+///         { "name": "max_hp", "offset": 8, "type": "f64" }
+///       ]
+///     }
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayoutFile {
+    pub structs: HashMap<String, StructLayout>,
+}
+
+impl LayoutFile {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_slice(&fs::read(path)?)
+    }
+
+    pub fn from_slice(json: &[u8]) -> io::Result<Self> {
+        let file: Self = serde_json::from_slice(json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        for (name, layout) in &file.structs {
+            layout
+                .validate()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{name}: {e}")))?;
+        }
+        Ok(file)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StructLayout> {
+        self.structs.get(name)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StructLayout {
+    /// How many bytes to read, defaults to the end of the last field
+    #[serde(default)]
+    pub size: Option<u32>,
+    pub fields: Vec<FieldLayout>,
+}
+
+impl StructLayout {
+    pub fn size(&self) -> u32 {
+        self.size.unwrap_or_else(|| {
+            self.fields
+                .iter()
+                // overflowing fields are rejected by validate
+                .map(|f| f.end().unwrap_or(u32::MAX))
+                .max()
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldLayout> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(f) = self.fields.iter().find(|f| f.end().is_none()) {
+            return Err(format!(
+                "field {} at 0x{:x} is past the address space",
+                f.name, f.offset
+            ));
+        }
+        let size = self.size();
+        match self
+            .fields
+            .iter()
+            .find(|f| f.end().is_some_and(|end| end > size))
+        {
+            Some(f) => Err(format!(
+                "field {} is past the struct size 0x{size:x}",
+                f.name
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldLayout {
+    pub name: String,
+    pub offset: u32,
+    #[serde(rename = "type")]
+    pub ty: FieldType,
+}
+
+impl FieldLayout {
+    /// The offset right after the field, None if it overflows
+    pub fn end(&self) -> Option<u32> {
+        self.offset.checked_add(self.ty.size())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    U8,
+    U16,
+    U32,
+    I32,
+    F32,
+    F64,
+    Bool,
+    Ptr,
+    StdString,
+}
+
+impl FieldType {
+    pub fn size(self) -> u32 {
+        match self {
+            Self::U8 | Self::Bool => 1,
+            Self::U16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::F64 => 8,
+            Self::Ptr => PTR_SIZE as u32,
+            Self::StdString => size_of::<StdString>() as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Ptr(RawPtr),
+    String(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(v) => v.fmt(f),
+            Value::Float(v) => v.fmt(f),
+            Value::Bool(v) => v.fmt(f),
+            Value::Ptr(v) => write!(f, "{v:?}"),
+            Value::String(v) => write!(f, "{v:?}"),
+        }
+    }
+}
+
+/// A struct read with a runtime [StructLayout], fields are decoded on access
+#[derive(Debug, Clone)]
+pub struct DynStruct<'a> {
+    layout: &'a StructLayout,
+    bytes: Vec<u8>,
+}
+
+impl<'a> DynStruct<'a> {
+    pub fn read(proc: &ProcessRef, addr: Addr, layout: &'a StructLayout) -> io::Result<Self> {
+        Ok(Self {
+            layout,
+            bytes: proc.read_multiple(addr, layout.size())?,
+        })
+    }
+
+    pub fn layout(&self) -> &'a StructLayout {
+        self.layout
+    }
+
+    /// Read the field by name, the process is only needed for strings
+    pub fn get(&self, proc: &ProcessRef, name: &str) -> io::Result<Value> {
+        let field = self.layout.field(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No field {name} in the layout"),
+            )
+        })?;
+        let bytes = field
+            .end()
+            .and_then(|end| self.bytes.get(field.offset as usize..end as usize))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Field {name} is past the read struct"),
+                )
+            })?;
+
+        fn decode<T: FromBytes>(bytes: &[u8]) -> T {
+            T::read_from_bytes(bytes).expect("the slice is sized by FieldType::size")
+        }
+
+        Ok(match field.ty {
+            FieldType::U8 => Value::Int(bytes[0] as _),
+            FieldType::U16 => Value::Int(decode::<u16>(bytes) as _),
+            FieldType::U32 => Value::Int(decode::<u32>(bytes) as _),
+            FieldType::I32 => Value::Int(decode::<i32>(bytes) as _),
+            FieldType::F32 => Value::Float(decode::<f32>(bytes) as _),
+            FieldType::F64 => Value::Float(decode::<f64>(bytes)),
+            FieldType::Bool => Value::Bool(decode::<ByteBool>(bytes).as_bool()),
+            FieldType::Ptr => Value::Ptr(decode::<RawPtr>(bytes)),
+            FieldType::StdString => Value::String(decode::<StdString>(bytes).read(proc)?),
+        })
+    }
+
+    /// All the fields in the layout order, with the errors of the ones
+    /// that failed to read
+    pub fn fields(&self, proc: &ProcessRef) -> Vec<(&'a str, io::Result<Value>)> {
+        self.layout
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), self.get(proc, &f.name)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn dyn_struct() -> io::Result<()> {
+    use super::MockProcess;
+
+    let layout: StructLayout = serde_json::from_str(
+        r#"{ "fields": [
+            { "name": "hp", "offset": 8, "type": "f64" },
+            { "name": "dead", "offset": 16, "type": "bool" }
+        ] }"#,
+    )
+    .unwrap();
+    assert_eq!(layout.size(), 17);
+
+    let mut bytes = vec![0; 17];
+    bytes[8..16].copy_from_slice(&4.0f64.to_le_bytes());
+    bytes[16] = 1;
+    let proc = MockProcess::new(1).with_bytes(0x1000, bytes).into_process();
+
+    let s = DynStruct::read(&proc, 0x1000, &layout)?;
+    assert_eq!(s.get(&proc, "hp")?, Value::Float(4.0));
+    assert_eq!(s.get(&proc, "dead")?, Value::Bool(true));
+    assert!(s.get(&proc, "nope").is_err());
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn huge_offset() {
+    let json = r#"{ "structs": { "Broken": { "fields": [
+        { "name": "hp", "offset": 4294967292, "type": "f64" }
+    ] } } }"#;
+    let e = LayoutFile::from_slice(json.as_bytes()).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+
+    // and a layout that skipped the validation does not panic either
+    let layout: StructLayout = serde_json::from_str(
+        r#"{ "size": 4, "fields": [{ "name": "hp", "offset": 4294967292, "type": "f64" }] }"#,
+    )
+    .unwrap();
+    let proc = super::MockProcess::new(1)
+        .with_bytes(0x1000, vec![0; 4])
+        .into_process();
+    let s = DynStruct::read(&proc, 0x1000, &layout).unwrap();
+    assert!(s.get(&proc, "hp").is_err());
+}
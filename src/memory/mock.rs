@@ -0,0 +1,108 @@
+use std::{collections::BTreeMap, io, sync::Mutex};
+
+use zerocopy::IntoBytes;
+
+use super::{Addr, ProcessMemory, ProcessRef};
+
+/// A fake process backed by a few in-memory byte regions, for testing the
+/// readers without a running game
+#[derive(Debug, Default)]
+pub struct MockProcess {
+    pid: u32,
+    regions: Mutex<BTreeMap<Addr, Vec<u8>>>,
+}
+
+impl MockProcess {
+    pub fn new(pid: u32) -> Self {
+        Self {
+            pid,
+            ..Default::default()
+        }
+    }
+
+    /// Map the bytes at the address, regions should not overlap
+    pub fn with_bytes(self, addr: Addr, bytes: impl Into<Vec<u8>>) -> Self {
+        self.regions.lock().unwrap().insert(addr, bytes.into());
+        self
+    }
+
+    pub fn with<T: IntoBytes + zerocopy::Immutable + ?Sized>(self, addr: Addr, value: &T) -> Self {
+        self.with_bytes(addr, value.as_bytes())
+    }
+
+    pub fn into_process(self) -> ProcessRef {
+        ProcessRef::from_memory(self)
+    }
+
+    fn with_region<R>(
+        &self,
+        addr: usize,
+        len: usize,
+        f: impl FnOnce(&mut [u8]) -> R,
+    ) -> io::Result<R> {
+        let mut regions = self.regions.lock().unwrap();
+        let found = Addr::try_from(addr).ok().and_then(|addr| {
+            let (start, bytes) = regions.range_mut(..=addr).next_back()?;
+            let offset = (addr - start) as usize;
+            bytes.get_mut(offset..offset + len)
+        });
+        match found {
+            Some(bytes) => Ok(f(bytes)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unmapped mock memory at 0x{addr:x} (+{len})"),
+            )),
+        }
+    }
+}
+
+impl ProcessMemory for MockProcess {
+    fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn read_memory(&self, addr: usize, buf: &mut [u8]) -> io::Result<()> {
+        self.with_region(addr, buf.len(), |bytes| buf.copy_from_slice(bytes))
+    }
+
+    fn write_memory(&self, addr: usize, buf: &[u8]) -> io::Result<()> {
+        self.with_region(addr, buf.len(), |bytes| bytes.copy_from_slice(buf))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn mock_reads() -> io::Result<()> {
+    use super::{MemoryStorage, Ptr, StdVec};
+
+    let proc = MockProcess::new(1)
+        .with(0x1000, &[0x2000u32, 0x200c, 0x2010])
+        .with(0x2000, &[1u32, 2, 3, 4])
+        .into_process();
+
+    let vec = Ptr::<StdVec<u32>>::of(0x1000).read(&proc)?;
+    assert_eq!(vec.len(), 3);
+    assert_eq!(vec.read(&proc)?, [1, 2, 3]);
+    assert_eq!(proc.read_each::<u32>(&[0x2004, 0x200c])?, [2, 4]);
+
+    proc.write_bytes(0x2000, &5u32.to_le_bytes())?;
+    assert_eq!(proc.read::<u32>(0x2000)?, 5);
+
+    assert!(proc.read::<u32>(0x200e).is_err());
+    assert!(proc.read::<u32>(0x3000).is_err());
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn partial_reads() {
+    let proc = MockProcess::new(1)
+        .with_bytes(0x1000, vec![1; 0x1000])
+        .into_process();
+
+    let read = proc.read_partial::<u32>(0x1800, 0x400);
+    assert_eq!(read.data.len(), 0x200);
+    assert!(read.error.is_some());
+
+    assert!(proc.read_partial::<u32>(0x1000, 0x400).is_complete());
+}
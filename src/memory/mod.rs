@@ -12,13 +12,27 @@ use std::{
 use lazy_regex::regex_replace_all;
 use zerocopy::{FromBytes, IntoBytes};
 
+mod containers;
+mod dump;
+mod layout;
+mod mock;
 mod process_ref;
+mod retry;
+mod stats;
 mod string;
 mod win32ptr;
 
 pub mod exe_image;
+pub mod pointer_scan;
+pub mod snapshot;
 
+pub use containers::*;
+pub use dump::*;
+pub use layout::*;
+pub use mock::*;
 pub use process_ref::*;
+pub use retry::*;
+pub use stats::*;
 pub use string::*;
 pub use win32ptr::*;
 
@@ -126,6 +140,8 @@ impl<T: Copy> From<T> for Align4<T> {
 pub trait MemoryStorage: Pod {
     type Value;
 
+    /// Reads are counted where this is called from, see [read_stats]
+    #[track_caller]
     fn read(&self, proc: &ProcessRef) -> io::Result<Self::Value>;
 
     fn bind(self, proc: ProcessRef) -> Remote<Self>
@@ -162,6 +178,27 @@ macro_rules! primitives {
 
 primitives!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
 
+/// Compile-time checks of a game struct layout - its total size and
+/// optionally the offsets of the fields after padding arrays, so that a
+/// miscounted `_skip` fails the build instead of reading garbage:
+///
+/// `assert_layout!(Cell: 0x18 { material: 0x14 });`
+macro_rules! assert_layout {
+    ($t:ty $(: $size:literal)? $({ $($field:ident: $offset:literal),* $(,)? })?) => {
+        const _: () = {
+            $(assert!(
+                std::mem::size_of::<$t>() == $size,
+                concat!("wrong size of ", stringify!($t)),
+            );)?
+            $($(assert!(
+                std::mem::offset_of!($t, $field) == $offset,
+                concat!("wrong offset of ", stringify!($t), "::", stringify!($field)),
+            );)*)?
+        };
+    };
+}
+pub(crate) use assert_layout;
+
 /// An escape hatch for the above lack of specialization
 #[derive(FromBytes, IntoBytes, Clone, Copy)]
 #[repr(transparent)]
@@ -207,23 +244,23 @@ impl<T> Clone for StdVec<T> {
 impl<T> Copy for StdVec<T> {}
 
 impl<T> StdVec<T> {
-    pub fn len(&self) -> u32 {
-        self.end.addr().wrapping_sub(self.start.addr()) / size_of::<T>() as u32
+    pub fn len(&self) -> TargetSize {
+        self.end.addr().wrapping_sub(self.start.addr()) / size_of::<T>() as TargetSize
     }
 
     pub fn is_empty(&self) -> bool {
         self.start.addr() == self.end.addr()
     }
 
-    pub fn get(&self, index: u32) -> Option<Ptr<T>> {
+    pub fn get(&self, index: TargetSize) -> Option<Ptr<T>> {
         if index < self.len() {
-            Some(Ptr::of(self.start.addr() + index * size_of::<T>() as u32))
+            Some(Ptr::of(self.start.addr() + index * size_of::<T>() as Addr))
         } else {
             None
         }
     }
 
-    pub fn truncated(&self, len: u32) -> StdVec<T> {
+    pub fn truncated(&self, len: TargetSize) -> StdVec<T> {
         if len >= self.len() {
             return *self;
         }
@@ -234,7 +271,7 @@ impl<T> StdVec<T> {
         }
     }
 
-    pub fn read_at(&self, index: u32, proc: &ProcessRef) -> io::Result<Option<T>>
+    pub fn read_at(&self, index: TargetSize, proc: &ProcessRef) -> io::Result<Option<T>>
     where
         T: Pod,
     {
@@ -259,14 +296,38 @@ where
     }
 }
 
+impl<T: Pod> StdVec<T> {
+    /// Read as much of the vector as possible, see [ProcessRef::read_partial]
+    #[track_caller]
+    pub fn read_partial(&self, proc: &ProcessRef) -> PartialRead<T> {
+        proc.read_partial(self.start.addr(), self.len())
+    }
+}
+
 impl<T: MemoryStorage> StdVec<T> {
+    /// Reads the whole backing buffer at once and then resolves each element
+    /// from it, so only the elements that point somewhere else (like
+    /// non-inline strings) cause extra reads
     pub fn read_storage(&self, proc: &ProcessRef) -> io::Result<Vec<T::Value>> {
-        let len = self.len();
-        let mut vec = Vec::with_capacity(len as usize);
-        for i in 0..len {
-            vec.push(self.read_at(i, proc)?.unwrap().read(proc)?);
-        }
-        Ok(vec)
+        self.read(proc)?
+            .iter()
+            .map(|item| item.read(proc))
+            .collect()
+    }
+}
+
+impl<T: Pod> StdVec<Ptr<T>> {
+    /// Read the pointers and then everything they point to in one batch,
+    /// null pointers are skipped
+    #[track_caller]
+    pub fn read_pointees(&self, proc: &ProcessRef) -> io::Result<Vec<T>> {
+        let addrs = self
+            .read(proc)?
+            .into_iter()
+            .filter(|p| !p.is_null())
+            .map(|p| p.addr())
+            .collect::<Vec<_>>();
+        proc.read_each(&addrs)
     }
 }
 
@@ -293,11 +354,11 @@ pub struct StdMapNode<K, V> {
 #[repr(C, packed)]
 pub struct StdMap<K, V> {
     root: Ptr<StdMapNode<K, V>>,
-    len: u32,
+    len: TargetSize,
 }
 
 impl<K, V> StdMap<K, V> {
-    pub fn len(&self) -> u32 {
+    pub fn len(&self) -> TargetSize {
         self.len
     }
 
@@ -365,6 +426,7 @@ where
 // why did I have to overengineer this pos lolol
 // the whole MemoryStorage thing only exists because of this
 impl<K: MemoryStorage, V> StdMap<K, V> {
+    #[track_caller]
     pub fn get<Q>(&self, proc: &ProcessRef, key: &Q) -> io::Result<Option<V::Value>>
     where
         V: MemoryStorage,
@@ -374,6 +436,7 @@ impl<K: MemoryStorage, V> StdMap<K, V> {
         self.get_raw(proc, key)?.map(|v| v.read(proc)).transpose()
     }
 
+    #[track_caller]
     pub fn get_raw<Q>(&self, proc: &ProcessRef, key: &Q) -> io::Result<Option<V>>
     where
         V: Pod,
@@ -0,0 +1,180 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+};
+
+use super::{exe_image::PeHeader, ProcessRef};
+
+/// A static address plus a list of offsets that lead to some value, as in
+/// `[[base] + offsets[0]] + offsets[1]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointerChain {
+    pub base: u32,
+    pub offsets: Vec<u32>,
+}
+
+impl Display for PointerChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (last, rest) = self
+            .offsets
+            .split_last()
+            .map_or((0, &[][..]), |(l, r)| (*l, r));
+        for _ in rest {
+            write!(f, "[")?;
+        }
+        write!(f, "[0x{:08x}]", self.base)?;
+        for offset in rest {
+            write!(f, " + 0x{offset:x}]")?;
+        }
+        write!(f, " + 0x{last:x}")
+    }
+}
+
+/// Finds pointer chains from the static data of the executable to a given
+/// address, to help with finding the globals again after game updates.
+///
+/// This is a forward search level by level, each pointer is only followed
+/// once (at the shallowest depth it was seen at), and each level is capped
+/// at [Self::max_breadth] pointers, so the work is bounded by
+/// `max_depth * max_breadth * max_offset` reads
+#[derive(Debug, Clone)]
+pub struct PointerScanner {
+    /// How many dereferences after the static one are allowed
+    pub max_depth: usize,
+    /// Largest offset from a pointer to what we're looking for
+    pub max_offset: u32,
+    pub max_results: usize,
+    /// Most pointers followed at each level, the rest are dropped
+    pub max_breadth: usize,
+}
+
+impl Default for PointerScanner {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            max_offset: 0x400,
+            max_results: 100,
+            max_breadth: 10_000,
+        }
+    }
+}
+
+/// Win32 user space is the lower 2GB, and the first 64K are never mapped
+fn plausible_ptr(value: u32) -> bool {
+    (0x10000..0x8000_0000).contains(&value)
+}
+
+impl PointerScanner {
+    pub fn scan(&self, proc: &ProcessRef, header: &PeHeader, target: u32) -> Vec<PointerChain> {
+        let statics = header.static_data();
+        let Ok(data) = proc.read_multiple::<u32>(statics.start, (statics.end - statics.start) / 4)
+        else {
+            return Vec::new();
+        };
+
+        // pointer value => chains that lead to it, more than max_results of
+        // those for one pointer would never be shown anyway
+        let mut level: HashMap<u32, Vec<PointerChain>> = HashMap::new();
+        for (i, value) in data.into_iter().enumerate() {
+            if !plausible_ptr(value) || level.len() >= self.max_breadth {
+                continue;
+            }
+            let chains = level.entry(value).or_default();
+            if chains.len() < self.max_results {
+                chains.push(PointerChain {
+                    base: statics.start + i as u32 * 4,
+                    offsets: Vec::new(),
+                });
+            }
+        }
+        let mut visited = level.keys().copied().collect::<HashSet<_>>();
+
+        let mut results = Vec::new();
+        for depth in 0..=self.max_depth {
+            for (&ptr, chains) in &level {
+                if ptr <= target && target - ptr <= self.max_offset {
+                    for chain in chains {
+                        let mut chain = chain.clone();
+                        chain.offsets.push(target - ptr);
+                        results.push(chain);
+                        if results.len() >= self.max_results {
+                            return results;
+                        }
+                    }
+                }
+            }
+            if depth == self.max_depth {
+                break;
+            }
+
+            let mut next: HashMap<u32, Vec<PointerChain>> = HashMap::new();
+            for (ptr, chains) in level {
+                // unreadable pointers are just not pointers
+                let Ok(block) = proc.read_multiple::<u32>(ptr, self.max_offset / 4 + 1) else {
+                    continue;
+                };
+                for (i, value) in block.into_iter().enumerate() {
+                    if !plausible_ptr(value) {
+                        continue;
+                    }
+                    if !next.contains_key(&value) {
+                        // followed at a shallower depth, or the level is full
+                        if visited.contains(&value) || next.len() >= self.max_breadth {
+                            continue;
+                        }
+                        visited.insert(value);
+                    }
+                    let entry = next.entry(value).or_default();
+                    for chain in &chains {
+                        if entry.len() >= self.max_results {
+                            break;
+                        }
+                        let mut chain = chain.clone();
+                        chain.offsets.push(i as u32 * 4);
+                        entry.push(chain);
+                    }
+                }
+            }
+            level = next;
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn finds_chains() {
+    use super::{exe_image::ExeImage, mock::MockProcess};
+
+    // the static data is the 16 bytes at 0x401010
+    let image = ExeImage::synthetic(0x400000, &[0; 0x10], &[0; 0x10]);
+    let proc = MockProcess::new(1)
+        .with(0x401010, &[0u32, 0x200_0000, 0, 0])
+        // a loop back to itself, and the pointer we're after at +8
+        .with(
+            0x200_0000,
+            &[0x200_0000u32, 0, 0x300_0000, 0, 0, 0, 0, 0, 0],
+        )
+        .with(0x300_0000, &[0u32; 9])
+        .into_process();
+
+    let scanner = PointerScanner {
+        max_offset: 0x20,
+        ..Default::default()
+    };
+    let chains = scanner.scan(&proc, image.header(), 0x300_0010);
+    assert_eq!(
+        chains,
+        [PointerChain {
+            base: 0x401014,
+            offsets: vec![0x8, 0x10],
+        }]
+    );
+    assert_eq!(chains[0].to_string(), "[[0x00401014] + 0x8] + 0x10");
+
+    let narrow = PointerScanner {
+        max_breadth: 0,
+        ..scanner
+    };
+    assert!(narrow.scan(&proc, image.header(), 0x300_0010).is_empty());
+}
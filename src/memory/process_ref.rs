@@ -1,8 +1,66 @@
-use std::io;
+use std::{fmt::Debug, io, sync::Arc};
 use zerocopy::{FromBytes, IntoBytes};
 
+use super::{retry::with_retries, stats::record, Addr, TargetSize};
+
+/// Reads are split at page boundaries when falling back to partial reads,
+/// since a page is what becomes unreadable when memory gets freed
+const PAGE_SIZE: Addr = 0x1000;
+
+/// The result of [ProcessRef::read_partial]
+#[derive(Debug)]
+pub struct PartialRead<T> {
+    /// The elements that were read successfully, from the start
+    pub data: Vec<T>,
+    /// Why the rest could not be read, None if everything was read
+    pub error: Option<io::Error>,
+}
+
+impl<T> PartialRead<T> {
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Back to the all-or-nothing result
+    pub fn into_result(self) -> io::Result<Vec<T>> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.data),
+        }
+    }
+}
+
+/// The raw memory access behind a [ProcessRef] - a live process, or
+/// something pretending to be one, like the [super::MockProcess]
+pub trait ProcessMemory: Debug + Send + Sync {
+    fn pid(&self) -> u32;
+
+    fn read_memory(&self, addr: usize, buf: &mut [u8]) -> io::Result<()>;
+
+    /// One read per region unless the implementation can do better
+    fn read_scattered(&self, regions: &mut [(usize, &mut [u8])]) -> io::Result<()> {
+        for (addr, buf) in regions {
+            self.read_memory(*addr, buf)?;
+        }
+        Ok(())
+    }
+
+    fn write_memory(&self, addr: usize, buf: &[u8]) -> io::Result<()>;
+
+    /// Checked after a failed read, to tell a bad pointer from the process
+    /// having exited
+    fn is_alive(&self) -> bool {
+        true
+    }
+
+    #[cfg(target_os = "linux")]
+    fn steam_compat_data_path(&self) -> &str {
+        ""
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct ProcessRef(platform::Handle);
+pub struct ProcessRef(pub(super) Arc<dyn ProcessMemory>);
 
 impl PartialEq for ProcessRef {
     fn eq(&self, other: &Self) -> bool {
@@ -13,7 +71,11 @@ impl Eq for ProcessRef {}
 
 impl ProcessRef {
     pub fn connect(pid: u32) -> io::Result<Self> {
-        platform::Handle::connect(pid).map(Self)
+        platform::Handle::connect(pid).map(Self::from_memory)
+    }
+
+    pub fn from_memory(memory: impl ProcessMemory + 'static) -> Self {
+        Self(Arc::new(memory))
     }
 
     pub fn pid(&self) -> u32 {
@@ -25,17 +87,107 @@ impl ProcessRef {
         self.0.steam_compat_data_path()
     }
 
-    pub fn read_multiple<T: Pod>(&self, addr: u32, len: u32) -> io::Result<Vec<T>> {
+    #[track_caller]
+    pub fn read_multiple<T: Pod>(&self, addr: Addr, len: TargetSize) -> io::Result<Vec<T>> {
         let mut v = T::new_vec_zeroed(len as usize).expect("alloc error");
-        self.0.read_memory(addr as usize, v.as_mut_bytes())?;
+        let bytes = v.as_mut_bytes();
+        self.read_with(bytes.len(), || self.0.read_memory(addr as usize, bytes))?;
         Ok(v)
     }
 
-    pub fn read<T: Pod>(&self, addr: u32) -> io::Result<T> {
+    /// Same as [Self::read_multiple], but when that fails (e.g. the memory
+    /// at the end was freed) it goes page by page and returns whatever could
+    /// be read before the failure instead of nothing
+    #[track_caller]
+    pub fn read_partial<T: Pod>(&self, addr: Addr, len: TargetSize) -> PartialRead<T> {
+        let error = match self.read_multiple(addr, len) {
+            Ok(data) => return PartialRead { data, error: None },
+            Err(e) => e,
+        };
+
+        let total = len as usize * size_of::<T>();
+        let mut bytes = Vec::with_capacity(total);
+        let mut error = Some(error);
+        while bytes.len() < total {
+            let at = addr + bytes.len() as Addr;
+            let chunk = ((PAGE_SIZE - at % PAGE_SIZE) as usize).min(total - bytes.len());
+            match self.read_multiple::<u8>(at, chunk as TargetSize) {
+                Ok(read) => bytes.extend_from_slice(&read),
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        // might've succeeded this time, the memory is not ours after all
+        if bytes.len() == total {
+            error = None;
+        }
+
+        let mut data = T::new_vec_zeroed(bytes.len() / size_of::<T>().max(1)).expect("alloc error");
+        let read = size_of_val(&data[..]);
+        data.as_mut_bytes().copy_from_slice(&bytes[..read]);
+        PartialRead { data, error }
+    }
+
+    #[track_caller]
+    pub fn read<T: Pod>(&self, addr: Addr) -> io::Result<T> {
         let mut t = T::new_zeroed();
-        self.0.read_memory(addr as usize, t.as_mut_bytes())?;
+        self.read_with(size_of::<T>(), || {
+            self.0.read_memory(addr as usize, t.as_mut_bytes())
+        })?;
         Ok(t)
     }
+
+    /// Read a value from each of the (scattered) addresses, batching them
+    /// into as few syscalls as the platform allows
+    #[track_caller]
+    pub fn read_each<T: Pod>(&self, addrs: &[Addr]) -> io::Result<Vec<T>> {
+        let mut v = T::new_vec_zeroed(addrs.len()).expect("alloc error");
+        if addrs.is_empty() || size_of::<T>() == 0 {
+            return Ok(v);
+        }
+        let mut regions = addrs
+            .iter()
+            .zip(v.as_mut_bytes().chunks_exact_mut(size_of::<T>()))
+            .map(|(addr, buf)| (*addr as usize, buf))
+            .collect::<Vec<_>>();
+        let bytes = addrs.len() * size_of::<T>();
+        self.read_with(bytes, || self.0.read_scattered(&mut regions))?;
+        Ok(v)
+    }
+
+    /// All the reads go through here, for the stats and the retries
+    #[track_caller]
+    fn read_with<R>(&self, bytes: usize, read: impl FnMut() -> io::Result<R>) -> io::Result<R> {
+        record(bytes, || {
+            with_retries(|| self.0.is_alive(), self.0.pid(), read)
+        })
+    }
+
+    /// Write raw bytes into the process memory.
+    ///
+    /// Be careful, the game does not expect anyone to do this
+    pub fn write_bytes(&self, addr: Addr, bytes: &[u8]) -> io::Result<()> {
+        self.0.write_memory(addr as usize, bytes)
+    }
+
+    /// Same as [Self::read_each] but for (addr, len) byte regions
+    #[track_caller]
+    pub fn read_regions(&self, regions: &[(Addr, TargetSize)]) -> io::Result<Vec<Vec<u8>>> {
+        let mut bufs = regions
+            .iter()
+            .map(|(_, len)| vec![0; *len as usize])
+            .collect::<Vec<_>>();
+        let mut regions = regions
+            .iter()
+            .zip(&mut bufs)
+            .map(|((addr, _), buf)| (*addr as usize, &mut buf[..]))
+            .collect::<Vec<_>>();
+        let bytes = regions.iter().map(|(_, buf)| buf.len()).sum();
+        self.read_with(bytes, || self.0.read_scattered(&mut regions))?;
+        Ok(bufs)
+    }
 }
 
 /// A shortcut for the zerocopy traits and sanity bounds
@@ -45,13 +197,25 @@ impl<T: IntoBytes + FromBytes + Sized + 'static> Pod for T {}
 
 #[cfg(target_os = "linux")]
 mod platform {
-    use libc::{c_void, iovec, process_vm_readv};
-    use std::{io, sync::Arc};
+    use libc::{c_void, iovec, process_vm_readv, process_vm_writev};
+    use std::{
+        fs::File,
+        io,
+        os::unix::fs::FileExt,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, OnceLock,
+        },
+    };
 
     #[derive(Debug, Clone)]
     pub struct Handle {
         pid: libc::pid_t,
         steam_compat_data_path: Arc<str>,
+        /// Set once process_vm_readv turned out to be unusable, e.g. in
+        /// sandboxes that filter the syscall, to read /proc/pid/mem instead
+        use_proc_mem: Arc<AtomicBool>,
+        proc_mem: Arc<OnceLock<Option<File>>>,
     }
 
     impl Handle {
@@ -65,9 +229,39 @@ mod platform {
             Ok(Self {
                 pid: pid as libc::pid_t,
                 steam_compat_data_path,
+                use_proc_mem: Default::default(),
+                proc_mem: Default::default(),
             })
         }
 
+        /// Switch to the /proc/pid/mem fallback if the error says the
+        /// syscall itself is not available, returns true if it did
+        fn fall_back(&self, e: &io::Error) -> bool {
+            if !matches!(e.raw_os_error(), Some(libc::ENOSYS | libc::EPERM)) {
+                return false;
+            }
+            if self.proc_mem_file().is_none() {
+                return false;
+            }
+            if !self.use_proc_mem.swap(true, Ordering::Relaxed) {
+                tracing::warn!("process_vm_readv failed ({e}), falling back to /proc/pid/mem");
+            }
+            true
+        }
+
+        fn proc_mem_file(&self) -> Option<&File> {
+            self.proc_mem
+                .get_or_init(|| File::open(format!("/proc/{}/mem", self.pid)).ok())
+                .as_ref()
+        }
+
+        fn read_proc_mem(&self, addr: usize, buf: &mut [u8]) -> io::Result<()> {
+            let file = self
+                .proc_mem_file()
+                .ok_or_else(|| io::Error::other("Couldn't open /proc/pid/mem"))?;
+            file.read_exact_at(buf, addr as u64)
+        }
+
         pub fn steam_compat_data_path(&self) -> &str {
             &self.steam_compat_data_path
         }
@@ -80,6 +274,9 @@ mod platform {
             if buf.is_empty() {
                 return Ok(());
             }
+            if self.use_proc_mem.load(Ordering::Relaxed) {
+                return self.read_proc_mem(addr, buf);
+            }
             let local_iov = iovec {
                 iov_base: buf.as_mut_ptr() as *mut c_void,
                 iov_len: buf.len(),
@@ -89,22 +286,150 @@ mod platform {
                 iov_len: buf.len(),
             };
             let result = unsafe { process_vm_readv(self.pid, &local_iov, 1, &remote_iov, 1, 0) };
+            if result == -1 {
+                let e = io::Error::last_os_error();
+                if self.fall_back(&e) {
+                    return self.read_proc_mem(addr, buf);
+                }
+                Err(e)
+            } else if result as usize != buf.len() {
+                // the read ran into an unmapped page
+                Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("Partial read, got {result} out of {} bytes", buf.len()),
+                ))
+            } else {
+                Ok(())
+            }
+        }
+
+        pub fn write_memory(&self, addr: usize, buf: &[u8]) -> io::Result<()> {
+            if buf.is_empty() {
+                return Ok(());
+            }
+            let local_iov = iovec {
+                iov_base: buf.as_ptr() as *mut c_void,
+                iov_len: buf.len(),
+            };
+            let remote_iov = iovec {
+                iov_base: addr as *mut c_void,
+                iov_len: buf.len(),
+            };
+            let result = unsafe { process_vm_writev(self.pid, &local_iov, 1, &remote_iov, 1, 0) };
             if result == -1 {
                 Err(io::Error::last_os_error())
+            } else if result as usize != buf.len() {
+                Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    format!("Partial write, {result} out of {} bytes", buf.len()),
+                ))
             } else {
                 Ok(())
             }
         }
+
+        pub fn is_alive(&self) -> bool {
+            // signal 0 only checks if the process exists
+            let result = unsafe { libc::kill(self.pid, 0) };
+            result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+        }
+
+        pub fn read_scattered(&self, regions: &mut [(usize, &mut [u8])]) -> io::Result<()> {
+            if self.use_proc_mem.load(Ordering::Relaxed) {
+                return regions
+                    .iter_mut()
+                    .try_for_each(|(addr, buf)| self.read_proc_mem(*addr, buf));
+            }
+            // the kernel refuses to take more than IOV_MAX (1024) at once
+            for chunk in regions.chunks_mut(1024) {
+                let mut local_iovs = Vec::with_capacity(chunk.len());
+                let mut remote_iovs = Vec::with_capacity(chunk.len());
+                for (addr, buf) in chunk.iter_mut() {
+                    local_iovs.push(iovec {
+                        iov_base: buf.as_mut_ptr() as *mut c_void,
+                        iov_len: buf.len(),
+                    });
+                    remote_iovs.push(iovec {
+                        iov_base: *addr as *mut c_void,
+                        iov_len: buf.len(),
+                    });
+                }
+                let expected = local_iovs.iter().map(|iov| iov.iov_len).sum::<usize>();
+                let result = unsafe {
+                    process_vm_readv(
+                        self.pid,
+                        local_iovs.as_ptr(),
+                        local_iovs.len() as _,
+                        remote_iovs.as_ptr(),
+                        remote_iovs.len() as _,
+                        0,
+                    )
+                };
+                if result == -1 {
+                    let e = io::Error::last_os_error();
+                    if self.fall_back(&e) {
+                        return self.read_scattered(regions);
+                    }
+                    return Err(e);
+                }
+                // a partial read means one of the regions was unmapped
+                if result as usize != expected {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("Partial scattered read, got {result} out of {expected} bytes"),
+                    ));
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl super::ProcessMemory for Handle {
+        fn pid(&self) -> u32 {
+            Handle::pid(self)
+        }
+
+        fn read_memory(&self, addr: usize, buf: &mut [u8]) -> io::Result<()> {
+            Handle::read_memory(self, addr, buf)
+        }
+
+        fn read_scattered(&self, regions: &mut [(usize, &mut [u8])]) -> io::Result<()> {
+            Handle::read_scattered(self, regions)
+        }
+
+        fn write_memory(&self, addr: usize, buf: &[u8]) -> io::Result<()> {
+            Handle::write_memory(self, addr, buf)
+        }
+
+        fn is_alive(&self) -> bool {
+            Handle::is_alive(self)
+        }
+
+        fn steam_compat_data_path(&self) -> &str {
+            Handle::steam_compat_data_path(self)
+        }
     }
 }
 
 #[cfg(windows)]
 mod platform {
-    use std::{io, sync::Arc};
+    use std::{
+        io,
+        sync::{Arc, OnceLock},
+    };
     use windows::Win32::System::{
-        Diagnostics::Debug::ReadProcessMemory, Threading::PROCESS_VM_READ,
+        Diagnostics::Debug::{ReadProcessMemory, WriteProcessMemory},
+        Threading::{
+            GetExitCodeProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_OPERATION,
+            PROCESS_VM_READ, PROCESS_VM_WRITE,
+        },
     };
 
+    use crate::memory::retry::win32_error_kind;
+
+    /// The exit code of a process that did not exit yet
+    const STILL_ACTIVE: u32 = 259;
+
     mod threadsafe_handle {
         use std::ops::Deref;
         use windows::{core::Owned, Win32::Foundation::HANDLE};
@@ -150,21 +475,30 @@ mod platform {
     pub struct Handle {
         pid: u32,
         handle: Arc<ThreadsafeHandle>,
+        /// Opened only when something actually wants to write, most users
+        /// never need the extra access rights
+        write_handle: Arc<OnceLock<ThreadsafeHandle>>,
     }
 
     /// Only difference from io::Error::from_os_error (which is the default Into
     /// conversion) is that Rust formats the error as a signed decimal number,
     /// which makes windows error codes into ugly large negatives instead of hex
     /// strings that windows does
+    ///
+    /// Also sets the kind, so that the transient ones get retried
     fn better_message(e: windows::core::Error) -> io::Error {
-        io::Error::new(io::ErrorKind::Other, e.to_string())
+        io::Error::new(win32_error_kind(e.code().0), e.to_string())
     }
 
     impl Handle {
         pub fn connect(pid: u32) -> io::Result<Self> {
             Ok(Self {
                 pid,
-                handle: Arc::new(open_process(PROCESS_VM_READ, pid).map_err(better_message)?),
+                handle: Arc::new(
+                    open_process(PROCESS_VM_READ | PROCESS_QUERY_LIMITED_INFORMATION, pid)
+                        .map_err(better_message)?,
+                ),
+                write_handle: Arc::default(),
             })
         }
 
@@ -189,6 +523,62 @@ mod platform {
             .map_err(better_message)?;
             Ok(())
         }
+
+        pub fn write_memory(&self, addr: usize, buf: &[u8]) -> io::Result<()> {
+            if buf.is_empty() {
+                return Ok(());
+            }
+            let handle = match self.write_handle.get() {
+                Some(handle) => handle,
+                None => {
+                    let handle = open_process(PROCESS_VM_WRITE | PROCESS_VM_OPERATION, self.pid)
+                        .map_err(better_message)?;
+                    self.write_handle.get_or_init(|| handle)
+                }
+            };
+            unsafe { WriteProcessMemory(**handle, addr as _, buf.as_ptr() as _, buf.len(), None) }
+                .map_err(better_message)?;
+            Ok(())
+        }
+
+        pub fn is_alive(&self) -> bool {
+            let mut code = 0;
+            match unsafe { GetExitCodeProcess(**self.handle, &mut code) } {
+                Ok(()) => code == STILL_ACTIVE,
+                // can't tell, assume it is
+                Err(_) => true,
+            }
+        }
+
+        /// There's no vectored ReadProcessMemory, so this is just a loop
+        pub fn read_scattered(&self, regions: &mut [(usize, &mut [u8])]) -> io::Result<()> {
+            for (addr, buf) in regions {
+                self.read_memory(*addr, buf)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl super::ProcessMemory for Handle {
+        fn pid(&self) -> u32 {
+            Handle::pid(self)
+        }
+
+        fn read_memory(&self, addr: usize, buf: &mut [u8]) -> io::Result<()> {
+            Handle::read_memory(self, addr, buf)
+        }
+
+        fn read_scattered(&self, regions: &mut [(usize, &mut [u8])]) -> io::Result<()> {
+            Handle::read_scattered(self, regions)
+        }
+
+        fn write_memory(&self, addr: usize, buf: &[u8]) -> io::Result<()> {
+            Handle::write_memory(self, addr, buf)
+        }
+
+        fn is_alive(&self) -> bool {
+            Handle::is_alive(self)
+        }
     }
 
     #[cfg(not(feature = "sneaky"))]
@@ -0,0 +1,154 @@
+use std::{
+    io,
+    sync::atomic::{AtomicU32, Ordering},
+    thread,
+    time::Duration,
+};
+
+use thiserror::Error;
+
+/// How failed reads are retried, for all the [super::ProcessRef]s at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Zero means no retries
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each next one
+    pub backoff: Duration,
+}
+
+static RETRIES: AtomicU32 = AtomicU32::new(0);
+static BACKOFF_MICROS: AtomicU32 = AtomicU32::new(1000);
+
+pub fn set_retry_policy(policy: RetryPolicy) {
+    RETRIES.store(policy.retries, Ordering::Relaxed);
+    let micros = policy.backoff.as_micros().min(u32::MAX as _) as u32;
+    BACKOFF_MICROS.store(micros, Ordering::Relaxed);
+}
+
+pub fn retry_policy() -> RetryPolicy {
+    RetryPolicy {
+        retries: RETRIES.load(Ordering::Relaxed),
+        backoff: Duration::from_micros(BACKOFF_MICROS.load(Ordering::Relaxed) as _),
+    }
+}
+
+/// The read failed because the process is no longer running, as opposed
+/// to just reading a bad pointer
+#[derive(Debug, Error)]
+#[error("The process {0} has exited")]
+pub struct ProcessGone(pub u32);
+
+impl ProcessGone {
+    pub fn is(e: &io::Error) -> bool {
+        e.get_ref().is_some_and(|e| e.is::<ProcessGone>())
+    }
+}
+
+impl From<ProcessGone> for io::Error {
+    fn from(e: ProcessGone) -> Self {
+        io::Error::new(io::ErrorKind::NotConnected, e)
+    }
+}
+
+/// Errors that might go away if the same read is just done again, like the
+/// system being short on memory quota for a moment
+pub fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock
+    )
+}
+
+/// HRESULT_FROM_WIN32 of the error codes, the way the windows crate
+/// reports them
+const fn from_win32(code: u32) -> i32 {
+    (0x8007_0000 | code) as i32
+}
+
+/// What ReadProcessMemory fails with when any of the range is not readable,
+/// which is mostly just a bad pointer
+const PARTIAL_COPY: i32 = from_win32(299);
+const NO_SYSTEM_RESOURCES: i32 = from_win32(1450);
+const WORKING_SET_QUOTA: i32 = from_win32(1453);
+const NOT_ENOUGH_QUOTA: i32 = from_win32(1816);
+
+/// The error kind for a failed Windows call, only the ones where the
+/// system is short on something for a moment are [is_transient].
+///
+/// Partial copies are not, retrying them would stall every probe of a
+/// stale or null pointer on the backoff, and linux doesn't retry its EFAULT
+/// either
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(super) fn win32_error_kind(hresult: i32) -> io::ErrorKind {
+    match hresult {
+        NO_SYSTEM_RESOURCES | WORKING_SET_QUOTA | NOT_ENOUGH_QUOTA => io::ErrorKind::WouldBlock,
+        PARTIAL_COPY => io::ErrorKind::Other,
+        _ => io::ErrorKind::Other,
+    }
+}
+
+/// Do the read, retrying it according to the current [RetryPolicy], and
+/// turning the failures into [ProcessGone] when the process is not there
+pub(super) fn with_retries<R>(
+    is_alive: impl Fn() -> bool,
+    pid: u32,
+    mut read: impl FnMut() -> io::Result<R>,
+) -> io::Result<R> {
+    let policy = retry_policy();
+    let mut attempt = 0;
+    loop {
+        let e = match read() {
+            Ok(r) => return Ok(r),
+            Err(e) => e,
+        };
+        if !is_alive() {
+            return Err(ProcessGone(pid).into());
+        }
+        if attempt >= policy.retries || !is_transient(&e) {
+            return Err(e);
+        }
+        thread::sleep(policy.backoff * 2u32.pow(attempt.min(16)));
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn retries() {
+    let flaky = |fails: u32| {
+        let mut calls = 0;
+        move || {
+            calls += 1;
+            if calls > fails {
+                Ok(calls)
+            } else {
+                Err(io::Error::from(io::ErrorKind::Interrupted))
+            }
+        }
+    };
+
+    set_retry_policy(RetryPolicy {
+        retries: 2,
+        backoff: Duration::ZERO,
+    });
+    assert_eq!(with_retries(|| true, 1, flaky(2)).unwrap(), 3);
+    assert!(with_retries(|| true, 1, flaky(3)).is_err());
+    assert!(ProcessGone::is(
+        &with_retries(|| false, 1, flaky(1)).unwrap_err()
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn win32_classification() {
+    let transient = |hresult| is_transient(&win32_error_kind(hresult).into());
+
+    assert!(!transient(PARTIAL_COPY));
+    assert!(!transient(from_win32(5))); // access denied
+    assert!(transient(WORKING_SET_QUOTA));
+    assert!(transient(NO_SYSTEM_RESOURCES));
+    assert!(transient(NOT_ENOUGH_QUOTA));
+    // and linux agrees on the bad pointers
+    #[cfg(unix)]
+    assert!(!is_transient(&io::Error::from_raw_os_error(libc::EFAULT)));
+}
@@ -0,0 +1,113 @@
+use std::fmt::{self, Display};
+
+use super::*;
+
+/// A copy of some regions of the process memory, to compare against a later
+/// copy of the same regions and see what changed - mostly useful for
+/// reverse-engineering structs
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    regions: Vec<(Addr, Vec<u8>)>,
+}
+
+impl Snapshot {
+    /// Snapshot the given (addr, len) regions, all read in one batch
+    pub fn take(proc: &ProcessRef, regions: &[(Addr, TargetSize)]) -> io::Result<Self> {
+        let bufs = proc.read_regions(regions)?;
+        Ok(Self {
+            regions: regions.iter().map(|(addr, _)| *addr).zip(bufs).collect(),
+        })
+    }
+
+    /// Snapshot a single region, or as much of it as could be read - the
+    /// snapshot is then shorter than asked for and the error says why
+    pub fn take_partial(
+        proc: &ProcessRef,
+        addr: Addr,
+        len: TargetSize,
+    ) -> (Self, Option<io::Error>) {
+        let PartialRead { data, error } = proc.read_partial(addr, len);
+        let snapshot = Self {
+            regions: vec![(addr, data)],
+        };
+        (snapshot, error)
+    }
+
+    pub fn size(&self) -> TargetSize {
+        self.regions
+            .iter()
+            .map(|(_, bytes)| bytes.len() as TargetSize)
+            .sum()
+    }
+
+    /// Snapshot the whole struct behind the pointer
+    pub fn of<T: Pod>(proc: &ProcessRef, ptr: Ptr<T>) -> io::Result<Self> {
+        Self::take(proc, &[(ptr.addr(), size_of::<T>() as TargetSize)])
+    }
+
+    /// Snapshot the same regions again
+    pub fn retake(&self, proc: &ProcessRef) -> io::Result<Self> {
+        let regions = self
+            .regions
+            .iter()
+            .map(|(addr, bytes)| (*addr, bytes.len() as TargetSize))
+            .collect::<Vec<_>>();
+        Self::take(proc, &regions)
+    }
+
+    /// Changed 4-byte words between this and a later snapshot of the same
+    /// regions
+    pub fn diff(&self, later: &Snapshot) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for ((base, before), (_, after)) in self.regions.iter().zip(&later.regions) {
+            for (i, (b, a)) in before.chunks(4).zip(after.chunks(4)).enumerate() {
+                if a == b {
+                    continue;
+                }
+                changes.push(Change {
+                    region: *base,
+                    offset: i as Addr * 4,
+                    before: word(b),
+                    after: word(a),
+                });
+            }
+        }
+        changes
+    }
+}
+
+fn word(bytes: &[u8]) -> u32 {
+    let mut buf = [0; 4];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    u32::from_le_bytes(buf)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change {
+    /// Start of the snapshot region this is in
+    pub region: Addr,
+    pub offset: Addr,
+    pub before: u32,
+    pub after: u32,
+}
+
+impl Change {
+    pub fn addr(&self) -> Addr {
+        self.region + self.offset
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (b, a) = (self.before, self.after);
+        write!(
+            f,
+            "+0x{:x}: 0x{b:08x} -> 0x{a:08x} (i32 {} -> {}, f32 {} -> {})",
+            self.offset,
+            b as i32,
+            a as i32,
+            f32::from_bits(b),
+            f32::from_bits(a),
+        )
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Reverse,
+    collections::HashMap,
+    io,
+    panic::Location,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        LazyLock, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static READ_COUNT: Cell<u64> = const { Cell::new(0) };
+}
+
+/// How many memory reads were done from the current thread so far, the
+/// difference of two calls is how many reads some code did
+pub fn thread_read_count() -> u64 {
+    READ_COUNT.get()
+}
+
+/// Reads done from one place in the code
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CallSite {
+    pub reads: u64,
+    pub failures: u64,
+    pub total: Duration,
+    pub slowest: Duration,
+}
+
+impl CallSite {
+    pub fn average(&self) -> Duration {
+        self.total / self.reads.max(1) as u32
+    }
+}
+
+/// Totals of all the memory reads done by the process since the start (or
+/// the last [reset_read_stats]), from all threads
+#[derive(Debug, Default, Clone)]
+pub struct ReadStats {
+    pub reads: u64,
+    pub bytes: u64,
+    pub failures: u64,
+    /// Sorted by the slowest read, slowest first
+    pub call_sites: Vec<(&'static Location<'static>, CallSite)>,
+}
+
+/// The counters of one call site, shared by all the threads
+#[derive(Default)]
+struct SiteCounters {
+    reads: AtomicU64,
+    failures: AtomicU64,
+    /// In nanoseconds, as are the slowest
+    total: AtomicU64,
+    slowest: AtomicU64,
+}
+
+impl SiteCounters {
+    fn snapshot(&self) -> CallSite {
+        CallSite {
+            reads: self.reads.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            total: Duration::from_nanos(self.total.load(Ordering::Relaxed)),
+            slowest: Duration::from_nanos(self.slowest.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn reset(&self) {
+        self.reads.store(0, Ordering::Relaxed);
+        self.failures.store(0, Ordering::Relaxed);
+        self.total.store(0, Ordering::Relaxed);
+        self.slowest.store(0, Ordering::Relaxed);
+    }
+}
+
+type Sites = HashMap<&'static Location<'static>, &'static SiteCounters>;
+
+static READS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// All the call sites any thread read from, the counters are leaked as
+/// there's only as many of them as there are reads in the code.
+///
+/// Only locked the first time a thread reads from a site, and for the
+/// totals
+static SITES: LazyLock<Mutex<Sites>> = LazyLock::new(Default::default);
+
+thread_local! {
+    /// The sites of [SITES] this thread already read from
+    static THREAD_SITES: RefCell<Sites> = RefCell::new(HashMap::new());
+}
+
+fn site(location: &'static Location<'static>) -> &'static SiteCounters {
+    THREAD_SITES.with_borrow_mut(|sites| {
+        *sites.entry(location).or_insert_with(|| {
+            let mut all = SITES.lock().unwrap();
+            *all.entry(location)
+                .or_insert_with(|| Box::leak(Box::new(SiteCounters::default())))
+        })
+    })
+}
+
+pub fn read_stats() -> ReadStats {
+    let mut call_sites = SITES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(loc, site)| (*loc, site.snapshot()))
+        // the ones from before a reset
+        .filter(|(_, site)| site.reads != 0)
+        .collect::<Vec<_>>();
+    call_sites.sort_unstable_by_key(|(_, site)| Reverse(site.slowest));
+
+    ReadStats {
+        reads: READS.load(Ordering::Relaxed),
+        bytes: BYTES.load(Ordering::Relaxed),
+        failures: FAILURES.load(Ordering::Relaxed),
+        call_sites,
+    }
+}
+
+pub fn reset_read_stats() {
+    READS.store(0, Ordering::Relaxed);
+    BYTES.store(0, Ordering::Relaxed);
+    FAILURES.store(0, Ordering::Relaxed);
+    // the threads keep their references, so zero them out instead
+    for site in SITES.lock().unwrap().values() {
+        site.reset();
+    }
+}
+
+/// Count and time a read of `bytes` bytes, attributing it to the caller
+#[track_caller]
+pub(super) fn record<R>(bytes: usize, read: impl FnOnce() -> io::Result<R>) -> io::Result<R> {
+    let location = Location::caller();
+    READ_COUNT.set(READ_COUNT.get() + 1);
+
+    let start = Instant::now();
+    let result = read();
+    let elapsed = start.elapsed().as_nanos() as u64;
+
+    READS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+    let site = site(location);
+    site.reads.fetch_add(1, Ordering::Relaxed);
+    site.total.fetch_add(elapsed, Ordering::Relaxed);
+    site.slowest.fetch_max(elapsed, Ordering::Relaxed);
+    if result.is_err() {
+        FAILURES.fetch_add(1, Ordering::Relaxed);
+        site.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    result
+}
+
+#[cfg(test)]
+#[test]
+fn counts_from_all_threads() {
+    fn read() -> io::Result<()> {
+        record(4, || Ok(()))
+    }
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| (0..100).for_each(|_| read().unwrap()));
+        }
+    });
+
+    // the only reads made from this file
+    let stats = read_stats();
+    let (_, site) = stats
+        .call_sites
+        .iter()
+        .find(|(loc, _)| loc.file() == file!())
+        .unwrap();
+    assert_eq!(site.reads, 400);
+    assert_eq!(site.failures, 0);
+}
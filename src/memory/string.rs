@@ -4,8 +4,8 @@ use super::*;
 #[repr(C)]
 pub struct StdString {
     buf: [u8; 16],
-    len: u32,
-    cap: u32,
+    len: TargetSize,
+    cap: TargetSize,
 }
 
 #[derive(Clone, Copy)]
@@ -15,7 +15,7 @@ pub enum DecodedStdString<'a> {
 }
 
 impl StdString {
-    pub fn len(&self) -> u32 {
+    pub fn len(&self) -> TargetSize {
         self.len
     }
 
@@ -23,11 +23,45 @@ impl StdString {
         self.len == 0
     }
 
+    pub fn capacity(&self) -> TargetSize {
+        self.cap
+    }
+
+    /// Overwrite the string living at the given address in place.
+    ///
+    /// This never reallocates, so the new value must fit into the existing
+    /// capacity, otherwise an error is returned and nothing is written
+    pub fn write(ptr: Ptr<StdString>, proc: &ProcessRef, value: &str) -> io::Result<()> {
+        let current = ptr.read(proc)?;
+        let len = value.len() as TargetSize;
+        if len > current.cap {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "New value of length {len} does not fit into the capacity of {}",
+                    current.cap
+                ),
+            ));
+        }
+        let mut bytes = Vec::with_capacity(value.len() + 1);
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.push(0);
+
+        // small strings (cap < 16) are stored inline in the buffer
+        let data_addr = if current.cap < 16 {
+            ptr.addr()
+        } else {
+            Addr::read_from_prefix(&current.buf).unwrap().0
+        };
+        proc.write_bytes(data_addr, &bytes)?;
+        proc.write_bytes(ptr.addr() + 16, &len.to_le_bytes())
+    }
+
     pub fn decode(&self) -> DecodedStdString {
         if let Some(inline) = self.buf[..15].get(..self.len as usize) {
             DecodedStdString::Inline(inline)
         } else {
-            DecodedStdString::Heap(RawPtr::of(u32::read_from_prefix(&self.buf).unwrap().0))
+            DecodedStdString::Heap(RawPtr::of(Addr::read_from_prefix(&self.buf).unwrap().0))
         }
     }
 }
@@ -77,8 +111,8 @@ impl MemoryStorage for StdString {
 #[repr(C)]
 pub struct StdWstring {
     buf: [u16; 8],
-    len: u32,
-    cap: u32,
+    len: TargetSize,
+    cap: TargetSize,
 }
 
 #[derive(Clone, Copy)]
@@ -88,7 +122,7 @@ pub enum DecodedStdWstring<'a> {
 }
 
 impl StdWstring {
-    pub fn len(&self) -> u32 {
+    pub fn len(&self) -> TargetSize {
         self.len
     }
 
@@ -100,7 +134,8 @@ impl StdWstring {
         if let Some(inline) = self.buf[..7].get(..self.len as usize) {
             DecodedStdWstring::Inline(inline)
         } else {
-            DecodedStdWstring::Heap(RawPtr::of((self.buf[1] as u32) << 16 | self.buf[0] as u32))
+            let addr = Addr::read_from_prefix(self.buf.as_bytes()).unwrap().0;
+            DecodedStdWstring::Heap(RawPtr::of(addr))
         }
     }
 }
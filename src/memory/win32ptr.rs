@@ -7,12 +7,25 @@ use crate::memory::debug_type;
 
 use super::*;
 
+/// An address in the game process.
+///
+/// Noita is a 32-bit game, but everything pointer-sized in the memory module
+/// goes through this and [TargetSize], so that reading a 64-bit process is a
+/// matter of changing these two and not of rewriting all the containers
+pub type Addr = u32;
+
+/// `size_t` of the game process - lengths and capacities in the containers
+pub type TargetSize = u32;
+
+/// Size of a pointer in the game process
+pub const PTR_SIZE: usize = size_of::<Addr>();
+
 #[derive(FromBytes, IntoBytes, Clone, Copy, PartialEq, Eq)]
 #[repr(transparent)]
-pub struct RawPtr(u32);
+pub struct RawPtr(Addr);
 
 impl RawPtr {
-    pub const fn of(addr: u32) -> Self {
+    pub const fn of(addr: Addr) -> Self {
         Self(addr)
     }
 
@@ -20,7 +33,7 @@ impl RawPtr {
         Ptr::of(self.0)
     }
 
-    pub const fn addr(self) -> u32 {
+    pub const fn addr(self) -> Addr {
         self.0
     }
 
@@ -29,17 +42,20 @@ impl RawPtr {
     }
 
     pub const fn offset(self, offset: i32) -> Self {
-        Self::of((self.0 as i32 + offset) as u32)
+        Self::of(self.0.wrapping_add_signed(offset as _))
     }
 
-    pub fn read_multiple<T: Pod>(self, proc: &ProcessRef, len: u32) -> io::Result<Vec<T>> {
+    #[track_caller]
+    pub fn read_multiple<T: Pod>(self, proc: &ProcessRef, len: TargetSize) -> io::Result<Vec<T>> {
         proc.read_multiple(self.0, len)
     }
 
-    pub fn read_at<T: Pod>(self, offset: u32, proc: &ProcessRef) -> io::Result<T> {
+    #[track_caller]
+    pub fn read_at<T: Pod>(self, offset: Addr, proc: &ProcessRef) -> io::Result<T> {
         proc.read(self.0 + offset)
     }
 
+    #[track_caller]
     pub fn read<T: Pod>(self, proc: &ProcessRef) -> io::Result<T> {
         proc.read(self.0)
     }
@@ -50,28 +66,28 @@ impl Debug for RawPtr {
         if self.is_null() {
             write!(f, "NULL")
         } else {
-            write!(f, "0x{:08x}", self.0)
+            write!(f, "0x{:0width$x}", self.0, width = PTR_SIZE * 2)
         }
     }
 }
 
-impl From<u32> for RawPtr {
-    fn from(addr: u32) -> Self {
+impl From<Addr> for RawPtr {
+    fn from(addr: Addr) -> Self {
         Self::of(addr)
     }
 }
 
 #[derive(FromBytes, IntoBytes)]
 #[repr(transparent)]
-pub struct Ptr<T, const BASE: u32 = 0> {
+pub struct Ptr<T, const BASE: Addr = 0> {
     raw: RawPtr,
     _phantom: PhantomData<T>,
 }
 
 pub type Ibo<T> = Ptr<T, 0x0040_0000>;
 
-impl<T, const BASE: u32> Ptr<T, BASE> {
-    pub const fn of(addr: u32) -> Self {
+impl<T, const BASE: Addr> Ptr<T, BASE> {
+    pub const fn of(addr: Addr) -> Self {
         Self {
             raw: RawPtr::of(addr),
             _phantom: PhantomData,
@@ -79,12 +95,16 @@ impl<T, const BASE: u32> Ptr<T, BASE> {
     }
 
     pub const fn offset(self, offset: i32) -> Self {
-        Self::of((self.raw.addr() as i32 + offset * size_of::<T>() as i32) as u32)
+        Self::of(
+            self.raw
+                .addr()
+                .wrapping_add_signed((offset as isize * size_of::<T>() as isize) as _),
+        )
     }
 }
 
 impl<T> Ptr<T> {
-    pub const fn addr(self) -> u32 {
+    pub const fn addr(self) -> Addr {
         self.raw.addr()
     }
 
@@ -97,23 +117,23 @@ impl<T> Ptr<T> {
     }
 }
 
-impl<T, const BASE: u32> Clone for Ptr<T, BASE> {
+impl<T, const BASE: Addr> Clone for Ptr<T, BASE> {
     fn clone(&self) -> Self {
         *self
     }
 }
 
-impl<T, const BASE: u32> Copy for Ptr<T, BASE> {}
+impl<T, const BASE: Addr> Copy for Ptr<T, BASE> {}
 
-impl<T, const BASE: u32> PartialEq for Ptr<T, BASE> {
+impl<T, const BASE: Addr> PartialEq for Ptr<T, BASE> {
     fn eq(&self, other: &Self) -> bool {
         self.raw == other.raw
     }
 }
 
-impl<T, const BASE: u32> Eq for Ptr<T, BASE> {}
+impl<T, const BASE: Addr> Eq for Ptr<T, BASE> {}
 
-impl<T, const BASE: u32> Debug for Ptr<T, BASE> {
+impl<T, const BASE: Addr> Debug for Ptr<T, BASE> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if BASE == 0 {
             if self.raw.is_null() {
@@ -122,18 +142,24 @@ impl<T, const BASE: u32> Debug for Ptr<T, BASE> {
                 write!(f, "{:?} as {}", self.raw, debug_type::<T>())
             }
         } else {
-            write!(f, "0x{BASE:08x}+{:?} as {}", self.raw, debug_type::<T>())
+            write!(
+                f,
+                "0x{BASE:0width$x}+{:?} as {}",
+                self.raw,
+                debug_type::<T>(),
+                width = PTR_SIZE * 2
+            )
         }
     }
 }
 
-impl<T, const BASE: u32> From<u32> for Ptr<T, BASE> {
-    fn from(addr: u32) -> Self {
+impl<T, const BASE: Addr> From<Addr> for Ptr<T, BASE> {
+    fn from(addr: Addr) -> Self {
         Self::of(addr)
     }
 }
 
-impl<T: Pod, const BASE: u32> MemoryStorage for Ptr<T, BASE> {
+impl<T: Pod, const BASE: Addr> MemoryStorage for Ptr<T, BASE> {
     type Value = T;
 
     #[track_caller]
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use eframe::egui::{vec2, CentralPanel, Context, RichText, Ui, Vec2, ViewportCommand, WindowLevel};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::app::AppState;
+
+/// Size of the window in mini mode
+const STRIP_SIZE: Vec2 = vec2(440.0, 36.0);
+
+/// Size to go back to if we don't know what it was before
+const FULL_SIZE: Vec2 = vec2(1000.0, 700.0);
+
+/// How often the strip redraws when nothing else makes it
+const REFRESH: Duration = Duration::from_millis(100);
+
+/// The main window collapsed into a small always-on-top strip of values,
+/// see [show]
+#[derive(Debug, Clone, Serialize, Deserialize, SmartDefault)]
+#[serde(default)]
+pub struct MiniModeSettings {
+    pub enabled: bool,
+    #[default(true)]
+    pub seed: bool,
+    #[default(true)]
+    pub hp: bool,
+    #[default(true)]
+    pub gold: bool,
+    #[default(true)]
+    pub shift_timer: bool,
+    /// Window size before going into mini mode
+    full_size: Option<Vec2>,
+    /// Whether the window was resized for the current mode
+    #[serde(skip)]
+    applied: bool,
+}
+
+/// Resize the window if mini mode was toggled, and draw the strip if it's
+/// on. Returns true if the strip was drawn instead of the tools
+pub fn show(ctx: &Context, state: &mut AppState) -> bool {
+    let game = state.game();
+    let seed = state.seed;
+    let mini = &mut state.settings.mini_mode;
+
+    if mini.enabled != mini.applied {
+        if mini.enabled {
+            // when starting up in mini mode the window is already small
+            let size = ctx.input(|i| i.viewport().inner_rect).map(|r| r.size());
+            if let Some(size) = size.filter(|s| s.y > STRIP_SIZE.y * 2.0) {
+                mini.full_size = Some(size);
+            }
+            ctx.send_viewport_cmd(ViewportCommand::InnerSize(STRIP_SIZE));
+            ctx.send_viewport_cmd(ViewportCommand::WindowLevel(WindowLevel::AlwaysOnTop));
+        } else {
+            let size = mini.full_size.unwrap_or(FULL_SIZE);
+            ctx.send_viewport_cmd(ViewportCommand::InnerSize(size));
+            ctx.send_viewport_cmd(ViewportCommand::WindowLevel(WindowLevel::Normal));
+        }
+        mini.applied = mini.enabled;
+    }
+    if !mini.enabled {
+        return false;
+    }
+
+    CentralPanel::default().show(ctx, |ui| {
+        ui.horizontal_centered(|ui| {
+            if ui
+                .button("⛶")
+                .on_hover_text("Back to the full window")
+                .clicked()
+            {
+                mini.enabled = false;
+            }
+            if mini.seed {
+                match seed {
+                    Some(seed) => value(ui, &format!("seed: {seed}")),
+                    None => value(ui, "seed: -"),
+                }
+            }
+            if mini.hp {
+                value(ui, &game.hp_text());
+            }
+            if mini.gold {
+                match game.gold {
+                    Some(gold) => value(ui, &format!("gold: {gold}")),
+                    None => value(ui, "gold: -"),
+                }
+            }
+            if mini.shift_timer {
+                value(ui, &game.shift_timer_text());
+            }
+        });
+    });
+
+    ctx.request_repaint_after(REFRESH);
+    true
+}
+
+fn value(ui: &mut Ui, text: &str) {
+    ui.separator();
+    ui.label(RichText::new(text).monospace());
+}
@@ -0,0 +1,48 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use super::{types::Entity, Noita, Seed};
+
+/// A [Noita] handle for async code - the reads are done on the tokio
+/// blocking pool, so they don't tie up the runtime threads, and can be
+/// composed with the usual future combinators
+#[derive(Debug, Clone)]
+pub struct AsyncNoita(Arc<Mutex<Noita>>);
+
+impl From<Noita> for AsyncNoita {
+    fn from(noita: Noita) -> Self {
+        Self(Arc::new(Mutex::new(noita)))
+    }
+}
+
+impl AsyncNoita {
+    /// Run any reads on the blocking pool
+    pub async fn with<T, F>(&self, f: F) -> io::Result<T>
+    where
+        F: FnOnce(&mut Noita) -> io::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let noita = self.0.clone();
+        tokio::task::spawn_blocking(move || f(&mut noita.lock().unwrap()))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    pub async fn read_seed(&self) -> io::Result<Option<Seed>> {
+        self.with(|n| n.read_seed()).await
+    }
+
+    pub async fn read_frame_counter(&self) -> io::Result<u32> {
+        self.with(|n| n.read_frame_counter()).await
+    }
+
+    pub async fn get_player(&self) -> io::Result<Option<(Entity, bool)>> {
+        self.with(|n| n.get_player()).await
+    }
+
+    pub async fn detect_run_change(&self) -> io::Result<bool> {
+        self.with(|n| n.detect_run_change()).await
+    }
+}
@@ -0,0 +1,149 @@
+use std::{collections::HashMap, io};
+
+use derive_more::Debug;
+use lazy_regex::regex;
+
+/// Every pixel of the biome map is a 512x512 area of the world
+pub const BIOME_CELL_SIZE: i32 = 512;
+/// Which row of the biome map is at world y = 0 (the x one is the middle),
+/// for when `_biomes_all.xml` does not say
+const DEFAULT_BIOME_MAP_OFFSET_Y: i32 = 14;
+
+pub const BIOME_MAP_PATH: &str = "data/biome_impl/biome_map.png";
+pub const BIOMES_ALL_PATH: &str = "data/biome/_biomes_all.xml";
+
+/// The main world biome map, the image with a colored pixel per biome area
+/// along with the color -> biome mapping from `_biomes_all.xml`.
+///
+/// The generated NG+ maps are not supported, only the png one
+#[derive(Debug, Clone)]
+pub struct BiomeMap {
+    width: u32,
+    height: u32,
+    /// The `biome_offset_y` of `_biomes_all.xml`
+    offset_y: i32,
+    #[debug(skip)]
+    pixels: Vec<u32>,
+    biomes: HashMap<u32, String>,
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+impl BiomeMap {
+    pub fn parse(png: &[u8], biomes_all: &str) -> io::Result<Self> {
+        let image = image::load_from_memory_with_format(png, image::ImageFormat::Png)
+            .map_err(|e| invalid(format!("Bad biome map image: {e}")))?
+            .into_rgba8();
+
+        let pixels = image
+            .pixels()
+            .map(|p| {
+                let [r, g, b, a] = p.0;
+                u32::from_be_bytes([a, r, g, b])
+            })
+            .collect();
+
+        let offset_y = regex!(r#"<BiomesToLoad\b[^>]*\bbiome_offset_y\s*=\s*"(-?\d+)""#)
+            .captures(biomes_all)
+            .and_then(|c| c[1].parse().ok())
+            .unwrap_or(DEFAULT_BIOME_MAP_OFFSET_Y);
+
+        let mut biomes = HashMap::new();
+        for tag in regex!(r"<Biome\b[^>]*>").find_iter(biomes_all) {
+            let attr = |name| {
+                regex!(r#"(\w+)\s*=\s*"([^"]*)""#)
+                    .captures_iter(tag.as_str())
+                    .find(|c| &c[1] == name)
+                    .map(|c| c[2].to_owned())
+            };
+            let (Some(file), Some(color)) = (attr("biome_filename"), attr("color")) else {
+                continue;
+            };
+            let Ok(color) = u32::from_str_radix(&color, 16) else {
+                tracing::warn!(file, color, "Bad biome color");
+                continue;
+            };
+            // data/biome/coalmine.xml -> coalmine
+            let name = file.rsplit('/').next().unwrap_or(&file);
+            let name = name.strip_suffix(".xml").unwrap_or(name);
+            biomes.insert(color, name.to_owned());
+        }
+
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            offset_y,
+            pixels,
+            biomes,
+        })
+    }
+
+    /// Internal name of the biome at the world position, e.g. `coalmine`.
+    /// The map wraps around horizontally, for the parallel worlds
+    pub fn get(&self, x: i32, y: i32) -> Option<&str> {
+        let px =
+            (x.div_euclid(BIOME_CELL_SIZE) + self.width as i32 / 2).rem_euclid(self.width as i32);
+        let py = y.div_euclid(BIOME_CELL_SIZE) + self.offset_y;
+        if !(0..self.height as i32).contains(&py) {
+            return None;
+        }
+        self.get_cell(px as u32, py as u32)
+    }
+
+    /// Biome of a pixel of the map itself
+    pub fn get_cell(&self, px: u32, py: u32) -> Option<&str> {
+        let color = self.pixels.get((py * self.width + px) as usize)?;
+        self.biomes.get(color).map(|s| s.as_str())
+    }
+
+    /// Size of the map in pixels, each one being [BIOME_CELL_SIZE] wide
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The ARGB pixels, row by row
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// World position of the top left corner of the main world map
+    pub fn origin(&self) -> (i32, i32) {
+        (
+            -(self.width as i32 / 2) * BIOME_CELL_SIZE,
+            -self.offset_y * BIOME_CELL_SIZE,
+        )
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn biome_at() {
+    let mut png = Vec::new();
+    image::RgbaImage::from_fn(2, 3, |_, y| match y {
+        0 => image::Rgba([0x11, 0x22, 0x33, 0xff]),
+        _ => image::Rgba([0x44, 0x55, 0x66, 0xff]),
+    })
+    .write_to(&mut io::Cursor::new(&mut png), image::ImageFormat::Png)
+    .unwrap();
+    let biomes_all = r#"
+<BiomesToLoad biome_offset_y="1" biome_image_map="data/biome_impl/biome_map.png">
+    <Biome biome_filename="data/biome/hills.xml" color="ff112233" />
+    <Biome biome_filename="data/biome/coalmine.xml" color="ff445566" />
+</BiomesToLoad>"#;
+
+    let map = BiomeMap::parse(&png, biomes_all).unwrap();
+    assert_eq!(map.origin(), (-BIOME_CELL_SIZE, -BIOME_CELL_SIZE));
+    assert_eq!(map.get(0, -1), Some("hills"));
+    assert_eq!(map.get(0, 0), Some("coalmine"));
+    assert_eq!(map.get(0, 2 * BIOME_CELL_SIZE), None);
+    // wraps around into the parallel worlds
+    assert_eq!(map.get(5 * BIOME_CELL_SIZE, 0), Some("coalmine"));
+
+    let map = BiomeMap::parse(&png, "<BiomesToLoad>").unwrap();
+    assert_eq!(
+        map.origin().1,
+        -DEFAULT_BIOME_MAP_OFFSET_Y * BIOME_CELL_SIZE
+    );
+}
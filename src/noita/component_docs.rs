@@ -0,0 +1,187 @@
+//! Generating the component structs from `component_documentation.txt`,
+//! the dump Noita writes when started with the dev flags, see the
+//! `gen_components` example.
+//!
+//! The dump lists the fields of each component in sections, which (as far
+//! as the components we checked by hand go) is also their order in memory:
+//!
+//! ```text
+//! WalletComponent
+//!  - Members -----------------------------
+//!     int64   money         0 [0, 1]   "..."
+//!  - Privates -----------------------------
+//!     int64   mMoneyPrevFrame   0 [0, 1]   "..."
+//! ```
+//!
+//! Columns are separated by two or more spaces. Generation of a struct
+//! stops at the first field of a type we don't know the layout of, since
+//! everything after it would be misplaced - a prefix of a component is
+//! still perfectly readable.
+
+use std::fmt::Write as _;
+
+use convert_case::{Case, Casing};
+use lazy_regex::regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentDoc {
+    pub name: String,
+    /// In the order of the dump, all sections together
+    pub fields: Vec<FieldDoc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDoc {
+    pub ty: String,
+    pub name: String,
+}
+
+pub fn parse(docs: &str) -> Vec<ComponentDoc> {
+    let mut components = Vec::new();
+    for line in docs.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('-') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            components.push(ComponentDoc {
+                name: trimmed.to_owned(),
+                fields: Vec::new(),
+            });
+            continue;
+        }
+        let Some(component) = components.last_mut() else {
+            continue;
+        };
+        let mut columns = regex!(r"\s{2,}").split(trimmed);
+        if let (Some(ty), Some(name)) = (columns.next(), columns.next()) {
+            component.fields.push(FieldDoc {
+                ty: ty.to_owned(),
+                name: name.to_owned(),
+            });
+        }
+    }
+    components
+}
+
+/// Our type for a type from the dump, with its size and alignment
+fn map_type(ty: &str) -> Option<(&'static str, u32, u32)> {
+    Some(match ty {
+        "int" | "int32" | "EntityID" | "EntityTypeID" => ("i32", 4, 4),
+        "unsigned int" | "uint32" | "unsigned int32" => ("u32", 4, 4),
+        "float" => ("f32", 4, 4),
+        "double" => ("Align4<f64>", 8, 4),
+        "int64" => ("Align4<i64>", 8, 4),
+        "uint64" => ("Align4<u64>", 8, 4),
+        "bool" => ("ByteBool", 1, 1),
+        "std::string" => ("StdString", 0x18, 4),
+        "vec2" => ("Vec2", 8, 4),
+        "ivec2" => ("Vec2i", 8, 4),
+        "LensValue<float>" => ("LensValue<f32>", 12, 4),
+        "LensValue<int>" => ("LensValue<i32>", 12, 4),
+        "LensValue<bool>" => ("LensValueBool", 8, 4),
+        _ => return None,
+    })
+}
+
+/// Rust source of the struct, in the style of [super::types::components]
+pub fn generate(component: &ComponentDoc) -> String {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    let mut offset = 0;
+    let mut stopped_at = None;
+
+    for field in &component.fields {
+        let Some((ty, size, align)) = map_type(&field.ty) else {
+            stopped_at = Some(field);
+            break;
+        };
+        let pad = (align - offset % align) % align;
+        if pad != 0 {
+            // only bools get us misaligned, so pad the last one
+            match fields.last_mut() {
+                Some((_, last)) if last == "ByteBool" => *last = format!("PadBool<{pad}>"),
+                _ => {
+                    let name = format!("_pad_0x{offset:x}");
+                    fields.push((name, format!("[u8; {pad}]")));
+                }
+            }
+            offset += pad;
+        }
+        fields.push((field.name.to_case(Case::Snake), ty.to_owned()));
+        offset += size;
+    }
+    // and the tail padding, same as between the fields
+    let pad = (4 - offset % 4) % 4;
+    if pad != 0 {
+        if let Some((_, last)) = fields.last_mut().filter(|(_, ty)| ty == "ByteBool") {
+            *last = format!("PadBool<{pad}>");
+        } else {
+            fields.push((format!("_pad_0x{offset:x}"), format!("[u8; {pad}]")));
+        }
+        offset += pad;
+    }
+
+    let mut out = String::new();
+    out.push_str("#[derive(FromBytes, IntoBytes, Debug)]\n#[repr(C)]\n");
+    let _ = writeln!(out, "pub struct {} {{", component.name);
+    for (name, ty) in &fields {
+        let vis = if name.starts_with('_') { "" } else { "pub " };
+        let _ = writeln!(out, "    {vis}{name}: {ty},");
+    }
+    if let Some(field) = stopped_at {
+        let _ = writeln!(
+            out,
+            "    // stopped at {}: {}, the rest is not mapped yet",
+            field.name, field.ty
+        );
+    }
+    out.push_str("}\n");
+    if stopped_at.is_none() {
+        let _ = writeln!(out, "assert_layout!({}: 0x{offset:x});", component.name);
+    }
+    let _ = write!(
+        out,
+        "\nimpl ComponentName for {0} {{\n    const NAME: &str = \"{0}\";\n}}\n",
+        component.name
+    );
+    out
+}
+
+#[cfg(test)]
+#[test]
+fn generate_component() {
+    let docs = parse(
+        "\
+WalletComponent
+ - Members -----------------------------
+    int64        money           0 [0, 1]   \"\"
+    bool         mHasReachedInf  0 [0, 1]   \"\"
+    float        mSomething      0 [0, 1]   \"\"
+    bool         mLast           0 [0, 1]   \"\"
+ - Privates -----------------------------
+    SomeObject   mObject         - \"\"
+",
+    );
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].fields.len(), 5);
+
+    let code = generate(&docs[0]);
+    assert!(code.contains("pub m_has_reached_inf: PadBool<3>,"));
+    assert!(code.contains("pub m_last: PadBool<3>,"));
+    assert!(code.contains("// stopped at mObject: SomeObject"));
+    assert!(!code.contains("assert_layout!"));
+
+    // lens values are 4-aligned like anything else with an int in it
+    let docs = parse(
+        "\
+GenomeDataComponent
+ - Members -----------------------------
+    bool               isPredator     0 [0, 1]   \"\"
+    LensValue<float>   foodChainRank  0 [0, 1]   \"\"
+",
+    );
+    let code = generate(&docs[0]);
+    assert!(code.contains("pub is_predator: PadBool<3>,"));
+    assert!(code.contains("pub food_chain_rank: LensValue<f32>,"));
+    assert!(code.contains("assert_layout!(GenomeDataComponent: 0x10);"));
+}
@@ -1,9 +1,14 @@
-use std::{borrow::Cow, ffi::CStr};
-
-use iced_x86::{Code, Instruction, OpKind, Register};
+use std::{
+    borrow::Cow,
+    ffi::CStr,
+    fmt::{self, Display},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use iced_x86::{Code, Decoder, DecoderOptions, FlowControl, Instruction, OpKind, Register};
 use memchr::memmem;
 
-use crate::memory::exe_image::ExeImage;
+use crate::memory::exe_image::{par_scan, ExeImage};
 
 use super::NoitaGlobals;
 
@@ -13,25 +18,69 @@ use super::NoitaGlobals;
 ///   lua_setfield(L,LUA_GLOBALSINDEX,"UniqueString");
 /// ```
 /// ..we look for the `PUSH imm32` of the unique string given as `name`, and
-/// then we look if there is a `PUSH imm32` at 8 bytes before that
-/// (`CALL EDI => lua_pushcclosure` and `PUSH EBX` being 3 bytes, and
-/// 5 bytes for the `PUSH imm32` image), and return it's argument.
+/// then for the [PUSH_CCLOSURE] sequence right before it (`CALL EDI =>
+/// lua_pushcclosure` and `PUSH EBX` being 3 bytes, and 5 bytes for the
+/// `PUSH imm32` image), and return the argument of its push.
 ///
-/// Note that this completely breaks (already) with noita_dev.exe lol
+/// The dev build does not keep the function in EDI, so the bytes in between
+/// are different there - for that we fall back to the closest `PUSH imm32`
+/// of an address in the code that decodes right up to the name push, with
+/// the `lua_pushcclosure` call in between
 fn find_lua_api_fn(image: &ExeImage, name: &CStr) -> Option<u32> {
-    match image.text()[image.find_push_str_pos(name)? - 8..] {
+    let pos = image.find_push_str_pos(name)?;
+    let text = image.text();
+
+    let push_at = |at: usize| match text[at..] {
         [0x68, a, b, c, d, ..] => {
-            let addr = u32::from_le_bytes([a, b, c, d]);
+            Some(u32::from_le_bytes([a, b, c, d])).filter(|addr| image.text_addrs().contains(addr))
+        }
+        _ => None,
+    };
+    let push_cclosure = Signature::parse(PUSH_CCLOSURE)?;
+
+    let found = pos
+        .checked_sub(8)
+        .filter(|&at| push_cclosure.matches(&text[at..]))
+        .and_then(push_at)
+        .or_else(|| {
+            (5..=DEV_PUSH_DISTANCE)
+                .filter_map(|back| pos.checked_sub(back))
+                .find_map(|at| push_at(at).filter(|_| calls_between(&text[at..pos])))
+        });
+    match found {
+        Some(addr) => {
             tracing::debug!("Found Lua API function {name:?} at 0x{addr:x}");
             Some(addr)
         }
-        _ => {
+        None => {
             tracing::warn!("Did not find Lua API function {name:?}");
             None
         }
     }
 }
 
+/// `PUSH function`, `CALL EDI`, `PUSH EBX`, see above
+const PUSH_CCLOSURE: &str = "68 ?? ?? ?? ?? FF D7 53";
+
+/// How far back from the name to look for the function push, see above
+const DEV_PUSH_DISTANCE: usize = 0x20;
+
+/// Whether the code is whole instructions with a call among them
+fn calls_between(code: &[u8]) -> bool {
+    let mut called = false;
+    for instr in Decoder::new(32, code, DecoderOptions::NONE) {
+        // also what an instruction cut off at the end decodes to
+        if instr.is_invalid() {
+            return false;
+        }
+        called |= matches!(
+            instr.flow_control(),
+            FlowControl::Call | FlowControl::IndirectCall
+        );
+    }
+    called
+}
+
 /// Adapt the above function to return a stream of instructions
 fn in_lua_api_fn<'a>(image: &'a ExeImage, name: &CStr) -> impl Iterator<Item = Instruction> + 'a {
     find_lua_api_fn(image, name)
@@ -158,6 +207,22 @@ fn find_entity_tag_manager_pointer(image: &ExeImage) -> Option<u32> {
         .map(|instr| instr.memory_displacement32())
 }
 
+/// Same as [find_entity_tag_manager_pointer], but with `ComponentHasTag`,
+/// which does the same thing with the component tag manager
+fn find_component_tag_manager_pointer(image: &ExeImage) -> Option<u32> {
+    in_lua_api_fn(image, c"ComponentHasTag")
+        .forced_rev()
+        .skip_while(|instr| instr.code() != Code::Call_rel32_32)
+        .skip(1)
+        .skip_while(|instr| instr.code() != Code::Call_rel32_32)
+        .find(|instr| {
+            instr.code() == Code::Mov_r32_rm32
+                && instr.op0_register() == Register::ECX
+                && instr.op1_kind() == OpKind::Memory
+        })
+        .map(|instr| instr.memory_displacement32())
+}
+
 /// Look for the `EntityGetComponent` Lua API function and then look for
 /// a `CALL rel32` instruction that immediately follows a `PUSH EAX`,
 /// it's a call to `GetComponentTypeManager` (as I call it).
@@ -227,23 +292,292 @@ pub fn find_noita_build(image: &ExeImage) -> Option<Cow<str>> {
     Some(String::from_utf8_lossy(prefix))
 }
 
+/// An IDA-style byte pattern, like `E8 ?? ?? ?? ?? 8B 0D`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature(Vec<Option<u8>>);
+
+impl Signature {
+    pub fn parse(pattern: &str) -> Option<Self> {
+        pattern
+            .split_whitespace()
+            .map(|byte| match byte {
+                "?" | "??" => Some(None),
+                _ => u8::from_str_radix(byte, 16).ok().map(Some),
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|bytes| !bytes.is_empty())
+            .map(Self)
+    }
+
+    /// Position of the first match in the haystack
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        par_scan(haystack, self.0.len(), |chunk| self.find_in(chunk))
+    }
+
+    /// Whether the bytes start with a match
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.get(..self.0.len()).is_some_and(|window| {
+            window
+                .iter()
+                .zip(&self.0)
+                .all(|(b, p)| p.is_none_or(|p| p == *b))
+        })
+    }
+
+    fn find_in(&self, haystack: &[u8]) -> Option<usize> {
+        // use the longest run of known bytes as the anchor for memchr to
+        // quickly find the candidates
+        let (anchor_pos, anchor) = self
+            .0
+            .split(Option::is_none)
+            .scan(0, |pos, run| {
+                let start = *pos;
+                *pos += run.len() + 1;
+                Some((start, run))
+            })
+            .max_by_key(|(_, run)| run.len())?;
+        let anchor = anchor.iter().flatten().copied().collect::<Vec<_>>();
+
+        memmem::find_iter(haystack, &anchor)
+            .filter_map(|pos| pos.checked_sub(anchor_pos))
+            .find(|&start| self.matches(&haystack[start..]))
+    }
+}
+
+/// Which Steam branch the build is from (or the `noita_dev.exe` shipped
+/// alongside the main one). Only a label for now, the discovery runs the
+/// same heuristics on all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Branch {
+    Main,
+    Beta,
+    Dev,
+}
+
+impl Branch {
+    /// Beta builds have it in their build string, and the dev build
+    /// mentions its own exe name
+    pub fn detect(image: &ExeImage) -> Self {
+        if memmem::find(image.rdata(), b"noita_dev.exe").is_some() {
+            return Self::Dev;
+        }
+        match find_noita_build(image) {
+            Some(build) if build.to_ascii_lowercase().contains("beta") => Self::Beta,
+            _ => Self::Main,
+        }
+    }
+}
+
+/// Where the build is from - the GOG release is built separately, so its
+/// timestamps never match the Steam ones. Only a label, there are no
+/// known GOG builds, so those always go through the discovery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Store {
+    Steam,
+    Gog,
+    /// Neither of the store libraries is referenced, e.g. a cracked or
+    /// otherwise modified exe
+    Unknown,
+}
+
+impl Store {
+    /// By which store library the exe links to
+    pub fn detect(image: &ExeImage) -> Self {
+        let rdata = image.rdata();
+        if memmem::find(rdata, b"steam_api.dll").is_some() {
+            Self::Steam
+        } else if memmem::find(rdata, b"Galaxy.dll").is_some() {
+            Self::Gog
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+impl Display for Store {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Steam => "Steam",
+            Self::Gog => "GOG",
+            Self::Unknown => "unknown store",
+        })
+    }
+}
+
+/// How well some part of the app works with a given build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    Ok,
+    Untested,
+    Broken,
+}
+
+impl Display for Support {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ok => "OK",
+            Self::Untested => "UNTESTED",
+            Self::Broken => "BROKEN",
+        })
+    }
+}
+
+/// A build we already know all the addresses for, so that no discovery is
+/// needed, along with what we know works on it
+#[derive(Debug)]
+pub struct KnownBuild {
+    pub timestamp: u32,
+    pub name: &'static str,
+    pub branch: Branch,
+    pub store: Store,
+    /// Version of the entity/component struct layouts
+    pub layout: u32,
+    /// Per-feature support, anything not listed is untested
+    pub support: &'static [(&'static str, Support)],
+    /// Anything odd about the build worth knowing
+    pub quirks: &'static [&'static str],
+    globals: fn() -> NoitaGlobals,
+}
+
+impl KnownBuild {
+    pub fn find(timestamp: u32) -> Option<&'static KnownBuild> {
+        KNOWN_BUILDS.iter().find(|b| b.timestamp == timestamp)
+    }
+
+    pub fn globals(&self) -> NoitaGlobals {
+        (self.globals)()
+    }
+
+    pub fn support(&self, feature: &str) -> Support {
+        self.support
+            .iter()
+            .find(|(f, _)| *f == feature)
+            .map_or(Support::Untested, |(_, s)| *s)
+    }
+}
+
+impl Display for KnownBuild {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        match self.branch {
+            Branch::Main => {}
+            Branch::Beta => write!(f, " (beta)")?,
+            Branch::Dev => write!(f, " (dev)")?,
+        }
+        if self.store != Store::Steam {
+            write!(f, " ({})", self.store)?;
+        }
+        Ok(())
+    }
+}
+
+pub const KNOWN_BUILDS: &[KnownBuild] = &[KnownBuild {
+    timestamp: 0x66ba59d6,
+    name: "2024-08-12",
+    branch: Branch::Main,
+    store: Store::Steam,
+    layout: 1,
+    support: &[
+        ("Orb Radar", Support::Ok),
+        ("Material Pipette", Support::Ok),
+        ("Live Stats", Support::Ok),
+        ("Material List", Support::Ok),
+    ],
+    quirks: &[],
+    globals: NoitaGlobals::debug,
+}];
+
 pub fn run(image: &ExeImage) -> NoitaGlobals {
+    run_with_progress(image, |_| {})
+}
+
+/// Same as [run], but the finders run in parallel, with `progress` called
+/// with the done fraction (0 to 1) as each of them finishes
+pub fn run_with_progress(image: &ExeImage, progress: impl Fn(f32) + Sync) -> NoitaGlobals {
     let mut g = NoitaGlobals::default();
 
-    let seed = find_seed_pointers(image);
-    g.world_seed = seed.map(|(seed, _)| seed).map(|p| p.into());
-    g.ng_count = seed.map(|(_, ng)| ng).map(|p| p.into());
-    g.global_stats = find_stats_map_pointer(image).map(|p| (p - 0x18).into());
-    g.game_global = find_game_global_pointer(image).map(|p| p.into());
-    g.entity_manager = find_entity_manager_pointer(image).map(|p| p.into());
-    g.entity_tag_manager = find_entity_tag_manager_pointer(image).map(|p| p.into());
-    g.component_type_manager = find_component_type_manager_pointer(image).map(|p| p.into());
-    g.translation_manager = find_translation_manager_pointer(image).map(|p| p.into());
-    g.platform = find_platform_pointer(image).map(|p| p.into());
+    let branch = Branch::detect(image);
+    let store = Store::detect(image);
+    tracing::debug!(?branch, ?store, "Running discovery");
+
+    // the finders below
+    const STEPS: usize = 9;
+    let done = AtomicUsize::new(0);
+    let step = &|| progress((done.fetch_add(1, Ordering::Relaxed) + 1) as f32 / STEPS as f32);
+
+    let mut seed: Option<(u32, u32)> = None;
+    let mut stats: Option<u32> = None;
+    rayon::scope(|s| {
+        macro_rules! spawn {
+            ($($out:expr => $find:ident),* $(,)?) => {$(
+                let out = &mut $out;
+                s.spawn(move |_| {
+                    *out = $find(image).map(Into::into);
+                    step();
+                });
+            )*};
+        }
+        spawn! {
+            seed => find_seed_pointers,
+            stats => find_stats_map_pointer,
+            g.game_global => find_game_global_pointer,
+            g.entity_manager => find_entity_manager_pointer,
+            g.entity_tag_manager => find_entity_tag_manager_pointer,
+            g.component_tag_manager => find_component_tag_manager_pointer,
+            g.component_type_manager => find_component_type_manager_pointer,
+            g.translation_manager => find_translation_manager_pointer,
+            g.platform => find_platform_pointer,
+        }
+    });
+    g.world_seed = seed.map(|(seed, _)| seed.into());
+    g.ng_count = seed.map(|(_, ng)| ng.into());
+    g.global_stats = stats.map(|p| (p - 0x18).into());
 
     g
 }
 
+#[cfg(test)]
+#[test]
+fn signature() {
+    let sig = Signature::parse("E8 ?? ?? ?? ?? 8B 0D").unwrap();
+    let haystack = [0x90, 0xe8, 1, 2, 3, 4, 0x8b, 0x0d, 0x90];
+    assert_eq!(sig.find(&haystack), Some(1));
+    assert_eq!(sig.find(&haystack[2..]), None);
+    assert_eq!(Signature::parse("E8 zz"), None);
+}
+
+#[cfg(test)]
+#[test]
+fn lua_api_fn() {
+    // the code at 0x401000, the names right after it at 0x401100
+    let mut text = vec![0xcc; 0x100];
+    #[rustfmt::skip]
+    let main = [
+        0x68, 0x80, 0x10, 0x40, 0x00, // push 0x401080
+        0xff, 0xd7, // call edi
+        0x53, // push ebx
+        0x68, 0x00, 0x11, 0x40, 0x00, // push "MainFn"
+    ];
+    #[rustfmt::skip]
+    let dev = [
+        0x68, 0x90, 0x10, 0x40, 0x00, // push 0x401090
+        0x56, // push esi
+        0xe8, 0x00, 0x00, 0x00, 0x00, // call
+        0x68, 0xa0, 0x10, 0x40, 0x00, // push 0x4010a0, not the function
+        0x68, 0x07, 0x11, 0x40, 0x00, // push "DevFn"
+    ];
+    // a push of a name that is not registered
+    let lone = [0x68, 0x0d, 0x11, 0x40, 0x00];
+    text[0x10..0x10 + main.len()].copy_from_slice(&main);
+    text[0x30..0x30 + dev.len()].copy_from_slice(&dev);
+    text[0x60..0x60 + lone.len()].copy_from_slice(&lone);
+    let image = ExeImage::synthetic(0x400000, &text, b"MainFn\0DevFn\0LoneFn\0");
+
+    assert_eq!(find_lua_api_fn(&image, c"MainFn"), Some(0x401080));
+    assert_eq!(find_lua_api_fn(&image, c"DevFn"), Some(0x401090));
+    assert_eq!(find_lua_api_fn(&image, c"LoneFn"), None);
+}
+
 #[cfg(test)]
 #[ignore]
 #[test]
@@ -277,9 +611,9 @@ fn test() -> anyhow::Result<()> {
 
     let proc = ProcessRef::connect(noita_pid.pid().as_u32())?;
     let header = PeHeader::read(&proc)?;
-    if header.timestamp() != 0x66ba59d6 {
-        bail!("Timestamp mismatch: 0x{:x}", header.timestamp());
-    }
+    let Some(build) = KnownBuild::find(header.timestamp()) else {
+        bail!("Unknown build: 0x{:x}", header.timestamp());
+    };
 
     let instant = Instant::now();
     let image = header.read_image(&proc)?;
@@ -299,10 +633,11 @@ fn test() -> anyhow::Result<()> {
         game_global,
         entity_manager,
         entity_tag_manager,
+        component_tag_manager,
         component_type_manager,
         translation_manager,
         platform,
-    } = NoitaGlobals::debug();
+    } = build.globals();
 
     assert_eq!(globals.world_seed, world_seed);
     assert_eq!(globals.ng_count, ng_count);
@@ -310,6 +645,7 @@ fn test() -> anyhow::Result<()> {
     assert_eq!(globals.game_global, game_global);
     assert_eq!(globals.entity_manager, entity_manager);
     assert_eq!(globals.entity_tag_manager, entity_tag_manager);
+    assert_eq!(globals.component_tag_manager, component_tag_manager);
     assert_eq!(globals.component_type_manager, component_type_manager);
     assert_eq!(globals.translation_manager, translation_manager);
     assert_eq!(globals.platform, platform);
@@ -0,0 +1,166 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Steam app id of Noita
+pub const NOITA_APP_ID: u32 = 881100;
+
+/// Where Noita is installed, found through the Steam libraries - for
+/// reading the game assets without the game running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Install {
+    /// The directory with noita.exe
+    pub game_dir: PathBuf,
+    /// The Proton compatdata directory of the game, with the wine prefix
+    /// inside, if there is one
+    #[cfg(target_os = "linux")]
+    pub compat_data: Option<PathBuf>,
+}
+
+impl Install {
+    /// Look through all the Steam libraries for an installed Noita
+    pub fn find() -> Option<Self> {
+        steam_roots()
+            .into_iter()
+            .flat_map(|root| library_folders(&root))
+            .find_map(|library| Self::in_library(&library))
+    }
+
+    fn in_library(library: &Path) -> Option<Self> {
+        let steamapps = library.join("steamapps");
+        let game_dir = steamapps.join("common").join("Noita");
+        if !game_dir.join("noita.exe").is_file() {
+            return None;
+        }
+        tracing::debug!(path = %game_dir.display(), "Found Noita install");
+        Some(Self {
+            #[cfg(target_os = "linux")]
+            compat_data: Some(steamapps.join("compatdata").join(NOITA_APP_ID.to_string()))
+                .filter(|p| p.is_dir()),
+            game_dir,
+        })
+    }
+
+    /// The archive with all of the game data files
+    pub fn data_wak(&self) -> PathBuf {
+        self.game_dir.join("data").join("data.wak")
+    }
+
+    /// The wine prefix the game runs in
+    #[cfg(target_os = "linux")]
+    pub fn proton_prefix(&self) -> Option<PathBuf> {
+        self.compat_data.as_ref().map(|p| p.join("pfx"))
+    }
+
+    /// Read a file relative to the game directory, e.g. something from the
+    /// `mods` folder, None if it's not there
+    pub fn read_file(&self, path: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.game_dir.join(path)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// The places Steam is usually installed at, that exist (canonicalize fails
+/// for the missing ones)
+fn steam_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+
+    #[cfg(windows)]
+    for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+        if let Some(dir) = env::var_os(var) {
+            roots.push(PathBuf::from(dir).join("Steam"));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(home) = env::var_os("HOME").map(PathBuf::from) {
+        roots.push(home.join(".steam/steam"));
+        roots.push(home.join(".local/share/Steam"));
+        // flatpak
+        roots.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+    }
+
+    // .steam/steam is usually a symlink to one of the others
+    let mut unique = Vec::new();
+    for root in roots.into_iter().filter_map(|p| fs::canonicalize(p).ok()) {
+        if !unique.contains(&root) {
+            unique.push(root);
+        }
+    }
+    unique
+}
+
+/// All the library folders of a Steam install, including its own
+fn library_folders(root: &Path) -> Vec<PathBuf> {
+    let vdf = root.join("steamapps").join("libraryfolders.vdf");
+    let mut folders = match fs::read_to_string(&vdf) {
+        Ok(vdf) => parse_library_paths(&vdf),
+        Err(e) => {
+            tracing::debug!(path = %vdf.display(), "No Steam library folders: {e}");
+            Vec::new()
+        }
+    };
+    if !folders.iter().any(|f| f == root) {
+        folders.insert(0, root.to_owned());
+    }
+    folders
+}
+
+/// Pull the `"path"` values out of libraryfolders.vdf, ignoring the rest of
+/// the structure as it changed a few times over the years
+fn parse_library_paths(vdf: &str) -> Vec<PathBuf> {
+    let mut strings = Vec::new();
+    let mut chars = vdf.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '"' {
+            continue;
+        }
+        let mut s = String::new();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '"' => break,
+                '\\' => s.extend(chars.next()),
+                ch => s.push(ch),
+            }
+        }
+        strings.push(s);
+    }
+    strings
+        .windows(2)
+        .filter(|kv| kv[0].eq_ignore_ascii_case("path"))
+        .map(|kv| PathBuf::from(&kv[1]))
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn library_paths() {
+    let vdf = r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"C:\\Program Files (x86)\\Steam"
+		"apps"
+		{
+			"881100"		"1234"
+		}
+	}
+	"1"
+	{
+		"path"		"D:\\Games\\SteamLibrary"
+	}
+}
+"#;
+    assert_eq!(
+        parse_library_paths(vdf),
+        [
+            PathBuf::from(r"C:\Program Files (x86)\Steam"),
+            PathBuf::from(r"D:\Games\SteamLibrary"),
+        ]
+    );
+}
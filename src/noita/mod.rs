@@ -1,20 +1,41 @@
-use std::{borrow::Cow, collections::HashMap, io, marker::PhantomData};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
 
 use convert_case::{Case, Casing};
 use derive_more::{derive::Display, Debug};
 use types::{
     cell_factory::{CellData, CellFactory},
-    components::{Component, ComponentName},
+    components::{
+        CharacterDataComponent, CharacterPlatformingComponent, Component, ComponentName,
+        DamageModelComponent, GenomeDataComponent, Inventory2Component, ItemComponent,
+        MaterialInventoryComponent, StatusEffectDataComponent, WalletComponent,
+        WorldStateComponent,
+    },
     platform::{FileDevice, PlatformWin},
-    ComponentBuffer, ComponentTypeManager, Entity, EntityManager, GameGlobal, GlobalStats,
-    TagManager, TranslationManager,
+    world::{Cell, ChunkMap, GridWorld, MaterialHistogram, PixelRect},
+    Bitset256, ComponentBuffer, ComponentTypeManager, Entity, EntityManager, GameGlobal, GameStats,
+    GlobalStats, TagManager, TranslationManager, Vec2, Vec2i,
 };
 
-use crate::memory::{MemoryStorage, Pod, ProcessRef, Ptr};
+use biome::BiomeMap;
+use versioned::{Versioned, LATEST_LAYOUT};
+
+use crate::memory::{MemoryStorage, Pod, ProcessRef, Ptr, RawPtr, StdVec};
 
+#[cfg(feature = "async")]
+pub mod async_noita;
+pub mod biome;
+pub mod component_docs;
 pub mod discovery;
+pub mod install;
 pub mod rng;
 pub mod types;
+pub mod versioned;
 
 #[derive(Debug, Clone)]
 pub struct Noita {
@@ -22,10 +43,38 @@ pub struct Noita {
     g: NoitaGlobals,
 
     entity_tag_cache: HashMap<String, Option<u8>>,
+    component_tag_cache: HashMap<String, Option<u8>>,
     no_player_not_polied: bool,
 
     materials: Vec<String>,
     material_ui_names: Vec<String>,
+    biome_map: Option<Result<BiomeMap, (io::ErrorKind, String)>>,
+
+    /// Shared between the clones, so all the tools benefit from it
+    cache: Option<Arc<Mutex<FrameCache>>>,
+
+    /// Layout version of the [Versioned] structs in the connected build
+    layout: u32,
+
+    last_run: Option<RunMarker>,
+}
+
+/// What the current run looked like at the last [Noita::detect_run_change]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RunMarker {
+    /// The last one known, it reads as None in the menus
+    seed: Option<Seed>,
+    frame: u32,
+}
+
+/// Reads that are memoized until the game advances a frame, see
+/// [Noita::sync_frame]
+#[derive(Debug, Default)]
+struct FrameCache {
+    frame: Option<u32>,
+    player: Option<Option<(Entity, bool)>>,
+    chunk_map: Option<ChunkMap>,
+    component_buffers: HashMap<&'static str, Ptr<ComponentBuffer>>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -36,6 +85,7 @@ pub struct NoitaGlobals {
     pub game_global: Option<Ptr<Ptr<GameGlobal>>>,
     pub entity_manager: Option<Ptr<Ptr<EntityManager>>>,
     pub entity_tag_manager: Option<Ptr<Ptr<TagManager>>>,
+    pub component_tag_manager: Option<Ptr<Ptr<TagManager>>>,
     pub component_type_manager: Option<Ptr<ComponentTypeManager>>,
     pub translation_manager: Option<Ptr<TranslationManager>>,
     pub platform: Option<Ptr<PlatformWin>>,
@@ -50,6 +100,7 @@ impl NoitaGlobals {
             game_global: Some(Ptr::of(0x0122172c)),
             entity_manager: Some(Ptr::of(0x1202b78)),
             entity_tag_manager: Some(Ptr::of(0x1204fbc)),
+            component_tag_manager: Some(Ptr::of(0x1204fb8)),
             component_type_manager: Some(Ptr::of(0x01221c08)),
             translation_manager: Some(Ptr::of(0x01205c08)),
             platform: Some(Ptr::of(0x0121fba0)),
@@ -119,21 +170,66 @@ impl TagRef for Option<u8> {
 }
 
 impl Noita {
+    /// Without the per-frame caching, see [Self::set_caching]
     pub fn new(proc: ProcessRef, g: NoitaGlobals) -> Self {
         Self {
             proc,
             g,
             entity_tag_cache: HashMap::new(),
+            component_tag_cache: HashMap::new(),
             no_player_not_polied: false,
             materials: Vec::new(),
             material_ui_names: Vec::new(),
+            biome_map: None,
+            cache: None,
+            last_run: None,
+            layout: LATEST_LAYOUT,
         }
     }
 
+    /// Set the entity/component layout version of the connected build,
+    /// which is the latest one by default
+    pub fn set_layout(&mut self, layout: u32) {
+        self.layout = layout;
+    }
+
+    pub const fn layout(&self) -> u32 {
+        self.layout
+    }
+
     pub const fn proc(&self) -> &ProcessRef {
         &self.proc
     }
 
+    /// Enable or disable memoizing the player, world and component store
+    /// reads within a game frame
+    pub fn set_caching(&mut self, enabled: bool) {
+        if enabled != self.cache.is_some() {
+            self.cache = enabled.then(Default::default);
+        }
+    }
+
+    /// Drop the cached reads if the game frame changed since the last call
+    pub fn sync_frame(&self, frame: u32) {
+        if let Some(cache) = &self.cache {
+            let mut cache = cache.lock().unwrap();
+            if cache.frame != Some(frame) {
+                *cache = FrameCache {
+                    frame: Some(frame),
+                    ..Default::default()
+                };
+            }
+        }
+    }
+
+    /// Drop the cached reads regardless of the frame, e.g. after writing
+    /// to the game memory
+    pub fn bump_cache(&self) {
+        if let Some(cache) = &self.cache {
+            *cache.lock().unwrap() = FrameCache::default();
+        }
+    }
+
     pub fn read_seed(&self) -> io::Result<Option<Seed>> {
         let world_seed = deep_read!(self.world_seed)?;
         if world_seed == 0 {
@@ -149,6 +245,51 @@ impl Noita {
         read_ptr!(self.global_stats)
     }
 
+    /// The game frame counter - the canonical tick, if it did not change
+    /// then nothing else in the game did either
+    pub fn read_frame_counter(&self) -> io::Result<u32> {
+        let game_global = self
+            .g
+            .game_global
+            .ok_or_else(not_found!("No game_global pointer"))?
+            .read(&self.proc)?;
+        self.proc
+            .read(game_global.addr() + std::mem::offset_of!(GameGlobal, frame_counter) as u32)
+    }
+
+    /// Current session playtime in seconds, without reading all the stats
+    pub fn read_playtime(&self) -> io::Result<f64> {
+        let stats = self
+            .g
+            .global_stats
+            .ok_or_else(not_found!("No global_stats pointer"))?;
+        let offset =
+            std::mem::offset_of!(GlobalStats, session) + std::mem::offset_of!(GameStats, playtime);
+        self.proc.read(stats.addr() + offset as u32)
+    }
+
+    /// Whether a new run started since the last call. That's the seed (or
+    /// the NG+ count) changing, or the frame counter going backwards, so that
+    /// restarting with the same seed is noticed too. The seed going away in
+    /// the menus is not a new run by itself.
+    ///
+    /// The first call only remembers the current run and returns false
+    pub fn detect_run_change(&mut self) -> io::Result<bool> {
+        let seed = self.read_seed()?;
+        let frame = self.read_frame_counter()?;
+        let last = self.last_run;
+        let current = RunMarker {
+            seed: seed.or(last.and_then(|last| last.seed)),
+            frame,
+        };
+        self.last_run = Some(current);
+        let Some(last) = last else {
+            return Ok(false);
+        };
+        let seed_changed = last.seed.is_some() && current.seed != last.seed;
+        Ok(seed_changed || current.frame < last.frame)
+    }
+
     pub fn read_game_global(&self) -> io::Result<GameGlobal> {
         deep_read!(self.game_global)
     }
@@ -162,6 +303,88 @@ impl Noita {
         Ok(Some(ptr.read(&self.proc)?))
     }
 
+    pub fn read_grid_world(&self) -> io::Result<GridWorld> {
+        deep_read!(self.game_global.grid_world)
+    }
+
+    fn read_chunk_map(&self) -> io::Result<ChunkMap> {
+        if let Some(cache) = &self.cache {
+            if let Some(chunk_map) = &cache.lock().unwrap().chunk_map {
+                return Ok(chunk_map.clone());
+            }
+        }
+        let chunk_map = self.read_grid_world()?.chunk_map;
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap().chunk_map = Some(chunk_map.clone());
+        }
+        Ok(chunk_map)
+    }
+
+    /// Material indices of the cells in the rectangle, row by row, None for
+    /// empty cells or ones that are not loaded
+    pub fn get_materials_in(&self, rect: PixelRect) -> io::Result<Vec<Option<u32>>> {
+        let Some(cell_factory) = self.read_cell_factory()? else {
+            return Ok(vec![None; rect.area()]);
+        };
+        let cells = self.read_chunk_map()?.read_cells(&self.proc, rect)?;
+        self.cell_materials(&cell_factory, &cells)
+    }
+
+    /// Count the materials of every `stride`-th pixel (in both directions)
+    /// of the rectangle
+    pub fn sample_materials(&self, rect: PixelRect, stride: u32) -> io::Result<MaterialHistogram> {
+        let stride = stride.max(1);
+        let mut histogram = MaterialHistogram::default();
+
+        let Some(cell_factory) = self.read_cell_factory()? else {
+            return Ok(histogram);
+        };
+        let chunk_map = self.read_chunk_map()?;
+
+        let mut cells = Vec::new();
+        for y in (rect.y..rect.y + rect.height as i32).step_by(stride as usize) {
+            let row = chunk_map.read_cells(&self.proc, PixelRect::new(rect.x, y, rect.width, 1))?;
+            cells.extend(row.into_iter().step_by(stride as usize));
+        }
+
+        for material in self.cell_materials(&cell_factory, &cells)? {
+            histogram.add(material);
+        }
+        Ok(histogram)
+    }
+
+    fn cell_materials(
+        &self,
+        cell_factory: &CellFactory,
+        cells: &[Ptr<Cell>],
+    ) -> io::Result<Vec<Option<u32>>> {
+        let material_offset = std::mem::offset_of!(Cell, material) as u32;
+        let addrs = cells
+            .iter()
+            .filter(|c| !c.is_null())
+            .map(|c| c.addr() + material_offset)
+            .collect::<Vec<_>>();
+        let mut materials = self.proc.read_each::<Ptr<CellData>>(&addrs)?.into_iter();
+
+        Ok(cells
+            .iter()
+            .map(|c| {
+                if c.is_null() {
+                    return None;
+                }
+                cell_factory.material_index(materials.next()?)
+            })
+            .collect())
+    }
+
+    /// Material index at the world pixel, None for air or unloaded chunks
+    pub fn get_material_at(&self, x: i32, y: i32) -> io::Result<Option<u32>> {
+        Ok(self
+            .get_materials_in(PixelRect::new(x, y, 1, 1))?
+            .pop()
+            .flatten())
+    }
+
     pub fn read_translation_manager(&self) -> io::Result<TranslationManager> {
         read_ptr!(self.translation_manager)
     }
@@ -186,6 +409,37 @@ impl Noita {
         Ok(None)
     }
 
+    /// Read from the game files on first use and cached, along with the
+    /// failure to do so, to not re-read them over and over
+    pub fn biome_map(&mut self) -> io::Result<&BiomeMap> {
+        if self.biome_map.is_none() {
+            let loaded = self.load_biome_map();
+            if let Err(e) = &loaded {
+                tracing::warn!(%e, "Failed to load the biome map");
+            }
+            self.biome_map = Some(loaded.map_err(|e| (e.kind(), e.to_string())));
+        }
+        match self.biome_map.as_ref().unwrap() {
+            Ok(map) => Ok(map),
+            Err((kind, e)) => Err(io::Error::new(*kind, e.clone())),
+        }
+    }
+
+    fn load_biome_map(&self) -> io::Result<BiomeMap> {
+        let png = self
+            .read_file(biome::BIOME_MAP_PATH)?
+            .ok_or_else(not_found!("No {}", biome::BIOME_MAP_PATH))?;
+        let biomes_all = self
+            .read_file(biome::BIOMES_ALL_PATH)?
+            .ok_or_else(not_found!("No {}", biome::BIOMES_ALL_PATH))?;
+        BiomeMap::parse(&png, &String::from_utf8_lossy(&biomes_all))
+    }
+
+    /// Internal name of the biome at the world position, e.g. `coalmine`
+    pub fn get_biome_at(&mut self, x: i32, y: i32) -> io::Result<Option<String>> {
+        Ok(self.biome_map()?.get(x, y).map(|s| s.to_owned()))
+    }
+
     pub fn translations(&self) -> io::Result<CachedTranslations> {
         let manager = self.read_translation_manager()?;
         let lang_key_indices = manager.key_to_index.read(&self.proc)?;
@@ -202,6 +456,19 @@ impl Noita {
     }
 
     pub fn get_player(&mut self) -> io::Result<Option<(Entity, bool)>> {
+        if let Some(cache) = &self.cache {
+            if let Some(player) = &cache.lock().unwrap().player {
+                return Ok(player.clone());
+            }
+        }
+        let player = self.read_player()?;
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap().player = Some(player.clone());
+        }
+        Ok(player)
+    }
+
+    fn read_player(&mut self) -> io::Result<Option<(Entity, bool)>> {
         let Some(player_unit_idx) = self.get_entity_tag_index("player_unit")? else {
             // no player_unit means definitely no player
             return Ok(None);
@@ -244,7 +511,48 @@ impl Noita {
         if entity.is_null() {
             return Ok(None);
         }
-        Ok(Some(entity.read(&self.proc)?))
+        Ok(Some(Entity::read_versioned(
+            &self.proc,
+            entity,
+            self.layout,
+        )?))
+    }
+
+    /// Walks all the live entities - the entity list is read upfront, but the
+    /// entities themselves are only read when the iterator gets to them
+    pub fn iter_entities(&self) -> io::Result<impl Iterator<Item = io::Result<Entity>> + '_> {
+        let entities = deep_read!(self.entity_manager)?.entities.read(&self.proc)?;
+        Ok(entities
+            .into_iter()
+            .filter(|ptr| !ptr.is_null())
+            .map(|ptr| Entity::read_versioned(&self.proc, ptr, self.layout)))
+    }
+
+    /// Live entities within the radius of the given point, optionally only
+    /// those with the given tag
+    pub fn entities_in_radius(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        tag: Option<&str>,
+    ) -> io::Result<Vec<Entity>> {
+        let tag = match tag {
+            Some(tag) => match self.get_entity_tag_index(tag)? {
+                Some(idx) => Some(idx),
+                // tag doesn't exist, so no entities have it
+                None => return Ok(Vec::new()),
+            },
+            None => None,
+        };
+        let entities = deep_read!(self.entity_manager)?.entities;
+        let mut entities = self.read_entities(&entities)?;
+        entities.retain(|e| {
+            let (dx, dy) = (e.transform.pos.x - center.x, e.transform.pos.y - center.y);
+            !e.dead.get().as_bool()
+                && tag.is_none_or(|tag| e.tags[tag])
+                && dx * dx + dy * dy <= radius * radius
+        });
+        Ok(entities)
     }
 
     /// Can store the index and check entity bitset directly to avoid hashmap
@@ -271,6 +579,112 @@ impl Noita {
         Ok(idx)
     }
 
+    /// Same as [Self::get_entity_tag_index], but for the component tags,
+    /// e.g. `enabled_in_world` or `enabled_in_hand`
+    pub fn get_component_tag_index(&mut self, tag: &str) -> io::Result<Option<u8>> {
+        if let Some(idx) = self.component_tag_cache.get(tag).copied().flatten() {
+            return Ok(Some(idx));
+        }
+        let idx = deep_read!(self.component_tag_manager)?
+            .tag_indices
+            .get(&self.proc, tag)?;
+        self.component_tag_cache.insert(tag.to_string(), idx);
+        Ok(idx)
+    }
+
+    /// Names of the tags set in a component tag bitset
+    pub fn read_component_tags(&self, tags: &Bitset256) -> io::Result<Vec<String>> {
+        let names = deep_read!(self.component_tag_manager)?
+            .tags
+            .read_storage(&self.proc)?;
+        Ok(names
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| u8::try_from(*i).is_ok_and(|i| tags[i]))
+            .map(|(_, name)| name)
+            .collect())
+    }
+
+    pub fn read_children(&self, entity: &Entity) -> io::Result<Vec<Entity>> {
+        if entity.children.is_null() {
+            return Ok(Vec::new());
+        }
+        self.read_entities(&entity.children.read(&self.proc)?)
+    }
+
+    fn read_entities(&self, ptrs: &StdVec<Ptr<Entity>>) -> io::Result<Vec<Entity>> {
+        let addrs = ptrs
+            .read(&self.proc)?
+            .into_iter()
+            .filter(|p| !p.is_null())
+            .map(|p| p.addr())
+            .collect::<Vec<_>>();
+        Entity::read_versioned_each(&self.proc, &addrs, self.layout)
+    }
+
+    /// Player wands and items from both the quick and the full inventory
+    pub fn read_player_inventory(&mut self) -> io::Result<Option<PlayerInventory>> {
+        let Some((player, _)) = self.get_player()? else {
+            return Ok(None);
+        };
+        let active_item = self
+            .component_store::<Inventory2Component>()?
+            .get(&player)?
+            .map(|inv| inv.m_active_item);
+
+        let item_store = self.component_store::<ItemComponent>()?;
+        let wand_tag = self.get_entity_tag_index("wand")?;
+        let mut inventory = PlayerInventory {
+            active_item,
+            ..Default::default()
+        };
+        for child in self.read_children(&player)? {
+            let target = match &*child.name.read(&self.proc)? {
+                "inventory_quick" => &mut inventory.quick,
+                "inventory_full" => &mut inventory.full,
+                _ => continue,
+            };
+            let entities = self.read_children(&child)?;
+            let items = item_store.get_many(&entities)?;
+            for (entity, item) in entities.into_iter().zip(items) {
+                target.push(InventoryItem {
+                    is_wand: entity.tags[wand_tag],
+                    entity,
+                    item,
+                });
+            }
+        }
+        Ok(Some(inventory))
+    }
+
+    /// A value set by the game scripts with `GlobalsSetValue`, those are
+    /// kept in the world state component
+    pub fn read_lua_global(&self, name: &str) -> io::Result<Option<String>> {
+        let Some(world_state) = self.read_world_state()? else {
+            return Ok(None);
+        };
+        world_state.lua_globals.get(&self.proc, name)
+    }
+
+    /// The component of the world state entity, None when there is no world
+    /// (e.g. in the main menu)
+    pub fn read_world_state(&self) -> io::Result<Option<WorldStateComponent>> {
+        let entity_manager = deep_read!(self.entity_manager)?;
+        Ok(self
+            .component_store::<WorldStateComponent>()?
+            .iter(&entity_manager)?
+            .next()
+            .map(|(_, world_state)| world_state))
+    }
+
+    /// Ids of the orbs picked up in the current run, see [MAIN_WORLD_ORBS]
+    pub fn read_orbs_found(&self) -> io::Result<Vec<i32>> {
+        match self.read_world_state()? {
+            Some(world_state) => world_state.orbs_found_thisrun.read(&self.proc),
+            None => Ok(Vec::new()),
+        }
+    }
+
     pub fn has_tag(&mut self, entity: &Entity, tag: impl TagRef) -> io::Result<bool> {
         Ok(entity.tags[tag.get_tag_index(self)?])
     }
@@ -316,6 +730,16 @@ impl Noita {
     }
 
     pub fn component_store<T: ComponentName>(&self) -> io::Result<ComponentStore<T>> {
+        if let Some(cache) = &self.cache {
+            if let Some(buffer) = cache.lock().unwrap().component_buffers.get(T::NAME) {
+                return Ok(ComponentStore {
+                    proc: self.proc.clone(),
+                    buffer: *buffer,
+                    _marker: PhantomData,
+                });
+            }
+        }
+
         let index = read_ptr!(self.component_type_manager)?
             .component_indices
             .get(&self.proc, T::NAME)?
@@ -333,12 +757,107 @@ impl Noita {
             ))?
             .read(&self.proc)?;
 
+        if let Some(cache) = &self.cache {
+            cache
+                .lock()
+                .unwrap()
+                .component_buffers
+                .insert(T::NAME, buffer);
+        }
+
         Ok(ComponentStore {
             proc: self.proc.clone(),
             buffer,
             _marker: PhantomData,
         })
     }
+
+    /// Check our struct of a component against the live instances of it
+    pub fn check_component_layout<T: ComponentName + Pod>(&self) -> io::Result<LayoutCheck> {
+        let buffer = self.component_store::<T>()?.buffer.read(&self.proc)?;
+        let mut addrs = buffer
+            .storage
+            .read(&self.proc)?
+            .into_iter()
+            .filter(|p| !p.is_null())
+            .map(|p| p.addr())
+            .collect::<Vec<_>>();
+        addrs.sort_unstable();
+        addrs.dedup();
+
+        let mut wrong_headers = 0;
+        for header in self.proc.read_each::<Component<()>>(&addrs)? {
+            let name = { header.type_name };
+            if name.is_null() || name.read(&self.proc).ok().as_deref() != Some(T::NAME) {
+                wrong_headers += 1;
+            }
+        }
+
+        Ok(LayoutCheck {
+            name: T::NAME,
+            size: size_of::<Component<T>>(),
+            instances: addrs.len(),
+            wrong_headers,
+            min_gap: addrs.windows(2).map(|w| w[1] - w[0]).min(),
+        })
+    }
+
+    /// [Self::check_component_layout] for all the components we have structs for
+    pub fn check_component_layouts(&self) -> Vec<(&'static str, io::Result<LayoutCheck>)> {
+        macro_rules! check {
+            ($($t:ty),* $(,)?) => {
+                vec![$((<$t>::NAME, self.check_component_layout::<$t>()),)*]
+            };
+        }
+        check![
+            WalletComponent,
+            ItemComponent,
+            MaterialInventoryComponent,
+            DamageModelComponent,
+            WorldStateComponent,
+            CharacterDataComponent,
+            CharacterPlatformingComponent,
+            GenomeDataComponent,
+            StatusEffectDataComponent,
+            Inventory2Component,
+        ]
+    }
+}
+
+/// What [Noita::check_component_layout] found out about a component type.
+///
+/// The game doesn't keep the component sizes anywhere we know of, so this
+/// works with what the instances tell: their headers should name the
+/// type, and since they don't overlap, the distance between two of them is
+/// an upper bound of the real size
+#[derive(Debug, Clone)]
+pub struct LayoutCheck {
+    pub name: &'static str,
+    /// Size of our struct, including the component header
+    pub size: usize,
+    pub instances: usize,
+    /// Instances without the expected type name in the header
+    pub wrong_headers: usize,
+    /// The smallest distance between two instances
+    pub min_gap: Option<u32>,
+}
+
+impl LayoutCheck {
+    pub fn problem(&self) -> Option<String> {
+        if self.wrong_headers > 0 {
+            return Some(format!(
+                "{} of {} instances have an unexpected header",
+                self.wrong_headers, self.instances
+            ));
+        }
+        match self.min_gap {
+            Some(gap) if (gap as usize) < self.size => Some(format!(
+                "Instances are only 0x{gap:x} bytes apart, but our struct is 0x{:x}",
+                self.size
+            )),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
@@ -352,8 +871,33 @@ impl Seed {
     pub fn sum(&self) -> u32 {
         self.world_seed.wrapping_add(self.ng_count)
     }
+
+    /// Width of the world before it repeats into the parallel worlds, the
+    /// NG+ worlds are narrower
+    pub fn world_width(&self) -> f32 {
+        if self.ng_count == 0 {
+            35840.0
+        } else {
+            32768.0
+        }
+    }
+
+    /// Which parallel world the x coordinate is in, 0 being the main one
+    /// and the negative ones being to the west
+    pub fn parallel_world(&self, x: f32) -> i32 {
+        let width = self.world_width();
+        ((x + width / 2.0) / width).floor() as i32
+    }
 }
 
+/// The orbs of the main world have the ids from 0 to 10, the ones picked
+/// up in the parallel worlds get ids past those
+pub const MAIN_WORLD_ORBS: i32 = 11;
+
+/// The orb count the endings care about, the main world and one parallel
+/// world on each side
+pub const TOTAL_ORBS: i32 = 33;
+
 #[derive(Debug)]
 pub struct ComponentStore<T> {
     proc: ProcessRef,
@@ -390,6 +934,127 @@ where
     pub fn get(&self, entity: &Entity) -> io::Result<Option<T>> {
         Ok(self.get_full(entity)?.map(|c| c.data))
     }
+
+    /// Same as [Self::get], but only if the component has the tag, see
+    /// [Noita::get_component_tag_index]
+    pub fn get_tagged(&self, entity: &Entity, tag: u8) -> io::Result<Option<T>> {
+        Ok(self
+            .get_full(entity)?
+            .filter(|c| { c.tags }[tag])
+            .map(|c| c.data))
+    }
+
+    /// Same as [Self::get_full] for many entities at once, with a constant
+    /// number of (batched) reads
+    pub fn get_full_many(&self, entities: &[Entity]) -> io::Result<Vec<Option<Component<T>>>> {
+        let buffer = self.buffer.read(&self.proc)?;
+
+        let idx_ptrs = entities
+            .iter()
+            .map(|e| buffer.indices.get(e.comp_idx).map(|p| p.addr()))
+            .collect::<Vec<_>>();
+        let indices = read_each_some::<u32>(&self.proc, &idx_ptrs)?
+            .into_iter()
+            .map(|i| i.unwrap_or(buffer.default_index));
+
+        let storage_ptrs = indices
+            .map(|i| buffer.storage.get(i).map(|p| p.addr()))
+            .collect::<Vec<_>>();
+        let component_ptrs = read_each_some::<RawPtr>(&self.proc, &storage_ptrs)?
+            .into_iter()
+            .map(|p| p.filter(|p| !p.is_null()).map(|p| p.addr()))
+            .collect::<Vec<_>>();
+
+        read_each_some(&self.proc, &component_ptrs)
+    }
+
+    /// All the entities that have this component, along with it.
+    ///
+    /// Components don't know their entities, so this still goes through the
+    /// entity list, but every array involved is read in one go
+    pub fn iter(
+        &self,
+        entity_manager: &EntityManager,
+    ) -> io::Result<impl Iterator<Item = (u32, T)>> {
+        Ok(self.iter_full(entity_manager)?.map(|(id, c)| (id, c.data)))
+    }
+
+    /// Same as [Self::iter], but only the components with the tag
+    pub fn iter_tagged(
+        &self,
+        entity_manager: &EntityManager,
+        tag: u8,
+    ) -> io::Result<impl Iterator<Item = (u32, T)>> {
+        Ok(self
+            .iter_full(entity_manager)?
+            .filter(move |(_, c)| { c.tags }[tag])
+            .map(|(id, c)| (id, c.data)))
+    }
+
+    fn iter_full(
+        &self,
+        entity_manager: &EntityManager,
+    ) -> io::Result<impl Iterator<Item = (u32, Component<T>)>> {
+        let buffer = self.buffer.read(&self.proc)?;
+        let indices = buffer.indices.read(&self.proc)?;
+        let storage = buffer.storage.read(&self.proc)?;
+
+        let (ids, component_ptrs): (Vec<_>, Vec<_>) = entity_manager
+            .entities
+            .read_pointees(&self.proc)?
+            .into_iter()
+            .filter_map(|entity| {
+                let idx = indices
+                    .get(entity.comp_idx as usize)
+                    .copied()
+                    .unwrap_or(buffer.default_index);
+                let ptr = storage.get(idx as usize).filter(|p| !p.is_null())?;
+                Some((entity.id, ptr.addr()))
+            })
+            .unzip();
+
+        let components = self.proc.read_each::<Component<T>>(&component_ptrs)?;
+        Ok(ids.into_iter().zip(components))
+    }
+
+    pub fn get_many(&self, entities: &[Entity]) -> io::Result<Vec<Option<T>>> {
+        Ok(self
+            .get_full_many(entities)?
+            .into_iter()
+            .map(|c| c.map(|c| c.data))
+            .collect())
+    }
+}
+
+/// Batch-read the present addresses, keeping the gaps
+fn read_each_some<T: Pod>(proc: &ProcessRef, addrs: &[Option<u32>]) -> io::Result<Vec<Option<T>>> {
+    let present = addrs.iter().flatten().copied().collect::<Vec<_>>();
+    let mut values = proc.read_each::<T>(&present)?.into_iter();
+    Ok(addrs
+        .iter()
+        .map(|addr| addr.and_then(|_| values.next()))
+        .collect())
+}
+
+#[derive(Debug, Default)]
+pub struct PlayerInventory {
+    /// Entity id of the held item
+    pub active_item: Option<u32>,
+    pub quick: Vec<InventoryItem>,
+    pub full: Vec<InventoryItem>,
+}
+
+#[derive(Debug)]
+pub struct InventoryItem {
+    pub entity: Entity,
+    pub item: Option<ItemComponent>,
+    pub is_wand: bool,
+}
+
+impl InventoryItem {
+    pub fn slot(&self) -> Option<Vec2i> {
+        self.item.as_ref().map(|item| item.inventory_slot)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -412,3 +1077,40 @@ impl CachedTranslations {
             })
     }
 }
+
+#[cfg(test)]
+#[test]
+fn run_change() -> io::Result<()> {
+    use crate::memory::MockProcess;
+
+    let frame_at = 0x3000 + std::mem::offset_of!(GameGlobal, frame_counter) as u32;
+    let proc = MockProcess::new(1)
+        .with(0x1000, &[0u32; 2])
+        .with(0x2000, &0x3000u32)
+        .with_bytes(0x3000, vec![0; size_of::<GameGlobal>()])
+        .into_process();
+    let mut noita = Noita::new(
+        proc.clone(),
+        NoitaGlobals {
+            world_seed: Some(Ptr::of(0x1000)),
+            ng_count: Some(Ptr::of(0x1004)),
+            game_global: Some(Ptr::of(0x2000)),
+            ..Default::default()
+        },
+    );
+    let mut at = |seed: u32, frame: u32| {
+        proc.write_bytes(0x1000, &seed.to_le_bytes())?;
+        proc.write_bytes(frame_at, &frame.to_le_bytes())?;
+        noita.detect_run_change()
+    };
+
+    assert!(!at(1234, 100)?);
+    assert!(!at(1234, 200)?);
+    // died and went to the menu
+    assert!(!at(0, 300)?);
+    assert!(!at(1234, 400)?);
+    // restarted with the same seed
+    assert!(at(1234, 10)?);
+    assert!(at(5678, 20)?);
+    Ok(())
+}
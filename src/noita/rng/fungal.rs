@@ -0,0 +1,188 @@
+//! Fungal shift prediction, a port of the material picking of
+//! `data/scripts/magic/fungal_shift.lua`.
+//!
+//! The shifts only depend on the seed and the `fungal_shift_iteration` Lua
+//! global (see [crate::noita::Noita::read_lua_global]), except for the held
+//! flask or pouch, which can replace one side of the shift - so for each one
+//! we say what would happen if a flask was held too.
+
+use super::ScriptRng;
+
+/// The last iteration the game still shifts at
+pub const MAX_SHIFTS: u32 = 20;
+
+/// Frames between two shifts, the `60*60*5` of the script
+pub const SHIFT_COOLDOWN: u32 = 60 * 60 * 5;
+
+/// The Lua global the script keeps the frame of the last shift in
+pub const LAST_SHIFT_FRAME_GLOBAL: &str = "fungal_shift_last_frame";
+
+/// Frames left until the next shift can happen, given the value of
+/// [LAST_SHIFT_FRAME_GLOBAL] (None if it's not set, before the first shift)
+pub fn cooldown_left(frame: u32, last_shift_frame: Option<i64>) -> u32 {
+    let Some(last) = last_shift_frame else {
+        return 0;
+    };
+    (last + SHIFT_COOLDOWN as i64 - frame as i64).clamp(0, SHIFT_COOLDOWN as i64) as u32
+}
+
+struct From {
+    probability: f64,
+    materials: &'static [&'static str],
+}
+
+struct To {
+    probability: f64,
+    material: &'static str,
+}
+
+const fn from(probability: f64, materials: &'static [&'static str]) -> From {
+    From {
+        probability,
+        materials,
+    }
+}
+
+const fn to(probability: f64, material: &'static str) -> To {
+    To {
+        probability,
+        material,
+    }
+}
+
+/// `materials_from` of the script
+#[rustfmt::skip]
+const MATERIALS_FROM: &[From] = &[
+    from(1.0, &["water", "swamp", "water_salt", "water_swamp", "snow", "water_ice"]),
+    from(1.0, &["lava"]),
+    from(1.0, &["radioactive_liquid", "poison", "material_darkness"]),
+    from(1.0, &["oil", "swamp", "material_darkness"]),
+    from(1.0, &["blood", "blood_fading", "blood_fungi", "blood_worm", "porridge", "blood_cold"]),
+    from(1.0, &["blood_fungi", "fungi", "fungisoil"]),
+    from(1.0, &["blood_cold", "blood_worm"]),
+    from(1.0, &["acid"]),
+    from(0.4, &["acid_gas", "acid_gas_static", "poison_gas", "fungal_gas", "radioactive_gas", "radioactive_gas_static"]),
+    from(0.4, &["magic_liquid_polymorph", "magic_liquid_unstable_polymorph"]),
+    from(0.4, &["magic_liquid_berserk", "magic_liquid_charm", "magic_liquid_invisibility"]),
+    from(0.6, &["diamond"]),
+    from(0.6, &["silver", "brass", "copper"]),
+    from(0.2, &["steam", "smoke"]),
+    from(0.4, &["sand"]),
+    from(0.4, &["snow_sticky"]),
+    from(0.05, &["rock_static"]),
+    from(0.0003, &["gold", "gold_radioactive"]),
+];
+
+/// `materials_to` of the script
+const MATERIALS_TO: &[To] = &[
+    to(1.0, "water"),
+    to(1.0, "lava"),
+    to(1.0, "radioactive_liquid"),
+    to(1.0, "oil"),
+    to(1.0, "blood"),
+    to(1.0, "blood_fungi"),
+    to(1.0, "acid"),
+    to(1.0, "water_swamp"),
+    to(1.0, "alcohol"),
+    to(1.0, "sima"),
+    to(1.0, "blood_worm"),
+    to(1.0, "poison"),
+    to(1.0, "vomit"),
+    to(1.0, "pea_soup"),
+    to(1.0, "fungi"),
+    to(0.8, "sand"),
+    to(0.8, "diamond"),
+    to(0.8, "silver"),
+    to(0.8, "steam"),
+    to(0.5, "rock_static"),
+    to(0.5, "gunpowder"),
+    to(0.5, "material_darkness"),
+    to(0.5, "material_confusion"),
+    to(0.2, "rock_static_radioactive"),
+    to(0.02, "magic_liquid_polymorph"),
+    to(0.02, "magic_liquid_random_polymorph"),
+    to(0.15, "magic_liquid_teleportation"),
+    to(0.01, "urine"),
+    to(0.01, "poo"),
+    to(0.01, "void_liquid"),
+    to(0.01, "cheese_static"),
+];
+
+/// Which side of the shift the held flask replaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlaskSide {
+    From,
+    To,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FungalShift {
+    /// The value of `fungal_shift_iteration` before the shift
+    pub iteration: u32,
+    /// Internal material names, all of these turn into [Self::to]
+    pub from: &'static [&'static str],
+    pub to: &'static str,
+    /// What the held flask or pouch material replaces, if it is held at
+    /// the time of the shift, None if the shift ignores it
+    pub flask: Option<FlaskSide>,
+}
+
+/// Predict the shift at the given iteration, None past [MAX_SHIFTS].
+/// `seed` is the world seed plus the NG+ count, same as in
+/// [super::NoitaRng::from_pos]
+pub fn predict_shift(seed: u32, iteration: u32) -> Option<FungalShift> {
+    if iteration > MAX_SHIFTS {
+        return None;
+    }
+    // when nothing got converted (with no flask held, that's when all the
+    // from materials are the to material) the script tries again
+    for tries in 0..20 {
+        let y = 42345 + iteration + 1000 * tries;
+        let mut rnd = ScriptRng::new(seed, 9123.0, y as f64);
+
+        let from = rnd.pick_weighted(MATERIALS_FROM, |f| f.probability);
+        let to = rnd.pick_weighted(MATERIALS_TO, |t| t.probability);
+
+        // rolled only if a flask is held, but that doesn't affect the
+        // picks above, so we can tell what would happen with it
+        let flask = if rnd.next_int(1, 100) > 75 {
+            None
+        } else if rnd.next_int(1, 100) <= 50 {
+            Some(FlaskSide::From)
+        } else {
+            Some(FlaskSide::To)
+        };
+
+        if from.materials.iter().any(|m| *m != to.material) {
+            return Some(FungalShift {
+                iteration,
+                from: from.materials,
+                to: to.material,
+                flask,
+            });
+        }
+    }
+    None
+}
+
+/// The shifts that are still to happen, starting from the current value of
+/// `fungal_shift_iteration`
+pub fn predict_shifts(seed: u32, iteration: u32) -> Vec<FungalShift> {
+    (iteration..=MAX_SHIFTS)
+        .filter_map(|i| predict_shift(seed, i))
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn shifts() {
+    let shifts = predict_shifts(1234, 0);
+    assert_eq!(shifts.len(), MAX_SHIFTS as usize + 1);
+    assert_eq!(predict_shift(1234, 3).as_ref(), shifts.get(3));
+    assert!(predict_shifts(1234, 5).iter().all(|s| s.iteration >= 5));
+    assert_eq!(predict_shift(1234, MAX_SHIFTS + 1), None);
+
+    assert_eq!(cooldown_left(100, None), 0);
+    assert_eq!(cooldown_left(100, Some(40)), SHIFT_COOLDOWN - 60);
+    assert_eq!(cooldown_left(100_000, Some(40)), 0);
+}
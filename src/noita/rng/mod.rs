@@ -0,0 +1,226 @@
+//! The game RNG, and the game scripts built on top of it ported over, for
+//! predicting what they'll do for a given seed
+
+pub mod fungal;
+pub mod perks;
+pub mod potion;
+pub mod wand;
+
+use std::f64::consts::PI;
+
+/// The baseline the Lua API distribution functions always pass to
+/// [NoitaRng::get_distribution]
+const DISTRIBUTION_BASELINE: f64 = 0.005;
+
+#[derive(Debug, Clone)]
+pub struct NoitaRng(i64);
+
+impl NoitaRng {
+    /// The random function itself is super standard, the secret sauce was
+    /// getting the state from the world seed and position
+    pub fn random(&mut self) -> f64 {
+        let hi = self.0 / 127773;
+        let lo = self.0 - hi * 127773;
+        self.0 = lo * 16807 - hi * 2836;
+        if self.0 <= 0 {
+            self.0 += 0x7fffffff;
+        }
+        self.0 as f64 * 4.656612875e-10
+    }
+
+    pub fn from_pos(seed_plus_ng: u32, x: f64, y: f64) -> Self {
+        let xo = x + ((seed_plus_ng ^ 0x93262e6f) & 0xfff) as f64;
+        let yo = y + (((seed_plus_ng ^ 0x93262e6f) >> 12) & 0xfff) as f64;
+
+        let xi = to_int_kinda(xo * 134217727.0);
+        let yi = to_int_kinda(if yo.abs() >= 102400.0 || xo.abs() <= 1.0 {
+            yo * 134217727.0
+        } else {
+            yo * (yo * 3483.328 + xi as f64)
+        });
+
+        let mixed = mix(xi as i32, yi as i32, seed_plus_ng);
+
+        let mut state = (mixed as f64) / 4294967295.0 * 2147483639.0 + 1.0;
+        if state >= 2147483647.0 {
+            state *= 0.5;
+        }
+
+        let mut rng = Self(state as i64);
+        rng.random();
+
+        for _ in 0..(seed_plus_ng & 3) {
+            rng.random();
+        }
+        rng
+    }
+
+    /// `Random(a, b)` of the Lua API, an int from a to b inclusive
+    pub fn random_int(&mut self, a: i32, b: i32) -> i32 {
+        a + ((b + 1 - a) as f64 * self.random()) as i32
+    }
+
+    /// `Randomf(a, b)` of the Lua API
+    pub fn random_float(&mut self, a: f64, b: f64) -> f64 {
+        a + (b - a) * self.random()
+    }
+
+    /// `GetDistribution` of the engine, a number in 0..1 that's more likely
+    /// to be closer to the mean the sharper the distribution is. It's
+    /// rejection sampling against a sine bump around the mean, with a small
+    /// flat baseline so that the far values still come up sometimes
+    fn get_distribution(&mut self, mean: f64, sharpness: f64, baseline: f64) -> f64 {
+        for _ in 0..100 {
+            let r1 = self.random();
+            let r2 = self.random();
+            let div = (r1 - mean).abs();
+            if r2 < (1.0 - div) * baseline {
+                return r1;
+            }
+            if div < 0.5 {
+                let v = ((0.5 - mean + r1) * PI).sin().powf(sharpness);
+                if v > r2 {
+                    return r1;
+                }
+            }
+        }
+        self.random()
+    }
+
+    /// `RandomDistribution(min, max, mean, sharpness)` of the Lua API
+    pub fn random_distribution(&mut self, min: i32, max: i32, mean: i32, sharpness: f64) -> i32 {
+        if sharpness == 0.0 {
+            return self.random_int(min, max);
+        }
+        let mean = (mean - min) as f64 / (max - min) as f64;
+        let v = self.get_distribution(mean, sharpness, DISTRIBUTION_BASELINE);
+        min + ((max - min) as f64 * v).round() as i32
+    }
+
+    /// `RandomDistributionf(min, max, mean, sharpness)` of the Lua API
+    pub fn random_distribution_f(&mut self, min: f64, max: f64, mean: f64, sharpness: f64) -> f64 {
+        if sharpness == 0.0 {
+            return self.random_float(min, max);
+        }
+        let mean = (mean - min) / (max - min);
+        min + (max - min) * self.get_distribution(mean, sharpness, DISTRIBUTION_BASELINE)
+    }
+
+    /// Fisher-Yates the way `shuffleTable` of the game scripts does it
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.random_int(0, i as i32) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// The `random_create` generator of the game scripts, a `ProceduralRandom`
+/// call at a position that moves down a pixel every time
+#[derive(Debug, Clone)]
+pub struct ScriptRng {
+    seed_plus_ng: u32,
+    x: f64,
+    y: f64,
+}
+
+impl ScriptRng {
+    pub fn new(seed_plus_ng: u32, x: f64, y: f64) -> Self {
+        Self { seed_plus_ng, x, y }
+    }
+
+    fn procedural(&mut self) -> NoitaRng {
+        let rng = NoitaRng::from_pos(self.seed_plus_ng, self.x, self.y);
+        self.y += 1.0;
+        rng
+    }
+
+    /// `random_next`
+    pub fn next(&mut self, min: f64, max: f64) -> f64 {
+        self.procedural().random_float(min, max)
+    }
+
+    /// `random_nexti`
+    pub fn next_int(&mut self, min: i32, max: i32) -> i32 {
+        self.procedural().random_int(min, max)
+    }
+
+    /// `pick_random_from_table_weighted`, the items come with their weights
+    pub fn pick_weighted<'a, T>(&mut self, items: &'a [T], weight: impl Fn(&T) -> f64) -> &'a T {
+        let sum = items.iter().map(&weight).sum();
+        let val = self.next(0.0, sum);
+        let mut min = 0.0;
+        for item in items {
+            let max = min + weight(item);
+            if val >= min && val <= max {
+                return item;
+            }
+            min = max;
+        }
+        &items[0]
+    }
+}
+
+// wrapping_sub soup
+fn mix(a: i32, b: i32, c: u32) -> u32 {
+    let mut x = (a.wrapping_sub(b) as u32).wrapping_sub(c) ^ c >> 13;
+    let mut y = (b as u32).wrapping_sub(x).wrapping_sub(c) ^ x << 8;
+    let mut z = c.wrapping_sub(x).wrapping_sub(y) ^ y >> 13;
+    x = x.wrapping_sub(y).wrapping_sub(z) ^ z >> 12;
+    y = y.wrapping_sub(x).wrapping_sub(z) ^ x << 16;
+    z = z.wrapping_sub(x).wrapping_sub(y) ^ y >> 5;
+    x = x.wrapping_sub(y).wrapping_sub(z) ^ z >> 3;
+    y = y.wrapping_sub(x).wrapping_sub(z) ^ x << 10;
+    z.wrapping_sub(x).wrapping_sub(y) ^ y >> 15
+}
+
+// pretty sure this was some bog standard double->int conversion function of stl or something
+fn to_int_kinda(input: f64) -> u64 {
+    // let is_normal_finite = ((bits >> 32) & 0x7fff_ffff) < 0x7ff0_0000;
+    let is_normal_finite = input.is_finite();
+    let valid_range = (-9.223372036854776e18..9.223372036854776e18).contains(&input);
+
+    // could remove this check ig, I've never seen that warning
+    if !is_normal_finite || !valid_range {
+        tracing::warn!("invalid float received");
+        return (-0.0f64).to_bits();
+    }
+
+    let abs_bits = input.to_bits() & !(1 << 63);
+    let in_abs = f64::from_bits(abs_bits);
+    if in_abs == 0.0 {
+        return 0;
+    }
+
+    let exponent = abs_bits >> 52;
+    let norm_mantissa = (abs_bits & ((1 << 52) - 1)) | (1 << 52);
+
+    let shift = 0x433 - exponent as i32;
+    let mut result = if shift > 0 {
+        norm_mantissa >> (shift & 63)
+    } else {
+        norm_mantissa << (-shift & 63)
+    };
+
+    // restore the sign lol
+    if input != in_abs {
+        result = result.wrapping_neg();
+    }
+
+    result & 0xffff_ffff
+}
+
+#[cfg(test)]
+#[test]
+fn distribution_golden() {
+    let mut rng = NoitaRng::from_pos(1234, 0.0, 0.0);
+    let rolls = [(); 5].map(|_| rng.random_distribution(1, 100, 40, 2.0));
+    assert_eq!(rolls, [28, 21, 37, 30, 56]);
+
+    let mut rng = NoitaRng::from_pos(1234, 0.0, 0.0);
+    let expected = [1.012383879436324, 1.0206062792779877, 1.0323355536004526];
+    for expected in expected {
+        let v = rng.random_distribution_f(0.8, 1.2, 1.0, 6.0);
+        assert!((v - expected).abs() < 1e-9, "{v} != {expected}");
+    }
+}
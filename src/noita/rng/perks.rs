@@ -0,0 +1,212 @@
+//! Holy Mountain perk prediction, a port of `perk_get_spawn_order` from
+//! `data/scripts/perks/perk.lua` and of how the temples and the rerolls
+//! walk the resulting perk deck.
+//!
+//! The deck is built from `perk_list.lua`, which can be read from the game
+//! files with [crate::noita::Noita::read_file] and parsed with
+//! [parse_perk_list], so that it follows game updates (but not the mods
+//! that add perks from their own scripts).
+//!
+//! Stackable perks that were already picked up to their maximum are
+//! dropped from the deck later in the run, that is not accounted for here.
+
+use lazy_regex::regex;
+
+use super::NoitaRng;
+
+/// Perks a temple offers when no "extra perk" perks were picked
+pub const TEMPLE_PERK_COUNT: usize = 3;
+
+const MIN_DISTANCE_BETWEEN_DUPLICATE_PERKS: usize = 4;
+
+/// The properties of a `perk_list` entry that matter for the deck
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PerkDef {
+    pub id: String,
+    pub stackable: bool,
+    pub stackable_is_rare: bool,
+    pub max_in_perk_pool: Option<usize>,
+    pub stackable_how_often_reappears: Option<usize>,
+    pub not_in_default_perk_pool: bool,
+}
+
+/// Pull the perk definitions out of `perk_list.lua`.
+///
+/// Not a Lua parser - each entry is the text from one `id = "..."` to the
+/// next, and the fields we care about are simple enough to be found in it
+pub fn parse_perk_list(lua: &str) -> Vec<PerkDef> {
+    let ids = regex!(r#"(?m)^\s*id\s*=\s*"(\w+)""#)
+        .captures_iter(lua)
+        .map(|c| (c.get(0).unwrap().start(), c[1].to_owned()))
+        .collect::<Vec<_>>();
+
+    let flag = |entry: &str, name: &str| {
+        regex!(r"(?m)^\s*(\w+)\s*=\s*(true|STACKABLE_YES)\b")
+            .captures_iter(entry)
+            .any(|c| &c[1] == name)
+    };
+    let number = |entry: &str, name: &str| {
+        regex!(r"(?m)^\s*(\w+)\s*=\s*(\d+)")
+            .captures_iter(entry)
+            .find(|c| &c[1] == name)
+            .and_then(|c| c[2].parse().ok())
+    };
+
+    ids.iter()
+        .enumerate()
+        .map(|(i, (start, id))| {
+            let end = ids.get(i + 1).map_or(lua.len(), |(next, _)| *next);
+            let entry = &lua[*start..end];
+            PerkDef {
+                id: id.clone(),
+                stackable: flag(entry, "stackable"),
+                stackable_is_rare: flag(entry, "stackable_is_rare"),
+                max_in_perk_pool: number(entry, "max_in_perk_pool"),
+                stackable_how_often_reappears: number(entry, "stackable_how_often_reappears"),
+                not_in_default_perk_pool: flag(entry, "not_in_default_perk_pool"),
+            }
+        })
+        .collect()
+}
+
+/// The order the perks come out in for a seed
+#[derive(Debug, Clone)]
+pub struct PerkDeck(Vec<String>);
+
+impl PerkDeck {
+    /// `seed` is the world seed plus the NG+ count, same as in
+    /// [NoitaRng::from_pos]
+    pub fn new(seed: u32, perks: &[PerkDef]) -> Self {
+        let mut rng = NoitaRng::from_pos(seed, 1.0, 2.0);
+
+        // along with the distance for the stackable ones
+        let mut deck = Vec::new();
+
+        for perk in perks.iter().filter(|p| !p.not_in_default_perk_pool) {
+            let mut how_many_times = 1;
+            let mut distance = None;
+            if perk.stackable {
+                let mut max_perks = rng.random_int(1, 2);
+                if let Some(max) = perk.max_in_perk_pool {
+                    max_perks = rng.random_int(1, max as i32);
+                }
+                if perk.stackable_is_rare {
+                    max_perks = 1;
+                }
+                distance = Some(
+                    perk.stackable_how_often_reappears
+                        .unwrap_or(MIN_DISTANCE_BETWEEN_DUPLICATE_PERKS),
+                );
+                how_many_times = rng.random_int(1, max_perks);
+            }
+            for _ in 0..how_many_times {
+                deck.push((perk.id.as_str(), distance));
+            }
+        }
+
+        rng.shuffle(&mut deck);
+
+        // stackable perks too close to the same perk before them are removed
+        for i in (0..deck.len()).rev() {
+            let (perk, Some(distance)) = deck[i] else {
+                continue;
+            };
+            if deck[i.saturating_sub(distance)..i]
+                .iter()
+                .any(|(p, _)| *p == perk)
+            {
+                deck.remove(i);
+            }
+        }
+
+        Self(deck.into_iter().map(|(p, _)| p.to_owned()).collect())
+    }
+
+    pub fn perks(&self) -> &[String] {
+        &self.0
+    }
+
+    /// The perks of the temple (0 being the first Holy Mountain), after the
+    /// given number of rerolls.
+    ///
+    /// Temples take the perks from the start of the deck in order, and
+    /// rerolls take them from the end going backwards, with the count shared
+    /// between all the temples - so `reroll_count` is the number of rerolls
+    /// done in the run before. Assumes every temple and every reroll had
+    /// [TEMPLE_PERK_COUNT] perks
+    pub fn temple(&self, mountain_index: usize, reroll_count: usize) -> Vec<&str> {
+        let len = self.0.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        (0..TEMPLE_PERK_COUNT)
+            .map(|j| {
+                let idx = if reroll_count == 0 {
+                    (mountain_index * TEMPLE_PERK_COUNT + j) % len
+                } else {
+                    let back = (reroll_count - 1) * TEMPLE_PERK_COUNT + j;
+                    len - 1 - back % len
+                };
+                self.0[idx].as_str()
+            })
+            .collect()
+    }
+}
+
+/// Shorthand for building the deck and looking up one temple in it
+pub fn predict_perks(
+    seed: u32,
+    perks: &[PerkDef],
+    mountain_index: usize,
+    reroll_count: usize,
+) -> Vec<String> {
+    PerkDeck::new(seed, perks)
+        .temple(mountain_index, reroll_count)
+        .into_iter()
+        .map(str::to_owned)
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn perk_deck() {
+    let perks = parse_perk_list(
+        r#"
+perk_list =
+{
+	{
+		id = "CRITICAL_HIT",
+		ui_name = "$perk_critical_hit",
+		stackable = STACKABLE_YES,
+		func = function( entity_perk_item, entity_who_picked, item_name )
+		end,
+	},
+	{
+		id = "BREATH_UNDERWATER",
+		stackable = STACKABLE_NO,
+	},
+	{
+		id = "EXTRA_PERK",
+		stackable = STACKABLE_YES,
+		stackable_is_rare = true,
+		max_in_perk_pool = 3,
+	},
+	{
+		id = "EDIT_WANDS_EVERYWHERE",
+		not_in_default_perk_pool = true,
+	},
+}
+"#,
+    );
+    assert_eq!(perks.len(), 4);
+    assert!(perks[0].stackable && !perks[1].stackable);
+    assert!(perks[2].stackable_is_rare);
+    assert_eq!(perks[2].max_in_perk_pool, Some(3));
+    assert!(perks[3].not_in_default_perk_pool);
+
+    let deck = PerkDeck::new(1234, &perks);
+    assert!(!deck.perks().iter().any(|p| p == "EDIT_WANDS_EVERYWHERE"));
+    assert_eq!(deck.temple(0, 0).len(), TEMPLE_PERK_COUNT);
+    assert_eq!(deck.temple(0, 1)[0], deck.perks().last().unwrap().as_str());
+    assert_eq!(predict_perks(1234, &perks, 1, 0), deck.temple(1, 0));
+}
@@ -0,0 +1,170 @@
+//! Flask and pouch contents, ports of the `init` functions of
+//! `data/scripts/items/potion_starting.lua` and `potion.lua`.
+//!
+//! The starting flask only depends on the seed. The random flasks (and
+//! pouches, whose `powder_stash.lua` is laid out the same way) are seeded
+//! with their position and pick from the material tables of their script,
+//! which are parsed out of the game files like the perk list is.
+
+use lazy_regex::regex;
+
+use super::NoitaRng;
+
+/// Where `potion_starting.lua` seeds the RNG at
+const STARTING_FLASK_POS: (f64, f64) = (-4.5, -4.0);
+
+/// `seed` is the world seed plus the NG+ count, same as in
+/// [NoitaRng::from_pos], `death_count` is the one from the global stats -
+/// until the first death the flask is always water
+pub fn starting_flask(seed: u32, death_count: u32) -> &'static str {
+    if death_count == 0 {
+        return "water";
+    }
+    let mut rng = NoitaRng::from_pos(seed, STARTING_FLASK_POS.0, STARTING_FLASK_POS.1);
+
+    let r = rng.random_int(1, 100);
+    if r <= 65 {
+        match rng.random_int(1, 100) {
+            ..=10 => "mud",
+            11..=20 => "water_swamp",
+            21..=30 => "water_salt",
+            31..=40 => "swamp",
+            41..=50 => "snow",
+            _ => "water",
+        }
+    } else if r <= 70 {
+        "blood"
+    } else if r <= 99 {
+        rng.random_int(0, 100);
+        random_from_array(
+            &mut rng,
+            &[
+                "acid",
+                "magic_liquid_polymorph",
+                "magic_liquid_random_polymorph",
+                "magic_liquid_berserk",
+                "magic_liquid_charm",
+                "magic_liquid_movement_faster",
+            ],
+        )
+    } else {
+        rng.random_int(0, 100000);
+        random_from_array(
+            &mut rng,
+            &[
+                "magic_liquid_hp_regeneration_unstable",
+                "blood_worm",
+                "gold",
+                "snow",
+            ],
+        )
+    }
+}
+
+/// `random_from_array` of the game scripts
+fn random_from_array<'a, T: ?Sized>(rng: &mut NoitaRng, items: &[&'a T]) -> &'a T {
+    items[rng.random_int(1, items.len() as i32) as usize - 1]
+}
+
+/// The `materials_standard` and `materials_magic` tables of a potion script
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PotionTables {
+    pub standard: Vec<String>,
+    pub magic: Vec<String>,
+}
+
+impl PotionTables {
+    /// Not a Lua parser either, picks the `material="..."` values out of the
+    /// top-level tables with those names
+    pub fn parse(lua: &str) -> Self {
+        let table = |name: &str| {
+            let Some(start) = lua.find(&format!("{name} =")) else {
+                return Vec::new();
+            };
+            let body = &lua[start..];
+            // the closing brace of the table is the first one not indented
+            let end = regex!(r"(?m)^\}")
+                .find(body)
+                .map_or(body.len(), |m| m.end());
+            regex!(r#"material\s*=\s*"(\w+)""#)
+                .captures_iter(&body[..end])
+                .map(|c| c[1].to_owned())
+                .collect()
+        };
+        Self {
+            standard: table("materials_standard"),
+            magic: table("materials_magic"),
+        }
+    }
+}
+
+/// What a flask or pouch entity at the given position contains, None if
+/// the tables are empty
+pub fn predict_potion(seed: u32, x: f64, y: f64, tables: &PotionTables) -> Option<String> {
+    let mut rng = NoitaRng::from_pos(seed, x, y);
+
+    let table = if rng.random_int(0, 100) <= 75 {
+        if rng.random_int(0, 100000) <= 50 {
+            return Some("magic_liquid_hp_regeneration".into());
+        }
+        if rng.random_int(200, 100000) <= 250 {
+            return Some("purifying_powder".into());
+        }
+        &tables.magic
+    } else {
+        &tables.standard
+    };
+    if table.is_empty() {
+        return None;
+    }
+    let items = table.iter().map(String::as_str).collect::<Vec<_>>();
+    Some(random_from_array(&mut rng, &items).to_owned())
+}
+
+#[cfg(test)]
+#[test]
+fn potions() {
+    let tables = PotionTables::parse(
+        r#"
+materials_standard =
+{
+	{
+		material="lava",
+		cost=300,
+	},
+	{
+		material="water",
+		cost=200,
+	},
+}
+
+materials_magic =
+{
+	{
+		material="acid",
+		cost=200,
+	},
+}
+
+function init( entity_id )
+	local potion_material = "water"
+end
+"#,
+    );
+    assert_eq!(tables.standard, ["lava", "water"]);
+    assert_eq!(tables.magic, ["acid"]);
+
+    for x in 0..50 {
+        let potion = predict_potion(1234, x as f64, 0.0, &tables).unwrap();
+        assert_eq!(
+            Some(&potion),
+            predict_potion(1234, x as f64, 0.0, &tables).as_ref()
+        );
+    }
+    assert_eq!(starting_flask(1234, 5), starting_flask(1234, 1));
+    assert_eq!(starting_flask(1234, 0), "water");
+    assert_eq!(
+        predict_potion(1234, 0.0, 0.0, &PotionTables::default()),
+        None
+    );
+}
@@ -0,0 +1,368 @@
+//! Procedural wand stats, a port of `get_gun_data` and friends from
+//! `data/scripts/gun/procedural/gun_procedural.lua`.
+//!
+//! The wand scripts seed the RNG with the position of the wand entity and
+//! then generate the stats from the "cost" (the budget the stats are bought
+//! with) and the level of the wand, e.g. `generate_gun(30, 1, false)` for the
+//! first tier of wands.
+//!
+//! The spells are picked afterwards by `GetRandomActionWithType`, which lives
+//! in the engine and needs the spell list of the game, so only the stats are
+//! predicted here.
+
+use super::NoitaRng;
+
+/// One of the distributions a stat can be rolled with
+#[derive(Debug, Clone, Copy)]
+struct Probs {
+    prob: f64,
+    min: f64,
+    max: f64,
+    mean: f64,
+    sharpness: f64,
+}
+
+const fn p(prob: f64, min: f64, max: f64, mean: f64, sharpness: f64) -> Probs {
+    Probs {
+        prob,
+        min,
+        max,
+        mean,
+        sharpness,
+    }
+}
+
+/// `gun_probs` from the script
+const DECK_CAPACITY: &[Probs] = &[
+    p(1.0, 3.0, 10.0, 6.0, 2.0),
+    p(0.1, 2.0, 7.0, 4.0, 4.0),
+    p(0.05, 1.0, 5.0, 3.0, 4.0),
+    p(0.15, 5.0, 11.0, 8.0, 2.0),
+    p(0.12, 2.0, 20.0, 8.0, 4.0),
+    p(0.15, 3.0, 12.0, 6.0, 6.0),
+    p(1.0, 1.0, 20.0, 6.0, 0.0),
+];
+const RELOAD_TIME: &[Probs] = &[
+    p(1.0, 5.0, 60.0, 30.0, 2.0),
+    p(0.5, 1.0, 100.0, 40.0, 2.0),
+    p(0.02, 1.0, 100.0, 40.0, 0.0),
+    p(0.35, 1.0, 240.0, 40.0, 0.0),
+];
+const FIRE_RATE_WAIT: &[Probs] = &[
+    p(1.0, 1.0, 30.0, 5.0, 2.0),
+    p(0.1, 1.0, 50.0, 15.0, 3.0),
+    p(0.1, -15.0, 15.0, 0.0, 3.0),
+    p(0.45, 0.0, 35.0, 12.0, 0.0),
+];
+const SPREAD_DEGREES: &[Probs] = &[p(1.0, -5.0, 10.0, 0.0, 3.0), p(0.1, -35.0, 35.0, 0.0, 0.0)];
+const SPEED_MULTIPLIER: &[Probs] = &[
+    p(1.0, 0.8, 1.2, 1.0, 6.0),
+    p(0.05, 1.0, 2.0, 1.1, 3.0),
+    p(0.05, 0.5, 1.0, 0.9, 3.0),
+    p(1.0, 0.8, 1.2, 1.0, 0.0),
+    p(0.001, 1.0, 10.0, 5.0, 2.0),
+];
+const ACTIONS_PER_ROUND: &[Probs] = &[
+    p(1.0, 1.0, 3.0, 1.0, 3.0),
+    p(0.2, 2.0, 4.0, 2.0, 8.0),
+    p(0.05, 1.0, 5.0, 2.0, 2.0),
+    p(1.0, 1.0, 5.0, 2.0, 0.0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stat {
+    ReloadTime,
+    FireRateWait,
+    SpreadDegrees,
+    SpeedMultiplier,
+    DeckCapacity,
+    ShuffleDeckWhenEmpty,
+    ActionsPerRound,
+}
+
+/// Stats of a generated wand
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wand {
+    pub is_rare: bool,
+    pub shuffle: bool,
+    pub actions_per_round: i32,
+    pub deck_capacity: i32,
+    /// In frames
+    pub reload_time: i32,
+    /// In frames
+    pub fire_rate_wait: i32,
+    pub spread_degrees: f64,
+    pub speed_multiplier: f64,
+    pub mana_max: f64,
+    pub mana_charge_speed: f64,
+}
+
+/// The `gun` table of the script, everything's a Lua number there
+#[derive(Debug, Default)]
+struct Gun {
+    cost: f64,
+    force_unshuffle: bool,
+    is_rare: bool,
+    shuffle_deck_when_empty: bool,
+    deck_capacity: f64,
+    actions_per_round: f64,
+    reload_time: f64,
+    fire_rate_wait: f64,
+    spread_degrees: f64,
+    speed_multiplier: f64,
+    mana_max: f64,
+    mana_charge_speed: f64,
+}
+
+/// Predict the stats of a wand spawned at the given position.
+/// `seed` is the world seed plus the NG+ count, same as in [NoitaRng::from_pos]
+pub fn predict_wand(
+    seed: u32,
+    x: f64,
+    y: f64,
+    cost: f64,
+    level: i32,
+    force_unshuffle: bool,
+) -> Wand {
+    let mut rng = NoitaRng::from_pos(seed, x, y);
+    let gun = get_gun_data(&mut rng, cost, level, force_unshuffle);
+
+    // the fractional parts are lost when they're set on the wand component
+    Wand {
+        is_rare: gun.is_rare,
+        shuffle: gun.shuffle_deck_when_empty,
+        actions_per_round: gun.actions_per_round as i32,
+        deck_capacity: gun.deck_capacity as i32,
+        reload_time: gun.reload_time as i32,
+        fire_rate_wait: gun.fire_rate_wait as i32,
+        spread_degrees: gun.spread_degrees,
+        speed_multiplier: gun.speed_multiplier,
+        mana_max: gun.mana_max,
+        mana_charge_speed: gun.mana_charge_speed,
+    }
+}
+
+fn get_gun_data(rng: &mut NoitaRng, mut cost: f64, level: i32, force_unshuffle: bool) -> Gun {
+    if level == 1 && rng.random_int(0, 100) < 50 {
+        cost += 5.0;
+    }
+    cost += rng.random_int(-3, 3) as f64;
+
+    let level_f = level as f64;
+    let mut gun = Gun {
+        cost,
+        shuffle_deck_when_empty: true,
+        mana_charge_speed: (50 * level + rng.random_int(-5, 5 * level)) as f64,
+        mana_max: 50.0 + 150.0 * level_f + (rng.random_int(-5, 5) * 10) as f64,
+        ..Default::default()
+    };
+
+    // slow mana charger
+    if rng.random_int(0, 100) < 20 {
+        gun.mana_charge_speed = (50 * level + rng.random_int(-5, 5 * level)) as f64 / 5.0;
+        gun.mana_max = (50.0 + 150.0 * level_f + (rng.random_int(-5, 5) * 10) as f64) * 3.0;
+        if gun.mana_charge_speed < 10.0 {
+            gun.mana_charge_speed = 10.0;
+        }
+    }
+
+    // really fast mana charger
+    if rng.random_int(0, 100) < 15 {
+        gun.mana_charge_speed = (50 * level + rng.random_int(-5, 5 * level)) as f64 * 5.0;
+        gun.mana_max = (50.0 + 150.0 * level_f + (rng.random_int(-5, 5) * 10) as f64) / 3.0;
+    }
+
+    if gun.mana_max < 50.0 {
+        gun.mana_max = 50.0;
+    }
+    if gun.mana_charge_speed < 10.0 {
+        gun.mana_charge_speed = 10.0;
+    }
+
+    if rng.random_int(0, 100) < 15 + level * 6 {
+        gun.force_unshuffle = true;
+    }
+
+    if rng.random_int(0, 100) < 5 {
+        gun.is_rare = true;
+        gun.cost += 65.0;
+    }
+
+    let mut vars_01 = [
+        Stat::ReloadTime,
+        Stat::FireRateWait,
+        Stat::SpreadDegrees,
+        Stat::SpeedMultiplier,
+    ];
+    let mut vars_03 = [Stat::ShuffleDeckWhenEmpty, Stat::ActionsPerRound];
+
+    rng.shuffle(&mut vars_01);
+    if !gun.force_unshuffle {
+        rng.shuffle(&mut vars_03);
+    }
+
+    for stat in vars_01
+        .into_iter()
+        .chain([Stat::DeckCapacity])
+        .chain(vars_03)
+    {
+        apply_random_variable(rng, &mut gun, stat);
+    }
+
+    // the leftover cost goes into the capacity
+    if gun.cost > 5.0 && rng.random_int(0, 1000) < 995 {
+        gun.deck_capacity += if gun.shuffle_deck_when_empty {
+            gun.cost / 5.0
+        } else {
+            gun.cost / 10.0
+        };
+        gun.cost = 0.0;
+    }
+
+    if force_unshuffle {
+        gun.shuffle_deck_when_empty = false;
+    }
+
+    // yes, one in ten thousand wands gets to go over 26
+    if rng.random_int(0, 10000) <= 9999 {
+        gun.deck_capacity = gun.deck_capacity.clamp(2.0, 26.0);
+    }
+    if gun.deck_capacity <= 1.0 {
+        gun.deck_capacity = 2.0;
+    }
+    gun
+}
+
+/// `get_gun_probs`, picks one of the distributions by their weights
+fn get_gun_probs(rng: &mut NoitaRng, probs: &'static [Probs]) -> &'static Probs {
+    let total = probs.iter().map(|p| p.prob).sum::<f64>();
+    let mut r = rng.random() * total;
+    for p in probs {
+        if r <= p.prob {
+            return p;
+        }
+        r -= p.prob;
+    }
+    // only reachable through float rounding, and the script returns nil
+    // (and errors out) in that case
+    &probs[probs.len() - 1]
+}
+
+fn roll(rng: &mut NoitaRng, p: &Probs) -> f64 {
+    rng.random_distribution(p.min as i32, p.max as i32, p.mean as i32, p.sharpness) as f64
+}
+
+fn apply_random_variable(rng: &mut NoitaRng, gun: &mut Gun, stat: Stat) {
+    let cost = gun.cost;
+    match stat {
+        Stat::ReloadTime => {
+            let p = get_gun_probs(rng, RELOAD_TIME);
+            let min = (60.0 - cost * 5.0).clamp(1.0, 240.0);
+            gun.reload_time = roll(rng, p).clamp(min, 1024.0);
+            gun.cost -= (60.0 - gun.reload_time) / 5.0;
+        }
+        Stat::FireRateWait => {
+            let p = get_gun_probs(rng, FIRE_RATE_WAIT);
+            let min = (16.0 - cost).clamp(-50.0, 50.0);
+            gun.fire_rate_wait = roll(rng, p).clamp(min, 50.0);
+            gun.cost -= 16.0 - gun.fire_rate_wait;
+        }
+        Stat::SpreadDegrees => {
+            let p = get_gun_probs(rng, SPREAD_DEGREES);
+            let min = (cost / -1.5).clamp(-35.0, 35.0);
+            gun.spread_degrees = roll(rng, p).clamp(min, 35.0);
+            gun.cost -= 16.0 - gun.spread_degrees;
+        }
+        Stat::SpeedMultiplier => {
+            let p = get_gun_probs(rng, SPEED_MULTIPLIER);
+            gun.speed_multiplier = rng.random_distribution_f(p.min, p.max, p.mean, p.sharpness);
+        }
+        Stat::DeckCapacity => {
+            let p = get_gun_probs(rng, DECK_CAPACITY);
+            let mut max = ((cost / 5.0) - 6.0).clamp(1.0, 20.0);
+            if gun.force_unshuffle {
+                max = (cost - 15.0) / 5.0;
+                if max > 6.0 {
+                    max = 6.0 + (cost - (15.0 + 6.0 * 5.0)) / 10.0;
+                }
+            }
+            let max = max.clamp(1.0, 20.0);
+            gun.deck_capacity = roll(rng, p).clamp(1.0, max);
+            gun.cost -= (gun.deck_capacity - 6.0) * 5.0;
+        }
+        Stat::ShuffleDeckWhenEmpty => {
+            let deck_capacity = gun.deck_capacity;
+            let mut random = rng.random_int(0, 1);
+            if gun.force_unshuffle {
+                random = 1;
+            }
+            let unshuffle_cost = 15.0 + deck_capacity * 5.0;
+            if random == 1 && cost >= unshuffle_cost && deck_capacity <= 9.0 {
+                gun.shuffle_deck_when_empty = false;
+                gun.cost -= unshuffle_cost;
+            }
+        }
+        Stat::ActionsPerRound => {
+            let p = get_gun_probs(rng, ACTIONS_PER_ROUND);
+            let deck_capacity = gun.deck_capacity;
+            let action_costs = [
+                0.0,
+                5.0 + deck_capacity * 2.0,
+                15.0 + deck_capacity * 3.5,
+                35.0 + deck_capacity * 5.0,
+                45.0 + deck_capacity * deck_capacity,
+            ];
+            let max = action_costs
+                .iter()
+                .rposition(|&c| c <= cost)
+                .map_or(1.0, |i| (i + 1) as f64);
+            let max = max.clamp(1.0, deck_capacity);
+            gun.actions_per_round = roll(rng, p).clamp(1.0, max).floor();
+            let i = gun.actions_per_round.clamp(1.0, action_costs.len() as f64) as usize;
+            gun.cost -= action_costs[i - 1];
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn wand_stats() {
+    let wand = predict_wand(1234, 100.0, -200.0, 30.0, 1, false);
+    assert_eq!(wand, predict_wand(1234, 100.0, -200.0, 30.0, 1, false));
+
+    for x in 0..200 {
+        let wand = predict_wand(1234, x as f64, 0.0, 60.0, 2, true);
+        assert!(!wand.shuffle);
+        assert!(wand.mana_max >= 50.0 && wand.mana_charge_speed >= 10.0);
+        assert!(wand.deck_capacity >= 2 && wand.actions_per_round >= 1);
+        assert!((-35.0..=35.0).contains(&wand.spread_degrees));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn wand_golden() {
+    let wand = predict_wand(1234, 100.0, -200.0, 30.0, 1, false);
+    assert_eq!(
+        (wand.shuffle, wand.actions_per_round, wand.deck_capacity),
+        (false, 1, 3)
+    );
+    assert_eq!((wand.reload_time, wand.fire_rate_wait), (21, 28));
+    assert_eq!((wand.spread_degrees, wand.mana_max), (-3.0, 210.0));
+    assert_eq!(wand.mana_charge_speed, 47.0);
+    assert!((wand.speed_multiplier - 0.883336587470905).abs() < 1e-9);
+
+    let wand = predict_wand(786433191, -3000.0, 5400.0, 60.0, 2, false);
+    assert_eq!(
+        (wand.actions_per_round, wand.deck_capacity, wand.reload_time),
+        (2, 5, 35)
+    );
+    assert_eq!((wand.mana_max, wand.mana_charge_speed), (100.0, 500.0));
+
+    let wand = predict_wand(42, 512.0, 1024.0, 120.0, 4, true);
+    assert_eq!(
+        (wand.deck_capacity, wand.reload_time, wand.fire_rate_wait),
+        (10, 26, 6)
+    );
+    assert_eq!((wand.mana_max, wand.mana_charge_speed), (660.0, 195.0));
+}
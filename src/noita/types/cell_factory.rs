@@ -1,8 +1,8 @@
 use std::io;
 
 use crate::memory::{
-    ByteBool, MemoryStorage, PadBool, Pod, ProcessRef, Ptr, RawPtr, StdMap, StdString, StdVec,
-    Vftable,
+    assert_layout, ByteBool, MemoryStorage, PadBool, Pod, ProcessRef, Ptr, RawPtr, StdMap,
+    StdString, StdVec, Vftable,
 };
 use derive_more::Debug;
 use open_enum::open_enum;
@@ -49,6 +49,14 @@ impl CellFactory {
         Ok(res)
     }
 
+    /// Index of the material the pointer into `cell_data` points at
+    pub fn material_index(&self, ptr: Ptr<CellData>) -> Option<u32> {
+        let offset = ptr.addr().checked_sub(self.cell_data.get(0)?.addr())?;
+        let size = size_of::<CellData>() as u32;
+        let index = offset / size;
+        (offset % size == 0 && index < self.number_of_materials).then_some(index)
+    }
+
     pub fn lookup_reaction(&self, proc: &ProcessRef, input: u32) -> io::Result<Vec<CellReaction>> {
         let mut res = self.reaction_lookup.lookup(proc, input)?;
         res.extend(self.fast_reaction_lookup.lookup(proc, input)?);
@@ -166,7 +174,7 @@ pub struct CellData {
     pub transformed: PadBool<1>,
     pub particle_effect: Ptr<ParticleConfig>,
 }
-const _: () = assert!(std::mem::size_of::<CellData>() == 0x290);
+assert_layout!(CellData: 0x290);
 
 #[derive(FromBytes, IntoBytes, Clone)]
 #[repr(C)]
@@ -241,7 +249,7 @@ pub struct CellGraphics {
     #[debug(skip)]
     _unknown: [u8; 0x18],
 }
-const _: () = assert!(std::mem::size_of::<CellGraphics>() == 0x40);
+assert_layout!(CellGraphics: 0x40);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -318,7 +326,7 @@ pub struct ConfigExplosion {
     pub impl_position: Vec2,
     pub impl_delay_frame: i32,
 }
-const _: () = assert!(std::mem::size_of::<ConfigExplosion>() == 0x174);
+assert_layout!(ConfigExplosion: 0x174);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -328,7 +336,7 @@ pub struct ConfigDamageCritical {
     pub damage_multiplier: f32,
     pub m_succeeded: PadBool<3>,
 }
-const _: () = assert!(std::mem::size_of::<ConfigDamageCritical>() == 0x10);
+assert_layout!(ConfigDamageCritical: 0x10);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -373,7 +381,7 @@ pub struct ParticleConfig {
     pub particle_single_width: ByteBool,
     pub fade_based_on_lifetime: PadBool<2>,
 }
-const _: () = assert!(std::mem::size_of::<ParticleConfig>() == 0x54);
+assert_layout!(ParticleConfig: 0x54);
 
 #[open_enum]
 #[repr(i32)]
@@ -411,7 +419,7 @@ pub struct CellReaction {
     pub explosion_config: Ptr<ConfigExplosion>,
     pub audio_fx_volume_1: f32,
 }
-const _: () = assert!(std::mem::size_of::<CellReaction>() == 0x44);
+assert_layout!(CellReaction: 0x44);
 
 impl CellReaction {
     pub fn pretty_print(&self, materials: &[String]) -> String {
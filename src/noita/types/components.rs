@@ -1,7 +1,8 @@
 use zerocopy::{FromBytes, IntoBytes};
 
 use crate::memory::{
-    Align4, ByteBool, CString, PadBool, Ptr, StdMap, StdString, StdVec, Vftable, WithPad,
+    assert_layout, Align4, ByteBool, CString, PadBool, Ptr, StdMap, StdString, StdVec, Vftable,
+    WithPad,
 };
 
 use super::{Bitset256, Entity, Vec2, Vec2i};
@@ -214,7 +215,7 @@ pub struct DamageModelComponent {
     pub m_fire_damage_buffered: f32,
     pub m_fire_damage_buffered_next_delivery_frame: i32,
 }
-const _: () = assert!(std::mem::size_of::<DamageModelComponent>() == 0x294);
+assert_layout!(DamageModelComponent: 0x294);
 
 impl ComponentName for DamageModelComponent {
     const NAME: &str = "DamageModelComponent";
@@ -240,7 +241,7 @@ pub struct ConfigDamagesByType {
     pub curse: f32,
     pub holy: f32,
 }
-const _: () = assert!(std::mem::size_of::<ConfigDamagesByType>() == 0x40);
+assert_layout!(ConfigDamagesByType: 0x40);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -269,7 +270,7 @@ pub struct ConfigPendingPortal {
     pub target_biome_name: StdString,
     pub entity: Ptr<Entity>,
 }
-const _: () = assert!(std::mem::size_of::<ConfigPendingPortal>() == 0x3c);
+assert_layout!(ConfigPendingPortal: 0x3c);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -282,7 +283,7 @@ pub struct ConfigNpcParty {
     pub member_entities: StdVec<u32>,
     pub member_files: StdVec<StdString>,
 }
-const _: () = assert!(std::mem::size_of::<ConfigNpcParty>() == 0x30);
+assert_layout!(ConfigNpcParty: 0x30);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -295,7 +296,7 @@ pub struct ConfigCutThroughWorld {
     pub edge_darkening_width: i32,
     pub global_id: u32,
 }
-const _: () = assert!(std::mem::size_of::<ConfigCutThroughWorld>() == 0x1c);
+assert_layout!(ConfigCutThroughWorld: 0x1c);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -363,8 +364,114 @@ pub struct WorldStateComponent {
     pub fog_target_extra: f32,
     pub perk_rats_player_friendly_prev: WithPad<ByteBool, 3>,
 }
-const _: () = assert!(std::mem::size_of::<WorldStateComponent>() == 0x180);
+assert_layout!(WorldStateComponent: 0x180);
 
 impl ComponentName for WorldStateComponent {
     const NAME: &str = "WorldStateComponent";
 }
+
+// the two below stop before their first bool, the padding of the bools
+// and everything after them is still to be checked against the game
+
+#[derive(FromBytes, IntoBytes, Debug)]
+#[repr(C)]
+pub struct CharacterDataComponent {
+    pub platforming_type: i32,
+    pub mass: f32,
+    pub buoyancy_check_offset_y: i32,
+    pub liquid_velocity_coeff: f32,
+    pub gravity: f32,
+    pub fly_recharge_spd: f32,
+    pub fly_recharge_spd_ground: f32,
+}
+assert_layout!(CharacterDataComponent: 0x1c);
+
+impl ComponentName for CharacterDataComponent {
+    const NAME: &str = "CharacterDataComponent";
+}
+
+#[derive(FromBytes, IntoBytes, Debug)]
+#[repr(C)]
+pub struct CharacterPlatformingComponent {
+    pub jump_velocity_x: f32,
+    pub jump_velocity_y: f32,
+    pub jump_keydown_buffer: i32,
+    pub fly_velocity_x: f32,
+    pub fly_speed_mult: f32,
+    pub fly_speed_change_spd: f32,
+}
+assert_layout!(CharacterPlatformingComponent: 0x18);
+
+impl ComponentName for CharacterPlatformingComponent {
+    const NAME: &str = "CharacterPlatformingComponent";
+}
+
+/// Only the herd id for now, the fields after it (is_predator,
+/// food_chain_rank, the berserk and friend ones) are left out until their
+/// types and offsets are checked against the game
+#[derive(FromBytes, IntoBytes, Debug)]
+#[repr(C)]
+pub struct GenomeDataComponent {
+    pub herd_id: LensValue<i32>,
+}
+assert_layout!(GenomeDataComponent: 0xc);
+
+impl ComponentName for GenomeDataComponent {
+    const NAME: &str = "GenomeDataComponent";
+}
+
+/// All the vectors are indexed by the status effect index (as in
+/// data/scripts/status_effects.lua order, starting from 1 with 0 being
+/// unused), stains are 0..1 fractions
+#[derive(FromBytes, IntoBytes, Debug)]
+#[repr(C)]
+pub struct StatusEffectDataComponent {
+    pub stain_effects: StdVec<f32>,
+    pub stain_effect_cooldowns: StdVec<i32>,
+    pub effects_previous: StdVec<f32>,
+    pub ingestion_effects: StdVec<f32>,
+    pub ingestion_effect_causes: StdVec<i32>,
+    pub ingestion_effect_causes_many: StdVec<i32>,
+    pub m_last_attacking_player_frame: i32,
+    /// What the stain bars in the HUD actually show
+    pub m_stain_effects_smoothed_for_ui: StdVec<f32>,
+    pub m_has_child_icons_cached: PadBool<3>,
+}
+assert_layout!(StatusEffectDataComponent: 0x5c {
+    m_last_attacking_player_frame: 0x48,
+    m_stain_effects_smoothed_for_ui: 0x4c,
+});
+
+impl ComponentName for StatusEffectDataComponent {
+    const NAME: &str = "StatusEffectDataComponent";
+}
+
+#[derive(FromBytes, IntoBytes, Debug)]
+#[repr(C)]
+pub struct Inventory2Component {
+    pub quick_inventory_slots: i32,
+    pub full_inventory_slots_x: i32,
+    pub full_inventory_slots_y: i32,
+    pub m_saved_active_item_index: u32,
+    pub m_active_item: u32,
+    pub m_actual_active_item: u32,
+    pub m_active_stash: u32,
+    pub m_throw_item: u32,
+    pub m_item_holstered: ByteBool,
+    pub m_initialized: ByteBool,
+    pub m_force_refresh: ByteBool,
+    pub m_dont_log_next_item_equip: ByteBool,
+    pub m_smoothed_item_x_offset: f32,
+    pub m_last_item_switch_frame: i32,
+    pub m_intro_equip_item_lerp: f32,
+    pub m_smoothed_item_angle_vec: Vec2,
+}
+assert_layout!(Inventory2Component: 0x34 {
+    m_active_item: 0x10,
+    m_actual_active_item: 0x14,
+    m_item_holstered: 0x1c,
+});
+
+impl ComponentName for Inventory2Component {
+    const NAME: &str = "Inventory2Component";
+}
@@ -5,16 +5,21 @@ use std::{
     io,
     ops::Index,
 };
+use world::GridWorld;
 
 use zerocopy::{FromBytes, IntoBytes};
 
 use crate::memory::{
-    ByteBool, MemoryStorage, PadBool, ProcessRef, Ptr, RawPtr, StdMap, StdString, StdVec, Vftable,
+    assert_layout, ByteBool, MemoryStorage, PadBool, ProcessRef, Ptr, RawPtr, StdMap, StdString,
+    StdVec, Vftable,
 };
 
+use super::versioned::Versioned;
+
 pub mod cell_factory;
 pub mod components;
 pub mod platform;
+pub mod world;
 
 #[derive(FromBytes, IntoBytes, Clone, Copy)]
 #[repr(C)]
@@ -78,7 +83,7 @@ impl Debug for Vec2i {
     }
 }
 
-#[derive(FromBytes, IntoBytes, Debug)]
+#[derive(FromBytes, IntoBytes, Debug, Clone)]
 #[repr(C)]
 pub struct EntityTransform {
     pub pos: Vec2,
@@ -87,7 +92,7 @@ pub struct EntityTransform {
     pub scale: Vec2,
 }
 
-#[derive(FromBytes, IntoBytes, Debug)]
+#[derive(FromBytes, IntoBytes, Debug, Clone)]
 #[repr(C)]
 pub struct Entity {
     pub id: u32,
@@ -103,6 +108,8 @@ pub struct Entity {
     pub parent: Ptr<Entity>,
 }
 
+impl Versioned for Entity {}
+
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
 pub struct EntityManager {
@@ -143,11 +150,13 @@ pub struct TagManager {
 #[repr(C)]
 pub struct GameGlobal {
     pub frame_counter: u32,
-    _skip: [u32; 5],
+    _skip: [u32; 3],
+    pub grid_world: Ptr<GridWorld>,
+    _skip1: u32,
     pub cell_factory: Ptr<CellFactory>,
     _skip2: [u32; 97],
 }
-const _: () = assert!(std::mem::size_of::<GameGlobal>() == 0x1a0);
+assert_layout!(GameGlobal: 0x1a0 { grid_world: 0x10, cell_factory: 0x18 });
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -166,6 +175,10 @@ pub struct ComponentBuffer {
     _skip2: [u8; 0x24],
     pub storage: StdVec<RawPtr>,
 }
+assert_layout!(ComponentBuffer {
+    indices: 0x10,
+    storage: 0x40
+});
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -251,4 +264,4 @@ pub struct Language {
     pub ui_configurecontrols_offset2: f32,
     pub strings: StdVec<StdString>,
 }
-const _: () = assert!(std::mem::size_of::<Language>() == 0xb4);
+assert_layout!(Language: 0xb4);
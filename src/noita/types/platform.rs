@@ -4,8 +4,8 @@ use open_enum::open_enum;
 use zerocopy::{FromBytes, IntoBytes};
 
 use crate::memory::{
-    Align4, ByteBool, MemoryStorage, ProcessRef, Ptr, Raw, RawPtr, StdMap, StdString, StdVec,
-    StdWstring, Vftable, WithPad,
+    assert_layout, Align4, ByteBool, MemoryStorage, ProcessRef, Ptr, Raw, RawPtr, StdMap,
+    StdString, StdVec, StdWstring, Vftable, WithPad,
 };
 
 use super::{cell_factory::CSafeArray, Vec2};
@@ -45,7 +45,7 @@ pub struct PlatformWin {
     pub random_seed: i32,
     pub joysticks_enabled: WithPad<ByteBool, 3>,
 }
-const _: () = assert!(std::mem::size_of::<PlatformWin>() == 0xac);
+assert_layout!(PlatformWin: 0xac);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -55,7 +55,7 @@ pub struct ControlsConfigKey {
     pub primary_name: StdString,
     pub secondary_name: StdString,
 }
-const _: () = assert!(std::mem::size_of::<ControlsConfigKey>() == 0x38);
+assert_layout!(ControlsConfigKey: 0x38);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -93,7 +93,7 @@ pub struct ControlsConfig {
     pub gamepad_analog_sticks_threshold: f32,
     pub gamepad_analog_buttons_threshold: f32,
 }
-const _: () = assert!(std::mem::size_of::<ControlsConfig>() == 0x698);
+assert_layout!(ControlsConfig: 0x698);
 
 #[open_enum]
 #[repr(u32)]
@@ -127,7 +127,7 @@ pub struct GraphicsSettings {
     pub current_display: u32,
     pub external_graphics_context: RawPtr,
 }
-const _: () = assert!(std::mem::size_of::<GraphicsSettings>() == 0x4c);
+assert_layout!(GraphicsSettings: 0x4c);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -148,7 +148,7 @@ pub struct AppConfig {
     pub graphics_settings: GraphicsSettings,
     pub set_random_seed_cb: RawPtr, // a function pointer (aka useless)
 }
-const _: () = assert!(std::mem::size_of::<AppConfig>() == 0x88);
+assert_layout!(AppConfig: 0x88);
 
 #[derive(FromBytes, IntoBytes, Debug)]
 #[repr(C)]
@@ -213,7 +213,7 @@ pub struct WizardAppConfig {
     pub _unknown_string: StdString,
     pub debug_dont_load_other_config: WithPad<ByteBool, 3>,
 }
-const _: () = assert!(std::mem::size_of::<WizardAppConfig>() == 0xed0);
+assert_layout!(WizardAppConfig: 0xed0);
 
 #[derive(FromBytes, IntoBytes, derive_more::Debug, Clone)]
 #[repr(C)]
@@ -224,7 +224,7 @@ pub struct FileSystem {
     pub default_device: Ptr<DiskFileDevice>,
     pub default_device_2: Ptr<DiskFileDevice>,
 }
-const _: () = assert!(std::mem::size_of::<FileSystem>() == 0x24);
+assert_layout!(FileSystem: 0x24);
 
 #[open_enum]
 #[repr(u32)]
@@ -259,7 +259,7 @@ pub struct ModFileEntry {
     pub unknown: i32,
     pub override_with: StdString,
 }
-const _: () = assert!(std::mem::size_of::<ModFileEntry>() == 0x44);
+assert_layout!(ModFileEntry: 0x44);
 
 impl IFileDevice for ModDiskFileDeviceCaching {
     /// Loosely follows the ModDiskFileDeviceCaching::OpenRead
@@ -0,0 +1,177 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io,
+};
+
+use derive_more::Debug;
+use zerocopy::{FromBytes, IntoBytes};
+
+use crate::memory::{assert_layout, ByteBool, MemoryStorage, ProcessRef, Ptr, RawPtr, Vftable};
+
+use super::{cell_factory::CellData, Vec2i};
+
+/// Chunks are 512x512 pixels, and the chunk map is 512x512 chunks, wrapping
+/// around
+const CHUNK_BITS: i32 = 9;
+const CHUNK_MASK: i32 = (1 << CHUNK_BITS) - 1;
+
+#[derive(FromBytes, IntoBytes, Debug)]
+#[repr(C)]
+pub struct GridWorld {
+    pub vftable: Vftable,
+    _skip: [u32; 0x13f],
+    pub chunk_map: ChunkMap,
+}
+assert_layout!(GridWorld { chunk_map: 0x500 });
+
+#[derive(FromBytes, IntoBytes, Debug, Clone)]
+#[repr(C)]
+pub struct ChunkMap {
+    _skip: [u32; 2],
+    /// 512*512 chunk pointers, null for chunks that aren't loaded
+    pub chunks: Ptr<Ptr<Chunk>>,
+    pub chunk_count: u32,
+    pub min_chunk: Vec2i,
+    pub max_chunk: Vec2i,
+    pub min_pixel: Vec2i,
+    pub max_pixel: Vec2i,
+}
+
+#[derive(FromBytes, IntoBytes, Debug)]
+#[repr(C)]
+pub struct Chunk {
+    /// 512*512 cell pointers, null for empty (air) cells
+    pub cells: Ptr<Ptr<Cell>>,
+}
+
+#[derive(FromBytes, IntoBytes, Debug)]
+#[repr(C)]
+pub struct Cell {
+    pub vftable: Vftable,
+    pub hp: i32,
+    _skip: [u32; 2],
+    pub is_burning: ByteBool,
+    pub fire_temperature: u8,
+    _pad: [u8; 2],
+    pub material: Ptr<CellData>,
+}
+assert_layout!(Cell: 0x18 { is_burning: 0x10, material: 0x14 });
+
+/// A rectangle of world pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    pub const fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// A square of the given radius around the point
+    pub const fn around(x: i32, y: i32, radius: u32) -> Self {
+        Self::new(
+            x - radius as i32,
+            y - radius as i32,
+            radius * 2 + 1,
+            radius * 2 + 1,
+        )
+    }
+
+    pub const fn area(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+}
+
+/// Material counts of some area of the world
+#[derive(Debug, Default, Clone)]
+pub struct MaterialHistogram {
+    /// Material index -> number of cells
+    pub counts: HashMap<u32, u32>,
+    /// Air, or cells in chunks that are not loaded
+    pub empty: u32,
+}
+
+impl MaterialHistogram {
+    pub fn add(&mut self, material: Option<u32>) {
+        match material {
+            Some(material) => *self.counts.entry(material).or_default() += 1,
+            None => self.empty += 1,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.counts.values().sum::<u32>() + self.empty
+    }
+
+    /// (material index, count) pairs, most common first
+    pub fn sorted(&self) -> Vec<(u32, u32)> {
+        let mut counts = self
+            .counts
+            .iter()
+            .map(|(m, c)| (*m, *c))
+            .collect::<Vec<_>>();
+        counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+fn chunk_index(x: i32, y: i32) -> u32 {
+    let cx = ((x >> CHUNK_BITS) - 256) & CHUNK_MASK;
+    let cy = ((y >> CHUNK_BITS) - 256) & CHUNK_MASK;
+    (cy << CHUNK_BITS | cx) as u32
+}
+
+impl ChunkMap {
+    /// The cell pointer array of the chunk the pixel is in, None if the
+    /// chunk is not loaded
+    pub fn chunk_cells(&self, proc: &ProcessRef, x: i32, y: i32) -> io::Result<Option<RawPtr>> {
+        let chunk = self.chunks.offset(chunk_index(x, y) as i32).read(proc)?;
+        if chunk.is_null() {
+            return Ok(None);
+        }
+        let cells = chunk.read(proc)?.cells;
+        Ok((!cells.is_null()).then(|| cells.raw()))
+    }
+
+    /// Cell pointers in the rectangle, row by row. Every row is read with
+    /// one read per chunk it crosses
+    pub fn read_cells(&self, proc: &ProcessRef, rect: PixelRect) -> io::Result<Vec<Ptr<Cell>>> {
+        let mut result = Vec::with_capacity(rect.area());
+        let mut chunks = HashMap::new();
+
+        for y in rect.y..rect.y + rect.height as i32 {
+            let mut x = rect.x;
+            let end = rect.x + rect.width as i32;
+            while x < end {
+                // up to the end of the chunk or the rect, whichever is first
+                let run = ((x | CHUNK_MASK) + 1).min(end) - x;
+
+                let cells = match chunks.entry(chunk_index(x, y)) {
+                    Entry::Occupied(e) => *e.get(),
+                    Entry::Vacant(e) => *e.insert(self.chunk_cells(proc, x, y)?),
+                };
+                match cells {
+                    Some(cells) => {
+                        let idx = ((y & CHUNK_MASK) << CHUNK_BITS | (x & CHUNK_MASK)) as u32;
+                        result.extend(proc.read_multiple::<Ptr<Cell>>(
+                            cells.addr() + idx * size_of::<Ptr<Cell>>() as u32,
+                            run as u32,
+                        )?);
+                    }
+                    None => result.extend((0..run).map(|_| Ptr::of(0))),
+                }
+                x += run;
+            }
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,103 @@
+use std::io;
+
+use crate::memory::{Addr, MemoryStorage, Pod, ProcessRef, Ptr};
+
+/// Version of the entity/component layouts the structs in [super::types]
+/// describe, see [super::discovery::KnownBuild::layout]
+pub const LATEST_LAYOUT: u32 = 1;
+
+/// Reads a struct from an older layout and converts it to the latest one
+pub type ReadOld<T> = fn(&ProcessRef, Addr) -> io::Result<T>;
+
+/// A struct that looked differently in some Noita builds.
+///
+/// Each old layout gets its own struct that converts into the latest one,
+/// and is listed in [Self::OLD_LAYOUTS]; the reads then pick the right one
+/// by the layout version of the connected build, e.g.
+///
+/// ```ignore
+/// impl Versioned for Entity {
+///     const OLD_LAYOUTS: &[(u32, ReadOld<Self>)] = &[(1, read_as::<EntityV1, _>)];
+/// }
+/// ```
+pub trait Versioned: Pod {
+    /// Readers of the older layouts, as the last layout version each one
+    /// applies to along with it, sorted by that version. Anything newer is
+    /// read as is
+    const OLD_LAYOUTS: &'static [(u32, ReadOld<Self>)] = &[];
+
+    fn old_reader(layout: u32) -> Option<ReadOld<Self>> {
+        Self::OLD_LAYOUTS
+            .iter()
+            .find(|(up_to, _)| layout <= *up_to)
+            .map(|(_, read)| *read)
+    }
+
+    fn read_versioned(proc: &ProcessRef, ptr: Ptr<Self>, layout: u32) -> io::Result<Self> {
+        match Self::old_reader(layout) {
+            Some(read) => read(proc, ptr.addr()),
+            None => ptr.read(proc),
+        }
+    }
+
+    /// Same as [Self::read_versioned], but keeps the batched read for the
+    /// latest layout
+    fn read_versioned_each(
+        proc: &ProcessRef,
+        addrs: &[Addr],
+        layout: u32,
+    ) -> io::Result<Vec<Self>> {
+        match Self::old_reader(layout) {
+            Some(read) => addrs.iter().map(|addr| read(proc, *addr)).collect(),
+            None => proc.read_each(addrs),
+        }
+    }
+}
+
+/// A [ReadOld] for an old layout struct that converts into the latest one
+pub fn read_as<Old: Pod + Into<T>, T>(proc: &ProcessRef, addr: Addr) -> io::Result<T> {
+    proc.read::<Old>(addr).map(Into::into)
+}
+
+#[cfg(test)]
+#[test]
+fn versioned_reads() -> io::Result<()> {
+    use crate::memory::MockProcess;
+    use zerocopy::{FromBytes, IntoBytes};
+
+    #[derive(FromBytes, IntoBytes, Debug, PartialEq)]
+    #[repr(C)]
+    struct Thing {
+        id: u32,
+        hp: u32,
+    }
+
+    #[derive(FromBytes, IntoBytes)]
+    #[repr(C)]
+    struct ThingV1 {
+        hp: u32,
+    }
+
+    impl From<ThingV1> for Thing {
+        fn from(old: ThingV1) -> Self {
+            Self { id: 0, hp: old.hp }
+        }
+    }
+
+    impl Versioned for Thing {
+        const OLD_LAYOUTS: &[(u32, ReadOld<Self>)] = &[(1, read_as::<ThingV1, _>)];
+    }
+
+    let proc = MockProcess::new(1).with(0x1000, &[7u32, 42]).into_process();
+
+    let ptr = Ptr::<Thing>::of(0x1000);
+    assert_eq!(
+        Thing::read_versioned(&proc, ptr, 1)?,
+        Thing { id: 0, hp: 7 }
+    );
+    assert_eq!(
+        Thing::read_versioned(&proc, ptr, 2)?,
+        Thing { id: 7, hp: 42 }
+    );
+    Ok(())
+}
@@ -1,12 +1,38 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+};
 
 use eframe::egui::{pos2, Context, Pos2};
 use noita_utility_box::noita::{rng::NoitaRng, Seed};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use smart_default::SmartDefault;
-use tracing::Instrument;
 
-use crate::util::{persist, Promise};
+use crate::util::persist;
+
+type Chunk = (i32, i32);
+
+/// A batch of chunks being searched on the rayon pool
+#[derive(Debug)]
+struct Search {
+    results: Receiver<(Chunk, Vec<(i32, i32)>)>,
+    pending: HashSet<Chunk>,
+    total: usize,
+    /// Orbs found by this search so far
+    found: usize,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        // the chunks that didn't start yet are skipped
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
 
 #[derive(Debug, SmartDefault)]
 pub struct OrbSearcher {
@@ -17,8 +43,9 @@ pub struct OrbSearcher {
     pub look_for_sampo_instead: bool,
     searched_chunks: HashSet<(i32, i32)>,
     known_orbs: Vec<Pos2>,
-    #[default(Promise::Taken)]
-    search_task: Promise<Vec<(i32, i32)>>,
+    search: Option<Search>,
+    /// Set by [OrbSearcher::stop], no new searches are started until resumed
+    stopped: bool,
 }
 
 persist!(OrbSearcher {
@@ -41,53 +68,114 @@ impl OrbSearcher {
     pub fn reset(&mut self) {
         self.known_orbs.clear();
         self.searched_chunks.clear();
-        self.search_task = Promise::Taken;
+        self.search = None;
+        self.stopped = false;
+    }
+
+    /// Chunks done and the total of the current search
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.search
+            .as_ref()
+            .map(|s| (s.total - s.pending.len(), s.total))
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Cancel the current search, the chunks it didn't get to are searched
+    /// again once resumed
+    pub fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            for chunk in &search.pending {
+                self.searched_chunks.remove(chunk);
+            }
+        }
+        self.stopped = true;
     }
 
-    pub fn is_searching(&self) -> bool {
-        !self.search_task.is_taken()
+    pub fn resume(&mut self) {
+        self.stopped = false;
     }
 
-    fn next_chunk(&mut self, pos: Pos2) -> Option<(i32, i32)> {
-        let xc = pos.x as i32 / self.chunk_size as i32;
-        let yc = pos.y as i32 / self.chunk_size as i32;
-        //meh
+    /// All the chunks in range around the position that weren't searched yet
+    fn next_chunks(&mut self, pos: Pos2) -> Vec<Chunk> {
+        // floor, and not towards zero, for the negative coordinates
+        let xc = (pos.x as i32).div_euclid(self.chunk_size as i32);
+        let yc = (pos.y as i32).div_euclid(self.chunk_size as i32);
+        let mut chunks = Vec::new();
         for x in xc - self.search_range[0]..=xc + self.search_range[0] {
             for y in yc - self.search_range[1]..=yc + self.search_range[1] {
                 if self.searched_chunks.insert((x, y)) {
-                    return Some((x, y));
+                    chunks.push((x, y));
                 }
             }
         }
-        None
+        chunks
     }
 
-    pub fn poll_search(&mut self, ctx: &Context, seed: Seed, pos: Pos2) {
-        if self.search_task.is_taken() {
-            if let Some((x, y)) = self.next_chunk(pos) {
-                let size = self.chunk_size;
-                let x = x * size as i32;
-                let y = y * size as i32;
-                let ctx = ctx.clone();
-                let sampo = self.look_for_sampo_instead;
-                self.search_task = Promise::spawn(
-                    async move {
-                        let orbs = find_orbs(seed.sum(), x, y, size, size, sampo);
-                        ctx.request_repaint();
-                        orbs
+    fn start_search(&self, ctx: &Context, seed: Seed, chunks: Vec<Chunk>) -> Search {
+        let (tx, results) = mpsc::channel();
+        let total = chunks.len();
+        let pending = chunks.iter().copied().collect();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let size = self.chunk_size;
+        let sampo = self.look_for_sampo_instead;
+        let ctx = ctx.clone();
+        let cancel = cancelled.clone();
+
+        // rayon steals the chunks between the threads, and each chunk is
+        // reported as soon as it's done
+        rayon::spawn(move || {
+            tracing::trace_span!("search", %seed, chunks = total, size).in_scope(|| {
+                chunks.into_par_iter().for_each_with(tx, |tx, chunk| {
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
                     }
-                    .instrument(tracing::trace_span!("search", %seed, x, y, size)),
-                );
+                    let (x, y) = (chunk.0 * size as i32, chunk.1 * size as i32);
+                    let orbs = find_orbs(seed.sum(), x, y, size, size, sampo);
+                    // fails when the search was cancelled, which is fine
+                    let _ = tx.send((chunk, orbs));
+                    ctx.request_repaint();
+                });
+            });
+        });
+        Search {
+            results,
+            pending,
+            total,
+            found: 0,
+            cancelled,
+        }
+    }
+
+    /// Collect the results and start searching the chunks that came in
+    /// range. Returns how many orbs the search found when it finishes
+    pub fn poll_search(&mut self, ctx: &Context, seed: Seed, pos: Pos2) -> Option<usize> {
+        let mut finished = None;
+        if let Some(search) = &mut self.search {
+            while let Ok((chunk, orbs)) = search.results.try_recv() {
+                search.pending.remove(&chunk);
+                search.found += orbs.len();
+                self.known_orbs
+                    .extend(orbs.into_iter().map(|(x, y)| pos2(x as f32, y as f32)));
+            }
+            if search.pending.is_empty() {
+                finished = Some(search.found);
+                self.search = None;
+            }
+        }
+        if self.search.is_none() && !self.stopped {
+            let chunks = self.next_chunks(pos);
+            if !chunks.is_empty() {
+                self.search = Some(self.start_search(ctx, seed, chunks));
             }
-        } else if let Some(orbs) = self.search_task.poll_take() {
-            self.known_orbs
-                .extend(orbs.into_iter().map(|(x, y)| pos2(x as f32, y as f32)));
-            return self.poll_search(ctx, seed, pos);
         }
         self.known_orbs.sort_unstable_by_key(|orb| {
             let dir = *orb - pos;
             dir.length_sq() as i32
         });
+        finished
     }
 }
 
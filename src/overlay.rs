@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use eframe::egui::{
+    self, pos2, vec2, CentralPanel, Color32, Context, Frame, Pos2, RichText, Sense, Stroke, Ui,
+    ViewportBuilder, ViewportCommand, ViewportId,
+};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::app::AppState;
+
+/// How often the overlay redraws when nothing else makes it
+const REFRESH: Duration = Duration::from_millis(100);
+
+/// The borderless window that can be put on top of the game, see [show]
+#[derive(Debug, Clone, Serialize, Deserialize, SmartDefault)]
+#[serde(default)]
+pub struct OverlaySettings {
+    pub enabled: bool,
+    /// A locked overlay is click-through, an unlocked one can be dragged
+    #[default(true)]
+    pub locked: bool,
+    /// Of the top left corner, on the screen
+    #[default(pos2(100.0, 100.0))]
+    pub position: Pos2,
+    #[default(true)]
+    pub orb_direction: bool,
+    #[default(true)]
+    pub shift_timer: bool,
+    #[default(true)]
+    pub hp: bool,
+}
+
+/// Show the overlay window if it's enabled
+pub fn show(ctx: &Context, state: &mut AppState) {
+    if !state.settings.overlay.enabled {
+        return;
+    }
+    let game = state.game();
+    let orb = state.orb_target;
+    let settings = &mut state.settings.overlay;
+
+    let builder = ViewportBuilder::default()
+        .with_title("Noita Utility Box overlay")
+        .with_decorations(false)
+        .with_transparent(true)
+        .with_always_on_top()
+        .with_resizable(false)
+        .with_taskbar(false)
+        .with_mouse_passthrough(settings.locked)
+        .with_inner_size(vec2(160.0, 80.0))
+        .with_position(settings.position);
+
+    ctx.show_viewport_immediate(ViewportId::from_hash_of("overlay"), builder, |ctx, _| {
+        let fill = if settings.locked {
+            Color32::from_black_alpha(120)
+        } else {
+            Color32::from_black_alpha(200)
+        };
+        CentralPanel::default()
+            .frame(Frame::none().fill(fill).inner_margin(6.0))
+            .show(ctx, |ui| {
+                let player = game.player.as_ref().map(|(player, _)| {
+                    let pos = player.transform.pos;
+                    pos2(pos.x, pos.y)
+                });
+
+                if settings.orb_direction {
+                    match player.zip(orb) {
+                        Some((player, orb)) => orb_ui(ui, orb - player),
+                        None => label(ui, "orb: -"),
+                    }
+                }
+                if settings.shift_timer {
+                    label(ui, &game.shift_timer_text());
+                }
+                if settings.hp {
+                    label(ui, &game.hp_text());
+                }
+
+                if !settings.locked {
+                    let response = ui.interact(ui.max_rect(), ui.id().with("drag"), Sense::drag());
+                    if response.drag_started() {
+                        ctx.send_viewport_cmd(ViewportCommand::StartDrag);
+                    }
+                }
+            });
+
+        if let Some(rect) = ctx.input(|i| i.viewport().outer_rect) {
+            settings.position = rect.min;
+        }
+        // it's not supposed to be closed, but just in case
+        if ctx.input(|i| i.viewport().close_requested()) {
+            settings.enabled = false;
+        }
+    });
+
+    ctx.request_repaint_after(REFRESH);
+}
+
+fn label(ui: &mut Ui, text: &str) {
+    ui.label(
+        RichText::new(text)
+            .monospace()
+            .strong()
+            .color(Color32::WHITE),
+    );
+}
+
+/// An arrow pointing at the orb, and the distance to it
+fn orb_ui(ui: &mut Ui, dir: egui::Vec2) {
+    ui.horizontal(|ui| {
+        let (rect, _) = ui.allocate_exact_size(vec2(16.0, 16.0), Sense::hover());
+        let arrow = dir.normalized() * rect.width();
+        let stroke = Stroke::new(2.0, Color32::WHITE);
+        ui.painter()
+            .arrow(rect.center() - arrow / 2.0, arrow, stroke);
+        label(ui, &format!("orb: {:.0} px", dir.length()));
+    });
+}
@@ -0,0 +1,98 @@
+//! The colors the radar and map tools draw with, so that they can be
+//! swapped for ones that don't rely on telling red and green apart
+
+use eframe::egui::{Color32, Visuals};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteKind {
+    /// Derived from the theme colors
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+}
+
+impl PaletteKind {
+    pub const ALL: [PaletteKind; 3] = [
+        PaletteKind::Default,
+        PaletteKind::Deuteranopia,
+        PaletteKind::Protanopia,
+    ];
+
+    pub fn name(self) -> &'static str {
+        tr(match self {
+            PaletteKind::Default => "Default",
+            PaletteKind::Deuteranopia => "Deuteranopia (green-weak)",
+            PaletteKind::Protanopia => "Protanopia (red-weak)",
+        })
+    }
+
+    pub fn colors(self, visuals: &Visuals) -> Palette {
+        match self {
+            PaletteKind::Default => Palette {
+                player: visuals.warn_fg_color,
+                target: visuals.strong_text_color(),
+                on_target: Color32::from_rgb(40, 255, 40),
+                ahead: Color32::from_rgb(40, 200, 40),
+                behind: visuals.error_fg_color,
+                ghost: visuals.weak_text_color(),
+                landmark: visuals.strong_text_color(),
+            },
+            // the Okabe-Ito colors, all pairs stay apart for both kinds of
+            // red-green blindness, the two presets only differ in avoiding
+            // red for protanopia since it looks too dark there
+            PaletteKind::Deuteranopia => Palette {
+                player: OKABE_ITO_ORANGE,
+                target: OKABE_ITO_SKY_BLUE,
+                on_target: OKABE_ITO_BLUE,
+                ahead: OKABE_ITO_BLUE,
+                behind: OKABE_ITO_VERMILLION,
+                ghost: OKABE_ITO_PURPLE,
+                landmark: visuals.strong_text_color(),
+            },
+            PaletteKind::Protanopia => Palette {
+                player: OKABE_ITO_YELLOW,
+                target: OKABE_ITO_SKY_BLUE,
+                on_target: OKABE_ITO_BLUE,
+                ahead: OKABE_ITO_SKY_BLUE,
+                behind: OKABE_ITO_ORANGE,
+                ghost: visuals.weak_text_color(),
+                landmark: visuals.strong_text_color(),
+            },
+        }
+    }
+}
+
+const OKABE_ITO_ORANGE: Color32 = Color32::from_rgb(230, 159, 0);
+const OKABE_ITO_SKY_BLUE: Color32 = Color32::from_rgb(86, 180, 233);
+const OKABE_ITO_YELLOW: Color32 = Color32::from_rgb(240, 228, 66);
+const OKABE_ITO_BLUE: Color32 = Color32::from_rgb(0, 114, 178);
+const OKABE_ITO_VERMILLION: Color32 = Color32::from_rgb(213, 94, 0);
+const OKABE_ITO_PURPLE: Color32 = Color32::from_rgb(204, 121, 167);
+
+/// What the colors mean rather than what they are
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    /// The player marker, the trail is a dimmer version of it
+    pub player: Color32,
+    /// Orbs and whatever else the radar is pointing at
+    pub target: Color32,
+    /// The radar indicator when standing right at the target
+    pub on_target: Color32,
+    /// Doing better than the ghost
+    pub ahead: Color32,
+    /// Doing worse than the ghost
+    pub behind: Color32,
+    pub ghost: Color32,
+    /// Holy mountains and such
+    pub landmark: Color32,
+}
+
+impl Palette {
+    pub fn trail(&self) -> Color32 {
+        self.player.gamma_multiply(0.5)
+    }
+}
@@ -0,0 +1,154 @@
+use std::time::{Duration, Instant};
+
+use eframe::egui::{self, CollapsingHeader, Grid, RichText};
+use noita_utility_box::memory::{read_stats, reset_read_stats, thread_read_count, ReadStats};
+
+/// How many call sites to show, the rest are usually fast
+const SLOWEST_CALL_SITES: usize = 10;
+
+/// Timings of the last `tick` and `ui` calls of a tool
+#[derive(Debug, Default, Clone)]
+pub struct ToolStats {
+    pub tick: Timing,
+    pub ui: Timing,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timing {
+    pub time: Duration,
+    pub reads: u64,
+}
+
+impl Timing {
+    /// Run the closure, recording how long it took and how many memory reads
+    /// it did
+    pub fn measure<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let reads = thread_read_count();
+        let result = f();
+        self.time = start.elapsed();
+        self.reads = thread_read_count() - reads;
+        result
+    }
+}
+
+/// Read totals sampled about once a second, to show the read rates
+#[derive(Debug, Default)]
+pub struct ReadRates {
+    last: Option<(Instant, ReadStats)>,
+    reads_per_sec: f64,
+    bytes_per_sec: f64,
+}
+
+impl ReadRates {
+    fn update(&mut self, stats: &ReadStats) {
+        let now = Instant::now();
+        match &self.last {
+            Some((at, _)) if now.duration_since(*at) < Duration::from_secs(1) => return,
+            Some((at, last)) => {
+                let secs = now.duration_since(*at).as_secs_f64();
+                // saturating because of the reset button
+                self.reads_per_sec = stats.reads.saturating_sub(last.reads) as f64 / secs;
+                self.bytes_per_sec = stats.bytes.saturating_sub(last.bytes) as f64 / secs;
+            }
+            None => {}
+        }
+        self.last = Some((now, stats.clone()));
+    }
+}
+
+/// The debug window with per-tool timings and memory read statistics, to
+/// find out which tool is causing stutters or hammering the game
+pub fn overlay<'a>(
+    ctx: &egui::Context,
+    open: &mut bool,
+    rates: &mut ReadRates,
+    tools: impl Iterator<Item = (&'a str, &'a ToolStats)>,
+) {
+    egui::Window::new("Performance")
+        .open(open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            Grid::new("perf_overlay").striped(true).show(ui, |ui| {
+                ui.strong("Tool");
+                ui.strong("Tick");
+                ui.strong("UI");
+                ui.strong("Reads");
+                ui.strong("Last error");
+                ui.end_row();
+
+                for (title, stats) in tools {
+                    ui.label(title);
+                    ui.monospace(format!("{:.2?}", stats.tick.time));
+                    ui.monospace(format!("{:.2?}", stats.ui.time));
+                    ui.monospace(format!("{}/{}", stats.tick.reads, stats.ui.reads))
+                        .on_hover_text("Memory reads during the last tick/ui call");
+                    match &stats.last_error {
+                        Some(e) => ui.label(RichText::new(e).color(ui.visuals().error_fg_color)),
+                        None => ui.weak("-"),
+                    };
+                    ui.end_row();
+                }
+            });
+
+            let stats = read_stats();
+            rates.update(&stats);
+            // keep repainting to keep the rates fresh
+            ctx.request_repaint_after(Duration::from_secs(1));
+
+            CollapsingHeader::new("Memory reads")
+                .default_open(true)
+                .show(ui, |ui| read_stats_ui(ui, &stats, rates));
+        });
+}
+
+fn read_stats_ui(ui: &mut egui::Ui, stats: &ReadStats, rates: &ReadRates) {
+    Grid::new("read_stats").show(ui, |ui| {
+        ui.label("Reads/s:");
+        ui.monospace(format!("{:.0}", rates.reads_per_sec));
+        ui.end_row();
+
+        ui.label("KiB/s:");
+        ui.monospace(format!("{:.1}", rates.bytes_per_sec / 1024.0));
+        ui.end_row();
+
+        ui.label("Total:");
+        ui.monospace(format!(
+            "{} reads, {:.1} MiB",
+            stats.reads,
+            stats.bytes as f64 / (1024.0 * 1024.0)
+        ));
+        ui.end_row();
+
+        ui.label("Failures:");
+        let failures = ui.monospace(stats.failures.to_string());
+        if stats.failures > 0 {
+            failures.on_hover_text("Some failures are normal, e.g. when the game is loading");
+        }
+        ui.end_row();
+    });
+
+    ui.label("Slowest call sites:");
+    Grid::new("read_call_sites").striped(true).show(ui, |ui| {
+        ui.strong("Location");
+        ui.strong("Reads");
+        ui.strong("Avg");
+        ui.strong("Max");
+        ui.strong("Failed");
+        ui.end_row();
+
+        for (location, site) in stats.call_sites.iter().take(SLOWEST_CALL_SITES) {
+            ui.monospace(format!("{}:{}", location.file(), location.line()));
+            ui.monospace(site.reads.to_string());
+            ui.monospace(format!("{:.2?}", site.average()));
+            ui.monospace(format!("{:.2?}", site.slowest));
+            ui.monospace(site.failures.to_string());
+            ui.end_row();
+        }
+    });
+
+    if ui.button("Reset").clicked() {
+        reset_read_stats();
+    }
+}
@@ -0,0 +1,305 @@
+//! The interface for third-party plugins, loaded by the app from dynamic
+//! libraries in its plugins directory.
+//!
+//! The Rust ABI is not stable, so a plugin built with a different compiler
+//! (or different egui) can't just implement the app's `Tool` trait. Instead
+//! the library exports a C function returning a [PluginVTable], which mirrors
+//! what a tool does: it gets ticked with the current game values, shows a
+//! panel (as rows of text) and takes options.
+//!
+//! The easy way to write one is to implement [Plugin] and use
+//! [export_plugin](crate::export_plugin) in a `cdylib` crate:
+//! ```no_run
+//! use std::ffi::CStr;
+//! use noita_utility_box::plugin::{GameValues, Plugin};
+//!
+//! #[derive(Default)]
+//! struct MaxGold(i64);
+//!
+//! impl Plugin for MaxGold {
+//!     const ID: &'static CStr = c"max_gold";
+//!     const TITLE: &'static CStr = c"Max Gold";
+//!
+//!     fn tick(&mut self, values: &GameValues) {
+//!         self.0 = self.0.max(values.gold);
+//!     }
+//!
+//!     fn rows(&self) -> Vec<(String, String)> {
+//!         vec![("Most gold held".into(), self.0.to_string())]
+//!     }
+//! }
+//!
+//! noita_utility_box::export_plugin!(MaxGold);
+//! ```
+
+use std::{
+    ffi::{c_char, c_void, CStr},
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+/// Bumped on any change to the types below, plugins built against another
+/// version are not loaded
+pub const ABI_VERSION: u32 = 1;
+
+/// The name of the function every plugin library exports, of the
+/// [PluginEntry] type
+pub const ENTRY_SYMBOL: &[u8] = b"noita_utility_box_plugin\0";
+
+pub type PluginEntry = unsafe extern "C" fn() -> *const PluginVTable;
+
+/// The callback the plugin calls once per row of its panel, the strings are
+/// UTF-8 pointer and length pairs. `ctx` must be the one passed to `rows`,
+/// and the callback must only be called from within that `rows` call.
+pub type RowCallback = unsafe extern "C" fn(
+    ctx: *mut c_void,
+    label: *const u8,
+    label_len: usize,
+    value: *const u8,
+    value_len: usize,
+);
+
+/// The values the app reads every frame, the same ones the built-in tools
+/// use. Unknown values (not connected, not in a run) are `-1` for integers
+/// and NaN for floats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct GameValues {
+    pub frame: i64,
+    pub world_seed: i64,
+    pub ng_count: i64,
+    /// In the units the game shows
+    pub hp: f64,
+    pub max_hp: f64,
+    pub gold: i64,
+    pub orbs: i64,
+    /// Frames until a fungal shift can happen again
+    pub shift_cooldown: i64,
+    pub x: f32,
+    pub y: f32,
+    /// 1 if the player is polymorphed, 0 if not, -1 if there's no player
+    pub polymorphed: i8,
+}
+
+impl Default for GameValues {
+    fn default() -> Self {
+        Self {
+            frame: -1,
+            world_seed: -1,
+            ng_count: -1,
+            hp: f64::NAN,
+            max_hp: f64::NAN,
+            gold: -1,
+            orbs: -1,
+            shift_cooldown: -1,
+            x: f32::NAN,
+            y: f32::NAN,
+            polymorphed: -1,
+        }
+    }
+}
+
+/// What a plugin library provides. The strings are NUL-terminated UTF-8 and
+/// must live as long as the library is loaded.
+///
+/// The instance pointer returned by `new` is only ever used from one thread
+/// at a time, but not always the same one.
+///
+/// All the functions are unsafe to call, the caller guarantees that:
+/// - `this` is a non-null pointer returned by `new` of the same vtable, that
+///   was not passed to `drop` yet
+/// - the other pointers are valid for reads of their lengths (or of one
+///   [GameValues]) for the duration of the call
+/// - `row` is only called with the `ctx` it was passed with
+#[derive(Debug)]
+#[repr(C)]
+pub struct PluginVTable {
+    pub abi_version: u32,
+    /// Stable identifier, used to remember the plugin options
+    pub id: *const c_char,
+    pub title: *const c_char,
+    /// Returns null if the plugin failed to initialize
+    pub new: unsafe extern "C" fn() -> *mut c_void,
+    pub drop: unsafe extern "C" fn(this: *mut c_void),
+    pub tick: unsafe extern "C" fn(this: *mut c_void, values: *const GameValues),
+    /// Calls `row` for every row of the panel
+    pub rows: unsafe extern "C" fn(this: *mut c_void, row: RowCallback, ctx: *mut c_void),
+    /// Returns false if the plugin has no such option
+    pub set_option: unsafe extern "C" fn(
+        this: *mut c_void,
+        key: *const u8,
+        key_len: usize,
+        value: *const u8,
+        value_len: usize,
+    ) -> bool,
+}
+
+// the pointers are to static strings
+unsafe impl Sync for PluginVTable {}
+
+/// The safe side of [PluginVTable], see the [module docs](self)
+pub trait Plugin: Default + 'static {
+    const ID: &'static CStr;
+    const TITLE: &'static CStr;
+
+    fn tick(&mut self, values: &GameValues);
+
+    fn rows(&self) -> Vec<(String, String)>;
+
+    fn set_option(&mut self, _key: &str, _value: &str) -> bool {
+        false
+    }
+}
+
+impl PluginVTable {
+    pub const fn of<T: Plugin>() -> Self {
+        Self {
+            abi_version: ABI_VERSION,
+            id: T::ID.as_ptr(),
+            title: T::TITLE.as_ptr(),
+            new: new::<T>,
+            drop: drop::<T>,
+            tick: tick::<T>,
+            rows: rows::<T>,
+            set_option: set_option::<T>,
+        }
+    }
+}
+
+// unwinding into the host is UB-adjacent (and an abort at best), so panics
+// are caught and the call does nothing instead.
+// These are only reachable through the vtable, the safety requirements are
+// the ones documented on it
+
+unsafe extern "C" fn new<T: Plugin>() -> *mut c_void {
+    catch_unwind(|| Box::into_raw(Box::<T>::default()).cast()).unwrap_or(std::ptr::null_mut())
+}
+
+unsafe extern "C" fn drop<T: Plugin>(this: *mut c_void) {
+    if !this.is_null() {
+        let _ = catch_unwind(|| unsafe { std::mem::drop(Box::from_raw(this.cast::<T>())) });
+    }
+}
+
+unsafe extern "C" fn tick<T: Plugin>(this: *mut c_void, values: *const GameValues) {
+    let (this, values) = unsafe { (&mut *this.cast::<T>(), &*values) };
+    let _ = catch_unwind(AssertUnwindSafe(|| this.tick(values)));
+}
+
+unsafe extern "C" fn rows<T: Plugin>(this: *mut c_void, row: RowCallback, ctx: *mut c_void) {
+    let this = unsafe { &*this.cast::<T>() };
+    let Ok(rows) = catch_unwind(AssertUnwindSafe(|| this.rows())) else {
+        return;
+    };
+    for (label, value) in rows {
+        // SAFETY: the host passed this ctx along with the callback
+        unsafe {
+            row(
+                ctx,
+                label.as_ptr(),
+                label.len(),
+                value.as_ptr(),
+                value.len(),
+            )
+        };
+    }
+}
+
+unsafe extern "C" fn set_option<T: Plugin>(
+    this: *mut c_void,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+) -> bool {
+    let this = unsafe { &mut *this.cast::<T>() };
+    let (key, value) = unsafe {
+        (
+            String::from_utf8_lossy(std::slice::from_raw_parts(key, key_len)),
+            String::from_utf8_lossy(std::slice::from_raw_parts(value, value_len)),
+        )
+    };
+    catch_unwind(AssertUnwindSafe(|| this.set_option(&key, &value))).unwrap_or(false)
+}
+
+/// Export the [PluginVTable] of a [Plugin] type from a `cdylib`
+#[macro_export]
+macro_rules! export_plugin {
+    ($t:ty) => {
+        #[no_mangle]
+        pub extern "C" fn noita_utility_box_plugin() -> *const $crate::plugin::PluginVTable {
+            static VTABLE: $crate::plugin::PluginVTable = $crate::plugin::PluginVTable::of::<$t>();
+            &VTABLE
+        }
+    };
+}
+
+#[cfg(test)]
+#[test]
+fn vtable_round_trip() {
+    #[derive(Default)]
+    struct Test(i64, String);
+
+    impl Plugin for Test {
+        const ID: &'static CStr = c"test";
+        const TITLE: &'static CStr = c"Test";
+
+        fn tick(&mut self, values: &GameValues) {
+            self.0 = values.gold;
+        }
+
+        fn rows(&self) -> Vec<(String, String)> {
+            vec![
+                ("gold".into(), self.0.to_string()),
+                ("x".into(), self.1.clone()),
+            ]
+        }
+
+        fn set_option(&mut self, key: &str, value: &str) -> bool {
+            key == "x" && {
+                self.1 = value.into();
+                true
+            }
+        }
+    }
+
+    unsafe extern "C" fn row(ctx: *mut c_void, l: *const u8, ll: usize, v: *const u8, vl: usize) {
+        let rows = unsafe { &mut *ctx.cast::<Vec<(String, String)>>() };
+        let text = |p, l| unsafe { String::from_utf8(std::slice::from_raw_parts(p, l).to_vec()) };
+        rows.push((text(l, ll).unwrap(), text(v, vl).unwrap()));
+    }
+
+    let vtable = PluginVTable::of::<Test>();
+    assert_eq!(unsafe { CStr::from_ptr(vtable.id) }, c"test");
+
+    let values = GameValues {
+        gold: 42,
+        ..Default::default()
+    };
+    let mut rows = Vec::<(String, String)>::new();
+    // SAFETY: the instance is only used between its new and drop, and the
+    // pointers are to locals that outlive the calls
+    unsafe {
+        let this = (vtable.new)();
+        (vtable.tick)(this, &values);
+        assert!((vtable.set_option)(
+            this,
+            b"x".as_ptr(),
+            1,
+            b"y".as_ptr(),
+            1
+        ));
+        assert!(!(vtable.set_option)(
+            this,
+            b"z".as_ptr(),
+            1,
+            b"y".as_ptr(),
+            1
+        ));
+        (vtable.rows)(this, row, (&raw mut rows).cast());
+        (vtable.drop)(this);
+    }
+    assert_eq!(
+        rows,
+        [("gold".into(), "42".into()), ("x".into(), "y".into())]
+    );
+}
@@ -0,0 +1,91 @@
+//! Named bundles of an address map choice and tool configs, to switch
+//! between e.g. the main branch, the beta and modded runs in one go
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Tools that are the same for all the profiles - the profiles are switched
+/// from the Noita panel, and the address maps and settings are app-wide
+pub const SHARED_TOOLS: &[&str] = &["ProcessPanel", "AddressMaps", "Settings"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    /// Name of the address map to use instead of the one picked by the
+    /// build timestamp
+    pub address_map: Option<String>,
+    /// Serialized tool configs by tool id, as JSON values so that they stay
+    /// opaque to the app storage format while the settings export can still
+    /// strip the secrets out of them
+    pub tools: BTreeMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub enum Request {
+    /// Save the current tool configs as a new profile and make it active
+    Create(String),
+    /// Save the current tool configs into the active profile and load the
+    /// given one, None is for no profile at all
+    Switch(Option<String>),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfilesData {
+    pub profiles: Vec<Profile>,
+    pub active: Option<String>,
+}
+
+impl ProfilesData {
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Profile> {
+        self.profiles.iter_mut().find(|p| p.name == name)
+    }
+
+    pub fn active(&self) -> Option<&Profile> {
+        self.get(self.active.as_deref()?)
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut Profile> {
+        let name = self.active.clone()?;
+        self.get_mut(&name)
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+        if self.active.as_deref() == Some(name) {
+            self.active = None;
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn export_strips_profile_secrets() {
+    use serde_json::json;
+
+    let profiles = ProfilesData {
+        profiles: vec![Profile {
+            name: "beta".into(),
+            tools: [(
+                "TwitchPredictions".into(),
+                json!({ "TwitchPredictions": { "client_id": "id", "token": "abc" } }),
+            )]
+            .into(),
+            ..Default::default()
+        }],
+        active: None,
+    };
+    let mut value = serde_json::to_value(&profiles).unwrap();
+    crate::settings_file::strip_secrets(&mut value);
+    assert_eq!(
+        value["profiles"][0]["tools"]["TwitchPredictions"],
+        json!({ "TwitchPredictions": { "client_id": "id" } })
+    );
+}
@@ -0,0 +1,195 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use noita_utility_box::noita::{
+    rng::fungal,
+    types::{
+        components::{DamageModelComponent, WalletComponent},
+        Entity,
+    },
+    Noita, Seed,
+};
+
+use crate::events::{EventBus, GameEvent};
+
+/// How often the reader checks if the game advanced, it doesn't read
+/// anything else until it does
+const POLL_INTERVAL: Duration = Duration::from_millis(33);
+
+/// The latest state of the game as read by the [GameReader]
+#[derive(Debug, Default, Clone)]
+pub struct GameState {
+    pub frame: Option<u32>,
+    pub seed: Option<Seed>,
+    /// The player entity, and whether it's polymorphed
+    pub player: Option<(Entity, bool)>,
+    /// A new run started since the previous state
+    pub new_run: bool,
+    /// Current and max HP of the player, in the internal units (the UI
+    /// shows 25 times that)
+    pub hp: Option<(f64, f64)>,
+    /// Frames until a fungal shift can happen again
+    pub shift_cooldown: Option<u32>,
+    pub gold: Option<u64>,
+    /// Orbs picked up in this run, in all the worlds
+    pub orbs: Option<usize>,
+}
+
+impl GameState {
+    /// HP the way the game shows it
+    pub fn hp_value(&self) -> Option<String> {
+        self.hp
+            .map(|(hp, max_hp)| format!("{:.0}/{:.0}", hp * 25.0, max_hp * 25.0))
+    }
+
+    pub fn shift_timer_value(&self) -> Option<String> {
+        self.shift_cooldown.map(|frames| match frames {
+            0 => "ready".into(),
+            _ => {
+                let secs = frames / 60;
+                format!("{}:{:02}", secs / 60, secs % 60)
+            }
+        })
+    }
+
+    pub fn hp_text(&self) -> String {
+        format!("HP: {}", self.hp_value().as_deref().unwrap_or("-"))
+    }
+
+    pub fn shift_timer_text(&self) -> String {
+        format!(
+            "shift: {}",
+            self.shift_timer_value().as_deref().unwrap_or("-")
+        )
+    }
+}
+
+/// Polls the game memory on a separate thread, so that slow reads don't
+/// make the UI stutter
+#[derive(Debug)]
+pub struct GameReader {
+    latest: Arc<Mutex<Arc<GameState>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl GameReader {
+    pub fn spawn(mut noita: Noita, events: EventBus) -> Self {
+        let latest = Arc::new(Mutex::new(Arc::new(GameState::default())));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let reader = Self {
+            latest: latest.clone(),
+            stop: stop.clone(),
+        };
+
+        thread::Builder::new()
+            .name("game-reader".into())
+            .spawn(move || {
+                tracing::info!("Game reader started");
+                while !stop.load(Ordering::Relaxed) {
+                    let prev = latest.lock().unwrap().clone();
+                    if let Some(state) = read(&mut noita, prev.frame) {
+                        for event in GameEvent::diff(&prev, &state) {
+                            events.publish(event);
+                        }
+                        *latest.lock().unwrap() = Arc::new(state);
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+                tracing::info!("Game reader stopped");
+            })
+            .expect("Failed to spawn the game reader thread");
+
+        reader
+    }
+
+    pub fn latest(&self) -> Arc<GameState> {
+        self.latest.lock().unwrap().clone()
+    }
+}
+
+impl Drop for GameReader {
+    fn drop(&mut self) {
+        // not joining, the thread might be in the middle of a slow read
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Returns None if the game did not advance since the previous read
+fn read(noita: &mut Noita, prev_frame: Option<u32>) -> Option<GameState> {
+    let frame = match noita.read_frame_counter() {
+        Ok(frame) if Some(frame) == prev_frame => return None,
+        Ok(frame) => {
+            noita.sync_frame(frame);
+            Some(frame)
+        }
+        Err(e) => {
+            tracing::debug!(%e, "Failed to read the frame counter");
+            noita.bump_cache();
+            None
+        }
+    };
+
+    let new_run = noita
+        .detect_run_change()
+        .inspect_err(|e| tracing::debug!(%e, "Failed to check for a new run"))
+        .unwrap_or_default();
+    let seed = noita.read_seed().ok().flatten();
+    let player = noita
+        .get_player()
+        .inspect_err(|e| tracing::debug!(%e, "Failed to read the player"))
+        .ok()
+        .flatten();
+
+    let hp = player.as_ref().and_then(|(player, _)| {
+        noita
+            .component_store::<DamageModelComponent>()
+            .and_then(|store| store.get(player))
+            .inspect_err(|e| tracing::debug!(%e, "Failed to read the player HP"))
+            .ok()
+            .flatten()
+            .map(|dm| (dm.hp.get(), dm.max_hp.get()))
+    });
+    let gold = player.as_ref().and_then(|(player, _)| {
+        noita
+            .component_store::<WalletComponent>()
+            .and_then(|store| store.get(player))
+            .inspect_err(|e| tracing::debug!(%e, "Failed to read the player wallet"))
+            .ok()
+            .flatten()
+            .map(|wallet| wallet.money.get())
+    });
+    let shift_cooldown = frame.and_then(|frame| {
+        let last = noita
+            .read_lua_global(fungal::LAST_SHIFT_FRAME_GLOBAL)
+            .inspect_err(|e| tracing::debug!(%e, "Failed to read the last shift frame"))
+            .ok()?;
+        Some(fungal::cooldown_left(
+            frame,
+            last.and_then(|l| l.parse().ok()),
+        ))
+    });
+
+    let orbs = noita
+        .read_orbs_found()
+        .inspect_err(|e| tracing::debug!(%e, "Failed to read the found orbs"))
+        .ok()
+        .map(|orbs| orbs.len());
+
+    Some(GameState {
+        frame,
+        seed,
+        player,
+        new_run,
+        hp,
+        shift_cooldown,
+        gold,
+        orbs,
+    })
+}
@@ -0,0 +1,97 @@
+//! Game values put into words (or JSON), for the integrations that answer
+//! questions about the run from outside the app
+
+use std::borrow::Cow;
+
+use anyhow::Context as _;
+use noita_utility_box::{
+    memory::MemoryStorage,
+    noita::{rng::fungal, Noita},
+};
+use serde::Serialize;
+
+/// The Lua global the fungal shift script counts the shifts in
+const SHIFT_ITERATION_GLOBAL: &str = "fungal_shift_iteration";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WandInfo {
+    /// In the quick inventory, from 0
+    pub slot: i32,
+    pub name: String,
+    /// Spells attached to the wand, including the always-cast ones
+    pub spells: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShiftInfo {
+    pub from: Vec<&'static str>,
+    pub to: &'static str,
+}
+
+/// The wands in the player quick inventory, by slot
+pub fn wands(noita: &mut Noita) -> anyhow::Result<Vec<WandInfo>> {
+    let Some(inventory) = noita.read_player_inventory()? else {
+        return Ok(Vec::new());
+    };
+    let translations = noita.translations().context("Reading translations")?;
+
+    let mut wands = Vec::new();
+    for item in inventory.quick.iter().filter(|i| i.is_wand) {
+        let name = match &item.item {
+            Some(item) => item.item_name.read(noita.proc())?,
+            None => String::new(),
+        };
+        let name = match name.strip_prefix('$') {
+            Some(key) => translations.translate(key, true),
+            None if name.is_empty() => Cow::Borrowed("Wand"),
+            None => Cow::Borrowed(&*name),
+        }
+        .into_owned();
+        wands.push(WandInfo {
+            slot: item.slot().map_or(-1, |s| s.x),
+            name,
+            spells: noita.read_children(&item.entity)?.len(),
+        });
+    }
+    wands.sort_by_key(|w| w.slot);
+    Ok(wands)
+}
+
+/// The next few fungal shifts, in order
+pub fn upcoming_shifts(noita: &Noita, count: usize) -> anyhow::Result<Vec<ShiftInfo>> {
+    let seed = noita.read_seed()?.context("No seed, not in a run")?;
+    let iteration = noita
+        .read_lua_global(SHIFT_ITERATION_GLOBAL)?
+        .and_then(|i| i.parse().ok())
+        .unwrap_or(0);
+    Ok(fungal::predict_shifts(seed.sum(), iteration)
+        .into_iter()
+        .take(count)
+        .map(|shift| ShiftInfo {
+            from: shift.from.to_vec(),
+            to: shift.to,
+        })
+        .collect())
+}
+
+pub fn wands_text(wands: &[WandInfo]) -> String {
+    if wands.is_empty() {
+        return "no wands".into();
+    }
+    wands
+        .iter()
+        .map(|w| format!("{}: {} ({} spells)", w.slot + 1, w.name, w.spells))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+pub fn shifts_text(shifts: &[ShiftInfo]) -> String {
+    if shifts.is_empty() {
+        return "no more shifts".into();
+    }
+    shifts
+        .iter()
+        .map(|s| format!("{} -> {}", s.from.join(", "), s.to))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
@@ -0,0 +1,160 @@
+//! Grabbing the game window into a png, for the death screenshots
+
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context as _;
+use image::RgbaImage;
+use noita_utility_box::noita::Seed;
+
+/// Capture the window of the process and save it under the storage dir,
+/// returns the path it was saved at. Blocking
+pub fn save_death_screenshot(pid: u32, seed: Option<Seed>) -> anyhow::Result<PathBuf> {
+    let image = capture_window(pid)?;
+
+    let dir = eframe::storage_dir(env!("CARGO_PKG_NAME"))
+        .context("No storage dir")?
+        .join("screenshots");
+    std::fs::create_dir_all(&dir).context("Creating the screenshots dir")?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let seed = seed.map_or_else(|| "unknown".into(), |s| s.to_string());
+    let path = dir.join(format!("death-{seed}-{timestamp}.png"));
+
+    image.save(&path).context("Saving the screenshot")?;
+    Ok(path)
+}
+
+#[cfg(windows)]
+fn capture_window(pid: u32) -> anyhow::Result<RgbaImage> {
+    use windows::Win32::{
+        Foundation::{BOOL, HWND, LPARAM, RECT},
+        Graphics::Gdi::{
+            CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+            ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+        },
+        Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS},
+        UI::WindowsAndMessaging::{
+            EnumWindows, GetClientRect, GetWindowThreadProcessId, IsWindowVisible,
+        },
+    };
+
+    // PW_CLIENTONLY | PW_RENDERFULLCONTENT, the latter isn't in the bindings.
+    // A BitBlt from the window DC comes out black for a GL window, this makes
+    // the compositor render it for us
+    const PRINT_FLAGS: PRINT_WINDOW_FLAGS = PRINT_WINDOW_FLAGS(0x1 | 0x2);
+
+    struct Search {
+        pid: u32,
+        found: Option<HWND>,
+    }
+
+    unsafe extern "system" fn find_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam.0 as *mut Search);
+        let mut pid = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid as *mut u32));
+        if pid == search.pid && IsWindowVisible(hwnd).as_bool() {
+            search.found = Some(hwnd);
+            return BOOL(0); // stop
+        }
+        BOOL(1)
+    }
+
+    let mut search = Search { pid, found: None };
+    // SAFETY: the pointer is to a local that outlives the call.
+    // It "fails" when the callback stops early, which is what we want
+    let _ = unsafe { EnumWindows(Some(find_window), LPARAM(&mut search as *mut _ as isize)) };
+    let hwnd = search.found.context("Noita has no visible window")?;
+
+    // SAFETY: plain GDI calls on a window that exists, everything created
+    // here is released before returning
+    unsafe {
+        let mut rect = RECT::default();
+        GetClientRect(hwnd, &mut rect)?;
+        let (width, height) = (rect.right - rect.left, rect.bottom - rect.top);
+        anyhow::ensure!(width > 0 && height > 0, "The Noita window is minimized");
+
+        let window_dc = GetDC(hwnd);
+        let mem_dc = CreateCompatibleDC(window_dc);
+        let bitmap = CreateCompatibleBitmap(window_dc, width, height);
+        let prev = SelectObject(mem_dc, bitmap);
+
+        let printed = PrintWindow(hwnd, mem_dc, PRINT_FLAGS).as_bool();
+
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // negative for top-down rows
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let lines = GetDIBits(
+            mem_dc,
+            bitmap,
+            0,
+            height as u32,
+            Some(pixels.as_mut_ptr().cast()),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+
+        let _ = SelectObject(mem_dc, prev);
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(mem_dc);
+        let _ = ReleaseDC(hwnd, window_dc);
+
+        anyhow::ensure!(printed, "PrintWindow failed");
+        anyhow::ensure!(lines == height, "GetDIBits failed");
+
+        // BGRA, with garbage in the alpha
+        for px in pixels.chunks_exact_mut(4) {
+            px.swap(0, 2);
+            px[3] = 255;
+        }
+        RgbaImage::from_raw(width as u32, height as u32, pixels).context("Bad screenshot size")
+    }
+}
+
+/// Wine sets the pid of the windows it makes, so `xdotool` can find the
+/// window, and ImageMagick grabs it - there's no common screenshot API on
+/// Linux worth linking to for this
+#[cfg(not(windows))]
+fn capture_window(pid: u32) -> anyhow::Result<RgbaImage> {
+    use std::process::Command;
+
+    let out = Command::new("xdotool")
+        .args(["search", "--onlyvisible", "--pid", &pid.to_string()])
+        .output()
+        .context("Running xdotool, is it installed?")?;
+    let window = String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .next()
+        .map(str::to_owned)
+        .context("Noita has no visible window")?;
+
+    let out = Command::new("import")
+        .args(["-window", &window, "png:-"])
+        .output()
+        .context("Running import, is ImageMagick installed?")?;
+    anyhow::ensure!(
+        out.status.success(),
+        "import exited with {}: {}",
+        out.status,
+        String::from_utf8_lossy(&out.stderr).trim()
+    );
+    let image = image::load_from_memory_with_format(&out.stdout, image::ImageFormat::Png)
+        .context("Decoding the screenshot")?;
+    Ok(image.into_rgba8())
+}
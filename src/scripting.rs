@@ -0,0 +1,307 @@
+//! [Rhai](https://rhai.rs) scripts for custom readouts, with read-only
+//! access to a few bits of the game.
+//!
+//! Scripts can't touch the filesystem or import modules, and are limited in
+//! how much work they can do per run so that a stray loop doesn't freeze the
+//! app. Everything that reads the game returns `()` when there's nothing to
+//! read (not connected, not in a run, no player).
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use noita_utility_box::noita::{
+    types::{
+        components::{CharacterDataComponent, DamageModelComponent, WalletComponent},
+        Entity,
+    },
+    Noita,
+};
+use rhai::{module_resolvers::DummyModuleResolver, Dynamic, Engine, EvalAltResult, Map, AST};
+
+use crate::reader::GameState;
+
+/// Roughly how many steps a script gets per run
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// What [component] knows how to read
+pub const COMPONENTS: &[&str] = &[
+    "DamageModelComponent",
+    "WalletComponent",
+    "CharacterDataComponent",
+];
+
+/// Short reference for the UI
+pub const HELP: &str = "\
+frame(), seed(), hp(), max_hp(), gold(), orbs(), shift_cooldown()
+player() - #{ x, y, polymorphed }
+component(name) - fields of a player component
+stat(name) - a key-value stat, like \"progress_ending0\"
+session() - #{ playtime, kills, gold, items, ... } of this session
+global(name) - a Lua global variable, as a string
+
+The script result is shown, a map shows as a list of values.";
+
+type EvalResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// What the script functions see while a script runs
+#[derive(Debug, Default)]
+struct ScriptContext {
+    game: Arc<GameState>,
+    noita: Option<Noita>,
+}
+
+pub struct ScriptEngine {
+    engine: Engine,
+    context: Arc<Mutex<ScriptContext>>,
+}
+
+impl std::fmt::Debug for ScriptEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ScriptEngine").finish_non_exhaustive()
+    }
+}
+
+impl Default for ScriptEngine {
+    fn default() -> Self {
+        let mut engine = Engine::new();
+        engine
+            .set_module_resolver(DummyModuleResolver::new())
+            .disable_symbol("eval")
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(10_000)
+            .set_max_array_size(10_000)
+            .set_max_map_size(1_000);
+
+        let context = Arc::new(Mutex::new(ScriptContext::default()));
+
+        let game = |f: fn(&GameState) -> Dynamic| {
+            let context = context.clone();
+            move || f(&context.lock().unwrap().game)
+        };
+        engine
+            .register_fn("frame", game(|g| opt(g.frame.map(i64::from))))
+            .register_fn("seed", game(|g| opt(g.seed.map(|s| s.to_string()))))
+            .register_fn("hp", game(|g| opt(g.hp.map(|(hp, _)| hp * 25.0))))
+            .register_fn("max_hp", game(|g| opt(g.hp.map(|(_, max)| max * 25.0))))
+            .register_fn("gold", game(|g| opt(g.gold.map(|g| g as i64))))
+            .register_fn("orbs", game(|g| opt(g.orbs.map(|o| o as i64))))
+            .register_fn(
+                "shift_cooldown",
+                game(|g| opt(g.shift_cooldown.map(|f| f as f64 / 60.0))),
+            )
+            .register_fn("player", game(player));
+
+        let ctx = context.clone();
+        engine.register_fn("component", move |name: &str| -> EvalResult<Dynamic> {
+            let mut ctx = ctx.lock().unwrap();
+            let Some((player, _)) = ctx.game.player.clone() else {
+                return Ok(Dynamic::UNIT);
+            };
+            let Some(noita) = ctx.noita.as_mut() else {
+                return Ok(Dynamic::UNIT);
+            };
+            Ok(opt(component(noita, &player, name)?))
+        });
+        let ctx = context.clone();
+        engine.register_fn("stat", move |name: &str| -> EvalResult<Dynamic> {
+            let ctx = ctx.lock().unwrap();
+            let Some(noita) = ctx.noita.as_ref() else {
+                return Ok(Dynamic::UNIT);
+            };
+            let stats = noita.read_stats().map_err(io_error)?;
+            let value = stats
+                .key_value_stats
+                .get(noita.proc(), name)
+                .map_err(io_error)?;
+            Ok(opt(value.map(i64::from)))
+        });
+        let ctx = context.clone();
+        engine.register_fn("session", move || -> EvalResult<Dynamic> {
+            let ctx = ctx.lock().unwrap();
+            let Some(noita) = ctx.noita.as_ref() else {
+                return Ok(Dynamic::UNIT);
+            };
+            let s = noita.read_stats().map_err(io_error)?.session;
+            Ok(map([
+                ("playtime", s.playtime.into()),
+                ("kills", i64::from(s.enemies_killed).into()),
+                ("places_visited", i64::from(s.places_visited).into()),
+                ("gold", s.gold.into()),
+                ("gold_all", s.gold_all.into()),
+                ("items", i64::from(s.items).into()),
+                ("projectiles_shot", i64::from(s.projectiles_shot).into()),
+                ("kicks", i64::from(s.kicks).into()),
+                ("damage_taken", (s.damage_taken * 25.0).into()),
+                ("healed", (s.healed * 25.0).into()),
+                ("teleports", i64::from(s.teleports).into()),
+                ("wands_edited", i64::from(s.wands_edited).into()),
+            ]))
+        });
+        let ctx = context.clone();
+        engine.register_fn("global", move |name: &str| -> EvalResult<Dynamic> {
+            let ctx = ctx.lock().unwrap();
+            let Some(noita) = ctx.noita.as_ref() else {
+                return Ok(Dynamic::UNIT);
+            };
+            Ok(opt(noita.read_lua_global(name).map_err(io_error)?))
+        });
+
+        Self { engine, context }
+    }
+}
+
+impl ScriptEngine {
+    pub fn compile(&self, source: &str) -> Result<AST, String> {
+        self.engine.compile(source).map_err(|e| e.to_string())
+    }
+
+    /// Run the script against the given game state. The game handle is only
+    /// borrowed for the duration of the run, it's put back afterwards
+    pub fn run(
+        &self,
+        ast: &AST,
+        game: Arc<GameState>,
+        noita: &mut Option<Noita>,
+    ) -> Result<Dynamic, String> {
+        {
+            let mut context = self.context.lock().unwrap();
+            context.game = game;
+            context.noita = noita.take();
+        }
+        let result = self.engine.eval_ast::<Dynamic>(ast);
+        *noita = self.context.lock().unwrap().noita.take();
+        result.map_err(|e| e.to_string())
+    }
+}
+
+fn opt<T: Into<Dynamic>>(value: Option<T>) -> Dynamic {
+    value.map_or(Dynamic::UNIT, Into::into)
+}
+
+fn map<const N: usize>(fields: [(&str, Dynamic); N]) -> Dynamic {
+    Dynamic::from_map(
+        fields
+            .into_iter()
+            .map(|(k, v)| (k.into(), v))
+            .collect::<Map>(),
+    )
+}
+
+fn io_error(e: io::Error) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+fn player(game: &GameState) -> Dynamic {
+    let Some((player, polymorphed)) = &game.player else {
+        return Dynamic::UNIT;
+    };
+    map([
+        ("x", (player.transform.pos.x as f64).into()),
+        ("y", (player.transform.pos.y as f64).into()),
+        ("polymorphed", (*polymorphed).into()),
+    ])
+}
+
+/// A few of the fields of a player component, the ones we know the layout of
+/// and that make sense to show
+fn component(noita: &mut Noita, player: &Entity, name: &str) -> EvalResult<Option<Dynamic>> {
+    let read = match name {
+        "DamageModelComponent" => noita
+            .component_store::<DamageModelComponent>()
+            .and_then(|store| store.get(player))
+            .map(|dm| {
+                dm.map(|dm| {
+                    map([
+                        ("hp", (dm.hp.get() * 25.0).into()),
+                        ("max_hp", (dm.max_hp.get() * 25.0).into()),
+                        ("max_hp_cap", (dm.max_hp_cap.get() * 25.0).into()),
+                        ("air_in_lungs", (dm.air_in_lungs as f64).into()),
+                        ("air_in_lungs_max", (dm.air_in_lungs_max as f64).into()),
+                        (
+                            "invincibility_frames",
+                            i64::from(dm.invincibility_frames).into(),
+                        ),
+                        ("is_on_fire", dm.is_on_fire.get().as_bool().into()),
+                    ])
+                })
+            }),
+        "WalletComponent" => noita
+            .component_store::<WalletComponent>()
+            .and_then(|store| store.get(player))
+            .map(|wallet| {
+                wallet.map(|wallet| {
+                    map([
+                        ("money", (wallet.money.get() as i64).into()),
+                        ("money_spent", (wallet.money_spent.get() as i64).into()),
+                    ])
+                })
+            }),
+        "CharacterDataComponent" => noita
+            .component_store::<CharacterDataComponent>()
+            .and_then(|store| store.get(player))
+            .map(|cd| {
+                cd.map(|cd| {
+                    map([
+                        ("mass", (cd.mass as f64).into()),
+                        ("gravity", (cd.gravity as f64).into()),
+                    ])
+                })
+            }),
+        _ => {
+            return Err(format!(
+                "Unknown component {name}, can read {}",
+                COMPONENTS.join(", ")
+            )
+            .into())
+        }
+    };
+    read.map_err(io_error)
+}
+
+/// How a script result is shown, maps become a list of values
+pub fn display(value: &Dynamic) -> Vec<(String, String)> {
+    match value.read_lock::<Map>() {
+        Some(map) => map
+            .iter()
+            .map(|(k, v)| (k.to_string(), display_value(v)))
+            .collect(),
+        None => vec![(String::new(), display_value(value))],
+    }
+}
+
+fn display_value(value: &Dynamic) -> String {
+    if value.is_unit() {
+        "-".into()
+    } else if let Ok(f) = value.as_float() {
+        format!("{f:.2}")
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn sandbox() {
+    let engine = ScriptEngine::default();
+    let game = Arc::new(GameState {
+        gold: Some(200),
+        ..Default::default()
+    });
+    let run = |source: &str| {
+        let ast = engine.compile(source)?;
+        engine.run(&ast, game.clone(), &mut None)
+    };
+
+    let result = run("#{ gold: gold() * 2, hp: hp() }").unwrap();
+    assert_eq!(
+        display(&result),
+        [("gold".into(), "400".into()), ("hp".into(), "-".into())]
+    );
+    assert!(run("loop {}").is_err());
+    assert!(run(r#"import "foo" as foo; 1"#).is_err());
+    assert!(run(r#"eval("1")"#).is_err());
+}
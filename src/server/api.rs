@@ -0,0 +1,197 @@
+//! The read-only HTTP endpoints of the local server, all of them answer GET
+//! requests with JSON:
+//!
+//! - `/seed` - the seed, split into the world seed and the NG+ count
+//! - `/player` - HP (in the units the game shows), max HP, gold, position
+//!   and whether the player is polymorphed
+//! - `/wands` - the wands in the quick inventory
+//! - `/shifts` - the fungal shifts that are still to happen, in order
+//! - `/stats` - deaths, wins, streaks and such, for the session and overall
+//!
+//! Everything is `null` when not connected to the game or not in a run.
+//!
+//! `/metrics` is the odd one out, it's for Prometheus, see [super::metrics].
+//!
+//! Requests must be addressed to `127.0.0.1:<port>` or `localhost:<port>`,
+//! and there are no CORS headers, so websites open in the browser can't read
+//! any of it.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context as _;
+use noita_utility_box::noita::Noita;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::{reader::GameState, readout};
+
+use super::{is_own_host, metrics};
+
+pub const ENDPOINTS: &[&str] = &[
+    "/seed", "/player", "/wands", "/shifts", "/stats", "/metrics",
+];
+
+/// Answer a plain HTTP request, the head of which is already peeked
+pub async fn handle(
+    mut stream: TcpStream,
+    head_len: usize,
+    game: Arc<GameState>,
+    noita: Arc<Mutex<Option<Noita>>>,
+    port: u16,
+) -> anyhow::Result<()> {
+    let mut head = vec![0; head_len];
+    stream.read_exact(&mut head).await?;
+    let head = String::from_utf8_lossy(&head);
+
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default();
+    // ignore the query string, we have no parameters
+    let path = request_line
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .to_owned();
+
+    // a page on some domain that resolves to 127.0.0.1 still sends its
+    // own name as the host
+    let host = head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("host").then_some(value)
+    });
+
+    let (status, body) = if !host.is_some_and(|host| is_own_host(host, port)) {
+        (
+            "403 Forbidden",
+            json!({ "error": "Only requests to 127.0.0.1 or localhost are allowed" }),
+        )
+    } else if method != "GET" {
+        (
+            "405 Method Not Allowed",
+            json!({ "error": "Only GET is supported" }),
+        )
+    } else if path == "/metrics" {
+        let text = tokio::task::spawn_blocking(move || {
+            metrics::render(&game, noita.lock().unwrap().as_ref())
+        })
+        .await?;
+        return respond(stream, "200 OK", metrics::CONTENT_TYPE, &text).await;
+    } else {
+        let result = tokio::task::spawn_blocking(move || route(&path, &game, &noita)).await?;
+        match result {
+            Ok(Some(body)) => ("200 OK", body),
+            Ok(None) => (
+                "404 Not Found",
+                json!({ "error": "No such endpoint", "endpoints": ENDPOINTS }),
+            ),
+            Err(e) => (
+                "500 Internal Server Error",
+                json!({ "error": format!("{e:#}") }),
+            ),
+        }
+    };
+
+    respond(stream, status, "application/json", &body.to_string()).await
+}
+
+async fn respond(
+    mut stream: TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> anyhow::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: {content_type}\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n\
+         {body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// None if there's no such endpoint
+fn route(
+    path: &str,
+    game: &GameState,
+    noita: &Mutex<Option<Noita>>,
+) -> anyhow::Result<Option<Value>> {
+    let mut noita = noita.lock().unwrap();
+    let value = match path {
+        "" => json!({ "endpoints": ENDPOINTS }),
+        "/seed" => match game.seed {
+            Some(seed) => json!({
+                "seed": seed.to_string(),
+                "world_seed": seed.world_seed,
+                "ng_count": seed.ng_count,
+            }),
+            None => Value::Null,
+        },
+        "/player" => match &game.player {
+            Some((player, polymorphed)) => json!({
+                "hp": game.hp.map(|(hp, _)| hp * 25.0),
+                "max_hp": game.hp.map(|(_, max_hp)| max_hp * 25.0),
+                "gold": game.gold,
+                "x": player.transform.pos.x,
+                "y": player.transform.pos.y,
+                "polymorphed": polymorphed,
+            }),
+            None => Value::Null,
+        },
+        "/wands" => match noita.as_mut() {
+            Some(noita) => serde_json::to_value(readout::wands(noita)?)?,
+            None => Value::Null,
+        },
+        "/shifts" => match noita.as_ref() {
+            Some(noita) if game.seed.is_some() => {
+                serde_json::to_value(readout::upcoming_shifts(noita, usize::MAX)?)?
+            }
+            _ => Value::Null,
+        },
+        "/stats" => match noita.as_ref() {
+            Some(noita) => stats(noita)?,
+            None => Value::Null,
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+fn stats(noita: &Noita) -> anyhow::Result<Value> {
+    let stats = noita.read_stats().context("Reading global stats")?;
+    let kv = |name: &str| -> anyhow::Result<u32> {
+        Ok(stats
+            .key_value_stats
+            .get(noita.proc(), name)?
+            .unwrap_or_default())
+    };
+    Ok(json!({
+        "deaths": stats.global.death_count,
+        "wins": kv("progress_ending0")? + kv("progress_ending1")?,
+        "streak": stats.session.streaks,
+        "best_streak": stats.highest.streaks,
+        "session": {
+            "playtime": stats.session.playtime,
+            "enemies_killed": stats.session.enemies_killed,
+            "places_visited": stats.session.places_visited,
+            "gold_all": stats.session.gold_all,
+            "items": stats.session.items,
+            "projectiles_shot": stats.session.projectiles_shot,
+            "kicks": stats.session.kicks,
+            "damage_taken": stats.session.damage_taken * 25.0,
+            "healed": stats.session.healed * 25.0,
+            "teleports": stats.session.teleports,
+            "wands_edited": stats.session.wands_edited,
+        },
+        "total_playtime": stats.global.playtime,
+    }))
+}
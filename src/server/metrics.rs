@@ -0,0 +1,136 @@
+//! The `/metrics` endpoint in the Prometheus text format, for graphing long
+//! runs in Grafana and such.
+//!
+//! Gauges that can't be read right now (no game, no run) are left out
+//! rather than reported as zeroes.
+
+use std::fmt::Write as _;
+
+use noita_utility_box::{memory::read_stats, noita::Noita};
+
+use crate::reader::GameState;
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+#[derive(Debug, Default)]
+struct Metrics(String);
+
+impl Metrics {
+    fn add(&mut self, name: &str, kind: &str, help: &str, value: Option<f64>) {
+        let Some(value) = value else {
+            return;
+        };
+        let _ = writeln!(self.0, "# HELP noita_{name} {help}");
+        let _ = writeln!(self.0, "# TYPE noita_{name} {kind}");
+        let _ = writeln!(self.0, "noita_{name} {value}");
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: Option<impl Into<f64>>) {
+        self.add(name, "gauge", help, value.map(Into::into));
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: Option<impl Into<f64>>) {
+        self.add(name, "counter", help, value.map(Into::into));
+    }
+}
+
+pub fn render(game: &GameState, noita: Option<&Noita>) -> String {
+    let mut m = Metrics::default();
+
+    m.gauge(
+        "connected",
+        "Whether the app is attached to the game",
+        Some(noita.is_some() as u8),
+    );
+    m.gauge("frame", "The game frame counter", game.frame);
+    m.gauge(
+        "hp",
+        "Player HP, in the units the game shows",
+        game.hp.map(|(hp, _)| hp * 25.0),
+    );
+    m.gauge(
+        "max_hp",
+        "Player max HP, in the units the game shows",
+        game.hp.map(|(_, max_hp)| max_hp * 25.0),
+    );
+    m.gauge("gold", "Player gold", game.gold.map(|g| g as f64));
+    m.gauge(
+        "orbs",
+        "Orbs picked up in this run",
+        game.orbs.map(|o| o as f64),
+    );
+    m.gauge(
+        "polymorphed",
+        "Whether the player is polymorphed",
+        game.player
+            .as_ref()
+            .map(|(_, polymorphed)| *polymorphed as u8),
+    );
+    m.gauge(
+        "shift_cooldown_seconds",
+        "Time until a fungal shift can happen again",
+        game.shift_cooldown.map(|frames| frames as f64 / 60.0),
+    );
+
+    let stats = noita.and_then(|noita| {
+        noita
+            .read_stats()
+            .inspect_err(|e| tracing::debug!(%e, "Failed to read the global stats"))
+            .ok()
+    });
+    m.gauge(
+        "session_kills",
+        "Enemies killed in this session",
+        stats.as_ref().map(|s| s.session.enemies_killed),
+    );
+    m.gauge(
+        "session_playtime_seconds",
+        "Playtime of this session",
+        stats.as_ref().map(|s| s.session.playtime),
+    );
+    m.counter(
+        "deaths_total",
+        "Deaths over all the sessions",
+        stats.as_ref().map(|s| s.global.death_count),
+    );
+    m.counter(
+        "playtime_seconds_total",
+        "Playtime over all the sessions",
+        stats.as_ref().map(|s| s.global.playtime),
+    );
+
+    // the app's own memory reads, not just the ones for this endpoint
+    let reads = read_stats();
+    m.counter(
+        "memory_reads_total",
+        "Game memory reads done by the app",
+        Some(reads.reads as f64),
+    );
+    m.counter(
+        "memory_read_bytes_total",
+        "Bytes read from the game memory by the app",
+        Some(reads.bytes as f64),
+    );
+    m.counter(
+        "memory_read_failures_total",
+        "Game memory reads that failed",
+        Some(reads.failures as f64),
+    );
+
+    m.0
+}
+
+#[cfg(test)]
+#[test]
+fn text_format() {
+    let game = GameState {
+        gold: Some(200),
+        ..Default::default()
+    };
+    let text = render(&game, None);
+    assert!(text.contains("# TYPE noita_gold gauge\nnoita_gold 200\n"));
+    assert!(text.contains("noita_connected 0\n"));
+    // unknown values are left out
+    assert!(!text.contains("noita_hp"));
+    assert!(!text.contains("noita_session_kills"));
+}
@@ -0,0 +1,393 @@
+//! A local server for things like Stream Deck plugins, overlays and
+//! scripts, that want to show the live values and to trigger a few actions
+//! in the app.
+//!
+//! It only listens on localhost, at `127.0.0.1:<port>` (the port is in the
+//! settings). WebSocket messages are JSON objects with a `type` field.
+//! Browser pages can only connect if they're served from the same
+//! `127.0.0.1:<port>` or `localhost:<port>`, so that any website open in
+//! the browser can't trigger the actions.
+//!
+//! The server sends the values on connect and then every time they change:
+//! ```json
+//! { "type": "values", "seed": "1234567+0", "hp": 100.0, "max_hp": 100.0,
+//!   "gold": 200, "shift_cooldown": 0.0, "shift_ready": true }
+//! ```
+//! Any of those can be `null` when not in a run or not connected to the
+//! game. HP is in the units the game shows, the shift cooldown is in
+//! seconds.
+//!
+//! Clients can send actions:
+//! ```json
+//! { "type": "action", "action": "copy-seed" }
+//! { "type": "action", "action": "reset-radar" }
+//! ```
+//! A message that can't be understood gets an error back:
+//! ```json
+//! { "type": "error", "message": "..." }
+//! ```
+//!
+//! Overlays that only want to show the state can connect to `/overlay`
+//! instead, which pushes deltas and a few more values, see [overlay].
+//!
+//! Plain HTTP requests to the same port get the read-only API, see [api].
+
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::watch,
+    task::{JoinHandle, JoinSet},
+};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::{header::ORIGIN, StatusCode},
+    Message,
+};
+
+use noita_utility_box::noita::Noita;
+
+use crate::reader::GameState;
+
+mod api;
+mod metrics;
+mod overlay;
+
+/// Longest request head we look at to tell WebSocket and HTTP apart
+const MAX_HEAD: usize = 4096;
+const HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize, SmartDefault)]
+#[serde(default)]
+pub struct ServerSettings {
+    pub enabled: bool,
+    #[default(24680)]
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Values {
+    pub seed: Option<String>,
+    pub hp: Option<f64>,
+    pub max_hp: Option<f64>,
+    pub gold: Option<u64>,
+    pub shift_cooldown: Option<f32>,
+    pub shift_ready: Option<bool>,
+}
+
+impl Values {
+    pub fn new(game: &GameState) -> Self {
+        Self {
+            seed: game.seed.map(|s| s.to_string()),
+            hp: game.hp.map(|(hp, _)| hp * 25.0),
+            max_hp: game.hp.map(|(_, max_hp)| max_hp * 25.0),
+            gold: game.gold,
+            shift_cooldown: game.shift_cooldown.map(|frames| frames as f32 / 60.0),
+            shift_ready: game.shift_cooldown.map(|frames| frames == 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    CopySeed,
+    ResetRadar,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum ServerMessage<'a> {
+    Values(&'a Values),
+    Error { message: String },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum ClientMessage {
+    Action { action: Action },
+}
+
+#[derive(Debug)]
+struct Running {
+    port: u16,
+    task: JoinHandle<()>,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        // the connections are in a JoinSet owned by the task, so they go too
+        self.task.abort();
+    }
+}
+
+/// What the connections get from the app
+#[derive(Debug, Clone)]
+struct Shared {
+    values: watch::Receiver<Values>,
+    game: watch::Receiver<Arc<GameState>>,
+    /// For the API endpoints that read more than the background reader does
+    noita: Arc<Mutex<Option<Noita>>>,
+    actions: mpsc::Sender<Action>,
+    port: u16,
+}
+
+/// The server, started and stopped following the [ServerSettings]
+#[derive(Debug)]
+pub struct LocalServer {
+    running: Option<Running>,
+    values: watch::Sender<Values>,
+    game: watch::Sender<Arc<GameState>>,
+    noita: Arc<Mutex<Option<Noita>>>,
+    actions_tx: mpsc::Sender<Action>,
+    actions: mpsc::Receiver<Action>,
+}
+
+impl Default for LocalServer {
+    fn default() -> Self {
+        let (actions_tx, actions) = mpsc::channel();
+        Self {
+            running: None,
+            values: watch::Sender::new(Values::default()),
+            game: watch::Sender::new(Arc::default()),
+            noita: Arc::default(),
+            actions_tx,
+            actions,
+        }
+    }
+}
+
+impl LocalServer {
+    /// (Re)start or stop the server if the settings changed, and send the
+    /// values to the clients if those changed
+    pub fn update(&mut self, settings: &ServerSettings, game: Arc<GameState>) {
+        match &self.running {
+            Some(running) if !settings.enabled || running.port != settings.port => {
+                tracing::info!(port = running.port, "Stopping the local server");
+                self.running = None;
+            }
+            _ => {}
+        }
+        if settings.enabled && self.running.is_none() {
+            self.running = Some(self.start(settings.port));
+        }
+        let values = Values::new(&game);
+        self.game.send_replace(game);
+        self.values.send_if_modified(|current| {
+            let changed = *current != values;
+            *current = values;
+            changed
+        });
+    }
+
+    fn start(&self, port: u16) -> Running {
+        tracing::info!(port, "Starting the local server");
+        let error = Arc::new(Mutex::new(None));
+        let task_error = error.clone();
+        let shared = Shared {
+            values: self.values.subscribe(),
+            game: self.game.subscribe(),
+            noita: self.noita.clone(),
+            actions: self.actions_tx.clone(),
+            port,
+        };
+        let task = tokio::spawn(async move {
+            if let Err(e) = listen(port, shared).await {
+                tracing::warn!(port, e = format!("{e:#}"), "Local server failed");
+                *task_error.lock().unwrap() = Some(format!("{e:#}"));
+            }
+        });
+        Running { port, task, error }
+    }
+
+    pub fn set_noita(&mut self, noita: Option<Noita>) {
+        *self.noita.lock().unwrap() = noita;
+    }
+
+    /// Actions the clients asked for since the last call
+    pub fn take_actions(&mut self) -> Vec<Action> {
+        self.actions.try_iter().collect()
+    }
+
+    /// Where it listens, or why it doesn't
+    pub fn status(&self) -> Option<Result<String, String>> {
+        let running = self.running.as_ref()?;
+        Some(match &*running.error.lock().unwrap() {
+            Some(e) => Err(e.clone()),
+            None => Ok(format!("127.0.0.1:{}", running.port)),
+        })
+    }
+}
+
+async fn listen(port: u16, shared: Shared) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("Listening on port {port}"))?;
+    let mut connections = JoinSet::new();
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        while connections.try_join_next().is_some() {}
+
+        let shared = shared.clone();
+        connections.spawn(async move {
+            tracing::debug!(%addr, "Local server client connected");
+            if let Err(e) = serve(stream, shared).await {
+                tracing::debug!(%addr, e = format!("{e:#}"), "Local server client failed");
+            }
+        });
+    }
+}
+
+/// Tell a WebSocket handshake from a plain HTTP request without consuming
+/// it, tungstenite wants to read the handshake itself
+async fn serve(stream: TcpStream, shared: Shared) -> anyhow::Result<()> {
+    let mut buf = vec![0; MAX_HEAD];
+    let head_len = tokio::time::timeout(HEAD_TIMEOUT, async {
+        loop {
+            let n = stream.peek(&mut buf).await?;
+            if n == 0 {
+                anyhow::bail!("Connection closed");
+            }
+            if let Some(end) = buf[..n].windows(4).position(|w| w == b"\r\n\r\n") {
+                return Ok(end + 4);
+            }
+            if n == buf.len() {
+                anyhow::bail!("Request head too long");
+            }
+            // peek returns right away while there's unread data
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .context("Timed out reading the request")??;
+
+    let head = String::from_utf8_lossy(&buf[..head_len]).to_ascii_lowercase();
+    if head.contains("upgrade: websocket") {
+        let path = head.split(' ').nth(1).unwrap_or_default();
+        if path.trim_end_matches('/') == "/overlay" {
+            overlay::serve(stream, shared).await
+        } else {
+            serve_ws(stream, shared).await
+        }
+    } else {
+        let game = shared.game.borrow().clone();
+        api::handle(stream, head_len, game, shared.noita, shared.port).await
+    }
+}
+
+/// Whether a `Host` header (or the host part of an origin) is this server
+fn is_own_host(host: &str, port: u16) -> bool {
+    let host = host.trim().to_ascii_lowercase();
+    ["127.0.0.1", "localhost"]
+        .iter()
+        .any(|name| host == format!("{name}:{port}"))
+}
+
+/// Whether an `Origin` header is of a page served by this server
+fn is_own_origin(origin: &str, port: u16) -> bool {
+    origin
+        .trim()
+        .strip_prefix("http://")
+        .is_some_and(|host| is_own_host(host, port))
+}
+
+async fn serve_ws(stream: TcpStream, shared: Shared) -> anyhow::Result<()> {
+    let Shared {
+        mut values,
+        actions,
+        port,
+        ..
+    } = shared;
+    // browsers always send the origin, other clients have no reason to
+    let check_origin = |request: &Request, response: Response| {
+        let Some(origin) = request.headers().get(ORIGIN) else {
+            return Ok(response);
+        };
+        if is_own_origin(origin.to_str().unwrap_or_default(), port) {
+            return Ok(response);
+        }
+        tracing::warn!(
+            ?origin,
+            "Rejected a WebSocket connection from another origin"
+        );
+        let mut response = ErrorResponse::new(Some("Origin not allowed".into()));
+        *response.status_mut() = StatusCode::FORBIDDEN;
+        Err(response)
+    };
+    let ws = tokio_tungstenite::accept_hdr_async(stream, check_origin).await?;
+    let (mut tx, mut rx) = ws.split();
+
+    let send = |message: ServerMessage| serde_json::to_string(&message).map(Message::text);
+
+    let current = values.borrow_and_update().clone();
+    tx.send(send(ServerMessage::Values(&current))?).await?;
+
+    loop {
+        tokio::select! {
+            changed = values.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                let current = values.borrow_and_update().clone();
+                tx.send(send(ServerMessage::Values(&current))?).await?;
+            }
+            message = rx.next() => {
+                let text = match message.transpose()? {
+                    None | Some(Message::Close(_)) => return Ok(()),
+                    Some(Message::Text(text)) => text,
+                    // pings are answered by tungstenite itself
+                    Some(_) => continue,
+                };
+                match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Action { action }) => {
+                        let _ = actions.send(action);
+                    }
+                    Err(e) => {
+                        let message = e.to_string();
+                        tx.send(send(ServerMessage::Error { message })?).await?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn message_schema() {
+    let values = Values {
+        gold: Some(200),
+        ..Default::default()
+    };
+    let json = serde_json::to_value(ServerMessage::Values(&values)).unwrap();
+    assert_eq!(json["type"], "values");
+    assert_eq!(json["gold"], 200);
+    assert!(json["hp"].is_null());
+
+    let message = r#"{ "type": "action", "action": "reset-radar" }"#;
+    assert!(matches!(
+        serde_json::from_str(message).unwrap(),
+        ClientMessage::Action {
+            action: Action::ResetRadar
+        }
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn own_origin() {
+    assert!(is_own_origin("http://127.0.0.1:24680", 24680));
+    assert!(is_own_origin("http://LOCALHOST:24680", 24680));
+    assert!(!is_own_origin("http://localhost:24681", 24680));
+    assert!(!is_own_origin("https://example.com", 24680));
+    assert!(!is_own_origin("http://localhost:24680.example.com", 24680));
+    assert!(!is_own_origin("null", 24680));
+}
@@ -0,0 +1,153 @@
+//! The `/overlay` WebSocket stream, for self-hosted browser source overlays.
+//!
+//! It's push-only: on connect the client gets the whole state, and after
+//! that only the fields that changed:
+//! ```json
+//! { "type": "state", "seed": "1234567+0", "hp": 100.0, "max_hp": 100.0,
+//!   "gold": 200, "shift_cooldown": 0.0, "shift_ready": true, "orbs": 0,
+//!   "polymorphed": false, "wands": [{ "slot": 0, "name": "Wand", "spells": 2 }] }
+//! { "type": "delta", "gold": 250 }
+//! ```
+//! A field that becomes unavailable comes in the delta as `null`. The wands
+//! are re-read about once a second, the rest follows the game.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{SinkExt, StreamExt};
+use noita_utility_box::noita::Noita;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tokio::{net::TcpStream, time::MissedTickBehavior};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    reader::GameState,
+    readout::{self, WandInfo},
+};
+
+use super::{Shared, Values};
+
+const WANDS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct OverlayState {
+    #[serde(flatten)]
+    values: Values,
+    orbs: Option<usize>,
+    polymorphed: Option<bool>,
+    wands: Option<Vec<WandInfo>>,
+}
+
+impl OverlayState {
+    fn update(&mut self, game: &GameState) {
+        self.values = Values::new(game);
+        self.orbs = game.orbs;
+        self.polymorphed = game.player.as_ref().map(|(_, polymorphed)| *polymorphed);
+    }
+
+    fn to_json(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => unreachable!("a struct serializes to an object"),
+        }
+    }
+}
+
+/// The fields of `new` that differ from `old`, missing ones become `null`
+fn delta(old: &Map<String, Value>, new: &Map<String, Value>) -> Map<String, Value> {
+    let mut delta = Map::new();
+    for (key, value) in new {
+        if old.get(key) != Some(value) {
+            delta.insert(key.clone(), value.clone());
+        }
+    }
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            delta.insert(key.clone(), Value::Null);
+        }
+    }
+    delta
+}
+
+fn message(kind: &str, mut fields: Map<String, Value>) -> Message {
+    fields.insert("type".into(), kind.into());
+    Message::text(Value::Object(fields).to_string())
+}
+
+async fn read_wands(noita: Arc<Mutex<Option<Noita>>>) -> Option<Vec<WandInfo>> {
+    tokio::task::spawn_blocking(move || {
+        let mut noita = noita.lock().unwrap();
+        readout::wands(noita.as_mut()?).ok()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+pub async fn serve(stream: TcpStream, shared: Shared) -> anyhow::Result<()> {
+    let Shared {
+        mut game, noita, ..
+    } = shared;
+    let ws = tokio_tungstenite::accept_async(stream).await?;
+    let (mut tx, mut rx) = ws.split();
+
+    let mut state = OverlayState::default();
+    state.update(&game.borrow_and_update());
+    state.wands = read_wands(noita.clone()).await;
+    let mut sent = state.to_json();
+    tx.send(message("state", sent.clone())).await?;
+
+    let mut wands_interval = tokio::time::interval(WANDS_INTERVAL);
+    wands_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    wands_interval.tick().await;
+
+    loop {
+        tokio::select! {
+            changed = game.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                state.update(&game.borrow_and_update());
+            }
+            _ = wands_interval.tick() => {
+                state.wands = read_wands(noita.clone()).await;
+            }
+            message = rx.next() => match message.transpose()? {
+                None | Some(Message::Close(_)) => return Ok(()),
+                // nothing to say to us here, and pings are answered by
+                // tungstenite itself
+                Some(_) => continue,
+            },
+        }
+
+        let current = state.to_json();
+        let changes = delta(&sent, &current);
+        if !changes.is_empty() {
+            tx.send(message("delta", changes)).await?;
+            sent = current;
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn state_delta() {
+    let mut state = OverlayState::default();
+    let old = state.to_json();
+    assert!(delta(&old, &old).is_empty());
+
+    state.values.gold = Some(250);
+    state.orbs = Some(1);
+    let new = state.to_json();
+    let changes = delta(&old, &new);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes["gold"], 250);
+    assert_eq!(changes["orbs"], 1);
+
+    // flattened, so the overlay sees the same field names as the values
+    assert!(new.contains_key("shift_ready"));
+    assert!(!new.contains_key("values"));
+}
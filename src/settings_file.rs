@@ -0,0 +1,149 @@
+//! The whole persisted app state in a single JSON file, for moving between
+//! machines and attaching reproducible configs to bug reports
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context as _;
+use serde_json::{Map, Value};
+
+/// Keys with any of these in their name are left out of the exports unless
+/// asked otherwise, and keep their current values on import
+const SECRET_KEYS: &[&str] = &["token", "password", "secret"];
+
+#[derive(Debug)]
+pub enum Request {
+    Export {
+        path: PathBuf,
+        include_secrets: bool,
+    },
+    Import(PathBuf),
+}
+
+/// The settings tool inputs for the export and import
+#[derive(Debug, Default, Clone)]
+pub struct TransferUi {
+    pub path: String,
+    pub include_secrets: bool,
+}
+
+fn is_secret(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+/// Where the exports go if no path was given
+pub fn default_path() -> anyhow::Result<PathBuf> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let dir = eframe::storage_dir(env!("CARGO_PKG_NAME"))
+        .context("No storage dir")?
+        .join("exports");
+    Ok(dir.join(format!("settings-{now}.json")))
+}
+
+/// Remove the secret keys everywhere in the value
+pub fn strip_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| !is_secret(key));
+            map.values_mut().for_each(strip_secrets);
+        }
+        Value::Array(values) => values.iter_mut().for_each(strip_secrets),
+        _ => {}
+    }
+}
+
+/// Copy the secret keys missing from the imported value over from the
+/// current one, so that importing a stripped export doesn't log you out of
+/// everything. Arrays are matched by index, which is good enough for the
+/// tool lists
+pub fn keep_secrets(imported: &mut Value, current: &Value) {
+    match (imported, current) {
+        (Value::Object(imported), Value::Object(current)) => keep_secrets_in(imported, current),
+        (Value::Array(imported), Value::Array(current)) => {
+            for (imported, current) in imported.iter_mut().zip(current) {
+                keep_secrets(imported, current);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn keep_secrets_in(imported: &mut Map<String, Value>, current: &Map<String, Value>) {
+    for (key, current) in current {
+        match imported.get_mut(key) {
+            Some(imported) => keep_secrets(imported, current),
+            None if is_secret(key) => {
+                imported.insert(key.clone(), current.clone());
+            }
+            None => {}
+        }
+    }
+}
+
+pub fn write(path: &Path, mut value: Value, include_secrets: bool) -> anyhow::Result<()> {
+    if !include_secrets {
+        strip_secrets(&mut value);
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_vec_pretty(&value)?)
+        .with_context(|| format!("Writing {}", path.display()))
+}
+
+pub fn read(path: &Path, current: &Value) -> anyhow::Result<Value> {
+    let data = std::fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+    let mut value = serde_json::from_slice(&data).context("Not a settings export")?;
+    keep_secrets(&mut value, current);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn strips_nested_secrets() {
+        let mut value = json!({
+            "settings": { "server": { "port": 24680 } },
+            "tools": [{ "TwitchBot": { "channel": "me", "token": "abc" } }],
+            "obs_password": "hunter2",
+        });
+        strip_secrets(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "settings": { "server": { "port": 24680 } },
+                "tools": [{ "TwitchBot": { "channel": "me" } }],
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_current_secrets_on_import() {
+        let current = json!({
+            "tools": [{ "TwitchBot": { "channel": "me", "token": "abc" } }],
+            "obs_password": "hunter2",
+        });
+        let mut imported = json!({
+            "tools": [{ "TwitchBot": { "channel": "someone" } }],
+            "obs_password": "imported",
+        });
+        keep_secrets(&mut imported, &current);
+        assert_eq!(
+            imported,
+            json!({
+                "tools": [{ "TwitchBot": { "channel": "someone", "token": "abc" } }],
+                "obs_password": "imported",
+            })
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Audible alerts, for the things that are easy to miss while playing.
+//!
+//! The sounds are short tone sequences synthesized on the fly, so there are
+//! no sound files to ship. Playback happens on a thread of its own that
+//! owns the output stream, opened on the first alert.
+
+use std::{
+    sync::{
+        mpsc::{self, Sender},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use rodio::{
+    source::{self, SineWave},
+    OutputStream, Source,
+};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alert {
+    OrbProximity,
+    LowHp,
+    ShiftReady,
+    WebsocketDropped,
+}
+
+impl Alert {
+    pub const ALL: [Alert; 4] = [
+        Alert::OrbProximity,
+        Alert::LowHp,
+        Alert::ShiftReady,
+        Alert::WebsocketDropped,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Alert::OrbProximity => "Orb proximity",
+            Alert::LowHp => "Low HP",
+            Alert::ShiftReady => "Fungal shift ready",
+            Alert::WebsocketDropped => "OBS connection lost",
+        }
+    }
+
+    /// Frequency in Hz and duration in ms of each tone, 0 Hz being a pause
+    fn tones(self) -> &'static [(f32, u64)] {
+        match self {
+            Alert::OrbProximity => &[(880.0, 120), (1320.0, 180)],
+            Alert::LowHp => &[(660.0, 90), (0.0, 60), (660.0, 90), (0.0, 60), (660.0, 90)],
+            Alert::ShiftReady => &[(523.0, 100), (659.0, 100), (784.0, 200)],
+            Alert::WebsocketDropped => &[(440.0, 150), (330.0, 250)],
+        }
+    }
+}
+
+/// Volume of each alert, from 0 (off) to 1. The ones that have no other
+/// switch to turn them on are off by default
+#[derive(Debug, Clone, Serialize, Deserialize, SmartDefault)]
+#[serde(default)]
+pub struct SoundSettings {
+    #[default(0.5)]
+    pub orb_proximity: f32,
+    #[default(0.5)]
+    pub low_hp: f32,
+    pub shift_ready: f32,
+    pub websocket_dropped: f32,
+}
+
+impl SoundSettings {
+    pub fn volume_mut(&mut self, alert: Alert) -> &mut f32 {
+        match alert {
+            Alert::OrbProximity => &mut self.orb_proximity,
+            Alert::LowHp => &mut self.low_hp,
+            Alert::ShiftReady => &mut self.shift_ready,
+            Alert::WebsocketDropped => &mut self.websocket_dropped,
+        }
+    }
+
+    pub fn volume(&self, alert: Alert) -> f32 {
+        let volume = match alert {
+            Alert::OrbProximity => self.orb_proximity,
+            Alert::LowHp => self.low_hp,
+            Alert::ShiftReady => self.shift_ready,
+            Alert::WebsocketDropped => self.websocket_dropped,
+        };
+        volume.clamp(0.0, 1.0)
+    }
+}
+
+/// Play the alert at its configured volume, without blocking the caller
+pub fn play(alert: Alert, settings: &SoundSettings) {
+    play_at(alert, settings.volume(alert));
+}
+
+pub fn play_at(alert: Alert, volume: f32) {
+    if volume <= 0.0 {
+        return;
+    }
+    static PLAYER: OnceLock<Mutex<Sender<(Alert, f32)>>> = OnceLock::new();
+    let player = PLAYER.get_or_init(|| Mutex::new(spawn_player()));
+    // if the thread is gone, we already logged why
+    let _ = player.lock().unwrap().send((alert, volume));
+}
+
+fn spawn_player() -> Sender<(Alert, f32)> {
+    let (tx, rx) = mpsc::channel::<(Alert, f32)>();
+    let spawned = thread::Builder::new().name("sound".into()).spawn(move || {
+        // the stream stops playing when dropped, so it lives here
+        let (_stream, handle) = match OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                tracing::warn!(%e, "No audio output, alerts will be silent");
+                return;
+            }
+        };
+        for (alert, volume) in rx {
+            let tones = alert.tones().iter().map(move |&(freq, ms)| {
+                SineWave::new(freq)
+                    .take_duration(Duration::from_millis(ms))
+                    .fade_in(Duration::from_millis(5))
+                    .amplify(volume)
+            });
+            if let Err(e) = handle.play_raw(source::from_iter(tones).convert_samples()) {
+                tracing::debug!(%e, ?alert, "Couldn't play the alert");
+            }
+        }
+    });
+    if let Err(e) = spawned {
+        tracing::warn!(%e, "Failed to spawn the sound thread");
+    }
+    tx
+}
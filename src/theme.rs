@@ -0,0 +1,92 @@
+use eframe::egui::{Color32, Context, Theme, ThemePreference, Visuals};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::i18n::tr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeMode {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 3] = [ThemeMode::System, ThemeMode::Light, ThemeMode::Dark];
+
+    pub fn name(self) -> &'static str {
+        tr(match self {
+            ThemeMode::System => "Follow the system",
+            ThemeMode::Light => "Light",
+            ThemeMode::Dark => "Dark",
+        })
+    }
+}
+
+impl From<ThemeMode> for ThemePreference {
+    fn from(mode: ThemeMode) -> Self {
+        match mode {
+            ThemeMode::System => ThemePreference::System,
+            ThemeMode::Light => ThemePreference::Light,
+            ThemeMode::Dark => ThemePreference::Dark,
+        }
+    }
+}
+
+/// The look of the app, applied with [update]
+#[derive(Debug, Clone, Serialize, Deserialize, SmartDefault)]
+#[serde(default)]
+pub struct ThemeSettings {
+    pub mode: ThemeMode,
+    /// Replaces the selection and link colors, None for the egui defaults
+    pub accent: Option<[u8; 3]>,
+    #[default(1.0)]
+    pub ui_scale: f32,
+    /// What was last given to egui, to only restyle when something changes
+    #[serde(skip)]
+    applied: Option<(ThemeMode, Option<[u8; 3]>, f32)>,
+}
+
+impl ThemeSettings {
+    pub const SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+}
+
+/// Apply the theme if it changed since the last time, call it every frame
+pub fn update(ctx: &Context, theme: &mut ThemeSettings) {
+    // Ctrl+- and Ctrl+= change the zoom behind our back, keep up with it
+    let zoom = ctx.zoom_factor();
+    if let Some((_, _, applied_scale)) = &mut theme.applied {
+        if *applied_scale != zoom && *applied_scale == theme.ui_scale {
+            theme.ui_scale = zoom;
+            *applied_scale = zoom;
+        }
+    }
+
+    let current = (theme.mode, theme.accent, theme.ui_scale);
+    if theme.applied == Some(current) {
+        return;
+    }
+    tracing::info!(?current, "Applying the theme");
+
+    ctx.set_theme(theme.mode);
+    ctx.set_zoom_factor(theme.ui_scale.clamp(
+        *ThemeSettings::SCALE_RANGE.start(),
+        *ThemeSettings::SCALE_RANGE.end(),
+    ));
+
+    for (which, mut visuals) in [
+        (Theme::Dark, Visuals::dark()),
+        (Theme::Light, Visuals::light()),
+    ] {
+        if let Some([r, g, b]) = theme.accent {
+            let accent = Color32::from_rgb(r, g, b);
+            visuals.selection.bg_fill = accent.gamma_multiply(0.6);
+            visuals.selection.stroke.color = accent;
+            visuals.hyperlink_color = accent;
+        }
+        ctx.set_visuals_of(which, visuals);
+    }
+
+    theme.applied = Some(current);
+}
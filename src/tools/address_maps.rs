@@ -1,14 +1,23 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use anyhow::Context as _;
 use derive_more::Debug;
 use eframe::egui::{
-    collapsing_header::CollapsingState, Align, Button, CollapsingHeader, Id, TextEdit, Ui, Vec2,
-    Widget,
+    collapsing_header::CollapsingState, Align, Button, CollapsingHeader, ComboBox, DragValue, Grid,
+    Id, RichText, ScrollArea, TextEdit, Ui, Vec2, Widget,
 };
 use egui_extras::{Column, TableBuilder};
 use noita_utility_box::{
-    memory::{exe_image::PeHeader, ProcessRef, Ptr},
+    memory::{
+        exe_image::PeHeader,
+        pointer_scan::{PointerChain, PointerScanner},
+        snapshot::{Change, Snapshot},
+        DynStruct, LayoutFile, ProcessRef, Ptr,
+    },
     noita::{discovery, NoitaGlobals},
 };
 use serde::{Deserialize, Serialize};
@@ -16,7 +25,7 @@ use smart_default::SmartDefault;
 
 use crate::app::AppState;
 
-use super::{Result, Tool};
+use super::{Result, Tool, ToolError};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(default)]
@@ -75,6 +84,7 @@ impl AddressMap {
             game_global: self.get("game-global"),
             entity_manager: self.get("entity-manager"),
             entity_tag_manager: self.get("entity-tag-manager"),
+            component_tag_manager: self.get("component-tag-manager"),
             component_type_manager: self.get("component-type-manager"),
             translation_manager: self.get("translation-manager"),
             platform: self.get("platform"),
@@ -82,7 +92,7 @@ impl AddressMap {
     }
 }
 
-fn hex_input(value: &mut u32) -> impl Widget + '_ {
+pub(super) fn hex_input(value: &mut u32) -> impl Widget + '_ {
     move |ui: &mut Ui| {
         let mut ts = format!("0x{:x}", value);
         let response = ui.add(
@@ -254,10 +264,287 @@ impl Tool for AddressMaps {
             s.maps.push(AddressMap::default());
         }
 
+        ui.separator();
+
+        CollapsingHeader::new("Snapshot diff")
+            .show(ui, |ui| snapshot_ui(ui, state))
+            .body_returned
+            .transpose()?;
+
+        CollapsingHeader::new("Struct layouts")
+            .show(ui, |ui| layouts_ui(ui, state))
+            .body_returned
+            .transpose()?;
+
+        CollapsingHeader::new("Pointer chains")
+            .show(ui, |ui| pointer_scan_ui(ui, state))
+            .body_returned
+            .transpose()?;
+
         Ok(())
     }
 }
 
+/// Kept in egui temp memory, no need to persist any of it
+#[derive(Debug, Default, Clone)]
+struct SnapshotState {
+    address: u32,
+    size: u32,
+    snapshot: Option<Snapshot>,
+    /// Set when only a part of the region could be read
+    partial: Option<String>,
+    changes: Vec<Change>,
+}
+
+/// Snapshot a region and see which words in it change, for figuring out
+/// struct layouts
+fn snapshot_ui(ui: &mut Ui, state: &mut AppState) -> Result {
+    // the address maps themselves are usable without noita, so don't error
+    let Some(noita) = state.noita.as_ref() else {
+        ui.label("Not connected to Noita");
+        return Ok(());
+    };
+
+    let id = ui.make_persistent_id("snapshot");
+    let mut s = ui.data_mut(|d| d.get_temp::<SnapshotState>(id).unwrap_or_default());
+
+    ui.horizontal(|ui| {
+        ui.label("Address: ");
+        ui.add(hex_input(&mut s.address));
+        ui.label("Size: ");
+        ui.add(hex_input(&mut s.size));
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Take snapshot").clicked() {
+            let (snapshot, error) = Snapshot::take_partial(noita.proc(), s.address, s.size);
+            s.partial = error.map(|e| format!("Only read 0x{:x} bytes: {e}", snapshot.size()));
+            s.snapshot = Some(snapshot);
+            s.changes.clear();
+        }
+        if let Some(snapshot) = &s.snapshot {
+            if ui.button("Compare").clicked() {
+                let later = snapshot.retake(noita.proc())?;
+                s.changes = snapshot.diff(&later);
+                s.snapshot = Some(later);
+            }
+        }
+        Ok::<_, ToolError>(())
+    })
+    .inner?;
+
+    if let Some(partial) = &s.partial {
+        ui.label(RichText::new(partial).color(ui.visuals().warn_fg_color));
+    }
+    if s.snapshot.is_some() {
+        ui.label(format!(
+            "{} changed words since the last snapshot",
+            s.changes.len()
+        ));
+        ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+            for change in &s.changes {
+                ui.monospace(change.to_string());
+            }
+        });
+    }
+
+    ui.data_mut(|d| d.insert_temp(id, s));
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+struct PointerScanState {
+    target: u32,
+    max_depth: u32,
+    max_offset: u32,
+    chains: Option<Vec<PointerChain>>,
+}
+
+impl Default for PointerScanState {
+    fn default() -> Self {
+        let defaults = PointerScanner::default();
+        Self {
+            target: 0,
+            max_depth: defaults.max_depth as u32,
+            max_offset: defaults.max_offset,
+            chains: None,
+        }
+    }
+}
+
+/// Find static pointer chains to an address seen in the game, to get a
+/// moved global back after an update
+fn pointer_scan_ui(ui: &mut Ui, state: &mut AppState) -> Result {
+    let Some(noita) = state.noita.as_ref() else {
+        ui.label("Not connected to Noita");
+        return Ok(());
+    };
+
+    let id = ui.make_persistent_id("pointer_scan");
+    let mut s = ui.data_mut(|d| d.get_temp::<PointerScanState>(id).unwrap_or_default());
+
+    ui.horizontal(|ui| {
+        ui.label("Address: ");
+        ui.add(hex_input(&mut s.target));
+        ui.label("Max offset: ");
+        ui.add(hex_input(&mut s.max_offset));
+        ui.label("Depth: ");
+        ui.add(DragValue::new(&mut s.max_depth).range(0..=3));
+    });
+    if ui.add_enabled(s.target != 0, Button::new("Scan")).clicked() {
+        let header = PeHeader::read(noita.proc()).context("Reading the exe header")?;
+        let scanner = PointerScanner {
+            max_depth: s.max_depth as usize,
+            max_offset: s.max_offset.min(0x1000),
+            ..Default::default()
+        };
+        s.chains = Some(scanner.scan(noita.proc(), &header, s.target));
+    }
+
+    match &s.chains {
+        Some(chains) if chains.is_empty() => {
+            ui.label("No chains found");
+        }
+        Some(chains) => {
+            ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for chain in chains {
+                    ui.monospace(chain.to_string());
+                }
+            });
+        }
+        None => {}
+    }
+
+    ui.data_mut(|d| d.insert_temp(id, s));
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone)]
+struct LayoutsState {
+    path: String,
+    file: Option<Arc<LayoutFile>>,
+    error: Option<String>,
+    selected: Option<String>,
+    address: u32,
+}
+
+/// Load a layout file and read structs with it, for trying out layouts
+/// of game structs the compiled-in ones got wrong
+fn layouts_ui(ui: &mut Ui, state: &mut AppState) -> Result {
+    let id = ui.make_persistent_id("layouts");
+    let mut s = ui.data_mut(|d| d.get_temp::<LayoutsState>(id).unwrap_or_default());
+
+    ui.horizontal(|ui| {
+        ui.add(TextEdit::singleline(&mut s.path).hint_text("Layout JSON file"));
+        if ui
+            .add_enabled(!s.path.trim().is_empty(), Button::new("Load"))
+            .clicked()
+        {
+            match LayoutFile::load(s.path.trim()) {
+                Ok(file) => {
+                    s.file = Some(Arc::new(file));
+                    s.error = None;
+                }
+                Err(e) => s.error = Some(format!("Couldn't load the layouts: {e}")),
+            }
+        }
+    });
+    if let Some(error) = &s.error {
+        ui.label(RichText::new(error).color(ui.visuals().error_fg_color));
+    }
+
+    if let Some(file) = s.file.clone() {
+        let mut names = file.structs.keys().collect::<Vec<_>>();
+        names.sort_unstable();
+
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("layout")
+                .selected_text(s.selected.as_deref().unwrap_or("Select struct"))
+                .show_ui(ui, |ui| {
+                    for name in names {
+                        ui.selectable_value(&mut s.selected, Some(name.clone()), name);
+                    }
+                });
+            ui.label("at");
+            ui.add(hex_input(&mut s.address));
+        });
+
+        let layout = s.selected.as_deref().and_then(|name| file.get(name));
+        match (layout, state.noita.as_ref()) {
+            (Some(_), None) => {
+                ui.label("Not connected to Noita");
+            }
+            (Some(layout), Some(noita)) if s.address != 0 => {
+                let proc = noita.proc();
+                let read = DynStruct::read(proc, s.address, layout)?;
+                Grid::new("layout_fields").striped(true).show(ui, |ui| {
+                    for (name, value) in read.fields(proc) {
+                        ui.label(name);
+                        match value {
+                            Ok(v) => ui.monospace(v.to_string()),
+                            Err(e) => ui.label(
+                                RichText::new(e.to_string()).color(ui.visuals().error_fg_color),
+                            ),
+                        };
+                        ui.end_row();
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+
+    ui.data_mut(|d| d.insert_temp(id, s));
+    Ok(())
+}
+
+/// Discovery results saved on disk, so that a build that was discovered
+/// before (but whose map was removed or never kept) doesn't need the whole
+/// exe image read and scanned again
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedDiscovery {
+    name: String,
+    entries: Vec<AddressEntry>,
+}
+
+impl CachedDiscovery {
+    /// Keyed by our version too, so that improved heuristics get a chance
+    fn path(header: &PeHeader) -> Option<PathBuf> {
+        let dir = eframe::storage_dir(env!("CARGO_PKG_NAME"))?.join("discovery");
+        Some(dir.join(format!(
+            "{:08x}-{:016x}-{}.json",
+            header.timestamp(),
+            header.fingerprint(),
+            env!("CARGO_PKG_VERSION"),
+        )))
+    }
+
+    fn load(header: &PeHeader) -> Option<Self> {
+        let path = Self::path(header)?;
+        let data = fs::read(&path).ok()?;
+        match serde_json::from_slice(&data) {
+            Ok(cached) => Some(cached),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), "Broken discovery cache: {e}");
+                None
+            }
+        }
+    }
+
+    fn save(&self, header: &PeHeader) {
+        let Some(path) = Self::path(header) else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, serde_json::to_vec(self)?));
+        if let Err(e) = result {
+            tracing::warn!(path = %path.display(), "Couldn't save the discovery cache: {e}");
+        }
+    }
+}
+
 impl AddressMapsData {
     pub fn get(&self, noita_ts: u32) -> Option<AddressMap> {
         self.maps
@@ -266,7 +553,29 @@ impl AddressMapsData {
             .cloned()
     }
 
-    pub fn discover(&mut self, proc: &ProcessRef, header: &PeHeader) -> anyhow::Result<()> {
+    /// The map with the given comment, for the profiles to pick one
+    pub fn by_name(&self, name: &str) -> Option<AddressMap> {
+        self.maps
+            .iter()
+            .find(|m| m.0.lock().unwrap().name == name)
+            .cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.maps
+            .iter()
+            .map(|m| m.0.lock().unwrap().name.clone())
+            .collect()
+    }
+
+    /// Runs the discovery without touching the maps, so that it can be done
+    /// off the UI thread - it takes a while. Returns None if nothing was found.
+    /// The `progress` is called with the done fraction of the image scan
+    pub fn discover_map(
+        proc: &ProcessRef,
+        header: &PeHeader,
+        progress: impl Fn(f32) + Sync,
+    ) -> anyhow::Result<Option<AddressMap>> {
         fn add_entry<T>(
             entries: &mut Vec<AddressEntry>,
             name: &str,
@@ -284,6 +593,15 @@ impl AddressMapsData {
             }
         }
 
+        if let Some(cached) = CachedDiscovery::load(header) {
+            tracing::info!("Using the cached discovery results");
+            return Ok(Some(AddressMap::new(
+                cached.name,
+                header.timestamp(),
+                cached.entries,
+            )));
+        }
+
         let image = header
             .clone()
             .read_image(proc)
@@ -296,10 +614,11 @@ impl AddressMapsData {
             game_global,
             entity_manager,
             entity_tag_manager,
+            component_tag_manager,
             component_type_manager,
             translation_manager,
             platform,
-        } = discovery::run(&image);
+        } = discovery::run_with_progress(&image, progress);
 
         let mut entries = Vec::new();
         add_entry(&mut entries, "seed", world_seed, "Current world seed");
@@ -333,6 +652,12 @@ impl AddressMapsData {
             entity_tag_manager,
             "Entity tag manager, also used to find the player",
         );
+        add_entry(
+            &mut entries,
+            "component-tag-manager",
+            component_tag_manager,
+            "Component tag manager, to tell e.g. the components of held and stashed items apart",
+        );
         add_entry(
             &mut entries,
             "component-type-manager",
@@ -352,16 +677,31 @@ impl AddressMapsData {
             "Platform-specific stuff, only used to get the game install directory",
         );
 
-        if !entries.is_empty() {
-            let name = match discovery::find_noita_build(&image) {
-                Some(noita) => format!("Autodiscovered - {noita}"),
-                None => "Autodiscovered (no noita build string found!)".into(),
-            };
+        if entries.is_empty() {
+            return Ok(None);
+        }
 
-            self.maps
-                .push(AddressMap::new(name, header.timestamp(), entries));
+        let mut name = match discovery::find_noita_build(&image) {
+            Some(noita) => format!("Autodiscovered - {noita}"),
+            None => "Autodiscovered (no noita build string found!)".into(),
+        };
+        // same build strings, different exes
+        match discovery::Store::detect(&image) {
+            discovery::Store::Steam => {}
+            store => name.push_str(&format!(" ({store})")),
         }
 
-        Ok(())
+        let cached = CachedDiscovery { name, entries };
+        cached.save(header);
+
+        Ok(Some(AddressMap::new(
+            cached.name,
+            header.timestamp(),
+            cached.entries,
+        )))
+    }
+
+    pub fn add(&mut self, map: AddressMap) {
+        self.maps.push(map);
     }
 }
@@ -0,0 +1,275 @@
+use std::fmt::{self, Display};
+
+use eframe::egui::{
+    pos2, Button, ComboBox, Context, DragValue, Frame, Grid, RichText, TextEdit, Ui,
+};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::{app::AppState, reader::GameState, util::persist};
+
+use super::{address_maps::hex_input, Result, Tool};
+
+/// How a watched address is read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum ValueKind {
+    #[default]
+    U32,
+    I32,
+    F32,
+    F64,
+}
+
+impl ValueKind {
+    const ALL: [ValueKind; 4] = [
+        ValueKind::U32,
+        ValueKind::I32,
+        ValueKind::F32,
+        ValueKind::F64,
+    ];
+}
+
+impl Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ValueKind::U32 => "u32",
+            ValueKind::I32 => "i32",
+            ValueKind::F32 => "f32",
+            ValueKind::F64 => "f64",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct WatchedAddress {
+    name: String,
+    address: u32,
+    kind: ValueKind,
+}
+
+impl WatchedAddress {
+    fn read(&self, state: &AppState) -> Option<String> {
+        let proc = state.noita.as_ref()?.proc();
+        let value = match self.kind {
+            ValueKind::U32 => proc.read::<u32>(self.address).map(|v| v.to_string()),
+            ValueKind::I32 => proc.read::<i32>(self.address).map(|v| v.to_string()),
+            ValueKind::F32 => proc.read::<f32>(self.address).map(|v| format!("{v:.2}")),
+            ValueKind::F64 => proc.read::<f64>(self.address).map(|v| format!("{v:.2}")),
+        };
+        value
+            .inspect_err(
+                |e| tracing::debug!(%e, address = self.address, "Failed to read a watched address"),
+            )
+            .ok()
+    }
+}
+
+/// A single cell of the dashboard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Widget {
+    Seed,
+    Hp,
+    Gold,
+    ShiftTimer,
+    Orbs,
+    OrbDistance,
+    Frame,
+    Address(WatchedAddress),
+}
+
+impl Widget {
+    /// What can be added from the "Add widget" menu
+    fn palette() -> [Widget; 8] {
+        [
+            Widget::Seed,
+            Widget::Hp,
+            Widget::Gold,
+            Widget::ShiftTimer,
+            Widget::Orbs,
+            Widget::OrbDistance,
+            Widget::Frame,
+            Widget::Address(WatchedAddress::default()),
+        ]
+    }
+
+    fn title(&self) -> &str {
+        match self {
+            Widget::Seed => "Seed",
+            Widget::Hp => "HP",
+            Widget::Gold => "Gold",
+            Widget::ShiftTimer => "Shift timer",
+            Widget::Orbs => "Orbs",
+            Widget::OrbDistance => "Orb distance",
+            Widget::Frame => "Frame",
+            Widget::Address(watched) if watched.name.is_empty() => "Address",
+            Widget::Address(watched) => &watched.name,
+        }
+    }
+
+    fn value(&self, state: &AppState, game: &GameState) -> Option<String> {
+        match self {
+            Widget::Seed => state.seed.map(|seed| seed.to_string()),
+            Widget::Hp => game.hp_value(),
+            Widget::Gold => game.gold.map(|gold| gold.to_string()),
+            Widget::ShiftTimer => game.shift_timer_value(),
+            Widget::Orbs => game.orbs.map(|orbs| orbs.to_string()),
+            Widget::OrbDistance => {
+                let (player, _) = game.player.as_ref()?;
+                let pos = pos2(player.transform.pos.x, player.transform.pos.y);
+                state
+                    .orb_target
+                    .map(|orb| format!("{:.0} px", (orb - pos).length()))
+            }
+            Widget::Frame => game.frame.map(|frame| frame.to_string()),
+            Widget::Address(watched) => watched.read(state),
+        }
+    }
+}
+
+/// A grid of small readouts picked from a palette, so that the values from
+/// different tools can be seen at once
+#[derive(Debug, SmartDefault)]
+pub struct Dashboard {
+    #[default(vec![Widget::Seed, Widget::Hp, Widget::Gold, Widget::ShiftTimer])]
+    widgets: Vec<Widget>,
+    #[default(2)]
+    columns: usize,
+
+    editing: bool,
+    /// Formatted values of the widgets, by index, as of the last tick
+    values: Vec<Option<String>>,
+    last_frame: Option<u32>,
+}
+
+persist!(Dashboard {
+    widgets: Vec<Widget>,
+    columns: usize,
+});
+
+#[typetag::serde]
+impl Tool for Dashboard {
+    fn tick(&mut self, ctx: &Context, state: &mut AppState) {
+        let wants_orb = self.widgets.contains(&Widget::OrbDistance);
+        state.orb_target_wanted |= wants_orb;
+
+        // while editing the watched addresses can change under a paused game
+        let changed = self.editing || self.values.len() != self.widgets.len();
+        if !state.game_advanced(&mut self.last_frame) && !changed {
+            return;
+        }
+        let game = state.game();
+        let values = self
+            .widgets
+            .iter()
+            .map(|widget| widget.value(state, &game))
+            .collect::<Vec<_>>();
+        if values != self.values {
+            self.values = values;
+            ctx.request_repaint();
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, _state: &mut AppState) -> Result {
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut self.editing, "✏ Edit");
+            if !self.editing {
+                return;
+            }
+            ui.label("Columns:");
+            ui.add(DragValue::new(&mut self.columns).range(1..=8));
+            ui.menu_button("➕ Add widget", |ui| {
+                for widget in Widget::palette() {
+                    if ui.button(widget.title()).clicked() {
+                        self.widgets.push(widget);
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+        ui.separator();
+
+        if self.widgets.is_empty() {
+            ui.label("No widgets, click ✏ Edit to add some");
+            return Ok(());
+        }
+
+        let mut action = None;
+        Grid::new("dashboard")
+            .num_columns(self.columns)
+            .spacing([6.0, 6.0])
+            .show(ui, |ui| {
+                for (i, widget) in self.widgets.iter_mut().enumerate() {
+                    let value = self.values.get(i).cloned().flatten();
+                    Frame::group(ui.style()).show(ui, |ui| {
+                        ui.set_min_width(100.0);
+                        ui.vertical(|ui| {
+                            ui.label(RichText::new(widget.title()).small().weak());
+                            ui.label(
+                                RichText::new(value.as_deref().unwrap_or("-"))
+                                    .monospace()
+                                    .size(18.0),
+                            );
+                            if self.editing {
+                                action = edit_ui(ui, widget, i).or(action);
+                            }
+                        });
+                    });
+                    if (i + 1) % self.columns.max(1) == 0 {
+                        ui.end_row();
+                    }
+                }
+            });
+
+        match action {
+            Some(Action::Remove(i)) => {
+                self.widgets.remove(i);
+            }
+            Some(Action::Swap(a, b)) if b < self.widgets.len() => self.widgets.swap(a, b),
+            _ => return Ok(()),
+        }
+        // the values are by index, recompute them on the next tick
+        self.values.clear();
+
+        Ok(())
+    }
+}
+
+enum Action {
+    Remove(usize),
+    Swap(usize, usize),
+}
+
+fn edit_ui(ui: &mut Ui, widget: &mut Widget, i: usize) -> Option<Action> {
+    if let Widget::Address(watched) = widget {
+        ui.add(
+            TextEdit::singleline(&mut watched.name)
+                .hint_text("Name")
+                .desired_width(100.0),
+        );
+        ui.horizontal(|ui| {
+            ui.add(hex_input(&mut watched.address));
+            ComboBox::from_id_salt(("dashboard_kind", i))
+                .width(50.0)
+                .selected_text(watched.kind.to_string())
+                .show_ui(ui, |ui| {
+                    for kind in ValueKind::ALL {
+                        ui.selectable_value(&mut watched.kind, kind, kind.to_string());
+                    }
+                });
+        });
+    }
+    ui.horizontal(|ui| {
+        let mut action = None;
+        if ui.add_enabled(i > 0, Button::new("⏴").small()).clicked() {
+            action = Some(Action::Swap(i - 1, i));
+        }
+        if ui.add(Button::new("⏵").small()).clicked() {
+            action = Some(Action::Swap(i, i + 1));
+        }
+        if ui.add(Button::new("🗑").small()).clicked() {
+            action = Some(Action::Remove(i));
+        }
+        action
+    })
+    .inner
+}
@@ -10,6 +10,7 @@ use strfmt::{FmtError, Format};
 
 use crate::{
     app::AppState,
+    sound::{self, Alert},
     util::{persist, Promise},
 };
 use derive_more::Debug;
@@ -56,6 +57,9 @@ pub struct LiveStats {
 
     /// Used for persistence
     was_connected: bool,
+
+    /// Game frame of the last stats read
+    last_frame: Option<u32>,
 }
 
 persist!(LiveStats {
@@ -89,6 +93,11 @@ impl Tool for LiveStats {
             return;
         };
 
+        // stats only change when the game runs
+        if !state.game_advanced(&mut self.last_frame) && !self.format_changed {
+            return;
+        }
+
         let new_stats = noita
             .read_stats()
             .context("Reading global stats")
@@ -169,7 +178,7 @@ impl Tool for LiveStats {
         }
     }
 
-    fn ui(&mut self, ui: &mut Ui, _state: &mut AppState) -> Result {
+    fn ui(&mut self, ui: &mut Ui, state: &mut AppState) -> Result {
         match &self.stats {
             Some(Ok(s)) => {
                 Grid::new("live_stats").show(ui, |ui| {
@@ -267,6 +276,9 @@ impl Tool for LiveStats {
                                     }
                                 }
                             });
+                            if self.was_connected {
+                                state.toast("Reconnected to OBS");
+                            }
                             self.was_connected = true;
                             ObsState::Connected(Arc::new(client), end_promise)
                         }
@@ -276,6 +288,8 @@ impl Tool for LiveStats {
             },
             ObsState::Connected(client, end_promise) => {
                 if end_promise.poll().is_some() {
+                    state.toast_error("OBS closed the websocket connection");
+                    sound::play(Alert::WebsocketDropped, &state.settings.sounds);
                     self.disconnect();
                     return Ok(());
                 }
@@ -0,0 +1,165 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use eframe::egui::{Context, DragValue, Grid, RichText, Ui};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::{
+    app::AppState,
+    cli::parse_switch,
+    sound::{self, Alert},
+};
+
+use super::{Result, Tool};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Threshold {
+    /// In the HP the game shows
+    Absolute,
+    /// Of the max HP
+    Percent,
+}
+
+#[derive(Debug, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LowHpAlarm {
+    #[default(true)]
+    enabled: bool,
+    #[default(Threshold::Percent)]
+    mode: Threshold,
+    #[default(25.0)]
+    threshold: f64,
+    /// Seconds before the alarm can go off again
+    #[default(10.0)]
+    cooldown: f32,
+    #[default(true)]
+    flash: bool,
+    #[serde(skip)]
+    last_alarm: Option<Instant>,
+}
+
+impl LowHpAlarm {
+    fn is_low(&self, hp: f64, max_hp: f64) -> bool {
+        // dead or not spawned yet
+        if hp <= 0.0 || max_hp <= 0.0 {
+            return false;
+        }
+        match self.mode {
+            Threshold::Absolute => hp * 25.0 < self.threshold,
+            Threshold::Percent => hp / max_hp * 100.0 < self.threshold,
+        }
+    }
+
+    fn alarm(&mut self, state: &mut AppState) {
+        self.last_alarm = Some(Instant::now());
+        sound::play(Alert::LowHp, &state.settings.sounds);
+        if self.flash {
+            state.flash();
+        }
+    }
+}
+
+#[typetag::serde]
+impl Tool for LowHpAlarm {
+    fn tick(&mut self, _ctx: &Context, state: &mut AppState) {
+        if !self.enabled {
+            return;
+        }
+        let Some((hp, max_hp)) = state.game().hp else {
+            return;
+        };
+        let cooled_down = self
+            .last_alarm
+            .is_none_or(|t| t.elapsed() >= Duration::from_secs_f32(self.cooldown));
+        if cooled_down && self.is_low(hp, max_hp) {
+            self.alarm(state);
+        }
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "enabled" => self.enabled = parse_switch(value)?,
+            "flash" => self.flash = parse_switch(value)?,
+            "threshold" => {
+                // 25% or 100 (HP)
+                let (value, mode) = match value.strip_suffix('%') {
+                    Some(percent) => (percent, Threshold::Percent),
+                    None => (value, Threshold::Absolute),
+                };
+                self.threshold = value
+                    .parse()
+                    .with_context(|| format!("Expected HP or a percentage, got {value}"))?;
+                self.mode = mode;
+            }
+            "cooldown" => {
+                self.cooldown = value
+                    .parse()
+                    .with_context(|| format!("Expected a number of seconds, got {value}"))?
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, state: &mut AppState) -> Result {
+        Grid::new("low_hp_alarm").num_columns(2).show(ui, |ui| {
+            ui.checkbox(&mut self.enabled, "Enabled");
+            ui.checkbox(&mut self.flash, "Flash the window");
+            ui.end_row();
+
+            ui.label("Below");
+            ui.horizontal(|ui| {
+                let suffix = match self.mode {
+                    Threshold::Absolute => " HP",
+                    Threshold::Percent => "%",
+                };
+                ui.add(
+                    DragValue::new(&mut self.threshold)
+                        .range(1.0..=f64::MAX)
+                        .suffix(suffix),
+                );
+                ui.selectable_value(&mut self.mode, Threshold::Absolute, "HP");
+                ui.selectable_value(&mut self.mode, Threshold::Percent, "% of max");
+            });
+            ui.end_row();
+
+            ui.label("Cooldown");
+            ui.add(
+                DragValue::new(&mut self.cooldown)
+                    .range(1.0..=600.0)
+                    .speed(0.5)
+                    .suffix(" s"),
+            );
+            ui.end_row();
+
+            ui.label("Current HP");
+            match state.game().hp {
+                Some((hp, max_hp)) => {
+                    let text = format!("{:.0}/{:.0}", hp * 25.0, max_hp * 25.0);
+                    let text = if self.is_low(hp, max_hp) {
+                        RichText::new(text).color(ui.visuals().error_fg_color)
+                    } else {
+                        RichText::new(text)
+                    };
+                    ui.label(text.monospace());
+                }
+                None => {
+                    ui.label("-");
+                }
+            }
+            ui.end_row();
+        });
+
+        if ui.button("Test the alarm").clicked() {
+            self.alarm(state);
+        }
+        if state.settings.sounds.volume(Alert::LowHp) == 0.0 {
+            ui.label(
+                RichText::new("The low HP sound is muted in the settings")
+                    .color(ui.visuals().warn_fg_color),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+
+use derive_more::Debug;
+use eframe::egui::{
+    pos2, vec2, Align2, Button, Color32, ColorImage, FontId, Pos2, Rect, RichText, Rounding, Sense,
+    Stroke, TextureHandle, TextureOptions, Ui, Vec2,
+};
+use noita_utility_box::noita::{
+    biome::{BiomeMap, BIOME_CELL_SIZE},
+    Seed,
+};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::{app::AppState, cli::parse_switch, trail::Trail};
+
+use super::{Result, Tool};
+
+/// A zoomed out schematic of the world from the biome map, with the player
+/// on it
+#[derive(Debug, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapViewer {
+    #[default(true)]
+    follow_player: bool,
+    /// Screen points per world pixel
+    #[default(1.0 / 32.0)]
+    zoom: f32,
+    /// World position at the center of the view when not following the player
+    center: Pos2,
+    /// Trails saved from previous runs, by seed, to race against
+    ghosts: BTreeMap<String, Trail>,
+    #[serde(skip)]
+    map: Option<MapTexture>,
+}
+
+/// How far from the ghost path the player can be for the time delta to
+/// still be shown
+const GHOST_RANGE: f32 = 128.0;
+
+/// The biome map uploaded to the GPU, along with what we draw on top of it
+#[derive(Debug)]
+struct MapTexture {
+    /// The process and the run it was made for, it's made again for
+    /// another one
+    pid: u32,
+    seed: Option<Seed>,
+    #[debug(skip)]
+    texture: TextureHandle,
+    /// World rect of the main world map
+    world: Rect,
+    /// World rects of the Holy Mountain cells
+    temples: Vec<Rect>,
+}
+
+impl MapTexture {
+    fn new(ui: &Ui, map: &BiomeMap, pid: u32, seed: Option<Seed>) -> Self {
+        let (width, height) = map.size();
+        let pixels = map
+            .pixels()
+            .iter()
+            .map(|argb| {
+                let [_, r, g, b] = argb.to_be_bytes();
+                // the biome colors are way too bright to draw anything over
+                Color32::from_rgb(r / 2, g / 2, b / 2)
+            })
+            .collect();
+        let image = ColorImage {
+            size: [width as usize, height as usize],
+            pixels,
+        };
+        let texture = ui
+            .ctx()
+            .load_texture("biome_map", image, TextureOptions::NEAREST);
+
+        let (x, y) = map.origin();
+        let cell = BIOME_CELL_SIZE as f32;
+        let min = pos2(x as f32, y as f32);
+        let world = Rect::from_min_size(min, vec2(width as f32, height as f32) * cell);
+
+        let mut temples = Vec::new();
+        for py in 0..height {
+            for px in 0..width {
+                if map.get_cell(px, py).is_some_and(|b| b.contains("temple")) {
+                    let min = min + vec2(px as f32, py as f32) * cell;
+                    temples.push(Rect::from_min_size(min, Vec2::splat(cell)));
+                }
+            }
+        }
+
+        Self {
+            pid,
+            seed,
+            texture,
+            world,
+            temples,
+        }
+    }
+}
+
+impl MapViewer {
+    /// Saving the trail as the ghost of the seed, and how far ahead or behind
+    /// it we are
+    fn ghost_ui(&mut self, ui: &mut Ui, state: &mut AppState, seed: &str, player: Option<Pos2>) {
+        if ui
+            .add_enabled(!state.trail.is_empty(), Button::new("Save trail as ghost"))
+            .on_hover_text(
+                "Keep the path of this run, to compare the next runs on this seed against it",
+            )
+            .clicked()
+        {
+            self.ghosts.insert(seed.to_owned(), state.trail.clone());
+            state.toast(format!("Saved the ghost for seed {seed}"));
+        }
+        let Some(ghost) = self.ghosts.get(seed) else {
+            return;
+        };
+
+        // compare the time it took us to get here with the time the ghost
+        // took to get to its closest point, the frame counter starts over
+        // with each run
+        let delta = player.zip(state.game_frame).and_then(|(player, frame)| {
+            let point = ghost.nearest(player, GHOST_RANGE)?;
+            Some((frame as f32 - point.frame as f32) / 60.0)
+        });
+        match delta {
+            Some(delta) => {
+                let palette = state.settings.palette.colors(ui.visuals());
+                let color = if delta > 0.0 {
+                    palette.behind
+                } else {
+                    palette.ahead
+                };
+                let text = RichText::new(format!("ghost: {delta:+.1}s"));
+                ui.label(text.monospace().color(color));
+            }
+            None => {
+                ui.label(RichText::new("ghost: off the path").monospace().weak());
+            }
+        }
+        if ui.button("Delete ghost").clicked() {
+            self.ghosts.remove(seed);
+        }
+    }
+}
+
+#[typetag::serde]
+impl Tool for MapViewer {
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "follow-player" => self.follow_player = parse_switch(value)?,
+            "zoom" => self.zoom = value.parse()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, state: &mut AppState) -> Result {
+        let player = state.game().player.as_ref().map(|(player, _)| {
+            let pos = player.transform.pos;
+            pos2(pos.x, pos.y)
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.follow_player, "Follow player");
+            if ui.button("Reset zoom").clicked() {
+                self.zoom = MapViewer::default().zoom;
+            }
+            ui.label(RichText::new("Drag to pan, scroll to zoom, hover to see the biome").weak());
+        });
+
+        let seed = state.seed.map(|s| s.to_string());
+        if let Some(seed) = &seed {
+            ui.horizontal(|ui| self.ghost_ui(ui, state, seed, player));
+        }
+
+        let run = state.seed;
+        let noita = state.get_noita()?;
+        let pid = noita.proc().pid();
+        let map = match &self.map {
+            Some(map) if map.pid == pid && map.seed == run => map,
+            _ => {
+                let biome_map = noita.biome_map()?;
+                self.map.insert(MapTexture::new(ui, biome_map, pid, run))
+            }
+        };
+        let ghost = seed.and_then(|s| self.ghosts.get(&s));
+
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::drag());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, Rounding::ZERO, ui.visuals().extreme_bg_color);
+        let palette = state.settings.palette.colors(ui.visuals());
+
+        if response.dragged() {
+            if let (true, Some(player)) = (self.follow_player, player) {
+                self.center = player;
+                self.follow_player = false;
+            }
+            self.center -= response.drag_delta() / self.zoom;
+        }
+        if response.hovered() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                self.zoom = (self.zoom * (scroll / 200.0).exp()).clamp(1.0 / 512.0, 1.0);
+            }
+        }
+        let center = match player {
+            Some(player) if self.follow_player => player,
+            _ => self.center,
+        };
+
+        let to_screen = |p: Pos2| rect.center() + (p - center) * self.zoom;
+        let to_world = |p: Pos2| center + (p - rect.center()) / self.zoom;
+
+        // the map repeats into the parallel worlds on both sides
+        let width = map.world.width();
+        let pw = ((center.x - map.world.center().x) / width).round();
+        let uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+        for offset in [pw - 1.0, pw, pw + 1.0] {
+            let shift = vec2(offset * width, 0.0);
+            let world = map.world.translate(shift);
+            let screen = Rect::from_min_max(to_screen(world.min), to_screen(world.max));
+            painter.image(map.texture.id(), screen, uv, Color32::WHITE);
+
+            let stroke = Stroke::new(1.0, palette.landmark);
+            for temple in &map.temples {
+                let temple = temple.translate(shift);
+                let screen = Rect::from_min_max(to_screen(temple.min), to_screen(temple.max));
+                if rect.intersects(screen) {
+                    painter.rect_stroke(screen, Rounding::ZERO, stroke);
+                }
+            }
+        }
+
+        if let Some(ghost) = ghost {
+            let stroke = Stroke::new(1.0, palette.ghost);
+            ghost.paint(&painter, to_screen, stroke);
+        }
+        let trail = Stroke::new(1.0, palette.trail());
+        state.trail.paint(&painter, to_screen, trail);
+
+        if let Some(player) = player {
+            let pos = to_screen(player);
+            painter.circle_filled(pos, 3.0, palette.player);
+            painter.circle_stroke(pos, 6.0, Stroke::new(1.0, palette.player));
+        }
+
+        if let Some(hover) = response.hover_pos() {
+            let world = to_world(hover);
+            let biome = state
+                .get_noita()?
+                .get_biome_at(world.x as i32, world.y as i32)?;
+            painter.text(
+                rect.left_bottom() + vec2(5.0, -5.0),
+                Align2::LEFT_BOTTOM,
+                format!(
+                    "x:{:.0} y:{:.0} {}",
+                    world.x,
+                    world.y,
+                    biome.as_deref().unwrap_or("-")
+                ),
+                FontId::monospace(10.0),
+                ui.visuals().text_color(),
+            );
+        }
+        Ok(())
+    }
+}
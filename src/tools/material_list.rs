@@ -258,7 +258,12 @@ impl Tool for MaterialList {
     }
 }
 
-fn layout_text_with_indices(ui: &Ui, text: &str, indices: Vec<usize>, quote: bool) -> LayoutJob {
+pub(crate) fn layout_text_with_indices(
+    ui: &Ui,
+    text: &str,
+    indices: Vec<usize>,
+    quote: bool,
+) -> LayoutJob {
     if indices.is_empty() {
         return LayoutJob::single_section(
             if quote {
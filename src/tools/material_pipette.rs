@@ -8,7 +8,7 @@ use noita_utility_box::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::app::AppState;
+use crate::{app::AppState, cli::parse_switch};
 
 use super::{Result, Tool, ToolError};
 
@@ -22,6 +22,15 @@ pub struct MaterialPipette {
 
 #[typetag::serde]
 impl Tool for MaterialPipette {
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "realtime" => self.realtime = parse_switch(value)?,
+            "auto-check" => self.auto_check = parse_switch(value)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
     fn ui(&mut self, ui: &mut Ui, state: &mut AppState) -> Result {
         let noita = state.get_noita()?;
 
@@ -2,11 +2,14 @@ use std::{
     any::TypeId,
     borrow::Cow,
     fmt::{self, Display},
+    panic::Location,
 };
 
 use crate::app::AppState;
+use crate::i18n::tr;
 use crate::util::to_title_case;
-use eframe::egui::{Context, Ui};
+use eframe::egui::{Context, RichText, Ui};
+use noita_utility_box::memory::ProcessGone;
 use thiserror::Error;
 
 macro_rules! tools {
@@ -23,6 +26,7 @@ macro_rules! tools {
             $(
                 &$crate::tools::ToolInfo {
                     default_constructor: || Box::new(<$prefix::$t>::default()),
+                    id: stringify!($t),
                     title: tools!(_get_title $($title)?; $t),
                     type_id: {
                         fn deferred() -> TypeId {
@@ -40,7 +44,15 @@ macro_rules! tools {
 tools! {
     process_panel::ProcessPanel : "Noita";
     orb_radar::OrbRadar;
+    map_viewer::MapViewer;
     live_stats::LiveStats;
+    dashboard::Dashboard;
+    low_hp_alarm::LowHpAlarm : "Low HP Alarm";
+    run_timer::RunTimer;
+    twitch_bot::TwitchBot;
+    twitch_predictions::TwitchPredictions;
+    scripts::Scripts;
+    plugins::Plugins;
     material_pipette::MaterialPipette;
     material_list::MaterialList;
     address_maps::AddressMaps;
@@ -50,6 +62,8 @@ tools! {
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct ToolInfo {
     pub default_constructor: fn() -> Box<dyn Tool>,
+    /// Stable identifier used for persisting tool preferences
+    pub id: &'static str,
     pub title: &'static str,
     type_id: fn() -> TypeId,
 }
@@ -58,6 +72,15 @@ impl ToolInfo {
     pub fn is_it(&self, tool: &dyn Tool) -> bool {
         (self.type_id)() == tool.type_id()
     }
+
+    pub fn of(tool: &dyn Tool) -> Option<&'static ToolInfo> {
+        TOOLS.iter().copied().find(|info| info.is_it(tool))
+    }
+
+    /// The title in the current UI language
+    pub fn display_title(&self) -> &'static str {
+        tr(self.title)
+    }
 }
 
 #[derive(Debug)]
@@ -70,40 +93,127 @@ impl Display for UnexpectedError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use UnexpectedError as E;
         match self {
-            E::Contextual(e) => write!(f, "Error: {e:#}"),
-            E::Io(e) => write!(f, "I/O error: {e}"),
+            E::Contextual(e) => write!(f, "{e:#}"),
+            E::Io(e) => write!(f, "{e}"),
         }
     }
 }
 
 #[derive(Debug, Error)]
 pub enum ToolError {
-    #[error("{0}")]
-    Unexpected(UnexpectedError),
-    #[error("{0}")]
-    BadState(String),
+    #[error("{error}")]
+    Unexpected {
+        error: UnexpectedError,
+        location: &'static Location<'static>,
+    },
+    #[error("{reason}")]
+    BadState {
+        reason: String,
+        location: &'static Location<'static>,
+    },
     #[error("{0}")]
     ImmediateRetry(Cow<'static, str>),
 }
 
 impl ToolError {
+    #[track_caller]
     pub fn bad_state<R>(reason: impl Into<String>) -> std::result::Result<R, Self> {
-        Err(ToolError::BadState(reason.into()))
+        Err(ToolError::BadState {
+            reason: reason.into(),
+            location: Location::caller(),
+        })
     }
     pub fn retry<R>(reason: impl Into<Cow<'static, str>>) -> std::result::Result<R, Self> {
         Err(ToolError::ImmediateRetry(reason.into()))
     }
+
+    /// Where in our code the error was created (or `?`-converted)
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        match self {
+            ToolError::Unexpected { location, .. } | ToolError::BadState { location, .. } => {
+                Some(location)
+            }
+            ToolError::ImmediateRetry(_) => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ToolError::Unexpected {
+                error: UnexpectedError::Io(_),
+                ..
+            } => "I/O error",
+            ToolError::Unexpected { .. } => "Unexpected error",
+            ToolError::BadState { .. } => "Bad state",
+            ToolError::ImmediateRetry(_) => "Retrying",
+        }
+    }
+
+    /// A plaintext report for bug reports and such
+    pub fn details(&self) -> String {
+        let mut details = format!("{}: {self}", self.kind());
+        if let Some(location) = self.location() {
+            details.push_str(&format!("\n  at {location}"));
+        }
+        details.push_str(concat!("\nbuild: ", env!("BUILD_INFO")));
+        details
+    }
+
+    /// The standard error panel all the tools use, returns true if the retry
+    /// button was clicked
+    pub fn show(&self, ui: &mut Ui) -> bool {
+        // bad state is informative, don't scream with red
+        let color = if matches!(self, ToolError::BadState { .. }) {
+            ui.visuals().warn_fg_color
+        } else {
+            ui.visuals().error_fg_color
+        };
+
+        ui.label(RichText::new(tr(self.kind())).strong().color(color));
+        ui.label(RichText::new(self.to_string()).color(color));
+        if let Some(location) = self.location() {
+            ui.label(RichText::new(format!("at {location}")).small().weak());
+        }
+
+        ui.horizontal(|ui| {
+            let retry = ui.button(tr("Retry")).clicked();
+            if ui
+                .button(tr("Copy details"))
+                .on_hover_text(tr(
+                    "Copy the error details to the clipboard, for bug reports",
+                ))
+                .clicked()
+            {
+                ui.ctx().copy_text(self.details());
+            }
+            retry
+        })
+        .inner
+    }
 }
 
 impl From<anyhow::Error> for ToolError {
+    #[track_caller]
     fn from(e: anyhow::Error) -> Self {
-        ToolError::Unexpected(UnexpectedError::Contextual(e))
+        ToolError::Unexpected {
+            error: UnexpectedError::Contextual(e),
+            location: Location::caller(),
+        }
     }
 }
 
 impl From<std::io::Error> for ToolError {
+    #[track_caller]
     fn from(e: std::io::Error) -> Self {
-        ToolError::Unexpected(UnexpectedError::Io(e))
+        // the process panel notices and disconnects, no need to show errors
+        // for it in every tool until then
+        if ProcessGone::is(&e) {
+            return ToolError::ImmediateRetry("Noita has exited".into());
+        }
+        ToolError::Unexpected {
+            error: UnexpectedError::Io(e),
+            location: Location::caller(),
+        }
     }
 }
 
@@ -117,6 +227,12 @@ pub trait Tool: Send + 'static {
     /// The main egui draw function for the tool
     fn ui(&mut self, ui: &mut Ui, state: &mut AppState) -> Result;
 
+    /// Apply a command-line override, returns false if the tool has no such
+    /// option
+    fn set_option(&mut self, _key: &str, _value: &str) -> anyhow::Result<bool> {
+        Ok(false)
+    }
+
     fn type_id(&self) -> TypeId {
         TypeId::of::<Self>()
     }
@@ -1,34 +1,216 @@
-use std::fmt::Write as _;
-
-use crate::{app::AppState, orb_searcher::OrbSearcher};
+use std::{fmt::Write as _, time::Instant};
+
+use crate::{
+    app::AppState,
+    cli::parse_switch,
+    events::{EventCursor, GameEvent},
+    orb_searcher::OrbSearcher,
+    sound::{self, Alert, SoundSettings},
+};
 use eframe::egui::{
-    pos2, vec2, Align, Align2, Color32, FontId, Layout, Rect, Rounding, Stroke, Ui,
+    pos2, vec2, Align, Align2, Context, FontId, Layout, Pos2, ProgressBar, Rect, RichText,
+    Rounding, Stroke, Ui, Vec2,
 };
-use noita_utility_box::noita::Seed;
+use noita_utility_box::noita::{Seed, MAIN_WORLD_ORBS, TOTAL_ORBS};
 use serde::{Deserialize, Serialize};
 
 use super::{Result, Tool};
 
+/// How close the player has to be to a known orb for the collected count
+/// going up to be that orb
+const PICKUP_RANGE: f32 = 64.0;
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct OrbRadar {
     realtime: bool,
     orb_searcher: OrbSearcher,
     #[serde(skip)]
-    prev_seed: Option<Seed>,
+    events: EventCursor,
+    #[serde(skip)]
+    motion: Motion,
+    /// The orb we last played the proximity chime for
+    #[serde(skip)]
+    chimed_for: Option<Pos2>,
+    /// `orbs_found_thisrun` of the world state, as of the last tick
+    #[serde(skip)]
+    orbs_found: Option<Vec<i32>>,
+    /// Known orbs that were picked up, the ones the player was next to when
+    /// the collected count went up
+    #[serde(skip)]
+    collected: Vec<Pos2>,
+}
+
+/// Player speed from how the position changes between the frames, for the ETA
+#[derive(Debug, Default)]
+struct Motion {
+    last: Option<(Pos2, Instant)>,
+    /// Smoothed, in pixels per second
+    speed: f32,
+}
+
+impl Motion {
+    fn update(&mut self, pos: Pos2) {
+        let now = Instant::now();
+        if let Some((last, at)) = self.last {
+            let dt = (now - at).as_secs_f32();
+            // too noisy at the UI framerate
+            if dt < 0.1 {
+                return;
+            }
+            let speed = (pos - last).length() / dt;
+            // way faster than anything can move is a teleport
+            if speed < 5000.0 {
+                self.speed = self.speed * 0.7 + speed * 0.3;
+            }
+        }
+        self.last = Some((pos, now));
+    }
 }
 
 #[typetag::serde]
 impl Tool for OrbRadar {
+    fn tick(&mut self, ctx: &Context, state: &mut AppState) {
+        if let Some(noita) = &state.noita {
+            // keep the last known list if the read fails mid-frame
+            if let Ok(orbs) = noita.read_orbs_found() {
+                let pos = state.game().player.as_ref().map(|(player, _)| {
+                    let pos = player.transform.pos;
+                    pos2(pos.x, pos.y)
+                });
+                self.update_collected(orbs, pos);
+            }
+        } else {
+            self.orbs_found = None;
+        }
+
+        let overlay = &state.settings.overlay;
+        let overlay_wants_orbs = overlay.enabled && overlay.orb_direction;
+        let wants_orbs = overlay_wants_orbs || std::mem::take(&mut state.orb_target_wanted);
+        if !state.settings.orb_chime && !wants_orbs {
+            return;
+        }
+        let Some((player, _)) = &state.game().player else {
+            return;
+        };
+        let pos = pos2(player.transform.pos.x, player.transform.pos.y);
+        let Some(seed) = state.seed else {
+            return;
+        };
+        // keep searching when the tab is not visible too
+        self.poll_search(ctx, state, seed, pos);
+        state.orb_target = self.nearest_orb();
+        if state.settings.orb_chime {
+            let settings = &state.settings;
+            self.check_proximity(pos, settings.orb_chime_distance, &settings.sounds);
+        }
+    }
+
     fn ui(&mut self, ui: &mut Ui, state: &mut AppState) -> Result {
         self.ui(ui, state);
         Ok(())
     }
+
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "realtime" => self.realtime = parse_switch(value)?,
+            "look-for-sampo" => self.orb_searcher.look_for_sampo_instead = parse_switch(value)?,
+            "reset" => {
+                if parse_switch(value)? {
+                    self.orb_searcher.reset();
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
 }
 
 impl OrbRadar {
+    /// Keep the search going around the player, with a toast for the
+    /// orbs each finished search found
+    fn poll_search(&mut self, ctx: &Context, state: &mut AppState, seed: Seed, pos: Pos2) {
+        if let Some(found @ 1..) = self.orb_searcher.poll_search(ctx, seed, pos) {
+            let what = if self.orb_searcher.look_for_sampo_instead {
+                "sampo"
+            } else {
+                "orb"
+            };
+            let s = if found == 1 { "" } else { "s" };
+            state.toast(format!("Orb radar found {found} new {what} location{s}"));
+        }
+    }
+
+    /// The nearest known orb that wasn't collected yet
+    fn nearest_orb(&self) -> Option<Pos2> {
+        let known = self.orb_searcher.known_orbs();
+        known
+            .iter()
+            .find(|orb| !self.collected.contains(*orb))
+            .copied()
+    }
+
+    fn update_collected(&mut self, orbs: Vec<i32>, pos: Option<Pos2>) {
+        let Some(before) = self.orbs_found.as_ref().map(Vec::len) else {
+            // no idea where the orbs collected so far were
+            self.orbs_found = Some(orbs);
+            return;
+        };
+        if orbs.len() < before {
+            // a new run
+            self.collected.clear();
+        } else if let Some(pos) = pos.filter(|_| orbs.len() > before) {
+            let picked = self.orb_searcher.known_orbs().iter().find(|orb| {
+                (**orb - pos).length() <= PICKUP_RANGE && !self.collected.contains(*orb)
+            });
+            self.collected.extend(picked);
+        }
+        self.orbs_found = Some(orbs);
+    }
+
+    /// Chime once when an orb gets in range, and again only for another orb
+    /// or after moving away from it
+    fn check_proximity(&mut self, pos: Pos2, distance: f32, sounds: &SoundSettings) {
+        let Some(orb) = self.nearest_orb() else {
+            return;
+        };
+        let dist = (orb - pos).length();
+        if self.chimed_for == Some(orb) {
+            if dist > distance * 1.5 {
+                self.chimed_for = None;
+            }
+        } else if dist <= distance {
+            self.chimed_for = Some(orb);
+            sound::play(Alert::OrbProximity, sounds);
+        }
+    }
+
+    /// The numbers for the nearest orb, easier to act on than the dots
+    /// The distance is only in pixels, the game has no meters or any other
+    /// unit of its own to convert to
+    fn readout_ui(&self, ui: &mut Ui, dir: Vec2) {
+        let dist = dir.length();
+        // y goes down in the game, so north is -y
+        let bearing = dir.x.atan2(-dir.y).to_degrees().rem_euclid(360.0);
+        let eta = if self.motion.speed > 10.0 {
+            format!("{:.0}s", dist / self.motion.speed)
+        } else {
+            "-".into()
+        };
+        ui.label(
+            RichText::new(format!(
+                "nearest: {dist:.0} px  bearing: {bearing:03.0}° {}  ETA: {eta}",
+                compass(bearing)
+            ))
+            .monospace(),
+        );
+    }
+
     pub fn ui(&mut self, ui: &mut Ui, state: &mut AppState) {
-        if state.seed != self.prev_seed {
-            self.prev_seed = state.seed;
+        let events = state.events.poll(&mut self.events);
+        if events
+            .iter()
+            .any(|e| matches!(e, GameEvent::SeedChanged(_)))
+        {
             self.orb_searcher.reset();
         }
 
@@ -47,9 +229,19 @@ impl OrbRadar {
                     self.orb_searcher.reset();
                 };
 
-                if self.orb_searcher.is_searching() {
+                if let Some((done, total)) = self.orb_searcher.progress() {
                     ui.label("Searching..");
                     ui.spinner();
+                    ui.add(
+                        ProgressBar::new(done as f32 / total as f32)
+                            .text(format!("{done}/{total} chunks"))
+                            .desired_width(120.0),
+                    );
+                    if ui.button("Stop").clicked() {
+                        self.orb_searcher.stop();
+                    }
+                } else if self.orb_searcher.is_stopped() && ui.button("Resume search").clicked() {
+                    self.orb_searcher.resume();
                 }
             });
 
@@ -57,10 +249,25 @@ impl OrbRadar {
                 ui.ctx().request_repaint();
             }
 
+            let data = state.game().player.as_ref().map(|(player, p)| {
+                let pos = player.transform.pos;
+                (pos2(pos.x, pos.y), *p)
+            });
+            let data = data.zip(state.seed);
+
+            if let Some(((pos, _), seed)) = data {
+                self.poll_search(ui.ctx(), state, seed, pos);
+                self.motion.update(pos);
+                if let Some(orb) = self.nearest_orb() {
+                    self.readout_ui(ui, orb - pos);
+                }
+            }
+
             let (_, rect) = ui.allocate_space(ui.available_size());
 
             let mut painter = ui.painter_at(rect);
 
+            let palette = state.settings.palette.colors(ui.visuals());
             let text_color = ui.style().visuals.text_color();
             let stroke = Stroke::new(2.0, text_color);
 
@@ -68,10 +275,7 @@ impl OrbRadar {
                 1.0 / ui.ctx().pixels_per_point(),
                 ui.style().visuals.weak_text_color(),
             );
-            let tracer_bright = Stroke::new(
-                1.0 / ui.ctx().pixels_per_point(),
-                ui.style().visuals.strong_text_color(),
-            );
+            let tracer_bright = Stroke::new(1.0 / ui.ctx().pixels_per_point(), palette.target);
 
             let rect = rect.shrink(stroke.width);
             painter.rect(
@@ -82,21 +286,7 @@ impl OrbRadar {
             );
             painter.set_clip_rect(rect);
 
-            let pos = state.noita.as_mut().and_then(|n| {
-                n.get_player()
-                    .map_err(|e| {
-                        tracing::warn!(%e, "failed to read player pos");
-                        e
-                    })
-                    .ok()
-                    .flatten()
-                    .map(|(player, p)| {
-                        let pos = player.transform.pos;
-                        (pos2(pos.x, pos.y), p)
-                    })
-            });
-
-            let Some(((pos, p), seed)) = pos.zip(state.seed) else {
+            let Some(((pos, p), seed)) = data else {
                 painter.text(
                     rect.center(),
                     Align2::CENTER_CENTER,
@@ -117,7 +307,8 @@ impl OrbRadar {
                 );
             }
 
-            self.orb_searcher.poll_search(ui.ctx(), seed, pos);
+            let trail = Stroke::new(1.0, palette.trail());
+            state.trail.paint(&painter, |p| rect.center() + (p - pos), trail);
 
             let Some(first_orb) = self.orb_searcher.known_orbs().first() else {
                 return;
@@ -134,11 +325,10 @@ impl OrbRadar {
                 let pos = rect.center() + dir;
 
                 if rect.contains(pos) {
-                    let color = ui.style().visuals.strong_text_color();
                     let color = if i == 0 {
-                        color
+                        palette.target
                     } else {
-                        color.linear_multiply(alpha)
+                        palette.target.linear_multiply(alpha)
                     };
 
                     painter.circle_stroke(pos, 6.0, Stroke::new(1.0, color));
@@ -185,21 +375,32 @@ impl OrbRadar {
             painter.line_segment([r(c - vec2(0.0, c_from)), r(c - vec2(0.0, c_to))], stroke);
             painter.line_segment([r(c + vec2(0.0, c_from)), r(c + vec2(0.0, c_to))], stroke);
 
+            let world = seed.parallel_world(pos.x);
             let mut text = format!(
-                "pos: x:{:.1} y:{:.1}\nchunks searched: {}\nchunk size: {}\norbs found: {}\n",
+                "pos: x:{:.1} y:{:.1}\nworld: {}\nchunks searched: {}\nchunk size: {}\norbs found: {}\n",
                 pos.x,
                 pos.y,
+                world_name(world),
                 self.orb_searcher.searched_chunks(),
                 self.orb_searcher.chunk_size(),
                 self.orb_searcher.known_orbs().len(),
             );
+            if let Some(found) = &self.orbs_found {
+                writeln!(&mut text, "{}", collected_summary(found)).unwrap();
+            }
 
             let text_pos = rect.right_top() + vec2(-5.0, 5.0);
             let font = FontId::monospace(6.0);
             let limit = (rect.height() / ui.fonts(|f| f.row_height(&font))) as usize / 2;
             let orbs = self.orb_searcher.known_orbs();
             for orb in orbs.iter().take(limit) {
-                writeln!(&mut text, "  ({: >5.0}, {: >5.0})", orb.x, orb.y).unwrap();
+                write!(&mut text, "  ({: >5.0}, {: >5.0})", orb.x, orb.y).unwrap();
+                // the search range is wide enough to cross into the next world
+                let orb_world = seed.parallel_world(orb.x);
+                if orb_world != world {
+                    write!(&mut text, " {}", world_name(orb_world)).unwrap();
+                }
+                text.push('\n');
             }
             if orbs.len() > limit {
                 writeln!(&mut text, "  ..{} more", orbs.len() - limit).unwrap();
@@ -214,7 +415,7 @@ impl OrbRadar {
             let circle_pos = rect.left_bottom() + vec2(radius + offset, -radius - offset);
 
             if pos.x.round() == first_orb.x.round() && pos.y.round() == first_orb.y.round() {
-                painter.circle(circle_pos, radius, Color32::from_rgb(40, 255, 40), stroke);
+                painter.circle(circle_pos, radius, palette.on_target, stroke);
                 return;
             }
             painter.circle_stroke(circle_pos, radius, stroke);
@@ -231,3 +432,47 @@ impl OrbRadar {
         });
     }
 }
+
+/// 8-point compass direction of a bearing in degrees
+fn compass(bearing: f32) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    POINTS[((bearing + 22.5) / 45.0) as usize % 8]
+}
+
+/// How many orbs were picked up this run, and how many of those were in
+/// the parallel worlds.
+///
+/// The ids don't say which side a parallel world orb was on, so those are
+/// only counted together
+fn collected_summary(found: &[i32]) -> String {
+    let parallel = found
+        .iter()
+        .filter(|id| !(0..MAIN_WORLD_ORBS).contains(*id))
+        .count();
+    let mut summary = format!("orbs collected: {}/{TOTAL_ORBS}", found.len());
+    if parallel != 0 {
+        write!(&mut summary, ", {parallel} in parallel worlds").unwrap();
+    }
+    summary
+}
+
+/// Name of a parallel world as the players call them
+fn world_name(world: i32) -> String {
+    match world {
+        0 => "main".into(),
+        ..0 => format!("west {}", -world),
+        _ => format!("east {world}"),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn collected() {
+    assert_eq!(collected_summary(&[]), "orbs collected: 0/33");
+    assert_eq!(collected_summary(&[0, 3, 10]), "orbs collected: 3/33");
+    // 11 and up are the parallel world ones, on either side
+    assert_eq!(
+        collected_summary(&[0, 11, 20, 32]),
+        "orbs collected: 4/33, 3 in parallel worlds"
+    );
+}
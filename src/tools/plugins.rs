@@ -0,0 +1,339 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::{c_void, CStr},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context as _};
+use eframe::egui::{CollapsingHeader, Context, Grid, RichText, Ui};
+use libloading::Library;
+use noita_utility_box::plugin::{GameValues, PluginEntry, PluginVTable, ABI_VERSION, ENTRY_SYMBOL};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::{app::AppState, cli::parse_switch, reader::GameState};
+
+use super::{Result, Tool};
+
+/// A plugin library with its instance, see [noita_utility_box::plugin]
+struct LoadedPlugin {
+    id: String,
+    title: String,
+    path: PathBuf,
+    vtable: *const PluginVTable,
+    instance: *mut c_void,
+    // dropped last, the pointers above point into it
+    _library: Library,
+}
+
+// the plugin contract says the instance can move between threads
+unsafe impl Send for LoadedPlugin {}
+
+impl std::fmt::Debug for LoadedPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("LoadedPlugin")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LoadedPlugin {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        // SAFETY: well, it's running someone else's native code, the user
+        // opted in to that by enabling plugins
+        unsafe {
+            let library = Library::new(path)?;
+            let entry = library.get::<PluginEntry>(ENTRY_SYMBOL)?;
+            let vtable = entry();
+            if vtable.is_null() {
+                bail!("The plugin returned no vtable");
+            }
+            let abi = (*vtable).abi_version;
+            if abi != ABI_VERSION {
+                bail!("Built for plugin ABI {abi}, the app has {ABI_VERSION}");
+            }
+            let id = CStr::from_ptr((*vtable).id).to_string_lossy().into_owned();
+            let title = CStr::from_ptr((*vtable).title)
+                .to_string_lossy()
+                .into_owned();
+            let instance = ((*vtable).new)();
+            if instance.is_null() {
+                bail!("The plugin failed to initialize");
+            }
+            Ok(Self {
+                id,
+                title,
+                path: path.to_owned(),
+                vtable,
+                instance,
+                _library: library,
+            })
+        }
+    }
+
+    fn vtable(&self) -> &PluginVTable {
+        unsafe { &*self.vtable }
+    }
+
+    // SAFETY (for all the vtable calls below): the instance came from this
+    // vtable's new and is only dropped in Drop, the other pointers are to
+    // locals that outlive the calls
+
+    fn tick(&mut self, values: &GameValues) {
+        unsafe { (self.vtable().tick)(self.instance, values) };
+    }
+
+    fn rows(&self) -> Vec<(String, String)> {
+        unsafe extern "C" fn row(
+            ctx: *mut c_void,
+            label: *const u8,
+            label_len: usize,
+            value: *const u8,
+            value_len: usize,
+        ) {
+            let rows = unsafe { &mut *ctx.cast::<Vec<(String, String)>>() };
+            let text = |ptr, len| unsafe {
+                String::from_utf8_lossy(std::slice::from_raw_parts(ptr, len)).into_owned()
+            };
+            rows.push((text(label, label_len), text(value, value_len)));
+        }
+        let mut rows = Vec::new();
+        unsafe { (self.vtable().rows)(self.instance, row, (&raw mut rows).cast()) };
+        rows
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> bool {
+        unsafe {
+            (self.vtable().set_option)(
+                self.instance,
+                key.as_ptr(),
+                key.len(),
+                value.as_ptr(),
+                value.len(),
+            )
+        }
+    }
+}
+
+impl Drop for LoadedPlugin {
+    fn drop(&mut self) {
+        unsafe { (self.vtable().drop)(self.instance) };
+    }
+}
+
+fn game_values(game: &GameState) -> GameValues {
+    let mut values = GameValues {
+        frame: game.frame.map_or(-1, i64::from),
+        gold: game.gold.map_or(-1, |g| g as i64),
+        orbs: game.orbs.map_or(-1, |o| o as i64),
+        shift_cooldown: game.shift_cooldown.map_or(-1, i64::from),
+        ..Default::default()
+    };
+    if let Some(seed) = game.seed {
+        values.world_seed = seed.world_seed.into();
+        values.ng_count = seed.ng_count.into();
+    }
+    if let Some((hp, max_hp)) = game.hp {
+        values.hp = hp * 25.0;
+        values.max_hp = max_hp * 25.0;
+    }
+    if let Some((player, polymorphed)) = &game.player {
+        values.x = player.transform.pos.x;
+        values.y = player.transform.pos.y;
+        values.polymorphed = *polymorphed as i8;
+    }
+    values
+}
+
+#[derive(Debug, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Plugins {
+    /// Off by default, loading plugins runs arbitrary native code
+    enabled: bool,
+    /// Empty for the plugins folder in the storage dir
+    dir: String,
+    /// Ids of the plugins the user turned off
+    disabled: BTreeSet<String>,
+    /// `<plugin id>.<key>` options, applied when the plugin loads
+    options: BTreeMap<String, String>,
+
+    #[serde(skip)]
+    loaded: Option<Vec<LoadedPlugin>>,
+    /// The `dir` the plugins were loaded from
+    #[serde(skip)]
+    loaded_dir: String,
+    #[serde(skip)]
+    errors: Vec<(PathBuf, String)>,
+    #[serde(skip)]
+    last_frame: Option<u32>,
+}
+
+impl Plugins {
+    fn dir(&self) -> Option<PathBuf> {
+        if self.dir.trim().is_empty() {
+            eframe::storage_dir(env!("CARGO_PKG_NAME")).map(|dir| dir.join("plugins"))
+        } else {
+            Some(PathBuf::from(self.dir.trim()))
+        }
+    }
+
+    fn load_all(&mut self) {
+        self.errors.clear();
+        self.loaded_dir.clone_from(&self.dir);
+        let mut loaded = Vec::new();
+        let Some(dir) = self.dir() else {
+            self.loaded = Some(loaded);
+            return;
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // no plugins dir, no plugins
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.loaded = Some(loaded);
+                return;
+            }
+            Err(e) => {
+                self.errors.push((dir, e.to_string()));
+                self.loaded = Some(loaded);
+                return;
+            }
+        };
+        let mut paths = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|e| e == std::env::consts::DLL_EXTENSION)
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            match LoadedPlugin::load(&path).with_context(|| format!("Loading {}", path.display())) {
+                Ok(mut plugin) => {
+                    tracing::info!(id = plugin.id, path = %path.display(), "Loaded a plugin");
+                    let prefix = format!("{}.", plugin.id);
+                    for (key, value) in &self.options {
+                        if let Some(key) = key.strip_prefix(&prefix) {
+                            plugin.set_option(key, value);
+                        }
+                    }
+                    loaded.push(plugin);
+                }
+                Err(e) => {
+                    tracing::warn!(e = format!("{e:#}"), "Failed to load a plugin");
+                    self.errors.push((path, format!("{e:#}")));
+                }
+            }
+        }
+        self.loaded = Some(loaded);
+    }
+}
+
+#[typetag::serde]
+impl Tool for Plugins {
+    fn tick(&mut self, _ctx: &Context, state: &mut AppState) {
+        if !self.enabled {
+            self.loaded = None;
+            return;
+        }
+        if self.loaded.is_none() {
+            self.load_all();
+        }
+        if !state.game_advanced(&mut self.last_frame) {
+            return;
+        }
+        let values = game_values(&state.game());
+        for plugin in self.loaded.iter_mut().flatten() {
+            if !self.disabled.contains(&plugin.id) {
+                plugin.tick(&values);
+            }
+        }
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "enabled" => self.enabled = parse_switch(value)?,
+            "dir" => {
+                self.dir = value.into();
+                self.loaded = None;
+            }
+            _ => {
+                let Some((id, plugin_key)) = key.split_once('.') else {
+                    return Ok(false);
+                };
+                if let Some(plugin) = self.loaded.iter_mut().flatten().find(|p| p.id == id) {
+                    if !plugin.set_option(plugin_key, value) {
+                        return Ok(false);
+                    }
+                }
+                self.options.insert(key.into(), value.into());
+            }
+        }
+        Ok(true)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, _state: &mut AppState) -> Result {
+        ui.checkbox(&mut self.enabled, "Load plugins")
+            .on_hover_text("Plugins are native code, only use the ones you trust");
+
+        ui.horizontal(|ui| {
+            ui.label("Folder");
+            let dir = self.dir();
+            let hint = dir.as_deref().map(Path::display).map(|d| d.to_string());
+            let edit = ui.add(
+                eframe::egui::TextEdit::singleline(&mut self.dir)
+                    .hint_text(hint.unwrap_or_default()),
+            );
+            // not on every keystroke, that would load every dir on the way
+            let typed = edit.lost_focus() && self.loaded_dir != self.dir;
+            if ui.button("Reload").clicked() || typed {
+                self.loaded = None;
+            }
+        });
+
+        for (path, e) in &self.errors {
+            ui.label(RichText::new(e).color(ui.visuals().error_fg_color))
+                .on_hover_text(path.display().to_string());
+        }
+
+        let Some(loaded) = &self.loaded else {
+            return Ok(());
+        };
+        if loaded.is_empty() && self.enabled {
+            ui.weak(format!(
+                "No plugins found, put the .{} files in the folder",
+                std::env::consts::DLL_EXTENSION
+            ));
+        }
+        for plugin in loaded {
+            let mut enabled = !self.disabled.contains(&plugin.id);
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut enabled, "");
+                CollapsingHeader::new(&plugin.title)
+                    .id_salt(&plugin.id)
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        Grid::new(("plugin", &plugin.id))
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                for (label, value) in plugin.rows() {
+                                    ui.label(label);
+                                    ui.monospace(value);
+                                    ui.end_row();
+                                }
+                            });
+                    })
+                    .header_response
+                    .on_hover_text(plugin.path.display().to_string());
+            });
+            if enabled {
+                self.disabled.remove(&plugin.id);
+            } else {
+                self.disabled.insert(plugin.id.clone());
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,25 +1,75 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use anyhow::{anyhow, Context as _};
 use derive_more::Debug;
 use eframe::egui::{
-    text::LayoutJob, ComboBox, Context, Grid, Hyperlink, RichText, TextFormat, TextStyle, Ui,
+    text::LayoutJob, Button, CollapsingHeader, ComboBox, Context, Grid, Hyperlink, ProgressBar,
+    RichText, TextEdit, TextFormat, TextStyle, Ui,
 };
+use lazy_regex::Regex;
 use noita_utility_box::{
-    memory::{exe_image::PeHeader, ProcessRef},
-    noita::Noita,
+    memory::{exe_image::PeHeader, DumpRecorder, MemoryDump, ProcessRef},
+    noita::{
+        discovery::{KnownBuild, Support},
+        LayoutCheck, Noita,
+    },
 };
 use smart_default::SmartDefault;
 use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
 use thiserror::Error;
 
-use crate::{app::AppState, util::persist};
+use crate::{
+    app::AppState,
+    cli::parse_switch,
+    profiles,
+    util::{persist, Progress, Promise},
+};
+
+use super::{
+    address_maps::{AddressMap, AddressMapsData},
+    Result, Tool, ToolError,
+};
+
+/// Executables the auto-detection looks for, in order of preference - the
+/// dev build is shipped alongside the main one for modders
+const NOITA_EXES: &[&str] = &["noita.exe", "noita_dev.exe"];
+
+/// The [ProcessPanel::exe_pattern] that matches the same as [NOITA_EXES]
+fn default_exe_pattern() -> String {
+    let exes = NOITA_EXES.iter().map(|exe| lazy_regex::regex::escape(exe));
+    exes.collect::<Vec<_>>().join("|")
+}
 
-use super::{Result, Tool};
+/// A running Noita process the auto-detection found
+#[derive(Debug, Clone)]
+struct Candidate {
+    pid: sysinfo::Pid,
+    exe_name: Option<String>,
+    exe_path: Option<String>,
+    /// Read from the exe header once, None if that failed
+    timestamp: Option<u32>,
+}
+
+impl Candidate {
+    fn read_timestamp(pid: sysinfo::Pid) -> Option<u32> {
+        let proc = ProcessRef::connect(pid.as_u32()).ok()?;
+        PeHeader::read(&proc).ok().map(|h| h.timestamp())
+    }
+}
 
 #[derive(Debug)]
 pub struct NoitaData {
     pid: sysinfo::Pid,
     exe_name: Option<String>,
     timestamp: u32,
+    build: Option<&'static KnownBuild>,
+    /// Set when recording the reads to save them as a memory dump later
+    recorder: Option<DumpRecorder>,
+    /// Set when this is not a live process but an opened memory dump
+    dump: Option<PathBuf>,
 
     noita: Noita,
 }
@@ -33,16 +83,67 @@ enum NoitaError {
         header: PeHeader,
     },
     #[error(transparent)]
-    Contextual(#[from] anyhow::Error),
+    Tool(#[from] ToolError),
+}
+
+impl From<anyhow::Error> for NoitaError {
+    #[track_caller]
+    fn from(e: anyhow::Error) -> Self {
+        NoitaError::Tool(e.into())
+    }
 }
 
 type NoitaResult<T> = std::result::Result<T, NoitaError>;
 
+/// Things asked for through [Tool::set_option], e.g. from the command
+/// palette, done on the next tick
+#[derive(Debug, Clone, Copy)]
+enum Request {
+    Reconnect,
+    Discover,
+}
+
 impl NoitaData {
-    fn connect(pid: sysinfo::Pid, exe_name: Option<String>, state: &AppState) -> NoitaResult<Self> {
+    fn connect(
+        pid: sysinfo::Pid,
+        exe_name: Option<String>,
+        record: bool,
+        state: &AppState,
+    ) -> NoitaResult<Self> {
         let proc = ProcessRef::connect(pid.as_u32())
             .with_context(|| format!("Couldn't open the process {pid}"))?;
 
+        if !record {
+            return Self::from_process(proc, exe_name, state);
+        }
+        let recorder = DumpRecorder::new(proc);
+        let mut data =
+            Self::from_process(ProcessRef::from_memory(recorder.clone()), exe_name, state)?;
+        data.recorder = Some(recorder);
+        Ok(data)
+    }
+
+    fn open_dump(path: PathBuf, state: &AppState) -> NoitaResult<Self> {
+        let dump = MemoryDump::load(&path)
+            .with_context(|| format!("Couldn't open the memory dump {}", path.display()))?;
+        tracing::info!(
+            path = %path.display(),
+            regions = dump.regions(),
+            size = dump.size(),
+            "Opened a memory dump"
+        );
+        let mut data = Self::from_process(dump.into_process(), None, state)?;
+        data.dump = Some(path);
+        Ok(data)
+    }
+
+    fn from_process(
+        proc: ProcessRef,
+        exe_name: Option<String>,
+        state: &AppState,
+    ) -> NoitaResult<Self> {
+        let pid = sysinfo::Pid::from_u32(proc.pid());
+
         let header =
             PeHeader::read(&proc).with_context(|| format!("Couldn't read the process {pid}"))?;
 
@@ -59,19 +160,62 @@ impl NoitaData {
 
         let timestamp = header.timestamp();
 
-        let Some(address_map) = state.address_maps.get(timestamp) else {
-            return Err(NoitaError::Unmapped { proc, header });
+        // the profile map is picked by hand, so it goes before everything
+        let profile_map = state
+            .profiles
+            .active()
+            .and_then(|p| p.address_map.as_deref())
+            .and_then(|name| state.address_maps.by_name(name));
+
+        // user maps first so that the built-in ones can be overridden
+        let globals = match profile_map.or_else(|| state.address_maps.get(timestamp)) {
+            Some(address_map) => address_map.as_noita_globals(),
+            None => match KnownBuild::find(timestamp) {
+                Some(build) => build.globals(),
+                None => return Err(NoitaError::Unmapped { proc, header }),
+            },
         };
 
-        let noita = Noita::new(proc, address_map.as_noita_globals());
+        let mut noita = Noita::new(proc, globals);
+
+        let build = KnownBuild::find(timestamp);
+        match build {
+            Some(build) => {
+                tracing::info!("Connected to Noita build {build}");
+                noita.set_layout(build.layout);
+            }
+            None => tracing::warn!("Connected to an unknown Noita build 0x{timestamp:x}"),
+        }
 
         Ok(Self {
             pid,
             exe_name,
             timestamp,
+            build,
+            recorder: None,
+            dump: None,
             noita,
         })
     }
+
+    /// Save what was recorded so far into a new file in the storage dir
+    fn save_dump(&self) -> anyhow::Result<Option<PathBuf>> {
+        let Some(recorder) = &self.recorder else {
+            return Ok(None);
+        };
+        let dir = eframe::storage_dir(env!("CARGO_PKG_NAME"))
+            .context("No storage dir")?
+            .join("dumps");
+        std::fs::create_dir_all(&dir)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = dir.join(format!("noita-{:x}-{now}.dump", self.timestamp));
+        recorder.dump().save(&path)?;
+        Ok(Some(path))
+    }
 }
 
 #[derive(Debug, SmartDefault)]
@@ -85,10 +229,44 @@ pub struct ProcessPanel {
     #[default(Ok(None))]
     noita: NoitaResult<Option<NoitaData>>,
     selected_process: Option<(sysinfo::Pid, Option<String>)>,
+
+    #[default(true)]
+    auto_discover: bool,
+    /// Timestamp of the build auto-discovery was last started for, so that
+    /// we don't keep retrying it in a loop when it fails
+    auto_discovered: Option<u32>,
+    #[debug(skip)]
+    discovery: Option<Promise<anyhow::Result<Option<AddressMap>>>>,
+    discovery_progress: Progress,
+
+    record_dump: bool,
+    dump_path: String,
+
+    /// All the Noita processes found on the last refresh
+    candidates: Vec<Candidate>,
+    /// Exe path of the instance picked the last time there were several
+    preferred_exe: Option<String>,
+
+    /// Regex for the process names to auto-detect instead of [NOITA_EXES],
+    /// for renamed executables and modded launchers
+    exe_pattern: String,
+    /// The compiled [Self::exe_pattern], along with the text it was compiled from
+    compiled_pattern: Option<(String, std::result::Result<Regex, lazy_regex::regex::Error>)>,
+    attach_pid: String,
+
+    /// Results of the last component layout check
+    layout_checks: Option<Vec<(&'static str, std::result::Result<LayoutCheck, String>)>>,
+
+    new_profile_name: String,
+    request: Option<Request>,
 }
 
 persist!(ProcessPanel {
     look_for_noita: bool,
+    auto_discover: bool,
+    dump_path: String,
+    preferred_exe: Option<String>,
+    exe_pattern: String,
 });
 
 impl ProcessPanel {
@@ -98,17 +276,76 @@ impl ProcessPanel {
         state: &mut AppState,
         noita: NoitaResult<Option<NoitaData>>,
     ) {
+        if let Err(NoitaError::Unmapped { proc, header }) = &noita {
+            if self.auto_discover
+                && self.discovery.is_none()
+                && self.auto_discovered != Some(header.timestamp())
+            {
+                self.start_discovery(ctx, proc, header);
+            }
+        }
+
         // update the global handle to be used by things
         if let Ok(Some(ref data)) = noita {
-            state.noita = Some(data.noita.clone());
+            state.set_noita(Some(data.noita.clone()));
         } else {
-            state.noita = None;
+            state.set_noita(None);
         }
         self.noita = noita;
         self.selected_process = None;
+        self.layout_checks = None;
         ctx.request_repaint();
     }
 
+    fn start_discovery(&mut self, ctx: &Context, proc: &ProcessRef, header: &PeHeader) {
+        tracing::info!(
+            "Unknown Noita build 0x{:x}, running auto-discovery",
+            header.timestamp()
+        );
+        self.auto_discovered = Some(header.timestamp());
+
+        self.discovery_progress.set(0.0);
+        let progress = self.discovery_progress.clone();
+        let (ctx, proc, header) = (ctx.clone(), proc.clone(), header.clone());
+        self.discovery = Some(Promise::spawn(async move {
+            // reads the whole exe and scans it, so keep it off the runtime threads
+            let repaint = ctx.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                AddressMapsData::discover_map(&proc, &header, |done| {
+                    progress.set(done);
+                    repaint.request_repaint();
+                })
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.into()));
+            ctx.request_repaint();
+            result
+        }));
+    }
+
+    fn poll_discovery(&mut self, ctx: &Context, state: &mut AppState) {
+        let Some(result) = self.discovery.as_mut().and_then(Promise::poll_take) else {
+            return;
+        };
+        self.discovery = None;
+
+        match result {
+            Ok(Some(map)) => {
+                state.address_maps.add(map);
+                state.toast("Discovered the addresses for the new Noita build");
+                // and reconnect, now with the new map
+                self.set_noita(ctx, state, Ok(None));
+            }
+            Ok(None) => {
+                state.toast_error("Auto-discovery found nothing for this Noita build");
+            }
+            Err(e) => {
+                state.toast_error(format!("Auto-discovery failed: {e:#}"));
+                self.set_noita(ctx, state, Err(e.into()));
+            }
+        }
+    }
+
     fn processes_box(&mut self, ui: &mut Ui, state: &mut AppState) {
         let mut combo = ComboBox::from_id_salt("processes").height(400.0);
 
@@ -149,21 +386,178 @@ impl ProcessPanel {
             self.set_noita(
                 ui.ctx(),
                 state,
-                NoitaData::connect(pid, exe, state).map(Some),
+                NoitaData::connect(pid, exe, self.record_dump, state).map(Some),
             );
         }
     }
+
+    /// The custom exe pattern, None if it's not set or invalid
+    fn exe_regex(&mut self) -> Option<&Regex> {
+        let pattern = self.exe_pattern.trim();
+        if pattern.is_empty() {
+            return None;
+        }
+        if self.compiled_pattern.as_ref().map(|(p, _)| p.as_str()) != Some(pattern) {
+            // the whole name has to match, or `noita` would also pick up e.g. noita_launcher
+            let compiled = lazy_regex::regex::RegexBuilder::new(&format!("^(?:{pattern})$"))
+                .case_insensitive(true)
+                .build();
+            self.compiled_pattern = Some((pattern.to_owned(), compiled));
+        }
+        self.compiled_pattern.as_ref()?.1.as_ref().ok()
+    }
+
+    fn refresh_candidates(&mut self) {
+        let regex = self.exe_regex().cloned();
+        let matches = |name: &str| match &regex {
+            Some(regex) => regex.is_match(name),
+            None => NOITA_EXES.contains(&name),
+        };
+
+        let mut candidates = Vec::new();
+        for p in self.system_info.processes().values() {
+            if p.thread_kind().is_some() || !matches(&p.name().to_string_lossy()) {
+                continue;
+            }
+            // the header read is not free, so keep it for known pids
+            let timestamp = match self.candidates.iter().find(|c| c.pid == p.pid()) {
+                Some(known) => known.timestamp,
+                None => Candidate::read_timestamp(p.pid()),
+            };
+            candidates.push(Candidate {
+                pid: p.pid(),
+                exe_name: p
+                    .exe()
+                    .and_then(|p| p.file_name().map(|f| f.to_string_lossy().into_owned())),
+                exe_path: p.exe().map(|p| p.to_string_lossy().into_owned()),
+                timestamp,
+            });
+        }
+        candidates.sort_unstable_by_key(|c| c.pid);
+        self.candidates = candidates;
+    }
+
+    fn connect_candidate(&mut self, ctx: &Context, state: &mut AppState, candidate: Candidate) {
+        let data = NoitaData::connect(candidate.pid, candidate.exe_name, self.record_dump, state);
+        self.set_noita(ctx, state, data.map(Some));
+    }
+
+    /// Lists all the found instances, connecting to the one the user picks
+    fn candidates_ui(
+        &mut self,
+        ui: &mut Ui,
+        state: &mut AppState,
+        connected: Option<sysinfo::Pid>,
+    ) {
+        let mut picked = None;
+        Grid::new("candidates").striped(true).show(ui, |ui| {
+            for c in &self.candidates {
+                ui.label(process_label(ui, c.pid, c.exe_name.as_deref()));
+                match (c.timestamp, c.timestamp.and_then(KnownBuild::find)) {
+                    (_, Some(build)) => ui.label(build.to_string()),
+                    (Some(ts), None) => ui.monospace(format!("0x{ts:x}")),
+                    (None, None) => ui.weak("?"),
+                };
+                if Some(c.pid) == connected {
+                    ui.weak("connected");
+                } else if ui
+                    .button("Connect")
+                    .on_hover_text(c.exe_path.as_deref().unwrap_or("Unknown exe path"))
+                    .clicked()
+                {
+                    picked = Some(c.clone());
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(candidate) = picked {
+            // remember which one it was, pids don't survive restarts
+            self.preferred_exe = candidate.exe_path.clone();
+            self.connect_candidate(ui.ctx(), state, candidate);
+        }
+    }
+
+    fn advanced_attach_ui(&mut self, ui: &mut Ui, state: &mut AppState) {
+        CollapsingHeader::new("Advanced attach").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut self.exe_pattern)
+                        .hint_text(default_exe_pattern()),
+                )
+                .on_hover_text("A regex for the whole process name to auto-detect instead of the usual Noita executables, for renamed ones or modded launchers");
+                ui.label("Process name pattern");
+            });
+            if let Some((_, Err(e))) = &self.compiled_pattern {
+                if !self.exe_pattern.trim().is_empty() {
+                    ui.label(RichText::new(e.to_string()).color(ui.visuals().error_fg_color));
+                }
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut self.attach_pid)
+                        .hint_text("pid")
+                        .desired_width(80.0),
+                );
+                let pid = self.attach_pid.trim().parse::<u32>().ok();
+                let clicked = ui
+                    .add_enabled(pid.is_some(), eframe::egui::Button::new("Attach by pid"))
+                    .on_hover_text("Connect to the process with this pid, whatever it's called")
+                    .clicked();
+                if let Some(pid) = pid.filter(|_| clicked) {
+                    let pid = sysinfo::Pid::from_u32(pid);
+                    let exe = self.system_info.process(pid).and_then(|p| {
+                        p.exe()
+                            .and_then(|p| p.file_name().map(|f| f.to_string_lossy().into_owned()))
+                    });
+                    match NoitaData::connect(pid, exe, self.record_dump, state) {
+                        // same as with the dumps, auto-detect would just swallow these
+                        Err(NoitaError::Tool(e)) => state.toast_error(e.to_string()),
+                        data => self.set_noita(ui.ctx(), state, data.map(Some)),
+                    }
+                }
+            });
+        });
+    }
+
+    fn open_dump_ui(&mut self, ui: &mut Ui, state: &mut AppState) {
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.dump_path).hint_text("Memory dump file"));
+            let path = self.dump_path.trim();
+            if ui
+                .add_enabled(!path.is_empty(), eframe::egui::Button::new("Open dump"))
+                .on_hover_text("Open a memory dump saved before instead of a live process")
+                .clicked()
+            {
+                match NoitaData::open_dump(path.into(), state) {
+                    // tool errors are not shown when auto-detecting, so toast instead
+                    Err(NoitaError::Tool(e)) => state.toast_error(e.to_string()),
+                    data => self.set_noita(ui.ctx(), state, data.map(Some)),
+                }
+            }
+        });
+    }
 }
 
 #[typetag::serde]
 impl Tool for ProcessPanel {
     fn tick(&mut self, ctx: &Context, state: &mut AppState) {
+        self.poll_discovery(ctx, state);
+        if let Some(request) = self.request.take() {
+            self.apply_request(ctx, state, request);
+        }
+
         let Ok(noita) = &self.noita else {
             return;
         };
         if noita.is_none() && !self.look_for_noita {
             return;
         }
+        // dumps don't go anywhere
+        if noita.as_ref().is_some_and(|n| n.dump.is_some()) {
+            return;
+        }
 
         // Has to be all because either we don't have noita and we're looking
         // for it or we have it, but we want to check if it's still there, for
@@ -174,77 +568,93 @@ impl Tool for ProcessPanel {
             ProcessRefreshKind::new().with_exe(UpdateKind::OnlyIfNotSet),
         );
 
-        if let Some(noita) = noita {
+        let connected = noita.as_ref().map(|n| n.pid);
+        self.refresh_candidates();
+
+        if let Some(pid) = connected {
             // check that we still have it
-            if self.system_info.process(noita.pid).is_none() {
+            if self.system_info.process(pid).is_none() {
                 self.set_noita(ctx, state, Ok(None));
-                return;
             }
-
-            state.seed = noita.noita.read_seed().ok().flatten();
-
             return;
         }
 
         // no noita and we're looking for it
 
-        let Some(p) = self
-            .system_info
-            .processes_by_exact_name("noita.exe".as_ref())
-            .find(|p| p.thread_kind().is_none())
-        else {
-            return;
+        let candidate = match &self.candidates[..] {
+            [] => return,
+            [only] => only.clone(),
+            several => {
+                let preferred = several
+                    .iter()
+                    .find(|c| c.exe_path.is_some() && c.exe_path == self.preferred_exe);
+                match preferred {
+                    Some(c) => c.clone(),
+                    // let the user pick in the UI
+                    None => return,
+                }
+            }
         };
-        let exe = p
-            .exe()
-            .and_then(|p| p.file_name().map(|f| f.to_string_lossy().into_owned()));
-
-        self.set_noita(
-            ctx,
-            state,
-            NoitaData::connect(p.pid(), exe, state).map(Some),
-        );
+        self.connect_candidate(ctx, state, candidate);
     }
 
     fn ui(&mut self, ui: &mut Ui, state: &mut AppState) -> Result {
         match &self.noita {
-            Err(e) => {
-                ui.label(RichText::new(format!("{e:#}")).color(ui.style().visuals.error_fg_color));
-
-                if let NoitaError::Unmapped { proc, header } = e {
-                    if ui.button("Run auto-discovery").clicked() {
-                        if let Err(e) = state.address_maps.discover(proc, header) {
-                            self.set_noita(ui.ctx(), state, Err(e.into()))
-                        } else {
-                            self.set_noita(ui.ctx(), state, Ok(None))
-                        }
-                    }
-                    if !self.look_for_noita {
-                        self.processes_box(ui, state);
-                    }
-                } else if self.look_for_noita {
+            Err(e @ NoitaError::Unmapped { proc, header }) => {
+                ui.label(RichText::new(e.to_string()).color(ui.visuals().warn_fg_color));
+                if self.discovery.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Running auto-discovery...");
+                    });
+                    ui.add(ProgressBar::new(self.discovery_progress.get()).show_percentage());
+                } else if ui.button("Run auto-discovery").clicked() {
+                    let (proc, header) = (proc.clone(), header.clone());
+                    self.start_discovery(ui.ctx(), &proc, &header);
+                }
+                if !self.look_for_noita {
+                    self.processes_box(ui, state);
+                }
+            }
+            Err(NoitaError::Tool(e)) => {
+                if self.look_for_noita {
                     self.set_noita(ui.ctx(), state, Ok(None));
                 } else {
+                    if e.show(ui) {
+                        self.set_noita(ui.ctx(), state, Ok(None));
+                    }
                     self.processes_box(ui, state);
                 }
             }
             Ok(None) => {
-                if self.look_for_noita {
+                if self.look_for_noita && self.candidates.len() > 1 {
+                    ui.label("Found several Noita processes, pick one:");
+                    self.candidates_ui(ui, state, None);
+                } else if self.look_for_noita {
                     ui.label("Noita process not found");
                 } else {
                     self.processes_box(ui, state);
                 }
+                self.open_dump_ui(ui, state);
+                self.advanced_attach_ui(ui, state);
             }
             Ok(Some(noita)) => {
                 Grid::new("noita").show(ui, |ui| {
-                    ui.label("Process:");
-                    ui.label(process_label(ui, noita.pid, noita.exe_name.as_deref()));
+                    if let Some(path) = &noita.dump {
+                        ui.label("Dump:");
+                        ui.label(path.display().to_string());
+                    } else {
+                        ui.label("Process:");
+                        ui.label(process_label(ui, noita.pid, noita.exe_name.as_deref()));
+                    }
                     ui.end_row();
 
                     ui.label("Version:");
                     ui.label(format!("0x{:x}", noita.timestamp));
                     ui.end_row();
 
+                    build_rows(ui, noita.build);
+
                     if let Some(s) = &state.seed {
                         ui.label("Seed:");
                         let seed = s.world_seed.to_string();
@@ -261,16 +671,251 @@ impl Tool for ProcessPanel {
                     }
                 });
 
-                if !self.look_for_noita && ui.button("Disconnect").clicked() {
+                if ui
+                    .button("Check component layouts")
+                    .on_hover_text("Compare the component structs we have against the components in the game, to catch the ones that changed in this build")
+                    .clicked()
+                {
+                    let checks = noita.noita.check_component_layouts();
+                    self.layout_checks = Some(
+                        checks
+                            .into_iter()
+                            .map(|(name, check)| (name, check.map_err(|e| e.to_string())))
+                            .collect(),
+                    );
+                }
+                if let Some(checks) = &self.layout_checks {
+                    layout_checks_ui(ui, checks);
+                }
+
+                if noita.recorder.is_some() && ui.button("Save memory dump").clicked() {
+                    match noita.save_dump() {
+                        Ok(Some(path)) => {
+                            state.toast(format!("Saved the memory dump to {}", path.display()))
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            state.toast_error(format!("Couldn't save the memory dump: {e:#}"))
+                        }
+                    }
+                }
+
+                let connected = noita.pid;
+                if noita.dump.is_some() {
+                    if ui.button("Close dump").clicked() {
+                        self.set_noita(ui.ctx(), state, Ok(None));
+                    }
+                } else if !self.look_for_noita && ui.button("Disconnect").clicked() {
                     self.set_noita(ui.ctx(), state, Ok(None));
                 }
+
+                if self.candidates.len() > 1 {
+                    CollapsingHeader::new("All Noita instances")
+                        .show(ui, |ui| self.candidates_ui(ui, state, Some(connected)));
+                }
             }
         }
 
         ui.checkbox(&mut self.look_for_noita, "Auto-detect Noita process");
+        ui.checkbox(&mut self.record_dump, "Record a memory dump")
+            .on_hover_text("Remember everything read from the next connected process, so that it can be saved to a file and opened later without the game");
+        ui.checkbox(&mut self.auto_discover, "Auto-discover unknown Noita builds")
+            .on_hover_text("Run the address discovery automatically when connecting to a Noita build with no address map");
+
+        CollapsingHeader::new("Profiles").show(ui, |ui| self.profiles_ui(ui, state));
 
         Ok(())
     }
+
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        let request = match key {
+            "reconnect" => Request::Reconnect,
+            "discover" => Request::Discover,
+            _ => return Ok(false),
+        };
+        if parse_switch(value)? {
+            self.request = Some(request);
+        }
+        Ok(true)
+    }
+}
+
+impl ProcessPanel {
+    /// Pick a profile, and the address map of the active one
+    fn profiles_ui(&mut self, ui: &mut Ui, state: &mut AppState) {
+        let profiles = &state.profiles;
+        let active = profiles.active.clone();
+        let prev_map = profiles.active().and_then(|p| p.address_map.clone());
+
+        let mut picked = active.clone();
+        ComboBox::from_label("Profile")
+            .selected_text(active.as_deref().unwrap_or("None"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut picked, None, "None");
+                for profile in &profiles.profiles {
+                    let name = Some(profile.name.clone());
+                    ui.selectable_value(&mut picked, name, &profile.name);
+                }
+            });
+        if picked != active {
+            let map = picked
+                .as_deref()
+                .and_then(|name| profiles.get(name))
+                .and_then(|p| p.address_map.clone());
+            state.request_profile(profiles::Request::Switch(picked));
+            self.reconnect_if_map_changed(ui.ctx(), state, prev_map, map);
+            return;
+        }
+
+        let names = state.address_maps.names();
+        if let Some(profile) = state.profiles.active_mut() {
+            let mut map = profile.address_map.clone();
+            ComboBox::from_label("Address map")
+                .selected_text(map.as_deref().unwrap_or("By build"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut map, None, "By build")
+                        .on_hover_text("Use the address map made for the connected build");
+                    for name in names {
+                        ui.selectable_value(&mut map, Some(name.clone()), name);
+                    }
+                });
+            if map != profile.address_map {
+                profile.address_map.clone_from(&map);
+                self.reconnect_if_map_changed(ui.ctx(), state, prev_map, map);
+            }
+
+            if ui
+                .button("Delete profile")
+                .on_hover_text("The tool configs stay as they are")
+                .clicked()
+            {
+                if let Some(name) = active {
+                    state.profiles.remove(&name);
+                }
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut self.new_profile_name)
+                    .hint_text("New profile name")
+                    .desired_width(140.0),
+            );
+            let name = self.new_profile_name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), Button::new("Save as profile"))
+                .on_hover_text("Save the current tool configs as a profile, changes made while it's active are kept in it")
+                .clicked()
+            {
+                state.request_profile(profiles::Request::Create(name.to_owned()));
+                self.new_profile_name.clear();
+            }
+        });
+    }
+
+    fn apply_request(&mut self, ctx: &Context, state: &mut AppState, request: Request) {
+        match request {
+            Request::Reconnect => {
+                let connected = match &self.noita {
+                    Ok(Some(data)) if data.dump.is_none() => {
+                        Some((data.pid, data.exe_name.clone()))
+                    }
+                    _ => None,
+                };
+                tracing::info!(?connected, "Reconnecting");
+                self.set_noita(ctx, state, Ok(None));
+                // otherwise the auto-detection picks it up on the next tick
+                if let Some((pid, exe_name)) = connected {
+                    let data = NoitaData::connect(pid, exe_name, self.record_dump, state);
+                    self.set_noita(ctx, state, data.map(Some));
+                }
+            }
+            Request::Discover => match &self.noita {
+                _ if self.discovery.is_some() => {}
+                Err(NoitaError::Unmapped { proc, header }) => {
+                    let (proc, header) = (proc.clone(), header.clone());
+                    self.start_discovery(ctx, &proc, &header);
+                }
+                Ok(Some(_)) => state.toast("The connected Noita build already has its addresses"),
+                _ => state.toast_error("Not connected to Noita"),
+            },
+        }
+    }
+
+    fn reconnect_if_map_changed(
+        &mut self,
+        ctx: &Context,
+        state: &mut AppState,
+        prev: Option<String>,
+        new: Option<String>,
+    ) {
+        if prev != new && matches!(self.noita, Ok(Some(_))) {
+            tracing::info!(?new, "Reconnecting with another address map");
+            self.set_noita(ctx, state, Ok(None));
+        }
+    }
+}
+
+fn layout_checks_ui(ui: &mut Ui, checks: &[(&str, std::result::Result<LayoutCheck, String>)]) {
+    Grid::new("layout_checks").striped(true).show(ui, |ui| {
+        for (name, check) in checks {
+            ui.label(*name);
+            match check {
+                Ok(check) => {
+                    ui.label(format!("{} instances", check.instances));
+                    match check.problem() {
+                        Some(problem) => {
+                            ui.label(RichText::new(problem).color(ui.visuals().warn_fg_color))
+                        }
+                        None => ui.label("OK"),
+                    };
+                }
+                Err(e) => {
+                    // most likely just no entities with it right now
+                    ui.weak("-");
+                    ui.weak(e);
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
+
+fn build_rows(ui: &mut Ui, build: Option<&KnownBuild>) {
+    ui.label("Build:");
+    let Some(build) = build else {
+        ui.label(RichText::new("Unknown").color(ui.visuals().warn_fg_color))
+            .on_hover_text("Not a build we know about, some things might not work");
+        ui.end_row();
+        return;
+    };
+    ui.label(format!("{build}, entity layout v{}", build.layout));
+    ui.end_row();
+
+    if !build.support.is_empty() {
+        ui.label("Support:");
+        ui.vertical(|ui| {
+            for (feature, support) in build.support {
+                let color = match support {
+                    Support::Ok => ui.visuals().text_color(),
+                    Support::Untested => ui.visuals().warn_fg_color,
+                    Support::Broken => ui.visuals().error_fg_color,
+                };
+                ui.label(RichText::new(format!("{feature} {support}")).color(color));
+            }
+        });
+        ui.end_row();
+    }
+
+    if !build.quirks.is_empty() {
+        ui.label("Quirks:");
+        ui.vertical(|ui| {
+            for quirk in build.quirks {
+                ui.label(*quirk);
+            }
+        });
+        ui.end_row();
+    }
 }
 
 fn process_label(ui: &Ui, pid: sysinfo::Pid, fname: Option<&str>) -> LayoutJob {
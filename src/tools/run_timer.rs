@@ -0,0 +1,519 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context as _;
+use eframe::egui::{
+    Align, CollapsingHeader, Color32, Context, Grid, Layout, RichText, ScrollArea, TextEdit, Ui,
+};
+use noita_utility_box::noita::{types::GlobalStats, Noita};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::{
+    app::AppState,
+    cli::parse_switch,
+    events::{EventCursor, GameEvent},
+    livesplit::{self, Command, LiveSplit, Status},
+};
+
+use super::{Result, Tool};
+
+/// How many finished runs are kept
+const MAX_HISTORY: usize = 200;
+
+/// Holy Mountains are a few thousand pixels apart, going back up into the
+/// same one (or being inside it when the check runs twice) is not a split
+const TEMPLE_SPACING: f32 = 1024.0;
+
+/// Seconds between the game time updates sent to LiveSplit, the splits send
+/// the exact time on their own
+const LIVESPLIT_TIME_INTERVAL: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Outcome {
+    Running,
+    Died,
+    Won,
+    /// A new run started or the timer was reset before the run ended
+    Reset,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Split {
+    name: String,
+    /// Seconds of in-game time
+    time: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Run {
+    seed: Option<String>,
+    /// Unix timestamp
+    started_at: u64,
+    splits: Vec<Split>,
+    /// Seconds of in-game time
+    time: f64,
+    outcome: Outcome,
+}
+
+impl Run {
+    fn split_time(&self, index: usize, name: &str) -> Option<f64> {
+        self.splits
+            .get(index)
+            .filter(|s| s.name == name)
+            .map(|s| s.time)
+    }
+}
+
+/// What the split triggers compare against, reset for each run
+#[derive(Debug, Default)]
+struct Watch {
+    /// Stats at the start of the run, None until the first read
+    wins: Option<u32>,
+    kv: HashMap<String, u32>,
+    kv_done: HashSet<String>,
+    temples: u32,
+    in_temple: bool,
+    last_temple_y: Option<f32>,
+    sampo: bool,
+    /// Triggers that failed this run, to only warn about them once
+    warned: HashSet<&'static str>,
+}
+
+/// A speedrun timer that follows the game by itself
+#[derive(Debug, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunTimer {
+    /// Start timing when a new run begins
+    #[default(true)]
+    auto_start: bool,
+    #[default(true)]
+    split_holy_mountain: bool,
+    #[default(true)]
+    split_sampo: bool,
+    /// Comma-separated key-value stats that make a split when they go up,
+    /// e.g. for the boss kills
+    kv_splits: String,
+
+    /// Drive a LiveSplit layout through its server component
+    livesplit: bool,
+    #[default(livesplit::DEFAULT_ADDRESS.into())]
+    livesplit_address: String,
+
+    current: Option<Run>,
+    history: Vec<Run>,
+
+    #[serde(skip)]
+    watch: Watch,
+    #[serde(skip)]
+    events: EventCursor,
+    #[serde(skip)]
+    last_frame: Option<u32>,
+    #[serde(skip)]
+    livesplit_client: Option<LiveSplit>,
+    /// The game time last sent to LiveSplit
+    #[serde(skip)]
+    livesplit_time: Option<f64>,
+}
+
+impl RunTimer {
+    /// Tell LiveSplit, if it's enabled
+    fn notify(&mut self, command: Command) {
+        if !self.livesplit {
+            self.livesplit_client = None;
+            return;
+        }
+        let client = match &self.livesplit_client {
+            Some(client) if client.address() == self.livesplit_address => client,
+            _ => self
+                .livesplit_client
+                .insert(LiveSplit::new(&self.livesplit_address)),
+        };
+        client.send(command);
+    }
+
+    fn send_time(&mut self, time: f64) {
+        self.livesplit_time = Some(time);
+        self.notify(Command::GameTime(time));
+    }
+
+    fn start(&mut self, seed: Option<String>) {
+        self.finish(Outcome::Reset);
+        self.notify(Command::Start);
+        self.livesplit_time = None;
+        self.watch = Watch::default();
+        self.current = Some(Run {
+            seed,
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            splits: Vec::new(),
+            time: 0.0,
+            outcome: Outcome::Running,
+        });
+    }
+
+    fn finish(&mut self, outcome: Outcome) {
+        let Some(mut run) = self.current.take() else {
+            return;
+        };
+        run.outcome = outcome;
+        self.history.push(run);
+        // a win was the last split already
+        if outcome != Outcome::Won {
+            self.notify(Command::Reset);
+        }
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    fn split(&mut self, name: impl Into<String>) {
+        let Some(run) = &mut self.current else {
+            return;
+        };
+        run.splits.push(Split {
+            name: name.into(),
+            time: run.time,
+        });
+        let time = run.time;
+        self.send_time(time);
+        self.notify(Command::Split);
+    }
+
+    /// The fastest won run
+    fn personal_best(&self) -> Option<&Run> {
+        self.history
+            .iter()
+            .filter(|r| r.outcome == Outcome::Won)
+            .min_by(|a, b| a.time.total_cmp(&b.time))
+    }
+
+    fn kv_split_names(&self) -> Vec<String> {
+        self.kv_splits
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_owned)
+            .collect()
+    }
+
+    /// Update the time of the current run and check the split triggers
+    fn watch(&mut self, noita: &mut Noita) -> anyhow::Result<()> {
+        let Some(run) = &mut self.current else {
+            return Ok(());
+        };
+        run.time = noita.read_playtime().context("Reading the playtime")?;
+        let time = run.time;
+        // no point in flooding it every frame, it's only for the display
+        let due = self
+            .livesplit_time
+            .is_none_or(|t| time < t || time - t >= LIVESPLIT_TIME_INTERVAL);
+        if due {
+            self.send_time(time);
+        }
+
+        // each on its own, so that e.g. a broken biome map doesn't keep the
+        // timer from stopping on a win
+        let triggers = [
+            ("Holy Mountain", self.watch_temples(noita)),
+            ("Sampo", self.watch_sampo(noita)),
+            ("kv stat", self.watch_kv_stats(noita)),
+            ("win", self.watch_win(noita)),
+        ];
+        for (trigger, result) in triggers {
+            if let Err(e) = result {
+                if self.watch.warned.insert(trigger) {
+                    tracing::warn!(trigger, e = format!("{e:#}"), "Run timer trigger failed");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn watch_temples(&mut self, noita: &mut Noita) -> anyhow::Result<()> {
+        if !self.split_holy_mountain {
+            return Ok(());
+        }
+        let Some((player, _)) = noita.get_player()? else {
+            return Ok(());
+        };
+        let pos = player.transform.pos;
+        // the biome map failing to load is warned about by itself, once
+        let in_temple = noita
+            .get_biome_at(pos.x as i32, pos.y as i32)
+            .is_ok_and(|b| b.is_some_and(|b| b.contains("temple")));
+        let w = &mut self.watch;
+        let deeper = w.last_temple_y.is_none_or(|y| pos.y > y + TEMPLE_SPACING);
+        if in_temple && !w.in_temple && deeper {
+            w.temples += 1;
+            w.last_temple_y = Some(pos.y);
+            let name = format!("Holy Mountain {}", w.temples);
+            self.split(name);
+        }
+        self.watch.in_temple = in_temple;
+        Ok(())
+    }
+
+    fn watch_sampo(&mut self, noita: &mut Noita) -> anyhow::Result<()> {
+        if !self.split_sampo || self.watch.sampo {
+            return Ok(());
+        }
+        let tag = noita.get_entity_tag_index("this_is_sampo")?;
+        if let Some(inventory) = noita.read_player_inventory()?.filter(|_| tag.is_some()) {
+            let mut items = inventory.quick.iter().chain(&inventory.full);
+            if items.any(|i| i.entity.tags[tag]) {
+                self.watch.sampo = true;
+                self.split("Sampo");
+            }
+        }
+        Ok(())
+    }
+
+    fn watch_kv_stats(&mut self, noita: &mut Noita) -> anyhow::Result<()> {
+        let names = self.kv_split_names();
+        if names.is_empty() {
+            return Ok(());
+        }
+        let stats = noita.read_stats().context("Reading global stats")?;
+        for name in names {
+            if self.watch.kv_done.contains(&name) {
+                continue;
+            }
+            let value = kv_stat(noita, &stats, &name)?;
+            let start = *self.watch.kv.entry(name.clone()).or_insert(value);
+            if value > start {
+                self.watch.kv_done.insert(name.clone());
+                self.split(name);
+            }
+        }
+        Ok(())
+    }
+
+    fn watch_win(&mut self, noita: &mut Noita) -> anyhow::Result<()> {
+        let stats = noita.read_stats().context("Reading global stats")?;
+        let wins = kv_stat(noita, &stats, "progress_ending0")?
+            + kv_stat(noita, &stats, "progress_ending1")?;
+        match self.watch.wins {
+            Some(start) if wins > start => {
+                self.split("Win");
+                self.finish(Outcome::Won);
+            }
+            Some(_) => {}
+            None => self.watch.wins = Some(wins),
+        }
+        Ok(())
+    }
+
+    fn splits_ui(&self, ui: &mut Ui, run: &Run) {
+        let pb = self.personal_best();
+        Grid::new("run_timer_splits")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, split) in run.splits.iter().enumerate() {
+                    ui.label(&split.name);
+                    ui.label(RichText::new(format_time(split.time)).monospace());
+                    match pb.and_then(|pb| pb.split_time(i, &split.name)) {
+                        Some(pb_time) => delta_label(ui, split.time - pb_time),
+                        None => {
+                            ui.label("");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+fn kv_stat(noita: &Noita, stats: &GlobalStats, name: &str) -> anyhow::Result<u32> {
+    Ok(stats
+        .key_value_stats
+        .get(noita.proc(), name)
+        .with_context(|| format!("Getting {name} stat"))?
+        .unwrap_or_default())
+}
+
+/// `m:ss.cc`, or `h:mm:ss.cc` for the long ones
+fn format_time(secs: f64) -> String {
+    let centis = (secs.max(0.0) * 100.0).round() as u64;
+    let (h, m, s, c) = (
+        centis / 360_000,
+        centis / 6000 % 60,
+        centis / 100 % 60,
+        centis % 100,
+    );
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}.{c:02}")
+    } else {
+        format!("{m}:{s:02}.{c:02}")
+    }
+}
+
+fn delta_label(ui: &mut Ui, delta: f64) {
+    let (sign, color) = if delta <= 0.0 {
+        ('-', Color32::from_rgb(80, 200, 80))
+    } else {
+        ('+', ui.visuals().error_fg_color)
+    };
+    let text = format!("{sign}{}", format_time(delta.abs()));
+    ui.label(RichText::new(text).monospace().color(color));
+}
+
+#[typetag::serde]
+impl Tool for RunTimer {
+    fn tick(&mut self, ctx: &Context, state: &mut AppState) {
+        for event in state.events.poll(&mut self.events) {
+            match event {
+                GameEvent::NewRun if self.auto_start => {
+                    self.start(state.seed.map(|s| s.to_string()));
+                }
+                GameEvent::PlayerDied => self.finish(Outcome::Died),
+                _ => {}
+            }
+        }
+
+        if self.current.is_none() || !state.game_advanced(&mut self.last_frame) {
+            return;
+        }
+        let Some(noita) = state.noita.as_mut() else {
+            return;
+        };
+        if let Err(e) = self.watch(noita) {
+            tracing::debug!(e = format!("{e:#}"), "Run timer update failed");
+        }
+        ctx.request_repaint();
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "auto-start" => self.auto_start = parse_switch(value)?,
+            "split-holy-mountain" => self.split_holy_mountain = parse_switch(value)?,
+            "split-sampo" => self.split_sampo = parse_switch(value)?,
+            "kv-splits" => self.kv_splits = value.into(),
+            "livesplit" => self.livesplit = parse_switch(value)?,
+            "livesplit-address" => self.livesplit_address = value.into(),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, state: &mut AppState) -> Result {
+        let shown = self.current.as_ref().or(self.history.last()).cloned();
+
+        ui.horizontal(|ui| {
+            let time = shown.as_ref().map_or(0.0, |r| r.time);
+            ui.label(RichText::new(format_time(time)).monospace().size(32.0));
+
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if self.current.is_some() {
+                    if ui.button("Reset").clicked() {
+                        self.finish(Outcome::Reset);
+                    }
+                    if ui.button("Split").clicked() {
+                        let n = self.current.as_ref().map_or(0, |r| r.splits.len()) + 1;
+                        self.split(format!("Split {n}"));
+                    }
+                } else if ui.button("Start").clicked() {
+                    self.start(state.seed.map(|s| s.to_string()));
+                }
+            });
+        });
+
+        match &shown {
+            Some(run) if run.outcome != Outcome::Running => {
+                ui.label(format!("{:?}", run.outcome));
+            }
+            None => {
+                ui.label("Start a new run, or press Start to time the current one");
+            }
+            _ => {}
+        }
+        if let Some(pb) = self.personal_best() {
+            let seed = pb.seed.as_deref().unwrap_or("-");
+            ui.label(format!(
+                "Personal best: {} (seed {seed})",
+                format_time(pb.time)
+            ));
+        }
+
+        if let Some(run) = &shown {
+            ui.separator();
+            self.splits_ui(ui, run);
+        }
+
+        ui.separator();
+        CollapsingHeader::new("Triggers").show(ui, |ui| {
+            ui.checkbox(&mut self.auto_start, "Start when a new run begins");
+            ui.checkbox(
+                &mut self.split_holy_mountain,
+                "Split on entering a Holy Mountain",
+            );
+            ui.checkbox(&mut self.split_sampo, "Split on picking up the Sampo");
+            ui.horizontal(|ui| {
+                ui.label("Split when these stats go up:");
+                ui.add(
+                    TextEdit::singleline(&mut self.kv_splits)
+                        .hint_text("stat names, comma-separated"),
+                );
+            });
+            ui.label("Death ends the run, the ending stats going up is a win");
+        });
+
+        CollapsingHeader::new("LiveSplit").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.livesplit, "Drive LiveSplit at");
+                ui.add_enabled(
+                    self.livesplit,
+                    TextEdit::singleline(&mut self.livesplit_address).desired_width(160.0),
+                );
+            });
+            let status = self.livesplit_client.as_ref().map(LiveSplit::status);
+            match status.filter(|_| self.livesplit) {
+                Some(Status::Connected) => {
+                    ui.label("Connected");
+                }
+                Some(Status::Error(e)) => {
+                    ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+                }
+                _ => {}
+            }
+            ui.label(
+                "Needs the LiveSplit Server component started, with the layout \
+                 comparing against game time",
+            );
+        });
+
+        CollapsingHeader::new(format!("History ({})", self.history.len())).show(ui, |ui| {
+            if ui.button("Clear").clicked() {
+                self.history.clear();
+            }
+            ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                Grid::new("run_timer_history")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for run in self.history.iter().rev() {
+                            ui.label(run.seed.as_deref().unwrap_or("-"));
+                            ui.label(format!("{:?}", run.outcome));
+                            ui.label(RichText::new(format_time(run.time)).monospace());
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn time_format() {
+    assert_eq!(format_time(0.0), "0:00.00");
+    assert_eq!(format_time(61.234), "1:01.23");
+    assert_eq!(format_time(3600.0 + 5.0), "1:00:05.00");
+}
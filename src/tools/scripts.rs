@@ -0,0 +1,173 @@
+use eframe::egui::{Button, CollapsingHeader, Context, Grid, RichText, TextEdit, Ui};
+use rhai::{Dynamic, AST};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+
+use crate::{
+    app::AppState,
+    cli::parse_switch,
+    scripting::{self, ScriptEngine},
+};
+
+use super::{Result, Tool};
+
+#[derive(Debug, Clone, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+struct Script {
+    #[default("Script")]
+    name: String,
+    #[default(true)]
+    enabled: bool,
+    #[default("#{ hp: hp(), gold: gold() }")]
+    source: String,
+}
+
+/// The runtime side of a [Script], recompiled when the source changes
+#[derive(Debug, Default)]
+struct Compiled {
+    source: String,
+    ast: Option<std::result::Result<AST, String>>,
+    result: Option<std::result::Result<Dynamic, String>>,
+}
+
+#[derive(Debug, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scripts {
+    #[default(true)]
+    enabled: bool,
+    #[default(vec![Script::default()])]
+    scripts: Vec<Script>,
+
+    #[serde(skip)]
+    engine: ScriptEngine,
+    #[serde(skip)]
+    compiled: Vec<Compiled>,
+    #[serde(skip)]
+    last_frame: Option<u32>,
+}
+
+impl Scripts {
+    /// Compile the scripts that changed, returns true if any did
+    fn compile(&mut self) -> bool {
+        self.compiled
+            .resize_with(self.scripts.len(), Default::default);
+        let mut changed = false;
+        for (script, compiled) in self.scripts.iter().zip(&mut self.compiled) {
+            if compiled.ast.is_some() && compiled.source == script.source {
+                continue;
+            }
+            compiled.source.clone_from(&script.source);
+            compiled.ast = Some(self.engine.compile(&script.source));
+            compiled.result = None;
+            changed = true;
+        }
+        changed
+    }
+}
+
+#[typetag::serde]
+impl Tool for Scripts {
+    fn tick(&mut self, _ctx: &Context, state: &mut AppState) {
+        if !self.enabled {
+            return;
+        }
+        let changed = self.compile();
+        if !state.game_advanced(&mut self.last_frame) && !changed {
+            return;
+        }
+        let game = state.game();
+        for (script, compiled) in self.scripts.iter().zip(&mut self.compiled) {
+            let Some(Ok(ast)) = &compiled.ast else {
+                continue;
+            };
+            if script.enabled {
+                compiled.result = Some(self.engine.run(ast, game.clone(), &mut state.noita));
+            }
+        }
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "enabled" => self.enabled = parse_switch(value)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, _state: &mut AppState) -> Result {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.enabled, "Run the scripts");
+            ui.label("❓").on_hover_text(scripting::HELP);
+        });
+
+        Grid::new("scripts_results")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (script, compiled) in self.scripts.iter().zip(&self.compiled) {
+                    if !script.enabled {
+                        continue;
+                    }
+                    match &compiled.result {
+                        Some(Ok(value)) => {
+                            for (i, (key, value)) in
+                                scripting::display(value).into_iter().enumerate()
+                            {
+                                match (i, key.is_empty()) {
+                                    (0, true) => ui.label(&script.name),
+                                    (_, true) => ui.label(""),
+                                    (_, false) => ui.label(format!("{}: {key}", script.name)),
+                                };
+                                ui.monospace(value);
+                                ui.end_row();
+                            }
+                        }
+                        Some(Err(e)) => {
+                            ui.label(&script.name);
+                            ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+                            ui.end_row();
+                        }
+                        None => {
+                            ui.label(&script.name);
+                            ui.weak("-");
+                            ui.end_row();
+                        }
+                    }
+                }
+            });
+
+        ui.separator();
+        let mut removed = None;
+        CollapsingHeader::new("Edit scripts").show(ui, |ui| {
+            for (i, script) in self.scripts.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut script.enabled, "");
+                    ui.add(TextEdit::singleline(&mut script.name).desired_width(120.0));
+                    if ui.add(Button::new("🗑").small()).clicked() {
+                        removed = Some(i);
+                    }
+                });
+                ui.add(
+                    TextEdit::multiline(&mut script.source)
+                        .code_editor()
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY),
+                );
+                if let Some(Some(Err(e))) = self.compiled.get(i).map(|c| &c.ast) {
+                    ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+                }
+            }
+            if ui.button("Add a script").clicked() {
+                self.scripts.push(Script::default());
+            }
+        });
+        if let Some(i) = removed {
+            self.scripts.remove(i);
+            if i < self.compiled.len() {
+                self.compiled.remove(i);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,33 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use anyhow::Context as _;
 use eframe::egui::{
-    self, Checkbox, CollapsingHeader, DragValue, FontId, Grid, Label, RichText, ScrollArea,
-    TextStyle, Ui,
+    Button, Checkbox, CollapsingHeader, ComboBox, DragValue, FontId, Grid, Label, RichText,
+    ScrollArea, Slider, TextEdit, TextStyle, Ui,
 };
+use noita_utility_box::memory::RetryPolicy;
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
 
-use crate::{app::AppState, update_check::RELEASE_VERSION};
+use crate::{
+    app::AppState,
+    cli::parse_switch,
+    file_output::FileOutputSettings,
+    i18n::{tr, Language},
+    mini_mode::MiniModeSettings,
+    overlay::OverlaySettings,
+    palette::PaletteKind,
+    server::ServerSettings,
+    settings_file::{self, TransferUi},
+    sound::{self, Alert, SoundSettings},
+    theme::{ThemeMode, ThemeSettings},
+    update_check::RELEASE_VERSION,
+};
+
+use super::{Result, Tool, ToolInfo, TOOLS};
 
-use super::{Result, Tool};
+/// About a frame at 60 FPS, no point in updating any faster than the game
+const MIN_TICK_INTERVAL: Duration = Duration::from_millis(16);
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Settings;
@@ -17,15 +37,189 @@ pub struct Settings;
 pub struct SettingsData {
     #[default(0.5)]
     pub background_update_interval: f32,
+    /// Seconds between the background updates of the tools, by tool id, the
+    /// missing ones use the background update interval
+    pub tool_intervals: BTreeMap<String, f32>,
+    /// Stretch all the update intervals, to go easier on the CPU
+    pub low_power_mode: bool,
+    #[default(4.0)]
+    pub low_power_multiplier: f32,
     #[default(true)]
     pub check_for_updates: bool,
     #[default(true)]
     pub notify_when_outdated: bool,
     #[default(true)]
     pub check_export_name: bool,
+    pub show_perf_overlay: bool,
+    #[default(true)]
+    pub cache_reads: bool,
+    /// How many times to retry the reads that failed for a transient reason
+    pub read_retries: u32,
+    /// Milliseconds before the first retry, doubled for each next one
+    #[default(1.0)]
+    pub read_retry_backoff: f32,
+
+    /// Play a sound when the player gets close to an orb the radar found
+    pub orb_chime: bool,
+    /// In pixels
+    #[default(200.0)]
+    pub orb_chime_distance: f32,
+
+    /// Record the player path for the orb radar and the map viewer
+    #[default(true)]
+    pub record_trail: bool,
+    /// Minimum distance between the trail points, in pixels
+    #[default(16.0)]
+    pub trail_resolution: f32,
+    /// How many points the trail keeps before dropping the oldest ones
+    #[default(20000)]
+    pub trail_length: usize,
+
+    /// Save a screenshot of the game window when the player dies
+    pub screenshot_on_death: bool,
+
+    pub overlay: OverlaySettings,
+    pub mini_mode: MiniModeSettings,
+    /// Text files with the game values, for stream overlays
+    pub file_output: FileOutputSettings,
+    /// Local WebSocket and HTTP server for Stream Deck plugins, overlays and
+    /// scripts
+    pub server: ServerSettings,
+    pub sounds: SoundSettings,
+    pub theme: ThemeSettings,
+    pub language: Language,
+    /// Colors of the radar and map tools
+    pub palette: PaletteKind,
+
+    /// Tool ids in the user-defined order, missing ones go after those in the
+    /// default order
+    pub tool_order: Vec<String>,
+    pub pinned_tools: Vec<String>,
+    /// Tool ids left out of the ➕ menu until shown again here
+    pub hidden_tools: Vec<String>,
 
     #[serde(skip)]
     pub newest_version: Option<String>,
+    #[serde(skip)]
+    pub transfer: TransferUi,
+}
+
+impl SettingsData {
+    pub fn is_pinned(&self, info: &ToolInfo) -> bool {
+        self.pinned_tools.iter().any(|id| id == info.id)
+    }
+
+    pub fn is_hidden(&self, info: &ToolInfo) -> bool {
+        self.hidden_tools.iter().any(|id| id == info.id)
+    }
+
+    /// Sort key for the tool list - pinned first, then the user order, then
+    /// the default order
+    pub fn tool_rank(&self, info: &ToolInfo) -> (bool, usize, usize) {
+        let user_pos = self.tool_order.iter().position(|id| id == info.id);
+        let default_pos = TOOLS.iter().position(|t| t.id == info.id);
+        (
+            !self.is_pinned(info),
+            user_pos.unwrap_or(usize::MAX),
+            default_pos.unwrap_or(usize::MAX),
+        )
+    }
+
+    /// Apply a command-line override, returns false if there's no such setting
+    pub fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "background-update-interval" => {
+                self.background_update_interval = value
+                    .parse()
+                    .with_context(|| format!("Expected a number of seconds, got {value}"))?
+            }
+            "low-power-mode" => self.low_power_mode = parse_switch(value)?,
+            "check-for-updates" => self.check_for_updates = parse_switch(value)?,
+            "notify-when-outdated" => self.notify_when_outdated = parse_switch(value)?,
+            "check-export-name" => self.check_export_name = parse_switch(value)?,
+            "perf-overlay" => self.show_perf_overlay = parse_switch(value)?,
+            "cache-reads" => self.cache_reads = parse_switch(value)?,
+            "orb-chime" => self.orb_chime = parse_switch(value)?,
+            "orb-chime-distance" => {
+                self.orb_chime_distance = value
+                    .parse()
+                    .with_context(|| format!("Expected a number of pixels, got {value}"))?
+            }
+            "mini-mode" => self.mini_mode.enabled = parse_switch(value)?,
+            "overlay" => self.overlay.enabled = parse_switch(value)?,
+            "file-output" => self.file_output.enabled = parse_switch(value)?,
+            "file-output-dir" => self.file_output.dir = value.into(),
+            "server" => self.server.enabled = parse_switch(value)?,
+            "server-port" => {
+                self.server.port = value
+                    .parse()
+                    .with_context(|| format!("Expected a port number, got {value}"))?
+            }
+            "record-trail" => self.record_trail = parse_switch(value)?,
+            "screenshot-on-death" => self.screenshot_on_death = parse_switch(value)?,
+            "trail-resolution" => {
+                self.trail_resolution = value
+                    .parse()
+                    .with_context(|| format!("Expected a number of pixels, got {value}"))?
+            }
+            "trail-length" => {
+                self.trail_length = value
+                    .parse()
+                    .with_context(|| format!("Expected a number of points, got {value}"))?
+            }
+            "read-retries" => {
+                self.read_retries = value
+                    .parse()
+                    .with_context(|| format!("Expected a number of retries, got {value}"))?
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            retries: self.read_retries,
+            backoff: Duration::from_secs_f32(self.read_retry_backoff.max(0.0) / 1000.0),
+        }
+    }
+
+    fn power_multiplier(&self) -> f32 {
+        if self.low_power_mode {
+            self.low_power_multiplier.max(1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// How often the tool should get a background update
+    pub fn tick_interval(&self, info: &ToolInfo) -> Duration {
+        let secs = self
+            .tool_intervals
+            .get(info.id)
+            .copied()
+            .unwrap_or(self.background_update_interval);
+        Duration::from_secs_f32((secs * self.power_multiplier()).max(0.0))
+    }
+
+    /// How long the background update loop sleeps - just enough for the tool
+    /// that wants the most frequent updates
+    pub fn background_sleep(&self) -> Duration {
+        let base = self.background_update_interval.max(0.0) * self.power_multiplier();
+        TOOLS
+            .iter()
+            .map(|info| self.tick_interval(info))
+            .chain([Duration::from_secs_f32(base)])
+            .min()
+            .unwrap_or_default()
+            .max(MIN_TICK_INTERVAL)
+    }
+
+    pub fn sorted_tools(&self) -> Vec<&'static ToolInfo> {
+        let mut tools = TOOLS.to_vec();
+        tools.sort_by_key(|info| self.tool_rank(info));
+        tools
+    }
 }
 
 #[typetag::serde]
@@ -38,8 +232,6 @@ impl Tool for Settings {
 
 impl Settings {
     pub fn ui(&mut self, ui: &mut Ui, state: &mut AppState) {
-        let s = &mut state.settings;
-
         ui.horizontal(|ui| {
             ui.spacing_mut().item_spacing.x = 0.0;
 
@@ -60,7 +252,7 @@ impl Settings {
             let info = RichText::new(label).font(font);
             ui.hyperlink_to(info, url.clone()).on_hover_text(url);
 
-            if let Some(s) = &s.newest_version {
+            if let Some(s) = &state.settings.newest_version {
                 let text = RichText::new(format!(" (latest: {s})"))
                     .small()
                     .color(ui.style().visuals.weak_text_color());
@@ -71,18 +263,65 @@ impl Settings {
         ui.separator();
 
         ScrollArea::vertical().show(ui, |ui| {
-            ui.label("Note: You can permanently scale the UI with Ctrl+- and Ctrl+=");
+            let s = &mut state.settings;
 
-            ui.horizontal(|ui| {
-                ui.label("Also, click this:");
-                egui::global_theme_preference_switch(ui);
+            Grid::new("theme").show(ui, |ui| {
+                ui.label(tr("Language"));
+                ComboBox::from_id_salt("language")
+                    .selected_text(s.language.native_name())
+                    .show_ui(ui, |ui| {
+                        for lang in Language::ALL {
+                            ui.selectable_value(&mut s.language, lang, lang.native_name());
+                        }
+                    });
+                ui.end_row();
+
+                let t = &mut s.theme;
+                ui.label(tr("Theme"));
+                ComboBox::from_id_salt("theme_mode")
+                    .selected_text(t.mode.name())
+                    .show_ui(ui, |ui| {
+                        for mode in ThemeMode::ALL {
+                            ui.selectable_value(&mut t.mode, mode, mode.name());
+                        }
+                    });
+                ui.end_row();
+
+                let mut custom = t.accent.is_some();
+                ui.checkbox(&mut custom, tr("Custom accent color"))
+                    .on_hover_text(tr("Used for the selections and links, e.g. to match your stream overlay"));
+                if !custom {
+                    t.accent = None;
+                } else if t.accent.is_none() {
+                    t.accent = Some([0x90, 0xd1, 0xff]);
+                }
+                if let Some(accent) = &mut t.accent {
+                    ui.color_edit_button_srgb(accent);
+                }
+                ui.end_row();
+
+                ui.label(tr("UI scale"))
+                    .on_hover_text(tr("Ctrl+- and Ctrl+= change it too"));
+                ui.add(Slider::new(&mut t.ui_scale, ThemeSettings::SCALE_RANGE).step_by(0.05));
+                ui.end_row();
+
+                ui.label(tr("Map colors"))
+                    .on_hover_text(tr("Colors of the orb radar and the map viewer, the presets keep apart what red-green color blindness mixes up"));
+                ComboBox::from_id_salt("palette")
+                    .selected_text(s.palette.name())
+                    .show_ui(ui, |ui| {
+                        for palette in PaletteKind::ALL {
+                            ui.selectable_value(&mut s.palette, palette, palette.name());
+                        }
+                    });
+                ui.end_row();
             });
 
             ui.separator();
 
             Grid::new("settings").show(ui, |ui| {
                 ui.label("Background updates interval")
-                    .on_hover_text("How often the background updates run (used by live stats and noita process auto-detection)");
+                    .on_hover_text("How often the background updates run (used by live stats and noita process auto-detection), tools can override it in the tool list below");
                 ui.add(
                     DragValue::new(&mut s.background_update_interval)
                         .range(0.0..=60.0)
@@ -91,6 +330,17 @@ impl Settings {
                 );
                 ui.end_row();
 
+                ui.checkbox(&mut s.low_power_mode, "Low power mode")
+                    .on_hover_text("Make all the background updates that many times less frequent");
+                ui.add_enabled(
+                    s.low_power_mode,
+                    DragValue::new(&mut s.low_power_multiplier)
+                        .range(1.0..=20.0)
+                        .speed(0.1)
+                        .prefix("x"),
+                );
+                ui.end_row();
+
                 if RELEASE_VERSION.is_some() {
                     ui.checkbox(&mut s.check_for_updates, "Check for updates on startup")
                         .on_hover_text("This makes one request to the GitHub API on startup to check the latest release version");
@@ -111,8 +361,183 @@ impl Settings {
                 ui.checkbox(&mut s.check_export_name, "Check export name")
                     .on_hover_text("When detecting noita, check that the executable export name is 'wizard_physics.exe'");
                 ui.end_row();
+
+                ui.checkbox(&mut s.show_perf_overlay, "Show performance overlay")
+                    .on_hover_text("Shows how long each tool takes to update and draw, and how many memory reads it does");
+                ui.end_row();
+
+                ui.checkbox(&mut s.cache_reads, "Cache game reads per frame")
+                    .on_hover_text("Tools share the player, world and component reads until the game advances a frame");
+                ui.end_row();
+
+                ui.label("Read retries")
+                    .on_hover_text("How many times to retry a memory read that failed for a transient reason, like Windows being short on memory quota");
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(&mut s.read_retries).range(0..=5));
+                    ui.add_enabled(
+                        s.read_retries > 0,
+                        DragValue::new(&mut s.read_retry_backoff)
+                            .range(0.0..=100.0)
+                            .speed(0.1)
+                            .prefix("backoff ")
+                            .suffix(" ms"),
+                    )
+                    .on_hover_text("Delay before the first retry, doubled for each next one");
+                });
+                ui.end_row();
+
+                ui.checkbox(&mut s.orb_chime, "Orb proximity chime")
+                    .on_hover_text("Play a sound when you get close to an orb found by the orb radar, even when its tab is not visible");
+                ui.add_enabled(
+                    s.orb_chime,
+                    DragValue::new(&mut s.orb_chime_distance)
+                        .range(10.0..=2000.0)
+                        .speed(1.0)
+                        .prefix("within ")
+                        .suffix(" px"),
+                );
+                ui.end_row();
+
+                ui.checkbox(&mut s.record_trail, "Record player trail")
+                    .on_hover_text("Remember the path you took, drawn on the orb radar and the map viewer");
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(s.record_trail, |ui| {
+                        ui.add(
+                            DragValue::new(&mut s.trail_resolution)
+                                .range(1.0..=512.0)
+                                .speed(1.0)
+                                .prefix("every ")
+                                .suffix(" px"),
+                        );
+                        ui.add(
+                            DragValue::new(&mut s.trail_length)
+                                .range(100..=1_000_000)
+                                .speed(100.0)
+                                .prefix("up to ")
+                                .suffix(" points"),
+                        );
+                        if ui.button("Clear").clicked() {
+                            state.trail.clear();
+                        }
+                    });
+                });
+                ui.end_row();
+
+                ui.checkbox(&mut s.screenshot_on_death, "Screenshot on death")
+                    .on_hover_text("Save a screenshot of the game when you die, into the screenshots folder next to the settings");
+                if cfg!(not(windows)) {
+                    ui.label("needs xdotool and ImageMagick");
+                }
+                ui.end_row();
+
+                let o = &mut s.overlay;
+                ui.checkbox(&mut o.enabled, "In-game overlay")
+                    .on_hover_text("A small borderless window to put on top of the game, click-through while locked");
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(o.enabled, |ui| {
+                        ui.checkbox(&mut o.locked, "Locked")
+                            .on_hover_text("Unlock to drag the overlay around");
+                        ui.checkbox(&mut o.orb_direction, "Orb");
+                        ui.checkbox(&mut o.shift_timer, "Shift timer");
+                        ui.checkbox(&mut o.hp, "HP");
+                    });
+                });
+                ui.end_row();
+
+                let m = &mut s.mini_mode;
+                ui.checkbox(&mut m.enabled, "Mini mode")
+                    .on_hover_text("Collapse the window into a small always-on-top strip with the selected values");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut m.seed, "Seed");
+                    ui.checkbox(&mut m.hp, "HP");
+                    ui.checkbox(&mut m.gold, "Gold");
+                    ui.checkbox(&mut m.shift_timer, "Shift timer");
+                });
+                ui.end_row();
+
+                let f = &mut s.file_output;
+                ui.checkbox(&mut f.enabled, "Text file outputs")
+                    .on_hover_text("Write the selected values into text files whenever they change, for OBS text sources to read");
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(f.enabled, |ui| {
+                        ui.checkbox(&mut f.seed, "Seed");
+                        ui.checkbox(&mut f.hp, "HP");
+                        ui.checkbox(&mut f.gold, "Gold");
+                        ui.checkbox(&mut f.shift_timer, "Shift timer");
+                        ui.checkbox(&mut f.orbs, "Orbs");
+                    });
+                });
+                ui.end_row();
+
+                if f.enabled {
+                    ui.label("Output folder");
+                    ui.vertical(|ui| {
+                        let hint = f.dir().map(|d| d.display().to_string()).unwrap_or_default();
+                        // applied when done typing, not to create a folder on every keystroke
+                        let id = ui.id().with("file_output_dir");
+                        let mut dir = ui
+                            .data_mut(|d| d.get_temp::<String>(id))
+                            .unwrap_or_else(|| f.dir.clone());
+                        let edit = ui.add(TextEdit::singleline(&mut dir).hint_text(hint));
+                        if edit.lost_focus() {
+                            f.dir = dir;
+                            ui.data_mut(|d| d.remove::<String>(id));
+                        } else if edit.has_focus() {
+                            ui.data_mut(|d| d.insert_temp(id, dir));
+                        }
+                        if let Some(e) = &state.file_output.error {
+                            ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+                        }
+                    });
+                    ui.end_row();
+                }
+
+                let srv = &mut s.server;
+                ui.checkbox(&mut srv.enabled, "Local server")
+                    .on_hover_text("A server on localhost with the live values over WebSocket and a read-only HTTP API, for Stream Deck plugins, overlays and scripts");
+                ui.horizontal(|ui| {
+                    ui.add_enabled(srv.enabled, DragValue::new(&mut srv.port).prefix("port "));
+                    match state.server.status() {
+                        Some(Ok(address)) => {
+                            ui.label(address);
+                        }
+                        Some(Err(e)) => {
+                            ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+                        }
+                        None => {}
+                    }
+                });
+                ui.end_row();
+
+                for alert in Alert::ALL {
+                    ui.label(format!("{} sound", alert.name()));
+                    ui.horizontal(|ui| {
+                        let volume = s.sounds.volume_mut(alert);
+                        ui.add(Slider::new(volume, 0.0..=1.0).text("volume"));
+                        if ui.button("▶").on_hover_text("Play it").clicked() {
+                            sound::play_at(alert, volume.max(0.1));
+                        }
+                    });
+                    ui.end_row();
+                }
             });
 
+            CollapsingHeader::new(tr("Tools"))
+                .default_open(true)
+                .show(ui, |ui| {
+                    tool_list_ui(ui, state);
+                    if ui
+                        .button(tr("Reset layout"))
+                        .on_hover_text(tr("Put the open tools back into the default tabs, undoing the splits and moves"))
+                        .clicked()
+                    {
+                        state.reset_layout();
+                    }
+                });
+
+            CollapsingHeader::new(tr("Export and import"))
+                .show(ui, |ui| transfer_ui(ui, state));
+
             CollapsingHeader::new("egui").show(ui, |ui| {
                 let prev_options = ui.ctx().options(|o| o.clone());
                 let mut options = prev_options.clone();
@@ -126,3 +551,133 @@ impl Settings {
         });
     }
 }
+
+fn tool_list_ui(ui: &mut Ui, state: &mut AppState) {
+    let mut tools = state.settings.sorted_tools();
+    let mut swap = None;
+
+    Grid::new("tool_list").show(ui, |ui| {
+        for i in 0..tools.len() {
+            let info = tools[i];
+            let pinned = state.settings.is_pinned(info);
+
+            let mut shown = !state.settings.is_hidden(info);
+            if ui
+                .checkbox(&mut shown, info.display_title())
+                .on_hover_text(tr(
+                    "Uncheck to hide the tool, it's left out of the ➕ menu until checked again",
+                ))
+                .changed()
+            {
+                if shown {
+                    state.settings.hidden_tools.retain(|id| id != info.id);
+                } else {
+                    state.settings.hidden_tools.push(info.id.to_owned());
+                }
+                state.set_tool_visible(info, shown);
+            }
+
+            if ui
+                .selectable_label(pinned, "⭐")
+                .on_hover_text(tr("Pin to the top of the tool list"))
+                .clicked()
+            {
+                if pinned {
+                    state.settings.pinned_tools.retain(|id| id != info.id);
+                } else {
+                    state.settings.pinned_tools.push(info.id.to_owned());
+                }
+            }
+
+            // only allow moving within the pinned/unpinned group
+            let same_group = |j: usize| state.settings.is_pinned(tools[j]) == pinned;
+
+            let can_go_up = i > 0 && same_group(i - 1);
+            let can_go_down = i + 1 < tools.len() && same_group(i + 1);
+
+            let up = ui.add_enabled(can_go_up, Button::new("⏶")).clicked();
+            let down = ui.add_enabled(can_go_down, Button::new("⏷")).clicked();
+            if up {
+                swap = Some((i, i - 1));
+            } else if down {
+                swap = Some((i, i + 1));
+            }
+
+            interval_ui(ui, &mut state.settings, info);
+            ui.end_row();
+        }
+    });
+
+    if let Some((a, b)) = swap {
+        tools.swap(a, b);
+        state.settings.tool_order = tools.iter().map(|info| info.id.to_owned()).collect();
+    }
+}
+
+/// The background update interval override of a tool
+fn interval_ui(ui: &mut Ui, settings: &mut SettingsData, info: &ToolInfo) {
+    ui.horizontal(|ui| {
+        let custom = settings.tool_intervals.get(info.id).copied();
+        let mut secs = custom.unwrap_or(settings.background_update_interval);
+        let response = ui
+            .add(
+                DragValue::new(&mut secs)
+                    .range(0.0..=60.0)
+                    .speed(0.02)
+                    .prefix("every ")
+                    .suffix(" s"),
+            )
+            .on_hover_text("How often the tool updates in the background, 0 is every frame");
+        if response.changed() {
+            settings.tool_intervals.insert(info.id.to_owned(), secs);
+        }
+        if custom.is_some()
+            && ui
+                .small_button("↺")
+                .on_hover_text("Back to the background updates interval")
+                .clicked()
+        {
+            settings.tool_intervals.remove(info.id);
+        }
+    });
+}
+
+fn transfer_ui(ui: &mut Ui, state: &mut AppState) {
+    ui.label(tr(
+        "All the settings, tool configs and address maps in one file",
+    ));
+    let t = &mut state.settings.transfer;
+    ui.add(
+        TextEdit::singleline(&mut t.path)
+            .hint_text(tr("File path, exports go into the storage dir if empty")),
+    );
+    ui.checkbox(&mut t.include_secrets, tr("Include tokens and passwords"))
+        .on_hover_text(tr("Leave this off for the files you share, importing a file without them keeps the current ones"));
+
+    let path = t.path.trim();
+    let mut request = None;
+    ui.horizontal(|ui| {
+        if ui.button(tr("Export settings")).clicked() {
+            let path = match path {
+                "" => settings_file::default_path(),
+                path => Ok(path.into()),
+            };
+            request = Some(path.map(|path| settings_file::Request::Export {
+                path,
+                include_secrets: t.include_secrets,
+            }));
+        }
+        if ui
+            .add_enabled(!path.is_empty(), Button::new(tr("Import settings")))
+            .on_hover_text(tr("Replaces all the current settings and tool configs"))
+            .clicked()
+        {
+            request = Some(Ok(settings_file::Request::Import(path.into())));
+        }
+    });
+    match request {
+        Some(Ok(request)) => state.request_settings_file(request),
+        Some(Err(e)) => state.toast_error(format!("Export failed: {e:#}")),
+        None => {}
+    }
+}
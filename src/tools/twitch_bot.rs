@@ -0,0 +1,436 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context as _;
+use eframe::egui::{Button, Context, DragValue, Grid, RichText, TextEdit, Ui};
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use smart_default::SmartDefault;
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{app::AppState, cli::parse_switch, readout};
+
+use super::{Result, Tool};
+
+const TWITCH_IRC: &str = "wss://irc-ws.chat.twitch.tv:443";
+
+/// How many shifts `!shifts` lists
+const SHIFTS_SHOWN: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChatCommand {
+    Wands,
+    Shifts,
+    Seed,
+    Hp,
+}
+
+impl ChatCommand {
+    const ALL: [ChatCommand; 4] = [
+        ChatCommand::Wands,
+        ChatCommand::Shifts,
+        ChatCommand::Seed,
+        ChatCommand::Hp,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ChatCommand::Wands => "!wands",
+            ChatCommand::Shifts => "!shifts",
+            ChatCommand::Seed => "!seed",
+            ChatCommand::Hp => "!hp",
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let word = text.split_whitespace().next()?;
+        Self::ALL
+            .into_iter()
+            .find(|c| c.name().eq_ignore_ascii_case(word))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SmartDefault)]
+#[serde(default)]
+struct CommandSettings {
+    #[default(true)]
+    enabled: bool,
+    /// Seconds before the command answers again
+    #[default(30.0)]
+    cooldown: f32,
+}
+
+/// A chat line from the IRC connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ChatMessage {
+    user: String,
+    text: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+enum ChatStatus {
+    #[default]
+    Connecting,
+    Joined,
+    Error(String),
+}
+
+/// The IRC connection task, aborted when dropped
+#[derive(Debug)]
+struct ChatConnection {
+    incoming: UnboundedReceiver<ChatMessage>,
+    outgoing: UnboundedSender<String>,
+    status: Arc<Mutex<ChatStatus>>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for ChatConnection {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl ChatConnection {
+    fn spawn(channel: String, username: String, token: String) -> Self {
+        let (in_tx, incoming) = mpsc::unbounded_channel();
+        let (outgoing, out_rx) = mpsc::unbounded_channel();
+        let status = Arc::new(Mutex::new(ChatStatus::Connecting));
+
+        let task_status = status.clone();
+        let task = tokio::spawn(async move {
+            let result = run_chat(&channel, &username, &token, in_tx, out_rx, &task_status).await;
+            let error = match result {
+                Ok(()) => "Disconnected".into(),
+                Err(e) => format!("{e:#}"),
+            };
+            tracing::warn!(error, "Twitch chat connection ended");
+            *task_status.lock().unwrap() = ChatStatus::Error(error);
+        });
+
+        Self {
+            incoming,
+            outgoing,
+            status,
+            task,
+        }
+    }
+
+    fn status(&self) -> ChatStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+async fn run_chat(
+    channel: &str,
+    username: &str,
+    token: &str,
+    incoming: UnboundedSender<ChatMessage>,
+    mut outgoing: UnboundedReceiver<String>,
+    status: &Mutex<ChatStatus>,
+) -> anyhow::Result<()> {
+    let (ws, _) = tokio_tungstenite::connect_async(TWITCH_IRC)
+        .await
+        .context("Connecting to Twitch chat")?;
+    let (mut tx, mut rx) = ws.split();
+
+    let token = token.trim_start_matches("oauth:");
+    for line in [
+        format!("PASS oauth:{token}"),
+        format!("NICK {}", username.to_lowercase()),
+        format!("JOIN #{channel}"),
+    ] {
+        tx.send(Message::text(line)).await?;
+    }
+
+    loop {
+        tokio::select! {
+            message = rx.next() => {
+                let Some(message) = message else {
+                    return Ok(());
+                };
+                let Message::Text(text) = message? else {
+                    continue;
+                };
+                for line in text.lines() {
+                    if line.starts_with("PING") {
+                        tx.send(Message::text(line.replacen("PING", "PONG", 1))).await?;
+                    } else if line.contains(" NOTICE * :Login authentication failed") {
+                        anyhow::bail!("Login authentication failed, check the token");
+                    } else if line.contains(" 366 ") {
+                        // end of the names list, sent after a successful join
+                        *status.lock().unwrap() = ChatStatus::Joined;
+                    } else if let Some(message) = parse_privmsg(line) {
+                        // the receiver is gone if the tool was closed
+                        let _ = incoming.send(message);
+                    }
+                }
+            }
+            reply = outgoing.recv() => {
+                let Some(reply) = reply else {
+                    return Ok(());
+                };
+                tx.send(Message::text(format!("PRIVMSG #{channel} :{reply}"))).await?;
+            }
+        }
+    }
+}
+
+/// Parse `:user!user@user.tmi.twitch.tv PRIVMSG #channel :text`
+fn parse_privmsg(line: &str) -> Option<ChatMessage> {
+    let rest = line.strip_prefix(':')?;
+    let (prefix, rest) = rest.split_once(' ')?;
+    let rest = rest.strip_prefix("PRIVMSG ")?;
+    let (_channel, text) = rest.split_once(" :")?;
+    let user = prefix.split('!').next()?;
+    Some(ChatMessage {
+        user: user.to_lowercase(),
+        text: text.trim_end().to_owned(),
+    })
+}
+
+/// Answers a few chat commands about the current run in Twitch chat
+#[derive(Debug, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TwitchBot {
+    /// Channel to join, without the #
+    channel: String,
+    /// Account the bot talks as, can be the streamer account
+    username: String,
+    /// OAuth token of that account, with the chat:read and chat:edit scopes
+    token: String,
+    /// Connect on startup if it was connected when the app closed
+    was_connected: bool,
+    commands: BTreeMap<String, CommandSettings>,
+    /// Comma-separated usernames allowed to use the commands, empty for
+    /// everyone
+    allowlist: String,
+
+    #[serde(skip)]
+    connection: Option<ChatConnection>,
+    #[serde(skip)]
+    last_used: BTreeMap<&'static str, Instant>,
+}
+
+impl TwitchBot {
+    fn connect(&mut self) {
+        self.connection = Some(ChatConnection::spawn(
+            self.channel.trim().trim_start_matches('#').to_lowercase(),
+            self.username.trim().to_owned(),
+            self.token.trim().to_owned(),
+        ));
+        self.was_connected = true;
+    }
+
+    fn disconnect(&mut self) {
+        self.connection = None;
+        self.was_connected = false;
+    }
+
+    fn command_settings(&mut self, command: ChatCommand) -> &mut CommandSettings {
+        self.commands.entry(command.name().into()).or_default()
+    }
+
+    fn is_allowed(&self, user: &str) -> bool {
+        let mut allowlist = self
+            .allowlist
+            .split(',')
+            .map(|u| u.trim().trim_start_matches('@'))
+            .filter(|u| !u.is_empty())
+            .peekable();
+        allowlist.peek().is_none() || allowlist.any(|u| u.eq_ignore_ascii_case(user))
+    }
+
+    /// Returns the reply if the command should be answered
+    fn answer(&mut self, message: &ChatMessage, state: &mut AppState) -> Option<String> {
+        let command = ChatCommand::parse(&message.text)?;
+        if !self.is_allowed(&message.user) {
+            return None;
+        }
+        let settings = self.command_settings(command).clone();
+        if !settings.enabled {
+            return None;
+        }
+        let cooldown = Duration::from_secs_f32(settings.cooldown.max(0.0));
+        if self
+            .last_used
+            .get(command.name())
+            .is_some_and(|t| t.elapsed() < cooldown)
+        {
+            return None;
+        }
+        self.last_used.insert(command.name(), Instant::now());
+
+        let reply = match command {
+            ChatCommand::Seed => match state.seed {
+                Some(seed) => format!("seed: {seed}"),
+                None => "seed: not in a run".into(),
+            },
+            ChatCommand::Hp => state.game().hp_text(),
+            ChatCommand::Wands => match state.noita.as_mut() {
+                Some(noita) => readout::wands(noita)
+                    .map(|wands| format!("wands: {}", readout::wands_text(&wands)))
+                    .unwrap_or_else(|e| {
+                        tracing::debug!(e = format!("{e:#}"), "Failed to read the wands");
+                        "wands: couldn't read them".into()
+                    }),
+                None => "wands: not connected to the game".into(),
+            },
+            ChatCommand::Shifts => match &state.noita {
+                Some(noita) => readout::upcoming_shifts(noita, SHIFTS_SHOWN)
+                    .map(|shifts| format!("next shifts: {}", readout::shifts_text(&shifts)))
+                    .unwrap_or_else(|e| {
+                        tracing::debug!(e = format!("{e:#}"), "Failed to predict the shifts");
+                        "shifts: couldn't predict them".into()
+                    }),
+                None => "shifts: not connected to the game".into(),
+            },
+        };
+        Some(format!("@{} {reply}", message.user))
+    }
+}
+
+#[typetag::serde]
+impl Tool for TwitchBot {
+    fn tick(&mut self, _ctx: &Context, state: &mut AppState) {
+        if self.was_connected && self.connection.is_none() && !self.token.is_empty() {
+            self.connect();
+        }
+        let Some(connection) = &mut self.connection else {
+            return;
+        };
+        let mut messages = Vec::new();
+        while let Ok(message) = connection.incoming.try_recv() {
+            messages.push(message);
+        }
+        for message in messages {
+            if let Some(reply) = self.answer(&message, state) {
+                if let Some(connection) = &self.connection {
+                    let _ = connection.outgoing.send(reply);
+                }
+            }
+        }
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "channel" => self.channel = value.into(),
+            "username" => self.username = value.into(),
+            "token" => self.token = value.into(),
+            "allowlist" => self.allowlist = value.into(),
+            "connect" => {
+                if parse_switch(value)? {
+                    self.connect();
+                } else {
+                    self.disconnect();
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, _state: &mut AppState) -> Result {
+        let connected = self.connection.is_some();
+        Grid::new("twitch_bot").num_columns(2).show(ui, |ui| {
+            ui.label("Channel");
+            ui.add_enabled(!connected, TextEdit::singleline(&mut self.channel));
+            ui.end_row();
+
+            ui.label("Bot account");
+            ui.add_enabled(!connected, TextEdit::singleline(&mut self.username));
+            ui.end_row();
+
+            ui.label("OAuth token");
+            ui.add_enabled(
+                !connected,
+                TextEdit::singleline(&mut self.token).password(true),
+            )
+            .on_hover_text("Needs the chat:read and chat:edit scopes");
+            ui.end_row();
+
+            ui.label("Allowed users");
+            ui.add(
+                TextEdit::singleline(&mut self.allowlist)
+                    .hint_text("comma-separated, empty for everyone"),
+            );
+            ui.end_row();
+        });
+
+        ui.horizontal(|ui| match &self.connection {
+            None => {
+                let ready = !self.channel.trim().is_empty()
+                    && !self.username.trim().is_empty()
+                    && !self.token.trim().is_empty();
+                if ui.add_enabled(ready, Button::new("Connect")).clicked() {
+                    self.connect();
+                }
+            }
+            Some(connection) => {
+                let status = connection.status();
+                if ui.button("Disconnect").clicked() {
+                    self.disconnect();
+                }
+                match status {
+                    ChatStatus::Connecting => {
+                        ui.spinner();
+                    }
+                    ChatStatus::Joined => {
+                        ui.label("Connected");
+                    }
+                    ChatStatus::Error(e) => {
+                        ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+                        if ui.button("Reconnect").clicked() {
+                            self.connect();
+                        }
+                    }
+                }
+            }
+        });
+
+        ui.separator();
+        Grid::new("twitch_bot_commands")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for command in ChatCommand::ALL {
+                    let settings = self.command_settings(command);
+                    ui.checkbox(&mut settings.enabled, command.name());
+                    ui.add_enabled(
+                        settings.enabled,
+                        DragValue::new(&mut settings.cooldown)
+                            .range(0.0..=3600.0)
+                            .speed(1.0)
+                            .prefix("cooldown ")
+                            .suffix(" s"),
+                    );
+                    ui.end_row();
+                }
+            });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn irc_privmsg() {
+    let line = ":someone!someone@someone.tmi.twitch.tv PRIVMSG #streamer :!seed please\r";
+    assert_eq!(
+        parse_privmsg(line),
+        Some(ChatMessage {
+            user: "someone".into(),
+            text: "!seed please".into(),
+        })
+    );
+    assert_eq!(ChatCommand::parse("!SEED please"), Some(ChatCommand::Seed));
+    assert_eq!(
+        parse_privmsg(":tmi.twitch.tv 001 bot :Welcome, GLHF!"),
+        None
+    );
+}
@@ -0,0 +1,518 @@
+use std::collections::HashSet;
+
+use anyhow::Context as _;
+use derive_more::Debug;
+use eframe::egui::{Button, Context, DragValue, Grid, RichText, TextEdit, Ui};
+use noita_utility_box::noita::Noita;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use smart_default::SmartDefault;
+
+use crate::{
+    app::AppState,
+    cli::parse_switch,
+    events::{EventCursor, GameEvent},
+    util::Promise,
+};
+
+use super::{Result, Tool};
+
+const HELIX: &str = "https://api.twitch.tv/helix";
+
+/// When a prediction opens and how it is resolved
+#[derive(Debug, Clone, Serialize, Deserialize, SmartDefault)]
+#[serde(default)]
+struct BossTrigger {
+    name: String,
+    /// The prediction title, up to 45 characters
+    question: String,
+    /// Entity tag of the boss, the prediction opens when it's within
+    /// [Self::radius] of the player, and resolves as won when the boss
+    /// entity goes away with the player still nearby. Bosses without one
+    /// never trigger
+    tag: String,
+    /// Known boss position, the prediction also opens when the player gets
+    /// this close to it
+    position: Option<(f32, f32)>,
+    #[default(600.0)]
+    radius: f32,
+}
+
+impl BossTrigger {
+    fn kolmi() -> Self {
+        Self {
+            name: "Kolmi".into(),
+            question: "Do we beat Kolmi?".into(),
+            tag: "boss_centipede".into(),
+            // the arena at the bottom of the Laboratory
+            position: Some((3560.0, 13050.0)),
+            radius: 600.0,
+        }
+    }
+}
+
+/// A prediction opened on Twitch
+#[derive(Debug, Clone)]
+struct Prediction {
+    id: String,
+    broadcaster_id: String,
+    yes: String,
+    no: String,
+}
+
+#[derive(Debug, Default)]
+enum PredictionState {
+    #[default]
+    Idle,
+    /// With the name of the boss, the list can be edited in the meantime
+    Opening(String, #[debug(skip)] Promise<anyhow::Result<Prediction>>),
+    Open(String, Prediction),
+    Closing(#[debug(skip)] Promise<anyhow::Result<()>>),
+    Error(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resolution {
+    Won,
+    Lost,
+    Canceled,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixData<T> {
+    data: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixUser {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixPrediction {
+    id: String,
+    outcomes: Vec<HelixOutcome>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HelixOutcome {
+    id: String,
+}
+
+fn helix(request: RequestBuilder, client_id: &str, token: &str) -> RequestBuilder {
+    request
+        .bearer_auth(token.trim_start_matches("oauth:"))
+        .header("Client-Id", client_id)
+}
+
+async fn open_prediction(
+    client_id: String,
+    token: String,
+    title: String,
+    window: u32,
+) -> anyhow::Result<Prediction> {
+    let client = Client::new();
+
+    // the token owner is the broadcaster
+    let users: HelixData<HelixUser> =
+        helix(client.get(format!("{HELIX}/users")), &client_id, &token)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Getting the broadcaster id")?
+            .json()
+            .await?;
+    let broadcaster_id = users.data.into_iter().next().context("No user")?.id;
+
+    let body = json!({
+        "broadcaster_id": broadcaster_id,
+        "title": title,
+        "outcomes": [{ "title": "Yes" }, { "title": "No" }],
+        "prediction_window": window,
+    });
+    let created: HelixData<HelixPrediction> = helix(
+        client.post(format!("{HELIX}/predictions")),
+        &client_id,
+        &token,
+    )
+    .json(&body)
+    .send()
+    .await?
+    .error_for_status()
+    .context("Creating the prediction")?
+    .json()
+    .await?;
+    let prediction = created.data.into_iter().next().context("No prediction")?;
+    let [yes, no] = <[HelixOutcome; 2]>::try_from(prediction.outcomes)
+        .map_err(|_| anyhow::anyhow!("Expected two outcomes"))?;
+
+    Ok(Prediction {
+        id: prediction.id,
+        broadcaster_id,
+        yes: yes.id,
+        no: no.id,
+    })
+}
+
+async fn close_prediction(
+    client_id: String,
+    token: String,
+    prediction: Prediction,
+    resolution: Resolution,
+) -> anyhow::Result<()> {
+    let mut body = json!({
+        "broadcaster_id": prediction.broadcaster_id,
+        "id": prediction.id,
+    });
+    match resolution {
+        Resolution::Won | Resolution::Lost => {
+            body["status"] = "RESOLVED".into();
+            body["winning_outcome_id"] = match resolution {
+                Resolution::Won => prediction.yes.into(),
+                _ => prediction.no.into(),
+            };
+        }
+        Resolution::Canceled => body["status"] = "CANCELED".into(),
+    }
+    helix(
+        Client::new().patch(format!("{HELIX}/predictions")),
+        &client_id,
+        &token,
+    )
+    .json(&body)
+    .send()
+    .await?
+    .error_for_status()
+    .context("Closing the prediction")?;
+    Ok(())
+}
+
+/// Opens a Twitch prediction when the player gets to a boss, and resolves
+/// it when the boss dies or the player does
+#[derive(Debug, SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TwitchPredictions {
+    enabled: bool,
+    /// Of the Twitch application the token was made for
+    client_id: String,
+    /// Broadcaster token with the channel:manage:predictions scope
+    token: String,
+    /// Seconds the viewers have to make their predictions
+    #[default(60)]
+    window: u32,
+    #[default(vec![BossTrigger::kolmi()])]
+    bosses: Vec<BossTrigger>,
+
+    #[serde(skip)]
+    state: PredictionState,
+    /// Bosses that already had a prediction this run
+    #[serde(skip)]
+    triggered: HashSet<String>,
+    /// Where the boss of the open prediction was last seen
+    #[serde(skip)]
+    boss_seen: Option<(f32, f32)>,
+    #[serde(skip)]
+    events: EventCursor,
+    #[serde(skip)]
+    last_frame: Option<u32>,
+}
+
+impl TwitchPredictions {
+    fn open(&mut self, name: String) {
+        let Some(boss) = self.bosses.iter().find(|boss| boss.name == name) else {
+            return;
+        };
+        tracing::info!(boss = boss.name, "Opening a prediction");
+        let question = boss.question.chars().take(45).collect();
+        self.triggered.insert(name.clone());
+        self.boss_seen = None;
+        self.state = PredictionState::Opening(
+            name,
+            Promise::spawn(open_prediction(
+                self.client_id.trim().to_owned(),
+                self.token.trim().to_owned(),
+                question,
+                self.window.clamp(30, 1800),
+            )),
+        );
+    }
+
+    fn close(&mut self, resolution: Resolution) {
+        let PredictionState::Open(_, prediction) = &self.state else {
+            return;
+        };
+        let prediction = prediction.clone();
+        tracing::info!(?resolution, "Closing the prediction");
+        self.state = PredictionState::Closing(Promise::spawn(close_prediction(
+            self.client_id.trim().to_owned(),
+            self.token.trim().to_owned(),
+            prediction,
+            resolution,
+        )));
+    }
+
+    /// Name of the boss the player just got to, if any
+    fn check_triggers(&self, noita: &mut Noita) -> anyhow::Result<Option<String>> {
+        let Some((player, _)) = noita.get_player()? else {
+            return Ok(None);
+        };
+        let player = player.transform.pos;
+        for boss in &self.bosses {
+            if self.triggered.contains(&boss.name) || boss.tag.trim().is_empty() {
+                continue;
+            }
+            let near = |x: f32, y: f32| {
+                let (dx, dy) = (x - player.x, y - player.y);
+                dx * dx + dy * dy < boss.radius * boss.radius
+            };
+            if boss.position.is_some_and(|(x, y)| near(x, y)) {
+                return Ok(Some(boss.name.clone()));
+            }
+            if let Some(entity) = boss_entity(noita, &boss.tag)? {
+                if near(entity.0, entity.1) {
+                    return Ok(Some(boss.name.clone()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether the boss of the open prediction died, i.e. its entity was
+    /// seen and then went away while the player is still there to see it
+    fn check_boss_killed(&mut self, noita: &mut Noita, name: &str) -> anyhow::Result<bool> {
+        let Some(boss) = self.bosses.iter().find(|boss| boss.name == name) else {
+            return Ok(false);
+        };
+        let Some((player, _)) = noita.get_player()? else {
+            return Ok(false);
+        };
+        let player = player.transform.pos;
+        let near = |(x, y): (f32, f32)| {
+            let (dx, dy) = (x - player.x, y - player.y);
+            dx * dx + dy * dy < (boss.radius * 2.0).powi(2)
+        };
+        if let Some(pos) = boss_entity(noita, boss.tag.trim())? {
+            self.boss_seen = Some(pos);
+            return Ok(false);
+        }
+        // not seen yet (still loading, or a wrong tag), or it just unloaded
+        // after the player left, then it has to be seen again
+        let killed = self.boss_seen.is_some_and(near);
+        if !killed {
+            self.boss_seen = None;
+        }
+        Ok(killed)
+    }
+}
+
+/// Position of the first live entity with the tag
+fn boss_entity(noita: &mut Noita, tag: &str) -> anyhow::Result<Option<(f32, f32)>> {
+    if tag.is_empty() {
+        return Ok(None);
+    }
+    let Some(tag) = noita.get_entity_tag_index(tag)? else {
+        return Ok(None);
+    };
+    Ok(noita
+        .get_first_tagged_entity(tag)?
+        .map(|e| (e.transform.pos.x, e.transform.pos.y)))
+}
+
+#[typetag::serde]
+impl Tool for TwitchPredictions {
+    fn tick(&mut self, ctx: &Context, state: &mut AppState) {
+        for event in state.events.poll(&mut self.events) {
+            match event {
+                GameEvent::NewRun => {
+                    self.triggered.clear();
+                    self.close(Resolution::Canceled);
+                }
+                GameEvent::PlayerDied => self.close(Resolution::Lost),
+                _ => {}
+            }
+        }
+
+        match &mut self.state {
+            PredictionState::Opening(name, promise) => match promise.poll_take() {
+                Some(Ok(prediction)) => {
+                    self.state = PredictionState::Open(std::mem::take(name), prediction);
+                    ctx.request_repaint();
+                }
+                Some(Err(e)) => {
+                    state.toast_error(format!("Failed to open a prediction: {e:#}"));
+                    self.state = PredictionState::Error(format!("{e:#}"));
+                }
+                None => {}
+            },
+            PredictionState::Closing(promise) => match promise.poll_take() {
+                Some(Ok(())) => self.state = PredictionState::Idle,
+                Some(Err(e)) => {
+                    state.toast_error(format!("Failed to close the prediction: {e:#}"));
+                    self.state = PredictionState::Error(format!("{e:#}"));
+                }
+                None => {}
+            },
+            _ => {}
+        }
+
+        if !self.enabled || !state.game_advanced(&mut self.last_frame) {
+            return;
+        }
+        let Some(noita) = state.noita.as_mut() else {
+            return;
+        };
+        let result = match &self.state {
+            PredictionState::Idle | PredictionState::Error(_) => {
+                self.check_triggers(noita).map(|name| {
+                    if let Some(name) = name {
+                        self.open(name);
+                    }
+                })
+            }
+            PredictionState::Open(name, _) => {
+                let name = name.clone();
+                self.check_boss_killed(noita, &name).map(|killed| {
+                    if killed {
+                        self.close(Resolution::Won);
+                    }
+                })
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            tracing::debug!(e = format!("{e:#}"), "Prediction trigger check failed");
+        }
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> anyhow::Result<bool> {
+        match key {
+            "enabled" => self.enabled = parse_switch(value)?,
+            "client-id" => self.client_id = value.into(),
+            "token" => self.token = value.into(),
+            "window" => {
+                self.window = value
+                    .parse()
+                    .with_context(|| format!("Expected a number of seconds, got {value}"))?
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, _state: &mut AppState) -> Result {
+        Grid::new("twitch_predictions")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Client ID");
+                ui.text_edit_singleline(&mut self.client_id);
+                ui.end_row();
+
+                ui.label("Broadcaster token");
+                ui.add(TextEdit::singleline(&mut self.token).password(true))
+                    .on_hover_text("Needs the channel:manage:predictions scope");
+                ui.end_row();
+
+                ui.label("Prediction window");
+                ui.add(
+                    DragValue::new(&mut self.window)
+                        .range(30..=1800)
+                        .suffix(" s"),
+                );
+                ui.end_row();
+            });
+
+        let ready = !self.client_id.trim().is_empty() && !self.token.trim().is_empty();
+        ui.add_enabled_ui(ready, |ui| {
+            ui.checkbox(&mut self.enabled, "Open predictions automatically");
+        });
+
+        let mut resolution = None;
+        ui.horizontal(|ui| match &self.state {
+            PredictionState::Idle => {
+                ui.label("No prediction running");
+            }
+            PredictionState::Opening(..) | PredictionState::Closing(_) => {
+                ui.spinner();
+            }
+            PredictionState::Open(name, _) => {
+                ui.label(format!("Prediction open for {name}"));
+                if ui.button("Yes").clicked() {
+                    resolution = Some(Resolution::Won);
+                }
+                if ui.button("No").clicked() {
+                    resolution = Some(Resolution::Lost);
+                }
+                if ui.button("Cancel").clicked() {
+                    resolution = Some(Resolution::Canceled);
+                }
+            }
+            PredictionState::Error(e) => {
+                ui.label(RichText::new(e).color(ui.visuals().error_fg_color));
+            }
+        });
+        if let Some(resolution) = resolution {
+            self.close(resolution);
+        }
+
+        ui.separator();
+        ui.label("Bosses");
+        let mut removed = None;
+        Grid::new("twitch_predictions_bosses")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Name");
+                ui.label("Question");
+                ui.label("Entity tag");
+                ui.label("Position");
+                ui.label("Radius");
+                ui.end_row();
+
+                for (i, boss) in self.bosses.iter_mut().enumerate() {
+                    ui.add(TextEdit::singleline(&mut boss.name).desired_width(80.0));
+                    ui.add(
+                        TextEdit::singleline(&mut boss.question)
+                            .char_limit(45)
+                            .desired_width(160.0),
+                    );
+                    let tag = TextEdit::singleline(&mut boss.tag)
+                        .hint_text(RichText::new("required").color(ui.visuals().error_fg_color))
+                        .desired_width(100.0);
+                    let tag = ui.add(tag);
+                    if boss.tag.trim().is_empty() {
+                        tag.on_hover_text("Without a tag the boss never triggers");
+                    }
+                    ui.horizontal(|ui| {
+                        let mut has_position = boss.position.is_some();
+                        ui.checkbox(&mut has_position, "");
+                        match (&mut boss.position, has_position) {
+                            (Some((x, y)), true) => {
+                                ui.add(DragValue::new(x).prefix("x: "));
+                                ui.add(DragValue::new(y).prefix("y: "));
+                            }
+                            (None, true) => boss.position = Some((0.0, 0.0)),
+                            (_, false) => boss.position = None,
+                        }
+                    });
+                    ui.add(
+                        DragValue::new(&mut boss.radius)
+                            .range(16.0..=10000.0)
+                            .suffix(" px"),
+                    );
+                    if ui.add(Button::new("🗑").small()).clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = removed {
+            self.bosses.remove(i);
+        }
+        if ui.button("Add a boss").clicked() {
+            self.bosses.push(BossTrigger::default());
+        }
+
+        Ok(())
+    }
+}
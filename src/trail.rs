@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+use eframe::egui::{Painter, Pos2, Stroke};
+use serde::{Deserialize, Serialize};
+
+/// Jumps longer than this between two points are teleports, and are not
+/// drawn as a line
+const TELEPORT_DISTANCE: f32 = 1000.0;
+
+/// A point of the player path, along with the game frame it was at
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrailPoint {
+    pub pos: Pos2,
+    pub frame: u32,
+}
+
+/// The path the player took, a ring buffer of positions at least
+/// `resolution` pixels apart
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Trail {
+    points: VecDeque<TrailPoint>,
+}
+
+impl Trail {
+    pub fn record(&mut self, point: TrailPoint, resolution: f32, capacity: usize) {
+        if let Some(last) = self.points.back() {
+            // the frame counter going back means a new run (or a reload)
+            if point.frame < last.frame {
+                self.clear();
+            } else if (point.pos - last.pos).length() < resolution {
+                return;
+            }
+        }
+        self.points.push_back(point);
+        while self.points.len() > capacity {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn points(&self) -> &VecDeque<TrailPoint> {
+        &self.points
+    }
+
+    /// The point closest to `pos`, if there is one within `max_distance`
+    pub fn nearest(&self, pos: Pos2, max_distance: f32) -> Option<&TrailPoint> {
+        self.points
+            .iter()
+            .map(|p| (p, (p.pos - pos).length()))
+            .filter(|(_, d)| *d <= max_distance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(p, _)| p)
+    }
+
+    /// Draw the path as a polyline, skipping the teleports
+    pub fn paint(&self, painter: &Painter, to_screen: impl Fn(Pos2) -> Pos2, stroke: Stroke) {
+        let clip = painter.clip_rect();
+        for (a, b) in self.points.iter().zip(self.points.iter().skip(1)) {
+            if (b.pos - a.pos).length() > TELEPORT_DISTANCE {
+                continue;
+            }
+            let (a, b) = (to_screen(a.pos), to_screen(b.pos));
+            if clip.contains(a) || clip.contains(b) {
+                painter.line_segment([a, b], stroke);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn trail_ring_buffer() {
+    use eframe::egui::pos2;
+
+    let point = |x, frame| TrailPoint {
+        pos: pos2(x, 0.0),
+        frame,
+    };
+    let mut trail = Trail::default();
+    trail.record(point(0.0, 0), 10.0, 3);
+    trail.record(point(5.0, 1), 10.0, 3);
+    assert_eq!(trail.points().len(), 1);
+
+    for i in 1..5 {
+        trail.record(point(i as f32 * 10.0, i), 10.0, 3);
+    }
+    assert_eq!(trail.points().len(), 3);
+    assert_eq!(trail.points()[0], point(20.0, 2));
+
+    assert_eq!(trail.nearest(pos2(33.0, 0.0), 5.0), Some(&point(30.0, 3)));
+    assert_eq!(trail.nearest(pos2(100.0, 0.0), 5.0), None);
+
+    trail.record(point(0.0, 0), 10.0, 3);
+    assert_eq!(trail.points().len(), 1);
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use eframe::egui::{Align, Context, Frame, Layout, OpenUrl, ScrollArea};
 use egui_modal::Modal;
@@ -8,6 +10,9 @@ use crate::{app::AppState, util::Promise};
 
 pub const RELEASE_VERSION: Option<&str> = option_env!("CI_RELEASE_VERSION");
 
+/// Don't keep a request hanging around forever on a bad connection
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
 #[derive(Debug, Deserialize)]
 struct UpdateInfo {
     html_url: String,
@@ -103,7 +108,7 @@ fn show_update_modal(ctx: &Context, update_info: &UpdateInfo, state: &mut AppSta
 
 #[derive(Debug, Default)]
 pub struct UpdateChecker {
-    update_task: Promise<Option<UpdateInfo>>,
+    update_task: Promise<Option<Option<UpdateInfo>>>,
 }
 
 impl UpdateChecker {
@@ -116,38 +121,47 @@ impl UpdateChecker {
             return;
         }
         match &mut self.update_task {
-            Promise::Taken => {}
+            Promise::Taken | Promise::Cancelled => {}
             // finished update task is taken, so it can only Done(None) on the first update
             Promise::Done(None) => {
                 if !state.settings.check_for_updates {
                     tracing::info!("Update check is disabled, skipping");
                     self.update_task = Promise::Taken;
+                    return;
                 }
                 let ctx = ctx.clone();
-                self.update_task = Promise::spawn(async move {
-                    match fetch_newer_release().await {
-                        Ok(info) => {
-                            ctx.request_repaint();
-                            info
-                        }
+                self.update_task = Promise::spawn_timeout(CHECK_TIMEOUT, async move {
+                    let info = match fetch_newer_release().await {
+                        Ok(info) => info,
                         Err(e) => {
                             tracing::error!(e = e.to_string(), "Update check failed");
                             None
                         }
-                    }
+                    };
+                    ctx.request_repaint();
+                    info
                 });
             }
+            // turned off while the request was still going
+            p if p.is_pending() && !state.settings.check_for_updates => {
+                tracing::info!("Update check was disabled, cancelling");
+                p.cancel();
+            }
             p => match p.poll() {
-                Some(Some(info)) => {
+                Some(Some(Some(info))) => {
                     if !show_update_modal(ctx, info, state) {
                         state.settings.newest_version = Some(info.tag_name.clone());
                         self.update_task = Promise::Taken;
                     }
                 }
-                Some(None) => {
+                Some(Some(None)) => {
                     tracing::info!("No updates found");
                     self.update_task = Promise::Taken;
                 }
+                Some(None) => {
+                    tracing::warn!("Update check timed out");
+                    self.update_task = Promise::Taken;
+                }
                 None => {}
             },
         }
@@ -3,17 +3,37 @@ use eframe::egui;
 use std::{
     borrow::Borrow,
     future::Future,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
-use tokio::sync::oneshot::{self, error::TryRecvError, Receiver};
+use tokio::{
+    sync::oneshot::{self, error::TryRecvError, Receiver},
+    task::AbortHandle,
+};
 
 /// A variant of poll-promise that can be used as storage. Uses tokio.
+///
+/// Dropping a pending promise (or replacing it with another one) cancels
+/// the task at its next await point, same as [Promise::cancel]
 #[derive(Debug)]
 pub enum Promise<T> {
-    Pending(Receiver<T>),
+    Pending(Receiver<T>, CancelOnDrop),
     Done(T),
     Taken,
+    Cancelled,
+}
+
+/// Aborts the task of a pending [Promise] when the promise goes away
+#[derive(Debug)]
+pub struct CancelOnDrop(#[debug(skip)] AbortHandle);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
 }
 
 // this could happen when the tokio runtime shuts down ig
@@ -30,19 +50,46 @@ impl<T> Promise<T> {
         // we use tokio and not pollster or something because
         // obws brings (and depends on) tokio anyway
         let (tx, rx) = oneshot::channel();
-        tokio::spawn(async { tx.send(future.await) });
-        Self::Pending(rx)
+        let task = tokio::spawn(async { tx.send(future.await) });
+        Self::Pending(rx, CancelOnDrop(task.abort_handle()))
+    }
+
+    /// Same as [Promise::spawn], but gives up and resolves to None if the
+    /// future takes longer than `timeout`
+    pub fn spawn_timeout<F>(timeout: Duration, future: F) -> Promise<Option<T>>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        Promise::spawn(async move { tokio::time::timeout(timeout, future).await.ok() })
+    }
+
+    /// Stop the task if it's still running, the promise will never resolve
+    /// after that. Does nothing if the promise is already complete
+    pub fn cancel(&mut self) {
+        if let Promise::Pending(..) = self {
+            // dropping the pending one aborts the task
+            *self = Promise::Cancelled;
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Promise::Cancelled)
+    }
+
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Promise::Pending(..))
     }
 
     /// Borrow the value if the promise is complete, otherwise return None.
-    /// Panics if the promise value was taken.
+    /// Panics if the promise value was taken, a cancelled one is never complete.
     pub fn poll<Q>(&mut self) -> Option<&Q>
     where
         Q: ?Sized,
         T: Borrow<Q>,
     {
         match self {
-            Promise::Pending(rx) => match rx.try_recv() {
+            Promise::Pending(rx, _) => match rx.try_recv() {
                 Ok(t) => {
                     *self = Promise::Done(t);
                     // recurse into the outer match lol
@@ -53,6 +100,7 @@ impl<T> Promise<T> {
             },
             Promise::Done(ref t) => Some(t.borrow()),
             Promise::Taken => panic!("Promise was taken"),
+            Promise::Cancelled => None,
         }
     }
 
@@ -70,16 +118,20 @@ impl<T> Promise<T> {
     /// Subsequent calls to `poll_take` or `poll` will panic.
     pub fn poll_take(&mut self) -> Option<T> {
         match std::mem::replace(self, Promise::Taken) {
-            Promise::Pending(mut rx) => match rx.try_recv() {
+            Promise::Pending(mut rx, task) => match rx.try_recv() {
                 Ok(t) => Some(t),
                 Err(TryRecvError::Empty) => {
-                    *self = Promise::Pending(rx);
+                    *self = Promise::Pending(rx, task);
                     None
                 }
                 Err(TryRecvError::Closed) => no_sender(),
             },
             Promise::Done(t) => Some(t),
             Promise::Taken => panic!("Promise was already taken"),
+            Promise::Cancelled => {
+                *self = Promise::Cancelled;
+                None
+            }
         }
     }
 
@@ -94,6 +146,20 @@ impl<T: Default> Default for Promise<T> {
     }
 }
 
+/// A fraction of work done, shared with the background task doing it
+#[derive(Debug, Clone, Default)]
+pub struct Progress(Arc<AtomicU32>);
+
+impl Progress {
+    pub fn set(&self, done: f32) {
+        self.0.store(done.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
 /// Implement [serde::Serialize] and [serde::Deserialize] for a struct, only
 /// writing/reading the specified fields and using Default when reading.
 #[allow(unused_macros)] // false positive?. it's definitely used
@@ -122,10 +188,19 @@ macro_rules! persist {
             fn deserialize<D: ::serde::Deserializer<'de>>(
                 deserializer: D,
             ) -> ::std::result::Result<Self, D::Error> {
+                // fields missing from older saves get their defaults instead
+                // of failing (and so resetting) the whole thing
                 #[derive(::serde::Deserialize)]
+                #[serde(default)]
                 struct Persisted {
                     $($field: $field_t,)*
                 }
+                impl ::std::default::Default for Persisted {
+                    fn default() -> Self {
+                        let _default = <$t as ::std::default::Default>::default();
+                        Persisted { $($field: _default.$field,)* }
+                    }
+                }
                 let _persisted = Persisted::deserialize(deserializer)?;
                 #[allow(clippy::needless_update)]
                 ::std::result::Result::Ok($t {
@@ -247,3 +322,37 @@ fn test_const_title_case() {
     const P: &str = to_title_case!("𐍈𓂀ܰᚦΞB𝔄꧁৹ဨ");
     println!("{P}")
 }
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_promise_cancel() {
+    use std::sync::atomic::AtomicBool;
+
+    let ran = Arc::new(AtomicBool::new(false));
+    let mut promise = Promise::spawn({
+        let ran = ran.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            ran.store(true, Ordering::Relaxed);
+        }
+    });
+    promise.cancel();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert!(promise.is_cancelled());
+    assert!(promise.poll_take().is_none());
+    assert!(!ran.load(Ordering::Relaxed));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_promise_timeout() {
+    let mut slow = Promise::spawn_timeout(Duration::from_millis(10), async {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    });
+    let mut fast = Promise::spawn_timeout(Duration::from_secs(10), async { 42 });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(slow.poll_take(), Some(None));
+    assert_eq!(fast.poll_take(), Some(Some(42)));
+}
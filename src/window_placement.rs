@@ -0,0 +1,162 @@
+//! Remembering where the windows were for each monitor setup, so that e.g.
+//! docking a laptop back puts the windows back onto the stream monitor
+//!
+//! egui only knows about the monitor a window is on, so a setup is told
+//! apart by the size and scale of that monitor
+
+use std::collections::BTreeMap;
+
+use eframe::egui::{
+    Context, Pos2, Vec2, ViewportBuilder, ViewportCommand, ViewportId, ViewportInfo,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Placement {
+    /// The outer position, with the window decorations
+    pub pos: Pos2,
+    /// The inner size, without the window decorations
+    pub size: Vec2,
+    pub maximized: bool,
+}
+
+impl Placement {
+    pub fn apply(&self, ctx: &Context, viewport: ViewportId) {
+        tracing::info!(?viewport, placement = ?self, "Restoring the window placement");
+        ctx.send_viewport_cmd_to(viewport, ViewportCommand::OuterPosition(self.pos));
+        ctx.send_viewport_cmd_to(viewport, ViewportCommand::InnerSize(self.size));
+        ctx.send_viewport_cmd_to(viewport, ViewportCommand::Maximized(self.maximized));
+    }
+}
+
+/// Where one window was on each of the monitor setups it was used with
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowPlacement {
+    /// By [setup_key]
+    setups: BTreeMap<String, Placement>,
+    /// The setup the window was last seen on
+    last: Option<String>,
+    /// Whether the placement was already restored, it's only done once when
+    /// the window opens, later on a different setup just means the window
+    /// was dragged to another monitor
+    #[serde(skip)]
+    restored: bool,
+}
+
+fn setup_key(viewport: &ViewportInfo) -> Option<String> {
+    let size = viewport.monitor_size?;
+    let scale = viewport.native_pixels_per_point?;
+    Some(format!("{:.0}x{:.0}@{scale}", size.x, size.y))
+}
+
+impl WindowPlacement {
+    /// Where the window was the last time, for opening it before anything
+    /// is known about the monitors
+    pub fn last(&self) -> Option<Placement> {
+        self.setups.get(self.last.as_deref()?).copied()
+    }
+
+    /// Open the window where it was the last time, only for creating it,
+    /// later it's up to [Self::track]
+    pub fn builder(&self, mut builder: ViewportBuilder) -> ViewportBuilder {
+        if let Some(placement) = self.last() {
+            builder = builder
+                .with_position(placement.pos)
+                .with_inner_size(placement.size)
+                .with_maximized(placement.maximized);
+        }
+        builder
+    }
+
+    /// Remember where the window is now, call every frame from inside the
+    /// window's viewport. Returns the placement to restore when the window
+    /// opens on a setup it was on before but at another spot
+    pub fn track(&mut self, ctx: &Context) -> Option<Placement> {
+        let (key, current) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (setup_key(viewport), self.current(viewport))
+        });
+        // not known on the very first frames
+        self.update(key?, current)
+    }
+
+    fn update(&mut self, key: String, current: Option<Placement>) -> Option<Placement> {
+        let restore = if !self.restored {
+            self.restored = true;
+            self.setups
+                .get(&key)
+                .copied()
+                .filter(|saved| Some(*saved) != current)
+        } else {
+            None
+        };
+        self.last = Some(key.clone());
+        // the window is going to move, don't overwrite it with where it was
+        if restore.is_none() {
+            if let Some(current) = current {
+                self.setups.insert(key, current);
+            }
+        }
+        restore
+    }
+
+    fn current(&self, viewport: &ViewportInfo) -> Option<Placement> {
+        if viewport.minimized == Some(true) {
+            return None;
+        }
+        if viewport.maximized == Some(true) {
+            // keep the size to go back to when unmaximized
+            let mut last = self.last()?;
+            last.maximized = true;
+            return Some(last);
+        }
+        Some(Placement {
+            pos: viewport.outer_rect?.min,
+            size: viewport.inner_rect?.size(),
+            maximized: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::{pos2, vec2};
+
+    use super::*;
+
+    fn at(x: f32) -> Option<Placement> {
+        Some(Placement {
+            pos: pos2(x, 0.0),
+            size: vec2(800.0, 600.0),
+            maximized: false,
+        })
+    }
+
+    #[test]
+    fn restores_per_setup() {
+        let mut placement = WindowPlacement::default();
+        let (desk, laptop) = ("2560x1440@1".to_owned(), "1920x1080@1.5".to_owned());
+
+        assert_eq!(placement.update(desk.clone(), at(3000.0)), None);
+        assert_eq!(placement.update(desk.clone(), at(3100.0)), None);
+
+        // dragged over to the other monitor and back, nothing snaps back
+        assert_eq!(placement.update(laptop.clone(), at(10.0)), None);
+        assert_eq!(placement.update(desk.clone(), at(3100.0)), None);
+
+        // opened undocked, the os put it somewhere
+        placement.restored = false;
+        assert_eq!(placement.update(laptop.clone(), at(20.0)), at(10.0));
+        assert_eq!(placement.update(laptop.clone(), at(10.0)), None);
+        assert_eq!(placement.last(), at(10.0));
+
+        // opened docked back, but on the laptop spot
+        placement.restored = false;
+        assert_eq!(placement.update(desk.clone(), at(10.0)), at(3100.0));
+        // the move can take a frame to land, then it's tracked as usual
+        assert_eq!(placement.update(desk.clone(), at(10.0)), None);
+        assert_eq!(placement.update(desk, at(3100.0)), None);
+        assert_eq!(placement.last(), at(3100.0));
+    }
+}